@@ -38,8 +38,60 @@ impl BufferCommand {
     pub fn args(&self) -> &[String] {
         &self.args
     }
+
+    /// Positional arguments as buffers to open, each with any `:N` line suffix split off.
+    pub fn targets(&self) -> Vec<BufferTarget> {
+        self.args
+            .iter()
+            .map(|arg| BufferTarget::parse(arg))
+            .collect()
+    }
+}
+
+/// A buffer to open and the one-based line to place the cursor on, from `name:line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferTarget {
+    pub name: String,
+    pub line: Option<usize>,
+}
+
+impl BufferTarget {
+    /// Split a trailing `:N` off `arg`, as in `src/main.rs:10` from compiler output.
+    ///
+    /// Only a positive number after a non-empty name counts, so `:10`, `notes:` and `a:b` are
+    /// kept whole.
+    pub fn parse(arg: &str) -> Self {
+        let split = arg.rsplit_once(':').and_then(|(name, line)| {
+            let digits = !line.is_empty() && line.bytes().all(|byte| byte.is_ascii_digit());
+            let line = line
+                .parse::<usize>()
+                .ok()
+                .filter(|line| digits && *line > 0)?;
+            (!name.is_empty()).then(|| (name.to_string(), line))
+        });
+        match split {
+            Some((name, line)) => Self {
+                name,
+                line: Some(line),
+            },
+            None => Self::whole(arg),
+        }
+    }
+
+    /// `arg` as a buffer name, without looking for a line suffix.
+    pub fn whole(arg: &str) -> Self {
+        Self {
+            name: arg.to_string(),
+            line: None,
+        }
+    }
 }
 
+/// Short options `:b` accepts, in the order completion offers them.
+pub const BUFFER_OPTIONS: &[char] = &[
+    'a', 'd', 'E', 'f', 'l', 'L', 'o', 'O', 'p', 'r', 's', 't', 'w',
+];
+
 /// Attempt to parse a `:b` command into short options and buffer arguments.
 pub fn parse(input: &str) -> Option<BufferCommand> {
     let tokens = match shlex::split(input) {
@@ -68,7 +120,14 @@ fn split_short_options(tokens: &[String]) -> (Vec<char>, Vec<String>) {
     let mut options = Vec::new();
     let mut args = Vec::new();
 
-    for token in tokens {
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        if token == "--" {
+            // Everything after `--` is an argument, e.g. the filter command of `:b -p`.
+            args.push(token.clone());
+            args.extend(tokens.by_ref().cloned());
+            break;
+        }
         if let Some(stripped) = token.strip_prefix('-') {
             if stripped.is_empty() || token.starts_with("--") {
                 args.push(token.clone());
@@ -107,15 +166,15 @@ fn partition_options(options: Vec<char>) -> (Vec<char>, Vec<char>) {
 
 fn option_timing(option: char) -> TimingBucket {
     match option {
-        'd' | 'r' => TimingBucket::PreSession,
-        'l' => TimingBucket::PostSession,
+        'd' | 'r' | 'L' | 'O' | 'f' | 'o' | 't' | 'a' | 'p' | 'E' => TimingBucket::PreSession,
+        'l' | 's' | 'w' => TimingBucket::PostSession,
         _ => TimingBucket::PostSession,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{TimingBucket, option_timing, parse};
+    use super::{BufferTarget, TimingBucket, option_timing, parse};
 
     #[test]
     fn parse_list_only() {
@@ -125,6 +184,16 @@ mod tests {
         assert!(command.args().is_empty());
     }
 
+    #[test]
+    fn parse_bulk_close_and_reopen() {
+        let close = parse(":b -Lf").expect("expected parse result");
+        assert_eq!(close.pre_session_options(), &['L', 'f']);
+        assert!(close.post_session_options().is_empty());
+
+        let reopen = parse(":b -O").expect("expected parse result");
+        assert_eq!(reopen.pre_session_options(), &['O']);
+    }
+
     #[test]
     fn parse_single_file() {
         let command = parse(":b file").expect("expected parse result");
@@ -133,6 +202,30 @@ mod tests {
         assert_eq!(command.args(), &["file".to_string()]);
     }
 
+    #[test]
+    fn parse_file_with_line() {
+        let command = parse(":b src/main.rs:10").expect("expected parse result");
+        assert_eq!(command.args(), &[String::from("src/main.rs:10")]);
+        assert_eq!(
+            command.targets(),
+            vec![BufferTarget {
+                name: String::from("src/main.rs"),
+                line: Some(10),
+            }]
+        );
+    }
+
+    #[test]
+    fn line_suffix_needs_a_name_and_a_positive_number() {
+        for arg in [":10", "notes:", "notes:0", "a:b", "notes:+3", "plain"] {
+            assert_eq!(BufferTarget::parse(arg), BufferTarget::whole(arg), "{arg}");
+        }
+        assert_eq!(
+            BufferTarget::parse("c:/dir/file.txt:7").name,
+            "c:/dir/file.txt"
+        );
+    }
+
     #[test]
     fn parse_list_with_file() {
         let command = parse(":b -l file").expect("expected parse result");
@@ -150,9 +243,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_list_by_modified() {
+        let command = parse(":b -s").expect("expected parse result");
+        assert!(command.pre_session_options().is_empty());
+        assert_eq!(command.post_session_options(), &['s']);
+    }
+
+    #[test]
+    fn parse_scratch() {
+        let command = parse(":b -t notes").expect("expected parse result");
+        assert_eq!(command.pre_session_options(), &['t']);
+        assert!(command.post_session_options().is_empty());
+        assert_eq!(command.args(), &[String::from("notes")]);
+    }
+
+    #[test]
+    fn parse_append_from_file() {
+        let command = parse(":b -a compare notes.txt").expect("expected parse result");
+        assert_eq!(command.pre_session_options(), &['a']);
+        assert_eq!(
+            command.args(),
+            &[String::from("compare"), String::from("notes.txt")]
+        );
+    }
+
+    #[test]
+    fn parse_export_runs_before_the_session() {
+        let command = parse(":b -E backup").expect("expected parse result");
+        assert_eq!(command.pre_session_options(), &['E']);
+        assert!(command.post_session_options().is_empty());
+        assert_eq!(command.args(), &[String::from("backup")]);
+    }
+
+    #[test]
+    fn parse_filter_keeps_the_command_after_double_dash() {
+        let command = parse(":b -p notes -- sort -r").expect("expected parse result");
+        assert_eq!(command.pre_session_options(), &['p']);
+        assert_eq!(
+            command.args(),
+            &[
+                String::from("notes"),
+                String::from("--"),
+                String::from("sort"),
+                String::from("-r")
+            ]
+        );
+    }
+
     #[test]
     fn classify_option_timing() {
         assert_eq!(option_timing('l'), TimingBucket::PostSession);
+        assert_eq!(option_timing('s'), TimingBucket::PostSession);
+        assert_eq!(option_timing('w'), TimingBucket::PostSession);
         assert_eq!(option_timing('x'), TimingBucket::PostSession);
         assert_eq!(option_timing('r'), TimingBucket::PreSession);
     }
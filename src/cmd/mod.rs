@@ -1 +1,5 @@
 pub mod bufcmd;
+
+/// Colon commands understood at the prompt, as dispatched by
+/// `ControlState::handle_prompt_command`.
+pub const PROMPT_COMMANDS: &[&str] = &[":b", ":fc", ":m", ":p"];
@@ -1 +1,242 @@
-//! Placeholder for future custom completer logic.
+//! Tab completion for variable names, prompt colon commands and `cd` operands, falling back
+//! to filename completion.
+
+use crate::cmd::PROMPT_COMMANDS;
+use crate::cmd::bufcmd::BUFFER_OPTIONS;
+use rustyline::Context;
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Completes `$NAME`/`${NAME}` from the process environment, colon commands such as `:b`,
+/// directories after `cd` and paths otherwise.
+pub struct IridiumCompleter {
+    filenames: FilenameCompleter,
+}
+
+impl IridiumCompleter {
+    pub fn new() -> Self {
+        Self {
+            filenames: FilenameCompleter::new(),
+        }
+    }
+}
+
+impl Default for IridiumCompleter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Completer for IridiumCompleter {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        if let Some(completion) = complete_variable(line, pos) {
+            return Ok(completion);
+        }
+        if let Some(completion) = complete_prompt_command(line, pos) {
+            return Ok(completion);
+        }
+        match complete_directory(line, pos) {
+            Some(completion) => Ok(completion),
+            None => self.filenames.complete(line, pos, ctx),
+        }
+    }
+}
+
+/// Complete the variable name being typed at `pos`, if the token starts with `$` or `${`.
+///
+/// Returns the byte offset where the name starts and the matching candidates. Names
+/// opened with `${` are completed with the closing brace.
+pub fn complete_variable(line: &str, pos: usize) -> Option<(usize, Vec<Pair>)> {
+    let before = &line[..pos];
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| is_name_char(*ch))
+        .last()
+        .map_or(pos, |(idx, _)| idx);
+    let head = &before[..start];
+    let braced = if head.ends_with("${") {
+        true
+    } else if head.ends_with('$') {
+        false
+    } else {
+        return None;
+    };
+
+    let names = env::vars_os().filter_map(|(key, _)| key.into_string().ok());
+    Some((start, variable_candidates(&before[start..], braced, names)))
+}
+
+/// Filter `names` down to those starting with `prefix`, sorted and deduplicated.
+pub fn variable_candidates(
+    prefix: &str,
+    braced: bool,
+    names: impl IntoIterator<Item = String>,
+) -> Vec<Pair> {
+    let mut matches: Vec<String> = names
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+        .into_iter()
+        .map(|name| Pair {
+            replacement: if braced {
+                format!("{name}}}")
+            } else {
+                name.clone()
+            },
+            display: name,
+        })
+        .collect()
+}
+
+/// Complete a prompt colon command such as `:b`, or a short option after `:b`.
+///
+/// Returns `None` unless the line starts with `:` and the cursor is on the command or on a
+/// `:b` option, so buffer names still fall through to filename completion.
+pub fn complete_prompt_command(line: &str, pos: usize) -> Option<(usize, Vec<Pair>)> {
+    let before = &line[..pos];
+    if !before.starts_with(':') {
+        return None;
+    }
+    let start = before.rfind(char::is_whitespace).map_or(0, |idx| {
+        idx + before[idx..].chars().next().map_or(1, char::len_utf8)
+    });
+    let word = &before[start..];
+
+    if start == 0 {
+        let candidates = PROMPT_COMMANDS
+            .iter()
+            .filter(|command| command.starts_with(word))
+            .map(|command| Pair {
+                display: command.to_string(),
+                replacement: command.to_string(),
+            })
+            .collect();
+        return Some((0, candidates));
+    }
+
+    let flags = word.strip_prefix('-')?;
+    if before.split_whitespace().next() != Some(":b") {
+        return None;
+    }
+    let candidates = BUFFER_OPTIONS
+        .iter()
+        .filter(|option| !flags.contains(**option))
+        .map(|option| Pair {
+            display: format!("-{option}"),
+            replacement: format!("{word}{option}"),
+        })
+        .collect();
+    Some((start, candidates))
+}
+
+/// Complete the operand of `cd` at `pos` with directories only, each followed by `/`.
+///
+/// Returns `None` unless the line's first word is `cd` and the cursor is past it. Entries
+/// starting with `.` are offered only when the typed name does too. The operand may contain
+/// backslash-escaped spaces, and replacements escape them the same way.
+pub fn complete_directory(line: &str, pos: usize) -> Option<(usize, Vec<Pair>)> {
+    let before = &line[..pos];
+    let mut words = before.split_whitespace();
+    if words.next() != Some("cd") {
+        return None;
+    }
+    let start = operand_start(before)?;
+    let word = unescape(&before[start..]);
+    let (dir, prefix) = match word.rfind('/') {
+        Some(idx) => (&word[..=idx], &word[idx + 1..]),
+        None => ("", word.as_str()),
+    };
+    Some((start, directory_candidates(dir, prefix)))
+}
+
+/// Byte offset just past the last whitespace in `before` that no backslash escapes.
+fn operand_start(before: &str) -> Option<usize> {
+    let mut start = None;
+    let mut escaped = false;
+    for (idx, ch) in before.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch.is_whitespace() {
+            start = Some(idx + ch.len_utf8());
+        }
+    }
+    start
+}
+
+/// Drop the backslashes that escape characters in a typed operand.
+fn unescape(word: &str) -> String {
+    let mut unescaped = String::with_capacity(word.len());
+    let mut chars = word.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(ch),
+        }
+    }
+    unescaped
+}
+
+/// Backslash-escape the characters the shell would otherwise split or interpret.
+fn escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for ch in path.chars() {
+        if ch.is_whitespace() || "\\'\"|&;<>()$`".contains(ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Subdirectories of `dir` (relative to the current directory when empty) starting with
+/// `prefix`, sorted, as escaped replacements for the whole `dir` + name word.
+pub fn directory_candidates(dir: &str, prefix: &str) -> Vec<Pair> {
+    let search = match dir {
+        "" => Path::new(".").to_path_buf(),
+        _ => match (dir.strip_prefix('~'), env::var_os("HOME")) {
+            (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+                Path::new(&home).join(rest.trim_start_matches('/'))
+            }
+            _ => Path::new(dir).to_path_buf(),
+        },
+    };
+    let Ok(entries) = fs::read_dir(&search) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+        })
+        .filter(|name| fs::metadata(search.join(name)).is_ok_and(|meta| meta.is_dir()))
+        .collect();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| Pair {
+            replacement: escape(&format!("{dir}{name}/")),
+            display: format!("{name}/"),
+        })
+        .collect()
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
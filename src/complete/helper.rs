@@ -1,16 +1,20 @@
+use crate::complete::completer::IridiumCompleter;
+use crate::complete::hinter::IridiumHinter;
 use rustyline::highlight::Highlighter;
-use rustyline::hint::HistoryHinter;
 use rustyline_derive::{Completer, Helper, Hinter, Validator};
 use std::borrow::Cow::{self, Borrowed, Owned};
 
 /// Aggregates the rustyline helper traits used by Iridium.
 #[derive(Completer, Helper, Hinter, Validator)]
-pub struct IridiumHelper(#[rustyline(Hinter)] HistoryHinter);
+pub struct IridiumHelper(
+    #[rustyline(Completer)] IridiumCompleter,
+    #[rustyline(Hinter)] IridiumHinter,
+);
 
 impl IridiumHelper {
     /// Build a helper with the provided hinter implementation.
-    pub fn new(hinter: HistoryHinter) -> Self {
-        Self { 0: hinter }
+    pub fn new(hinter: IridiumHinter) -> Self {
+        Self(IridiumCompleter::new(), hinter)
     }
 }
 
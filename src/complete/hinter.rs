@@ -1,4 +1,72 @@
-use rustyline::{Cmd, ConditionalEventHandler, Event, EventContext, KeyEvent, RepeatCount};
+use crate::complete::completer::complete_directory;
+use rustyline::hint::{Hinter, HistoryHinter};
+use rustyline::{
+    Cmd, ConditionalEventHandler, Context, Event, EventContext, KeyEvent, RepeatCount,
+};
+
+/// Inline hints drawn from history and, for `cd` operands, from the filesystem.
+///
+/// When both sources have a hint the history one wins unless `paths_first` is set.
+pub struct IridiumHinter {
+    history: HistoryHinter,
+    paths_first: bool,
+}
+
+impl IridiumHinter {
+    /// Construct a hinter that prefers history hints.
+    pub fn new() -> Self {
+        Self {
+            history: HistoryHinter::new(),
+            paths_first: false,
+        }
+    }
+
+    /// Offer directory hints ahead of history hints when `paths_first` is set.
+    pub fn with_paths_first(mut self, paths_first: bool) -> Self {
+        self.paths_first = paths_first;
+        self
+    }
+}
+
+impl Default for IridiumHinter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hinter for IridiumHinter {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, ctx: &Context<'_>) -> Option<String> {
+        if self.paths_first {
+            path_hint(line, pos).or_else(|| self.history.hint(line, pos, ctx))
+        } else {
+            self.history
+                .hint(line, pos, ctx)
+                .or_else(|| path_hint(line, pos))
+        }
+    }
+}
+
+/// The rest of the `cd` operand ending at `pos` when exactly one directory completes it.
+///
+/// Only hints with the cursor at the end of the line and a partial operand typed, so an empty
+/// `cd ` does not suggest whatever single directory happens to be nearby.
+pub fn path_hint(line: &str, pos: usize) -> Option<String> {
+    if pos != line.len() {
+        return None;
+    }
+    let (start, candidates) = complete_directory(line, pos)?;
+    let word = &line[start..pos];
+    match candidates.as_slice() {
+        [only] if !word.is_empty() => only
+            .replacement
+            .strip_prefix(word)
+            .filter(|rest| !rest.is_empty())
+            .map(str::to_string),
+        _ => None,
+    }
+}
 
 /// Conditional handler that accepts or inserts completion hints.
 #[derive(Clone)]
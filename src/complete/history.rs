@@ -1,6 +1,7 @@
 use std::collections::VecDeque;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader};
+use std::path::Path;
 
 use crate::process::history::history_file_path;
 
@@ -9,7 +10,14 @@ const DEFAULT_HISTORY_LIMIT: usize = 1024;
 
 /// Load shell history lines from disk up to the requested limit.
 pub fn load_history_entries(limit: Option<usize>) -> io::Result<Vec<String>> {
-    let path = history_file_path();
+    load_history_entries_from(&history_file_path(), limit)
+}
+
+/// Load up to `limit` commands from the history file at `path`, oldest first.
+pub(crate) fn load_history_entries_from(
+    path: &Path,
+    limit: Option<usize>,
+) -> io::Result<Vec<String>> {
     let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
 
     let file = match File::open(path) {
@@ -35,7 +43,7 @@ pub fn load_history_entries(limit: Option<usize>) -> io::Result<Vec<String>> {
 }
 
 /// Parse a persisted history line and extract the raw command if present.
-fn parse_history_command(line: &str) -> Option<String> {
+pub(crate) fn parse_history_command(line: &str) -> Option<String> {
     let mut parts = line.splitn(3, ':');
     let timestamp = parts.next()?;
     if timestamp.is_empty() {
@@ -1,32 +1,42 @@
 use super::model::ConfigurationModel;
-use super::paths::resolve_config_path;
+use super::paths::{ConfigSource, resolve_config_source};
 use std::fs;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
 
-/// Load the user's configuration file, falling back to defaults when absent or invalid.
-pub fn load() -> ConfigurationModel {
-    let path = resolve_config_path();
-    if let Some(path) = path {
-        match fs::read_to_string(&path) {
-            Ok(contents) => match serde_yaml::from_str::<ConfigurationModel>(&contents) {
-                Ok(mut cfg) => {
-                    cfg.set_source_path(path);
-                    return cfg;
-                }
-                Err(err) => {
-                    eprintln!(
-                        "Warning: unable to parse config file '{}': {err}",
-                        path.display()
-                    );
-                }
-            },
-            Err(err) => {
-                eprintln!(
-                    "Warning: unable to read config file '{}': {err}",
-                    path.display()
-                );
-            }
-        }
-    }
+/// Failure to use a configuration file that discovery selected.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("unable to read config file '{}': {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    #[error("unable to parse config file '{}': {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+}
 
-    ConfigurationModel::default()
+/// Load the user's configuration, reporting which source supplied it.
+///
+/// Falls back to defaults only when no file was found; a file that exists but cannot be
+/// read or parsed is an error so mistakes are not silently ignored.
+pub fn load() -> Result<(ConfigurationModel, ConfigSource), ConfigError> {
+    let source = resolve_config_source();
+    let Some(path) = source.path() else {
+        return Ok((ConfigurationModel::default(), source));
+    };
+
+    let contents = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut cfg = serde_yaml::from_str::<ConfigurationModel>(&contents).map_err(|source| {
+        ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        }
+    })?;
+    cfg.set_source_path(path.to_path_buf());
+    Ok((cfg, source))
 }
@@ -1,8 +1,14 @@
 mod loader;
 mod model;
 mod paths;
+mod report;
 pub mod section;
 
-pub use loader::load;
+#[allow(unused_imports)]
+pub use loader::{ConfigError, load};
 pub use model::ConfigurationModel;
+#[allow(unused_imports)]
+pub use paths::{ConfigSource, expand_path};
+#[allow(unused_imports)]
+pub use report::{effective_config, render_effective_config};
 pub use section::PersistenceConfigSection;
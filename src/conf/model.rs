@@ -1,5 +1,7 @@
 use super::paths::expand_path;
-use super::section::{ControlConfigSection, PersistenceConfigSection, UiConfigSection};
+use super::section::{
+    ControlConfigSection, EditorConfigSection, PersistenceConfigSection, UiConfigSection,
+};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
@@ -8,11 +10,12 @@ pub struct ConfigurationModel {
     #[serde(default)]
     pub persistence: PersistenceConfigSection,
     #[serde(default)]
-    #[allow(dead_code)]
     pub control: ControlConfigSection,
     #[serde(default)]
     #[allow(dead_code)]
     pub ui: UiConfigSection,
+    #[serde(default)]
+    pub editor: EditorConfigSection,
     #[serde(skip)]
     source_path: Option<PathBuf>,
 }
@@ -1,18 +1,81 @@
 use std::env;
-use std::path::PathBuf;
+use std::fmt;
+use std::path::{Path, PathBuf};
 
 pub const CONFIG_PATH_ENV: &str = "IRIDIUM_CONFIG";
 
-pub fn resolve_config_path() -> Option<PathBuf> {
-    if let Ok(env_path) = env::var(CONFIG_PATH_ENV) {
-        if !env_path.trim().is_empty() {
-            return Some(expand_path(&env_path));
+/// Where the active configuration came from, in discovery order.
+///
+/// 1. `IRIDIUM_CONFIG` (used even when the file is missing, so typos surface as errors)
+/// 2. `$XDG_CONFIG_HOME/iridium/config.yaml`
+/// 3. `$HOME/.config/iridium/config.yaml`
+/// 4. `$HOME/.iridiumrc` (legacy location)
+/// 5. Built-in defaults
+///
+/// Every file location uses the same YAML schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    Env(PathBuf),
+    XdgConfigHome(PathBuf),
+    HomeConfig(PathBuf),
+    Legacy(PathBuf),
+    Default,
+}
+
+impl ConfigSource {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ConfigSource::Env(path)
+            | ConfigSource::XdgConfigHome(path)
+            | ConfigSource::HomeConfig(path)
+            | ConfigSource::Legacy(path) => Some(path),
+            ConfigSource::Default => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Env(path) => write!(f, "{} (from {CONFIG_PATH_ENV})", path.display()),
+            ConfigSource::XdgConfigHome(path) => {
+                write!(f, "{} (from XDG_CONFIG_HOME)", path.display())
+            }
+            ConfigSource::HomeConfig(path) | ConfigSource::Legacy(path) => {
+                write!(f, "{}", path.display())
+            }
+            ConfigSource::Default => write!(f, "built-in defaults"),
+        }
+    }
+}
+
+/// Walk the discovery order and return the first configuration source that applies.
+pub fn resolve_config_source() -> ConfigSource {
+    if let Ok(env_path) = env::var(CONFIG_PATH_ENV)
+        && !env_path.trim().is_empty()
+    {
+        return ConfigSource::Env(expand_path(&env_path));
+    }
+
+    if let Some(xdg) = env::var_os("XDG_CONFIG_HOME").filter(|value| !value.is_empty()) {
+        let path = PathBuf::from(xdg).join("iridium").join("config.yaml");
+        if path.is_file() {
+            return ConfigSource::XdgConfigHome(path);
+        }
+    }
+
+    if let Some(home) = home_dir() {
+        let path = home.join(".config").join("iridium").join("config.yaml");
+        if path.is_file() {
+            return ConfigSource::HomeConfig(path);
+        }
+        let legacy = home.join(".iridiumrc");
+        if legacy.is_file() {
+            return ConfigSource::Legacy(legacy);
         }
     }
 
-    home_dir()
-        .map(|home| home.join(".iridiumrc"))
-        .filter(|path| path.exists())
+    ConfigSource::Default
 }
 
 pub fn expand_path(input: &str) -> PathBuf {
@@ -0,0 +1,109 @@
+//! Human-readable dump of the effective configuration for `--print-config`.
+
+use super::loader::{ConfigError, load};
+use super::model::ConfigurationModel;
+use super::paths::ConfigSource;
+use crate::store::persistence::{EncryptionKeySource, EncryptionMode, PersistenceConfig};
+use std::fmt::Write;
+
+const REDACTED: &str = "<redacted>";
+
+/// Load the configuration the shell would use and render it as a TOML-style listing.
+pub fn effective_config() -> Result<String, ConfigError> {
+    let (config, source) = load()?;
+    let persistence = PersistenceConfig::from_sources(Some(&config));
+    Ok(render_effective_config(&config, &source, &persistence))
+}
+
+/// Render the merged configuration; passphrases and keys are never written out.
+pub fn render_effective_config(
+    config: &ConfigurationModel,
+    source: &ConfigSource,
+    persistence: &PersistenceConfig,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# source: {source}");
+
+    let _ = writeln!(out, "\n[persistence]");
+    let _ = writeln!(out, "enabled = {}", persistence.is_enabled());
+    if let Some(path) = persistence.path() {
+        let _ = writeln!(out, "database_path = {:?}", path.display().to_string());
+    }
+    let _ = writeln!(out, "compression = {:?}", persistence.compression().name());
+    let autosave_ms = config
+        .persistence
+        .autosave()
+        .map_or(0, |interval| interval.as_millis());
+    let _ = writeln!(out, "autosave_ms = {autosave_ms}");
+    match persistence.encryption() {
+        EncryptionMode::Disabled => {
+            let _ = writeln!(out, "encryption = \"disabled\"");
+        }
+        EncryptionMode::Enabled(settings) => {
+            let _ = writeln!(out, "encryption = {:?}", settings.algorithm.name());
+            match &settings.key_source {
+                EncryptionKeySource::RawKey(_) => {
+                    let _ = writeln!(out, "key = {REDACTED:?}");
+                }
+                EncryptionKeySource::Passphrase { iterations, .. } => {
+                    let _ = writeln!(out, "passphrase = {REDACTED:?}");
+                    let _ = writeln!(out, "pbkdf2_iterations = {iterations}");
+                }
+                EncryptionKeySource::Recipient { pubkey } => {
+                    let _ = writeln!(out, "recipient = {:?}", hex::encode(pubkey));
+                }
+                EncryptionKeySource::Identity { .. } => {
+                    let _ = writeln!(out, "identity = {REDACTED:?}");
+                }
+            }
+        }
+    }
+
+    let _ = writeln!(out, "\n[control]");
+    let _ = writeln!(out, "noclobber = {}", config.control.noclobber());
+    let _ = writeln!(
+        out,
+        "hint_paths_first = {}",
+        config.control.hint_paths_first()
+    );
+    let _ = writeln!(out, "prompt = {:?}", config.control.prompt());
+
+    let _ = writeln!(out, "\n[editor]");
+    let _ = writeln!(out, "mkdir_on_save = {}", config.editor.mkdir_on_save());
+    let _ = writeln!(out, "fsync_on_save = {}", config.editor.fsync_on_save());
+    let idle_ms = config
+        .editor
+        .idle_suspend()
+        .map_or(0, |timeout| timeout.as_millis());
+    let _ = writeln!(out, "idle_suspend_ms = {idle_ms}");
+    let _ = writeln!(out, "quit_key = \"{}\"", config.editor.quit_key());
+    let _ = writeln!(out, "custom_cursor = {}", config.editor.custom_cursor());
+    let _ = writeln!(out, "start_mode = \"{}\"", config.editor.start_mode());
+    let _ = writeln!(
+        out,
+        "new_buffer_insert = {}",
+        config.editor.new_buffer_insert()
+    );
+    let _ = writeln!(out, "scroll = {}", config.editor.scroll());
+    let _ = writeln!(out, "report = {}", config.editor.report());
+    let _ = writeln!(
+        out,
+        "max_open_buffers = {}",
+        config.editor.max_open_buffers().unwrap_or(0)
+    );
+    let _ = writeln!(out, "undolevels = {}", config.editor.undolevels());
+    let _ = writeln!(out, "modeline = {}", config.editor.modeline());
+    let _ = writeln!(out, "backup = {}", config.editor.backup());
+    let _ = writeln!(out, "autowriteall = {}", config.editor.autowriteall());
+    let _ = writeln!(
+        out,
+        "quit_last_opens_new = {}",
+        config.editor.quit_last_opens_new()
+    );
+    let _ = writeln!(out, "showmode = {}", config.editor.showmode());
+    if let Some(dir) = config.editor.resolved_backupdir(config) {
+        let _ = writeln!(out, "backupdir = {:?}", dir.display().to_string());
+    }
+
+    out
+}
@@ -5,4 +5,27 @@ use serde::Deserialize;
 pub struct ControlConfigSection {
     pub auto_save_interval_ms: Option<u64>,
     pub default_buffer_mode: Option<String>,
+    pub noclobber: Option<bool>,
+    pub hint_paths_first: Option<bool>,
+    pub prompt: Option<String>,
+}
+
+impl ControlConfigSection {
+    /// Whether `>` starts out refusing to overwrite existing files (defaults to `false`).
+    pub fn noclobber(&self) -> bool {
+        self.noclobber.unwrap_or(false)
+    }
+
+    /// Whether `cd` directory hints take precedence over history hints (defaults to `false`).
+    pub fn hint_paths_first(&self) -> bool {
+        self.hint_paths_first.unwrap_or(false)
+    }
+
+    /// Prompt text shown before the status arrow (defaults to `{cwd}`).
+    ///
+    /// `{cwd}` expands to the current directory and `{jobs}` to the number of running
+    /// background jobs.
+    pub fn prompt(&self) -> &str {
+        self.prompt.as_deref().unwrap_or("{cwd}")
+    }
 }
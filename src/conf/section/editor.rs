@@ -0,0 +1,305 @@
+use super::super::model::ConfigurationModel;
+use crate::editor::settings::DEFAULT_REPORT;
+use crate::store::undo::DEFAULT_UNDO_LEVELS;
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct EditorConfigSection {
+    pub mkdir_on_save: Option<bool>,
+    pub idle_suspend_ms: Option<u64>,
+    pub quit_key: Option<String>,
+    pub custom_cursor: Option<bool>,
+    pub fsync_on_save: Option<bool>,
+    pub start_mode: Option<String>,
+    pub new_buffer_insert: Option<bool>,
+    pub scroll: Option<usize>,
+    pub report: Option<usize>,
+    pub max_open_buffers: Option<usize>,
+    pub undolevels: Option<usize>,
+    pub modeline: Option<bool>,
+    pub backup: Option<bool>,
+    pub backupdir: Option<String>,
+    pub autowriteall: Option<bool>,
+    pub quit_last_opens_new: Option<bool>,
+    pub showmode: Option<bool>,
+}
+
+impl EditorConfigSection {
+    /// Whether `:w` may create missing parent directories (defaults to `true`).
+    pub fn mkdir_on_save(&self) -> bool {
+        self.mkdir_on_save.unwrap_or(true)
+    }
+
+    /// Whether `:w` flushes the file to stable storage before replacing it (defaults to `true`).
+    pub fn fsync_on_save(&self) -> bool {
+        self.fsync_on_save.unwrap_or(true)
+    }
+
+    /// Mode the editor opens buffers in, `read` or `insert` (defaults to `read`).
+    pub fn start_mode(&self) -> &str {
+        self.start_mode.as_deref().unwrap_or("read")
+    }
+
+    /// Whether untitled and empty new buffers open in insert mode regardless of `start_mode`.
+    pub fn new_buffer_insert(&self) -> bool {
+        self.new_buffer_insert.unwrap_or(false)
+    }
+
+    /// Lines moved by half-page navigation; `0` scrolls half the view (the default).
+    pub fn scroll(&self) -> usize {
+        self.scroll.unwrap_or(0)
+    }
+
+    /// Lines a bulk edit must exceed before its summary is shown (defaults to 2).
+    pub fn report(&self) -> usize {
+        self.report.unwrap_or(DEFAULT_REPORT)
+    }
+
+    /// Most buffers `:b` may have open at once; `None` (or `0`) means no limit.
+    pub fn max_open_buffers(&self) -> Option<usize> {
+        self.max_open_buffers.filter(|limit| *limit > 0)
+    }
+
+    /// Undo changes kept per buffer; `0` disables undo (defaults to 1000).
+    pub fn undolevels(&self) -> usize {
+        self.undolevels.unwrap_or(DEFAULT_UNDO_LEVELS)
+    }
+
+    /// Whether opened buffers may set editor options through `iridium:` modelines (defaults to
+    /// `false`, since a file could otherwise change settings it was not trusted with).
+    pub fn modeline(&self) -> bool {
+        self.modeline.unwrap_or(false)
+    }
+
+    /// Whether saves keep a backup of the file they replace (defaults to `false`).
+    pub fn backup(&self) -> bool {
+        self.backup.unwrap_or(false)
+    }
+
+    /// Whether leaving a buffer for another writes it first (defaults to `false`).
+    pub fn autowriteall(&self) -> bool {
+        self.autowriteall.unwrap_or(false)
+    }
+
+    /// Whether `:q` on the last open buffer opens a new untitled buffer instead of leaving the
+    /// editor (defaults to `false`).
+    pub fn quit_last_opens_new(&self) -> bool {
+        self.quit_last_opens_new.unwrap_or(false)
+    }
+
+    /// Whether the status line shows the `[MODE]` label (defaults to `true`).
+    pub fn showmode(&self) -> bool {
+        self.showmode.unwrap_or(true)
+    }
+
+    /// Directory backups are collected in; `None` keeps `file~` beside each file.
+    pub fn resolved_backupdir(&self, config: &ConfigurationModel) -> Option<PathBuf> {
+        self.backupdir
+            .as_deref()
+            .filter(|raw| !raw.is_empty())
+            .map(|raw| config.resolve_path(raw))
+    }
+
+    /// Key combination that closes the editor immediately (defaults to `ctrl+c`).
+    pub fn quit_key(&self) -> &str {
+        self.quit_key.as_deref().unwrap_or("ctrl+c")
+    }
+
+    /// Whether the editor draws its blinking `‸` cursor glyph (defaults to `true`).
+    pub fn custom_cursor(&self) -> bool {
+        self.custom_cursor.unwrap_or(true)
+    }
+
+    /// How long a buffer may sit untouched before it is suspended to disk; `None` when off.
+    pub fn idle_suspend(&self) -> Option<Duration> {
+        self.idle_suspend_ms
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conf::ConfigurationModel;
+
+    #[test]
+    fn mkdir_on_save_defaults_to_enabled() {
+        let config = ConfigurationModel::default();
+        assert!(config.editor.mkdir_on_save());
+    }
+
+    #[test]
+    fn mkdir_on_save_can_be_disabled() {
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  mkdir_on_save: false\n").unwrap();
+        assert!(!config.editor.mkdir_on_save());
+    }
+
+    #[test]
+    fn fsync_on_save_defaults_to_enabled() {
+        let config = ConfigurationModel::default();
+        assert!(config.editor.fsync_on_save());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  fsync_on_save: false\n").unwrap();
+        assert!(!config.editor.fsync_on_save());
+    }
+
+    #[test]
+    fn start_mode_defaults_to_read() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.start_mode(), "read");
+        assert!(!config.editor.new_buffer_insert());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  start_mode: insert\n  new_buffer_insert: true\n")
+                .unwrap();
+        assert_eq!(config.editor.start_mode(), "insert");
+        assert!(config.editor.new_buffer_insert());
+    }
+
+    #[test]
+    fn scroll_defaults_to_half_page() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.scroll(), 0);
+
+        let config: ConfigurationModel = serde_yaml::from_str("editor:\n  scroll: 12\n").unwrap();
+        assert_eq!(config.editor.scroll(), 12);
+    }
+
+    #[test]
+    fn report_defaults_to_two_lines() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.report(), 2);
+
+        let config: ConfigurationModel = serde_yaml::from_str("editor:\n  report: 0\n").unwrap();
+        assert_eq!(config.editor.report(), 0);
+    }
+
+    #[test]
+    fn max_open_buffers_is_unlimited_unless_positive() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.max_open_buffers(), None);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  max_open_buffers: 0\n").unwrap();
+        assert_eq!(config.editor.max_open_buffers(), None);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  max_open_buffers: 8\n").unwrap();
+        assert_eq!(config.editor.max_open_buffers(), Some(8));
+    }
+
+    #[test]
+    fn undolevels_defaults_to_one_thousand() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.undolevels(), 1000);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  undolevels: 0\n").unwrap();
+        assert_eq!(config.editor.undolevels(), 0);
+    }
+
+    #[test]
+    fn modeline_defaults_to_off() {
+        let config = ConfigurationModel::default();
+        assert!(!config.editor.modeline());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  modeline: true\n").unwrap();
+        assert!(config.editor.modeline());
+    }
+
+    #[test]
+    fn autowriteall_defaults_to_off() {
+        let config = ConfigurationModel::default();
+        assert!(!config.editor.autowriteall());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  autowriteall: true\n").unwrap();
+        assert!(config.editor.autowriteall());
+    }
+
+    #[test]
+    fn quit_last_opens_new_defaults_to_off() {
+        let config = ConfigurationModel::default();
+        assert!(!config.editor.quit_last_opens_new());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  quit_last_opens_new: true\n").unwrap();
+        assert!(config.editor.quit_last_opens_new());
+    }
+
+    #[test]
+    fn showmode_defaults_to_on() {
+        let config = ConfigurationModel::default();
+        assert!(config.editor.showmode());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  showmode: false\n").unwrap();
+        assert!(!config.editor.showmode());
+    }
+
+    #[test]
+    fn backups_default_to_off_and_beside_the_file() {
+        let config = ConfigurationModel::default();
+        assert!(!config.editor.backup());
+        assert_eq!(config.editor.resolved_backupdir(&config), None);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  backup: true\n  backupdir: /var/backups/iridium\n")
+                .unwrap();
+        assert!(config.editor.backup());
+        assert_eq!(
+            config.editor.resolved_backupdir(&config),
+            Some(std::path::PathBuf::from("/var/backups/iridium"))
+        );
+    }
+
+    #[test]
+    fn quit_key_defaults_to_ctrl_c() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.quit_key(), "ctrl+c");
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  quit_key: ctrl+q\n").unwrap();
+        assert_eq!(config.editor.quit_key(), "ctrl+q");
+    }
+
+    #[test]
+    fn custom_cursor_defaults_to_enabled() {
+        let config = ConfigurationModel::default();
+        assert!(config.editor.custom_cursor());
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("editor:\n  custom_cursor: false\n").unwrap();
+        assert!(!config.editor.custom_cursor());
+    }
+
+    #[test]
+    fn idle_suspend_is_off_unless_positive() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.editor.idle_suspend(), None);
+
+        let config: ConfigurationModel = serde_yaml::from_str(
+            "editor:
+  idle_suspend_ms: 0
+",
+        )
+        .unwrap();
+        assert_eq!(config.editor.idle_suspend(), None);
+
+        let config: ConfigurationModel = serde_yaml::from_str(
+            "editor:
+  idle_suspend_ms: 1500
+",
+        )
+        .unwrap();
+        assert_eq!(
+            config.editor.idle_suspend(),
+            Some(std::time::Duration::from_millis(1500))
+        );
+    }
+}
@@ -1,7 +1,9 @@
 mod control;
+mod editor;
 mod persistence;
 mod ui;
 
 pub use control::ControlConfigSection;
+pub use editor::EditorConfigSection;
 pub use persistence::PersistenceConfigSection;
 pub use ui::UiConfigSection;
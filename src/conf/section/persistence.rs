@@ -1,6 +1,7 @@
 use super::super::model::ConfigurationModel;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct PersistenceConfigSection {
@@ -10,7 +11,13 @@ pub struct PersistenceConfigSection {
     pub key_file: Option<String>,
     pub passphrase: Option<String>,
     pub pbkdf2_iterations: Option<u32>,
+    /// Hex X25519 public key the database is encrypted to.
+    pub recipient: Option<String>,
+    /// File holding the hex X25519 private key that decrypts the database.
+    pub identity_file: Option<String>,
     pub compression: Option<String>,
+    /// Milliseconds between background writes of every buffer to the database; `0` disables.
+    pub autosave_ms: Option<u64>,
 }
 
 impl PersistenceConfigSection {
@@ -23,4 +30,37 @@ impl PersistenceConfigSection {
     pub fn resolved_key_path(&self, config: &ConfigurationModel) -> Option<PathBuf> {
         self.key_file.as_ref().map(|raw| config.resolve_path(raw))
     }
+
+    pub fn resolved_identity_path(&self, config: &ConfigurationModel) -> Option<PathBuf> {
+        self.identity_file
+            .as_ref()
+            .map(|raw| config.resolve_path(raw))
+    }
+
+    /// Interval between whole-store writes to the database, or `None` when disabled.
+    pub fn autosave(&self) -> Option<Duration> {
+        self.autosave_ms
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conf::ConfigurationModel;
+    use std::time::Duration;
+
+    #[test]
+    fn autosave_is_off_unless_positive() {
+        let config = ConfigurationModel::default();
+        assert_eq!(config.persistence.autosave(), None);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("persistence:\n  autosave_ms: 0\n").unwrap();
+        assert_eq!(config.persistence.autosave(), None);
+
+        let config: ConfigurationModel =
+            serde_yaml::from_str("persistence:\n  autosave_ms: 30000\n").unwrap();
+        assert_eq!(config.persistence.autosave(), Some(Duration::from_secs(30)));
+    }
 }
@@ -2,14 +2,15 @@
 
 use crate::complete::handler::TabEventHandler;
 use crate::complete::helper::IridiumHelper;
-use crate::complete::hinter::CompleteHintHandler;
+use crate::complete::hinter::{CompleteHintHandler, IridiumHinter};
 use crate::complete::history::load_history_entries;
 use crate::control_state::ControlFlow;
 use crate::control_state::ControlState;
 use rustyline::error::ReadlineError;
 use rustyline::history::DefaultHistory;
-use rustyline::{Cmd, Editor, Event, EventHandler, KeyEvent, Result, hint::HistoryHinter};
+use rustyline::{Cmd, Editor, Event, EventHandler, KeyEvent, Result};
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 /// Run the interactive shell loop, handling input, history, and control flow.
 #[doc(hidden)]
@@ -51,13 +52,86 @@ impl LineEditor for Editor<IridiumHelper, DefaultHistory> {
     }
 }
 
-pub fn control_loop() -> Result<()> {
+/// Profile every login session sources first.
+pub const SYSTEM_PROFILE: &str = "/etc/iridium/profile";
+
+/// Per-user profile under `HOME`, sourced after [`SYSTEM_PROFILE`].
+pub const USER_PROFILE: &str = ".iridium_profile";
+
+/// Command-line switches that shape the interactive session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// Open every buffer read-only, refusing insert mode and `:w` (`--readonly`).
+    pub readonly: bool,
+    /// Source the login profiles before the first prompt (`--login`, `-l`).
+    pub login: bool,
+}
+
+impl LaunchOptions {
+    /// Collect the recognised switches from `args`, ignoring anything else.
+    pub fn from_args<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut options = Self::default();
+        for arg in args {
+            match arg.as_ref() {
+                "--readonly" => options.readonly = true,
+                "--login" | "-l" => options.login = true,
+                _ => {}
+            }
+        }
+        options
+    }
+
+    /// Apply the options to a freshly built control state.
+    ///
+    /// A login session then sources [`SYSTEM_PROFILE`] and the user's [`USER_PROFILE`];
+    /// profiles that do not exist are skipped. Returns [`ControlFlow::EXIT`] when a profile
+    /// exits, in which case the session should end before its first prompt.
+    pub fn apply(&self, control_state: &mut ControlState) -> ControlFlow {
+        if self.readonly {
+            control_state.set_read_only(true);
+        }
+        if self.login {
+            let home = control_state.environment().borrow().var("HOME");
+            for profile in profile_paths(home.as_deref()) {
+                if !profile.is_file() {
+                    continue;
+                }
+                match control_state.source_file(&profile) {
+                    Ok(ControlFlow::EXIT) => return ControlFlow::EXIT,
+                    Ok(ControlFlow::CONTINUE) => {}
+                    Err(err) => eprintln!("iridium: {}: {err}", profile.display()),
+                }
+            }
+        }
+        ControlFlow::CONTINUE
+    }
+}
+
+/// Login profiles in the order they are sourced.
+pub fn profile_paths(home: Option<&str>) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(SYSTEM_PROFILE)];
+    if let Some(home) = home.filter(|home| !home.is_empty()) {
+        paths.push(Path::new(home).join(USER_PROFILE));
+    }
+    paths
+}
+
+pub fn control_loop(options: LaunchOptions) -> Result<()> {
     let mut stdout = io::stdout();
     let mut control_state = ControlState::new();
+    if options.apply(&mut control_state) == ControlFlow::EXIT {
+        ControlSession::flush_persistence(&mut control_state);
+        return Ok(());
+    }
     let mut rl = Editor::<IridiumHelper, DefaultHistory>::new()?;
 
     // Set the custom helper callback
-    rl.set_helper(Some(IridiumHelper::new(HistoryHinter::new())));
+    let hinter = IridiumHinter::new().with_paths_first(control_state.hint_paths_first());
+    rl.set_helper(Some(IridiumHelper::new(hinter)));
 
     // Loads iridium history file into context
     load_history(&mut rl);
@@ -259,6 +333,30 @@ mod tests {
         previous
     }
 
+    #[test]
+    fn launch_options_recognise_readonly() {
+        assert_eq!(
+            LaunchOptions::from_args(["--print-config"]),
+            LaunchOptions::default()
+        );
+        assert!(LaunchOptions::from_args(["--readonly"]).readonly);
+        assert!(LaunchOptions::from_args(["-l"]).login);
+        assert!(LaunchOptions::from_args(["--login"]).login);
+        assert!(!LaunchOptions::from_args(["--readonly"]).login);
+    }
+
+    #[test]
+    fn profiles_run_system_first_then_home() {
+        assert_eq!(profile_paths(None), vec![PathBuf::from(SYSTEM_PROFILE)]);
+        assert_eq!(
+            profile_paths(Some("/home/user")),
+            vec![
+                PathBuf::from(SYSTEM_PROFILE),
+                PathBuf::from("/home/user/.iridium_profile")
+            ]
+        );
+    }
+
     #[test]
     fn bind_handlers_sets_key_sequences() {
         let mut editor = Editor::<IridiumHelper, DefaultHistory>::new().unwrap();
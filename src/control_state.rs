@@ -1,18 +1,24 @@
 //! State machine backing the interactive control loop.
 
 use crate::cmd::bufcmd;
-use shlex;
 use std::env;
+use std::fs;
+use std::io;
 use std::mem;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+use crate::complete::history::load_history_entries_from;
 use crate::conf::{self, ConfigurationModel};
 use crate::editor::buffer_editor::BufferEditor;
+use crate::editor::config::EditorConfig;
 use crate::editor::terminal::Terminal;
 use crate::process;
 use crate::process::builtin::map::BuiltinMap;
+use crate::process::environment::{EnvironmentHandle, ShellEnvironment};
+use crate::store::buffer_snapshot::BufferSnapshot;
 use crate::store::buffer_store::BufferStore;
 use crate::store::persistence::{PersistenceConfig, PersistenceError, PersistenceManager};
 
@@ -30,36 +36,94 @@ pub struct ControlState {
     status: Option<i32>,
     builtin_map: BuiltinMap,
     mode: ShellMode,
-    #[allow(dead_code)]
     config: ConfigurationModel,
     buffers: Arc<Mutex<BufferStore>>,
     persistence: PersistenceManager,
     persistence_flushed: bool,
+    /// Interval between whole-store writes to the database, from `persistence.autosave_ms`.
+    persistence_autosave: Option<Duration>,
+    last_persistence_autosave: Instant,
+    /// Startup options for editor sessions, from the `editor` config section.
+    editor_config: EditorConfig,
+    /// History log read by `:fc` and appended to by the prompt; `None` uses `~/.iridium_history`.
+    history_file: Option<PathBuf>,
+    /// One-based cursor lines requested with `:b name:N` for the next editor session.
+    start_lines: Vec<(String, usize)>,
     #[cfg(test)]
     opened_buffers: Vec<String>,
     #[cfg(test)]
+    opened_lines: Vec<(String, usize)>,
+    #[cfg(test)]
+    editor_sessions: usize,
+    #[cfg(test)]
     force_quit_all: bool,
+    /// Stands in for the user's edits during a test editor session.
+    #[cfg(test)]
+    session_edit: Option<fn(&mut BufferStore, &str)>,
 }
 
 #[derive(Debug, Clone)]
 enum ShellMode {
     Prompt,
-    Buffer(String),
+    /// Buffers opened together as tabs of one editor session; the first is active.
+    Buffer(Vec<String>),
 }
 
 impl ControlState {
     /// Build a new control state with the default builtin set.
     pub fn new() -> Self {
-        let builtin_map = BuiltinMap::new();
-        let config = conf::load();
+        Self::with_isolated_environment(false)
+    }
+
+    /// Build a control state, optionally keeping a private environment instead of the process one.
+    ///
+    /// When `isolated` is set, builtins such as `cd` update a copy of the environment taken at
+    /// construction time and spawned commands receive that copy, so the host process' variables
+    /// and working directory are left untouched.
+    pub fn with_isolated_environment(isolated: bool) -> Self {
+        let mut builtin_map = if isolated {
+            BuiltinMap::with_environment(ShellEnvironment::isolated().into_handle())
+        } else {
+            BuiltinMap::new()
+        };
+        let config = match conf::load() {
+            Ok((config, _source)) => config,
+            Err(err) => {
+                eprintln!("Error: {err}; using built-in defaults");
+                ConfigurationModel::default()
+            }
+        };
+        builtin_map.options().borrow_mut().noclobber = config.control.noclobber();
+        let editor_config = EditorConfig::from_section(&config.editor);
+        let persistence_autosave = config.persistence.autosave();
         let persistence_config = PersistenceConfig::from_sources(Some(&config));
-        let persistence = PersistenceManager::new(persistence_config);
+        let persistence = PersistenceManager::new(persistence_config.clone());
 
         let mut backing_store = BufferStore::new();
+        backing_store.set_mkdir_on_save(config.editor.mkdir_on_save());
+        backing_store.set_fsync_on_save(config.editor.fsync_on_save());
+        backing_store.set_max_open_buffers(config.editor.max_open_buffers());
+        backing_store.set_undo_levels(config.editor.undolevels());
+        backing_store.set_backup(config.editor.backup());
+        backing_store.set_backup_dir(config.editor.resolved_backupdir(&config));
+        if let Some(timeout) = config.editor.idle_suspend()
+            && persistence.is_enabled()
+        {
+            if persistence.can_decrypt() {
+                let backend = PersistenceManager::new(persistence_config);
+                backing_store.enable_idle_suspend(timeout, Arc::new(backend));
+            } else {
+                eprintln!(
+                    "Warning: idle suspend needs the persistence identity to resume buffers; keeping them in memory"
+                );
+            }
+        }
         match persistence.load() {
             Ok(snapshots) => {
                 if !snapshots.is_empty() {
                     backing_store.hydrate(snapshots);
+                    let diff = backing_store.snapshot_diff();
+                    backing_store.mark_persisted(&diff);
                 }
             }
             Err(err) => {
@@ -69,6 +133,7 @@ impl ControlState {
 
         let buffers = Arc::new(Mutex::new(backing_store));
         Terminal::instance().attach_store(Arc::clone(&buffers));
+        builtin_map.set_buffer_store(Arc::clone(&buffers));
         let persistence_flushed = !persistence.is_enabled();
         Self {
             status: Some(0),
@@ -78,17 +143,41 @@ impl ControlState {
             buffers,
             persistence,
             persistence_flushed,
+            persistence_autosave,
+            last_persistence_autosave: Instant::now(),
+            editor_config,
+            history_file: None,
+            start_lines: Vec::new(),
             #[cfg(test)]
             opened_buffers: Vec::new(),
             #[cfg(test)]
+            opened_lines: Vec::new(),
+            #[cfg(test)]
+            editor_sessions: 0,
+            #[cfg(test)]
             force_quit_all: false,
+            #[cfg(test)]
+            session_edit: None,
         }
     }
 
     /// Render the prompt string with status colouring and the current directory.
+    ///
+    /// Background jobs that finished since the last prompt are reported first, so `{jobs}`
+    /// counts only those still running.
     pub fn prompt(&self) -> String {
         match &self.mode {
-            ShellMode::Prompt => generate_prompt(self.status, &self.builtin_map.get_pwd()),
+            ShellMode::Prompt => {
+                let jobs = self.builtin_map.jobs();
+                let mut jobs = jobs.borrow_mut();
+                jobs.report_finished(&mut io::stdout());
+                generate_prompt(
+                    self.status,
+                    &self.builtin_map.get_pwd(),
+                    self.config.control.prompt(),
+                    jobs.len(),
+                )
+            }
             ShellMode::Buffer(_) => {
                 let editor = BufferEditor::instance();
                 let editor = editor.lock().expect("buffer editor lock poisoned");
@@ -99,6 +188,11 @@ impl ControlState {
 
     /// Parse and execute a single line of user input, updating status and history.
     pub fn handle_line(&mut self, line: &str) -> ControlFlow {
+        // Any line may change buffers, so the next shutdown flush has to write them again.
+        if self.persistence.is_enabled() {
+            self.persistence_flushed = false;
+        }
+        self.autosave_persistence();
         match self.mode {
             ShellMode::Prompt => self.handle_prompt_line(line),
             ShellMode::Buffer(_) => {
@@ -108,27 +202,82 @@ impl ControlState {
         }
     }
 
+    /// Run each line of the file at `path` as a prompt command.
+    ///
+    /// Unlike typed lines, sourced ones get no `!` history expansion and are not recorded in
+    /// the history file. Blank lines and `#` comments are skipped, and an `exit` stops reading
+    /// the file.
+    pub fn source_file(&mut self, path: &Path) -> io::Result<ControlFlow> {
+        let contents = fs::read_to_string(path)?;
+        if self.persistence.is_enabled() {
+            self.persistence_flushed = false;
+        }
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let flow = if line.starts_with(':') {
+                self.handle_prompt_command(line)
+            } else {
+                self.execute_command_line(line)
+            };
+            if flow == ControlFlow::EXIT {
+                return Ok(ControlFlow::EXIT);
+            }
+        }
+        Ok(ControlFlow::CONTINUE)
+    }
+
     fn handle_prompt_line(&mut self, line: &str) -> ControlFlow {
+        self.suspend_idle_buffers();
+        let line = match self.expand_history(line) {
+            Ok(Some(expanded)) => {
+                println!("{expanded}");
+                expanded
+            }
+            Ok(None) => line.to_string(),
+            Err(message) => {
+                println!("{message}");
+                self.status = Some(1);
+                return ControlFlow::CONTINUE;
+            }
+        };
+        let line = line.as_str();
         let trimmed = line.trim();
 
         if trimmed.starts_with(':') {
             return self.handle_prompt_command(trimmed);
         }
 
-        let mut tokens = parse_tokens(line);
-        tokens = alias_parser(&self.builtin_map, tokens);
-
         let unix_timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
             .as_secs();
 
-        self.status = process::execute(&self.builtin_map, &tokens);
+        let flow = self.execute_command_line(line);
 
         if !line.is_empty() {
-            process::history::append_history(unix_timestamp, self.status, line);
+            process::history::append_history_to(
+                &self.history_path(),
+                unix_timestamp,
+                self.status,
+                line,
+            );
         }
 
+        flow
+    }
+
+    // Run a shell command line as typed, after any history expansion.
+    fn execute_command_line(&mut self, line: &str) -> ControlFlow {
+        let tokens = parse_tokens(line);
+
+        // Each command of an `&&`/`||` list has its own leading alias expanded.
+        self.status = process::execute_list(&tokens, |command| {
+            let command = alias_parser(&self.builtin_map, command.to_vec());
+            process::execute(&self.builtin_map, &command)
+        });
+
         if self.status == Some(process::exit::EXIT_CODE) {
             ControlFlow::EXIT
         } else {
@@ -137,6 +286,10 @@ impl ControlState {
     }
 
     fn handle_prompt_command(&mut self, command: &str) -> ControlFlow {
+        if command == ":fc" {
+            return self.handle_fc_command();
+        }
+
         // All buffer commands start with :b
         if command.contains(":b") {
             return self.handle_buffer_commands(&command);
@@ -156,16 +309,157 @@ impl ControlState {
         ControlFlow::CONTINUE
     }
 
+    /// Expand `!!`, `!N` and `!prefix` in `line` from the history log, as bash does before
+    /// a command is parsed.
+    fn expand_history(&self, line: &str) -> Result<Option<String>, String> {
+        if !line.contains('!') {
+            return Ok(None);
+        }
+        let entries = load_history_entries_from(
+            &self.history_path(),
+            Some(process::history::HISTORY_LIST_LIMIT),
+        )
+        .map_err(|err| format!("history: {err}"))?;
+        process::history::expand_history(line, &entries)
+    }
+
+    /// Record history in `path` instead of the default history file.
+    #[allow(dead_code)]
+    pub fn set_history_file(&mut self, path: PathBuf) {
+        self.history_file = Some(path);
+    }
+
+    fn history_path(&self) -> PathBuf {
+        self.history_file
+            .clone()
+            .unwrap_or_else(process::history::history_file_path)
+    }
+
+    // :fc — edit the most recent history command in a temporary buffer and run it once written.
+    fn handle_fc_command(&mut self) -> ControlFlow {
+        let last = match load_history_entries_from(&self.history_path(), Some(1)) {
+            Ok(mut entries) => entries.pop(),
+            Err(err) => {
+                println!("fc: unable to read history: {err}");
+                return ControlFlow::CONTINUE;
+            }
+        };
+        match last {
+            Some(command) => self.edit_and_run_command(&command),
+            None => {
+                println!("fc: history is empty");
+                ControlFlow::CONTINUE
+            }
+        }
+    }
+
+    /// Open `command` in a buffer backed by a temporary file and, if the user writes it with
+    /// `:w` or `:wq`, run each non-empty line of the file. Quitting without writing runs nothing.
+    fn edit_and_run_command(&mut self, command: &str) -> ControlFlow {
+        let path = env::temp_dir().join(format!("iridium-fc-{}.sh", Uuid::new_v4()));
+        let name = path.to_string_lossy().to_string();
+        {
+            let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+            match store.try_open(name.clone()) {
+                Ok(buffer) => buffer.append(command.to_string()),
+                Err(err) => {
+                    println!("fc: {err}");
+                    return ControlFlow::CONTINUE;
+                }
+            }
+            store.save_in_memory(&name);
+        }
+
+        self.mode = ShellMode::Buffer(vec![name.clone()]);
+        let _ = self.run_buffer_session();
+        self.buffers
+            .lock()
+            .expect("buffer store lock poisoned")
+            .remove(&name);
+        let edited = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+        let Ok(edited) = edited else {
+            println!("fc: command not written; nothing to run");
+            return ControlFlow::CONTINUE;
+        };
+
+        for line in edited.lines().filter(|line| !line.trim().is_empty()) {
+            println!("{line}");
+            if self.handle_line(line) == ControlFlow::EXIT {
+                return ControlFlow::EXIT;
+            }
+        }
+        ControlFlow::CONTINUE
+    }
+
+    /// Evict buffers idle past `editor.idle_suspend_ms` into the persistence database.
+    fn suspend_idle_buffers(&mut self) {
+        let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+        match store.suspend_idle() {
+            Ok(suspended) if !suspended.is_empty() => self.persistence_flushed = false,
+            Ok(_) => {}
+            Err(err) => eprintln!("Warning: unable to suspend idle buffers: {err}"),
+        }
+    }
+
+    /// Write every buffer to the persistence database once `persistence.autosave_ms` elapses.
+    fn autosave_persistence(&mut self) {
+        if let Err(err) = self.autosave_persistence_at(Instant::now()) {
+            eprintln!("Warning: unable to autosave buffers: {err}");
+        }
+    }
+
+    /// Autosave as of `now`, returning whether the interval had elapsed.
+    ///
+    /// Only buffers changed since the last write are encoded again. Unlike
+    /// [`Self::flush_persistence`] this leaves suspended buffers out of memory; their entries
+    /// already in the database are carried over unchanged.
+    fn autosave_persistence_at(&mut self, now: Instant) -> Result<bool, PersistenceError> {
+        let Some(interval) = self.persistence_autosave else {
+            return Ok(false);
+        };
+        if !self.persistence.is_enabled()
+            || now.saturating_duration_since(self.last_persistence_autosave) < interval
+        {
+            return Ok(false);
+        }
+        self.last_persistence_autosave = now;
+
+        let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+        let diff = store.snapshot_diff();
+        self.persistence.store_changes(&diff)?;
+        store.mark_persisted(&diff);
+        Ok(true)
+    }
+
     #[cfg(not(test))]
     fn run_buffer_session(&mut self) -> bool {
-        if let ShellMode::Buffer(buffer_name) = mem::replace(&mut self.mode, ShellMode::Prompt) {
+        if let ShellMode::Buffer(buffer_names) = mem::replace(&mut self.mode, ShellMode::Prompt) {
             if std::env::var("IRIDIUM_SKIP_EDITOR").is_ok() {
                 return true;
             }
             let editor = BufferEditor::instance();
             let mut editor = editor.lock().expect("buffer editor lock poisoned");
-            editor.open(buffer_name);
-            editor.run();
+            editor.configure(&self.editor_config);
+            for (name, line) in self.start_lines.drain(..) {
+                editor.set_start_line(name, line);
+            }
+            editor.open_tabs(&buffer_names);
+            let result = editor.run();
+            // Restart the idle clock now that the session has ended.
+            {
+                let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+                for name in &buffer_names {
+                    let _ = store.access(name);
+                }
+            }
+            if let Err(err) = result {
+                eprintln!(
+                    "Unable to open buffer '{}' in the editor: {err}",
+                    buffer_names.join("', '")
+                );
+                return true;
+            }
             if editor.take_quit_all_request() {
                 return false;
             }
@@ -175,8 +469,16 @@ impl ControlState {
 
     #[cfg(test)]
     fn run_buffer_session(&mut self) -> bool {
-        if let ShellMode::Buffer(buffer_name) = mem::replace(&mut self.mode, ShellMode::Prompt) {
-            self.opened_buffers.push(buffer_name);
+        if let ShellMode::Buffer(buffer_names) = mem::replace(&mut self.mode, ShellMode::Prompt) {
+            if let Some(edit) = self.session_edit {
+                let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+                for name in &buffer_names {
+                    edit(&mut store, name);
+                }
+            }
+            self.editor_sessions += 1;
+            self.opened_buffers.extend(buffer_names);
+            self.opened_lines.append(&mut self.start_lines);
         }
         if self.force_quit_all {
             self.force_quit_all = false;
@@ -200,42 +502,104 @@ impl ControlState {
         }
         let post_session_options = command.post_session_options();
 
-        let create_default_buffer =
-            args.is_empty() && !command.post_session_options().contains(&'l');
-        let buffer_targets: Vec<(String, bool)> = if args.is_empty() {
+        let create_default_buffer = args.is_empty()
+            && !post_session_options
+                .iter()
+                .any(|option| matches!(option, 'l' | 's' | 'w'));
+        let buffer_targets: Vec<(String, bool)> = if command.pre_session_options().contains(&'a') {
+            let [name, file] = args else {
+                println!(":buffer -a requires a buffer name and a file");
+                return ControlFlow::CONTINUE;
+            };
+            let path = match self.builtin_map.environment().borrow().current_dir() {
+                Ok(cwd) => cwd.join(file),
+                Err(_) => PathBuf::from(file),
+            };
+            if let Err(err) = store.try_open(name.clone()) {
+                println!("{err}");
+                return ControlFlow::CONTINUE;
+            }
+            if let Err(err) = store.append_file(name, &path) {
+                println!("Unable to read '{file}': {err}");
+                return ControlFlow::CONTINUE;
+            }
+            vec![(name.clone(), false)]
+        } else if command.pre_session_options().contains(&'o') {
+            match self.persistence.load() {
+                Ok(snapshots) => match most_recent_snapshot(snapshots) {
+                    Some(snapshot) => {
+                        let name = snapshot.name.clone();
+                        store.insert_snapshot(snapshot);
+                        vec![(name, false)]
+                    }
+                    None => {
+                        println!("(no saved buffers)");
+                        return ControlFlow::CONTINUE;
+                    }
+                },
+                Err(err) => {
+                    println!("Unable to load saved buffers: {err}");
+                    return ControlFlow::CONTINUE;
+                }
+            }
+        } else if args.is_empty() {
             if create_default_buffer {
-                let untitled = generate_untitled_name(&store);
+                let untitled = store.generate_untitled_name();
                 vec![(untitled, true)]
             } else {
                 Vec::new()
             }
         } else {
-            args.iter().cloned().map(|name| (name, false)).collect()
+            self.start_lines.clear();
+            let cwd = self.builtin_map.environment().borrow().current_dir().ok();
+            command
+                .targets()
+                .into_iter()
+                .zip(args)
+                .map(|(target, arg)| {
+                    // A buffer or file whose name really ends in `:N` wins over the line suffix.
+                    let exists = store.get(arg).is_some()
+                        || cwd.as_ref().is_some_and(|cwd| cwd.join(arg).exists());
+                    match target.line.filter(|_| !exists) {
+                        Some(line) => {
+                            self.start_lines.push((target.name.clone(), line));
+                            (target.name, false)
+                        }
+                        None => (arg.clone(), false),
+                    }
+                })
+                .collect()
         };
 
         let should_launch_editor = !buffer_targets.is_empty();
+        let scratch = command.pre_session_options().contains(&'t');
 
         for (name, requires_name) in &buffer_targets {
-            if *requires_name {
-                store.open_untitled(name.clone());
+            let opened = if scratch {
+                store.try_open_scratch(name.clone()).map(drop)
+            } else if *requires_name {
+                store.try_open_untitled(name.clone()).map(drop)
             } else {
-                store.open(name.clone());
+                store.try_open(name.clone()).map(drop)
+            };
+            if let Err(err) = opened {
+                println!("Unable to open buffer '{name}': {err}");
+                return ControlFlow::CONTINUE;
             }
         }
 
         drop(store);
 
         if should_launch_editor {
-            for (buffer_name, _) in &buffer_targets {
-                self.mode = ShellMode::Buffer(buffer_name.clone());
-                println!(
-                    "Opened buffer '{buffer_name}'. Press ':i' to enter insert mode and Ctrl+C to exit the editor."
-                );
-                let continue_sessions = self.run_buffer_session();
-                if !continue_sessions {
-                    break;
-                }
-            }
+            let buffer_names: Vec<String> =
+                buffer_targets.into_iter().map(|(name, _)| name).collect();
+            println!(
+                "Opened buffer '{}'. Press ':i' to enter insert mode and {} to exit the editor.",
+                buffer_names.join("', '"),
+                self.editor_config.quit_key
+            );
+            self.mode = ShellMode::Buffer(buffer_names);
+            let _ = self.run_buffer_session();
         }
 
         if !post_session_options.is_empty() {
@@ -248,10 +612,31 @@ impl ControlState {
         ControlFlow::CONTINUE
     }
 
-    fn handle_pipeline_commands(&mut self, bufcmd: &str) -> ControlFlow {
+    // :p stats — show how well the last persistence write compressed its buffers.
+    fn handle_pipeline_commands(&mut self, command: &str) -> ControlFlow {
+        let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+        match args.as_slice() {
+            ["stats"] => println!("{}", self.pipeline_stats_message()),
+            _ => println!("usage: :p stats"),
+        }
         ControlFlow::CONTINUE
     }
 
+    fn pipeline_stats_message(&self) -> String {
+        if !self.persistence.is_enabled() {
+            return "Persistence is disabled".to_string();
+        }
+        match self.persistence.last_compression() {
+            Some(stats) => format!(
+                "Last write: {} bytes compressed to {} ({:.2}:1)",
+                stats.uncompressed,
+                stats.compressed,
+                stats.ratio()
+            ),
+            None => "No buffers written to the persistence database yet".to_string(),
+        }
+    }
+
     fn apply_pre_session_options(
         &self,
         store: &mut BufferStore,
@@ -294,12 +679,44 @@ impl ControlState {
                         let new_name = pair[1].as_str();
                         let renamed = store.rename(old_name, new_name);
                         if renamed {
+                            // The editor session outlives the prompt, so keep it pointing at the
+                            // same buffer for the next `:b`.
+                            BufferEditor::instance()
+                                .lock()
+                                .expect("buffer editor lock poisoned")
+                                .rename_buffer_references(old_name, new_name);
                             println!("Renamed buffer '{}' to '{}'", old_name, new_name);
                         } else {
                             println!("Failed to rename buffer '{}' to '{}'", old_name, new_name);
                         }
                     }
                 }
+                'L' => {
+                    handled = true;
+                    let force = options.contains(&'f');
+                    let skipped = store.close_all(force);
+                    if skipped.is_empty() {
+                        println!("Closed all buffers");
+                    }
+                    for name in skipped {
+                        println!(
+                            "Buffer '{name}' has unsaved changes. Use :b -Lf to close it anyway."
+                        );
+                    }
+                }
+                'O' => {
+                    handled = true;
+                    let reopened = store.open_all();
+                    println!("Reopened {reopened} buffer(s)");
+                }
+                'p' => {
+                    handled = true;
+                    self.filter_buffer(store, args);
+                }
+                'E' => {
+                    handled = true;
+                    self.export_buffers(store, args);
+                }
                 _ => {}
             }
         }
@@ -307,20 +724,95 @@ impl ControlState {
         handled
     }
 
+    /// Write every buffer to a plain file under `:b -E <dir>`, resuming suspended ones first.
+    fn export_buffers(&self, store: &mut BufferStore, args: &[String]) {
+        let [dir] = args else {
+            println!(":buffer -E requires a directory");
+            return;
+        };
+        let path = match self.builtin_map.environment().borrow().current_dir() {
+            Ok(cwd) => cwd.join(dir),
+            Err(_) => PathBuf::from(dir),
+        };
+        if let Err(err) = store.resume_all() {
+            println!("Unable to resume suspended buffers: {err}");
+        }
+        match store.export_all(&path) {
+            Ok(written) => println!("Exported {written} buffer(s) to '{dir}'"),
+            Err(err) => println!("Failed to export buffers to '{dir}': {err}"),
+        }
+    }
+
+    /// Replace a buffer's lines with the output of `:b -p <name> -- cmd`.
+    fn filter_buffer(&self, store: &mut BufferStore, args: &[String]) {
+        let (name, command) = match args {
+            [name, rest @ ..] => (name, rest.strip_prefix(&["--".to_string()]).unwrap_or(rest)),
+            [] => (&String::new(), &[][..]),
+        };
+        if name.is_empty() || command.is_empty() {
+            println!(":buffer -p requires a buffer name and a command");
+            return;
+        }
+
+        let input = match store.access(name) {
+            Ok(Some(buffer)) => buffer.lines().to_vec(),
+            Ok(None) => {
+                println!("No buffer named '{name}'");
+                return;
+            }
+            Err(err) => {
+                println!("Unable to resume buffer '{name}': {err}");
+                return;
+            }
+        };
+        let environment = self.builtin_map.environment();
+        match process::filter_lines(command, &environment.borrow(), &input) {
+            Ok(output) => {
+                store.replace_lines(name, output);
+                println!("Filtered buffer '{name}' through '{}'", command.join(" "));
+            }
+            Err(message) => println!("Filter failed, buffer '{name}' unchanged: {message}"),
+        }
+    }
+
     fn apply_post_session_options(&mut self, options: &[char], args: &[String]) {
-        let store = self.buffers.lock().expect("buffer store lock poisoned");
+        let mut store = self.buffers.lock().expect("buffer store lock poisoned");
         for option in options {
             match option {
-                'l' => {
+                'l' | 's' => {
                     if store.is_empty() {
                         println!("(no buffers)");
                     } else {
-                        let names = store.list();
+                        let names = if *option == 's' {
+                            store.list_by_modified()
+                        } else {
+                            store.list()
+                        };
                         for name in &names {
                             println!("- {name}");
                         }
                     }
                 }
+                'w' => {
+                    for name in store.list() {
+                        if !store.is_dirty(&name) {
+                            continue;
+                        }
+                        if store.requires_name(&name) {
+                            println!("Skipping unnamed buffer '{name}'; rename it with :b -r");
+                        } else if store.is_read_only(&name) {
+                            println!("Skipping read-only buffer '{name}'");
+                        } else if store.disk_changed(&name) {
+                            println!(
+                                "Skipping '{name}'; its file changed on disk since it was read"
+                            );
+                        }
+                    }
+                    match store.save_all() {
+                        Ok(written) => println!("Saved {written} buffer(s)"),
+                        Err(err) => println!("Failed to save buffers: {err}"),
+                    }
+                }
                 _ => {
                     if let Some(buffer_name) = args.last() {
                         println!(
@@ -341,18 +833,39 @@ impl ControlState {
         store.list()
     }
 
+    /// Make every open and future buffer read-only (`--readonly`), or writable again.
+    pub fn set_read_only(&self, read_only: bool) {
+        let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+        store.set_all_read_only(read_only);
+    }
+
+    /// Whether `cd` directory hints should take precedence over history hints.
+    pub fn hint_paths_first(&self) -> bool {
+        self.config.control.hint_paths_first()
+    }
+
+    /// Environment shared by this state's builtins and spawned commands.
+    pub fn environment(&self) -> EnvironmentHandle {
+        self.builtin_map.environment()
+    }
+
     /// Persist all buffers via the configured persistence backend.
+    ///
+    /// Called when the control loop exits (EOF, interrupt or `exit`) and again on drop, so
+    /// untitled and in-memory buffers survive a restart. Does nothing when persistence is
+    /// disabled, e.g. through `IRIDIUM_DISABLE_PERSISTENCE`.
     pub fn flush_persistence(&mut self) -> Result<(), PersistenceError> {
-        if self.persistence_flushed {
+        if self.persistence_flushed || !self.persistence.is_enabled() {
             return Ok(());
         }
 
-        let snapshots = {
-            let store = self.buffers.lock().expect("buffer store lock poisoned");
-            store.snapshots()
-        };
-
-        self.persistence.store(&snapshots)?;
+        {
+            let mut store = self.buffers.lock().expect("buffer store lock poisoned");
+            store.resume_all()?;
+            let diff = store.snapshot_diff();
+            self.persistence.store_changes(&diff)?;
+            store.mark_persisted(&diff);
+        }
         self.persistence_flushed = true;
         Ok(())
     }
@@ -366,8 +879,8 @@ impl Drop for ControlState {
     }
 }
 
-/// Construct the shell prompt string combining status colouring and the cwd.
-fn generate_prompt(status: Option<i32>, pwd: &String) -> String {
+/// Construct the shell prompt string combining status colouring and the expanded `template`.
+fn generate_prompt(status: Option<i32>, pwd: &String, template: &str, jobs: usize) -> String {
     let arrow = 0x27A3;
     let red_text = "\u{1b}[31m";
     let green_text = "\u{1b}[32m";
@@ -377,7 +890,7 @@ fn generate_prompt(status: Option<i32>, pwd: &String) -> String {
     format!(
         "{}{}{}{}{}{}{}{}",
         purple_text,
-        update_cwd(pwd),
+        expand_prompt(template, pwd, jobs),
         match char::from_u32(0x0020) {
             Some(space) => space,
             None => ' ',
@@ -399,43 +912,64 @@ fn generate_prompt(status: Option<i32>, pwd: &String) -> String {
     )
 }
 
-/// Expand tokens if they match a defined alias, falling back to the original tokens.
+/// Substitute the `{jobs}` and `{cwd}` tokens of the configured prompt text.
+fn expand_prompt(template: &str, pwd: &str, jobs: usize) -> String {
+    template
+        .replace("{jobs}", &jobs.to_string())
+        .replace("{cwd}", &update_cwd(pwd))
+}
+
+/// Expand a leading alias, falling back to the original tokens.
+///
+/// As in bash, an alias whose value ends in a space (e.g. `sudo='sudo '`) makes the word after
+/// it eligible for alias expansion as well.
 fn alias_parser(builtin_map: &BuiltinMap, tokens: Vec<String>) -> Vec<String> {
     let aliases = builtin_map.get_alias();
     let aliases_borrow = aliases.as_ref().borrow();
-    let alias = tokens.join(" ");
+    let mut expanded = Vec::with_capacity(tokens.len());
+    let mut remaining = tokens.into_iter();
 
-    if aliases_borrow.contains_alias(&alias) {
-        let expansion = aliases_borrow.get_alias_expansion(&alias).unwrap();
-        return parse_tokens(expansion);
+    for word in remaining.by_ref() {
+        let Some(expansion) = aliases_borrow.get_alias_expansion(&word) else {
+            expanded.push(word);
+            break;
+        };
+        expanded.extend(parse_tokens(expansion));
+        if !expansion.ends_with(' ') {
+            break;
+        }
     }
 
-    tokens
+    expanded.extend(remaining);
+    expanded
 }
 
 /// Replace the home directory portion of the cwd with `~` for a compact prompt.
 fn update_cwd(cwd: &str) -> String {
-    cwd.replace(
-        &env::var("HOME").expect("Expected HOME environment variable to be set, aborting now."),
-        "~",
-    )
+    abbreviate_home(cwd, env::var("HOME").ok().as_deref())
+}
+
+/// Abbreviate `home` to `~`, leaving `cwd` unchanged when `HOME` is unset or empty.
+fn abbreviate_home(cwd: &str, home: Option<&str>) -> String {
+    match home {
+        Some(home) if !home.is_empty() => cwd.replace(home, "~"),
+        _ => cwd.to_string(),
+    }
 }
 
 /// Use shell-like parsing rules to split the input line into tokens.
 fn parse_tokens(line: &str) -> Vec<String> {
-    match shlex::split(line) {
+    match process::split_words(line) {
         Some(vec) => vec,
         None => panic!("Unable to parse string: {}", line),
     }
 }
 
-fn generate_untitled_name(store: &BufferStore) -> String {
-    loop {
-        let candidate = Uuid::new_v4().to_string();
-        if store.get(&candidate).is_none() {
-            return candidate;
-        }
-    }
+/// The saved buffer edited most recently; buffers without a timestamp sort oldest.
+fn most_recent_snapshot(snapshots: Vec<BufferSnapshot>) -> Option<BufferSnapshot> {
+    snapshots
+        .into_iter()
+        .max_by_key(|snapshot| snapshot.modified)
 }
 
 #[cfg(test)]
@@ -446,17 +980,227 @@ mod tests {
 
     fn make_state() -> ControlState {
         let persistence = PersistenceManager::new(PersistenceConfig::disabled());
+        let buffers = Arc::new(Mutex::new(BufferStore::new()));
+        let mut builtin_map = BuiltinMap::new();
+        builtin_map.set_buffer_store(Arc::clone(&buffers));
         ControlState {
             status: Some(0),
-            builtin_map: BuiltinMap::new(),
+            builtin_map,
             mode: ShellMode::Prompt,
             config: ConfigurationModel::default(),
-            buffers: Arc::new(Mutex::new(BufferStore::new())),
+            buffers,
             persistence,
             persistence_flushed: true,
+            persistence_autosave: None,
+            last_persistence_autosave: Instant::now(),
+            editor_config: EditorConfig::default(),
+            history_file: None,
+            start_lines: Vec::new(),
             opened_buffers: Vec::new(),
+            opened_lines: Vec::new(),
+            editor_sessions: 0,
             force_quit_all: false,
+            session_edit: None,
+        }
+    }
+
+    fn define_aliases(state: &ControlState, definitions: &[&str]) {
+        let args: Vec<String> = definitions.iter().map(|arg| arg.to_string()).collect();
+        let mut sink = Vec::new();
+        state
+            .builtin_map
+            .invoke_with_output("alias", &args, &mut sink);
+    }
+
+    #[test]
+    fn scratch_option_opens_unsaved_unpersisted_buffers() {
+        let mut state = make_state();
+        state.handle_buffer_commands(":b -t");
+        state.handle_buffer_commands(":b -t notes");
+
+        assert_eq!(state.opened_buffers.len(), 2);
+        assert_eq!(state.opened_buffers[1], "notes");
+        let store = state.buffers.lock().unwrap();
+        for name in &state.opened_buffers {
+            assert!(store.is_scratch(name), "{name} should be scratch");
+            assert!(!store.requires_name(name));
+        }
+        assert!(store.snapshots().is_empty());
+    }
+
+    #[test]
+    fn filter_option_pipes_a_buffer_through_sort() {
+        let mut state = make_state();
+        {
+            let mut store = state.buffers.lock().unwrap();
+            let buffer = store.open("names");
+            for name in ["delta", "alpha", "charlie", "bravo"] {
+                buffer.append(name.to_string());
+            }
+            store.get_mut("names").unwrap().mark_clean();
+        }
+
+        let flow = state.handle_buffer_commands(":b -p names -- sort");
+
+        assert_eq!(flow, ControlFlow::CONTINUE);
+        assert!(state.opened_buffers.is_empty());
+        let store = state.buffers.lock().unwrap();
+        assert_eq!(
+            store.get("names").unwrap().lines(),
+            &["alpha", "bravo", "charlie", "delta"]
+        );
+        assert!(store.is_dirty("names"));
+    }
+
+    #[test]
+    fn export_option_writes_buffers_without_opening_the_editor() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut state = make_state();
+        {
+            let mut store = state.buffers.lock().unwrap();
+            store.open("alpha").append("one".into());
+            store.open("beta").append("two".into());
+            store.mark_closed("beta");
         }
+
+        let command = format!(":b -E {}", dir.path().display());
+        assert_eq!(
+            state.handle_buffer_commands(&command),
+            ControlFlow::CONTINUE
+        );
+
+        assert!(state.opened_buffers.is_empty());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("alpha")).unwrap(),
+            "one\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("beta")).unwrap(),
+            "two\n"
+        );
+    }
+
+    #[test]
+    fn failing_filter_leaves_the_buffer_unchanged() {
+        let mut state = make_state();
+        state
+            .buffers
+            .lock()
+            .unwrap()
+            .open("names")
+            .append("keep".into());
+
+        state.handle_buffer_commands(":b -p names -- false");
+        state.handle_buffer_commands(":b -p names -- iridium-no-such-filter");
+
+        let store = state.buffers.lock().unwrap();
+        assert_eq!(store.get("names").unwrap().lines(), &["keep"]);
+    }
+
+    #[test]
+    fn line_suffix_opens_the_buffer_at_that_line() {
+        let mut state = make_state();
+
+        state.handle_buffer_commands(":b src/main.rs:10 notes");
+
+        assert_eq!(
+            state.opened_buffers,
+            vec!["src/main.rs".to_string(), "notes".to_string()]
+        );
+        assert_eq!(state.opened_lines, vec![("src/main.rs".to_string(), 10)]);
+        assert!(
+            state
+                .buffers
+                .lock()
+                .unwrap()
+                .get("src/main.rs:10")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn existing_buffer_named_with_a_colon_is_opened_whole() {
+        let mut state = make_state();
+        state.buffers.lock().unwrap().open("log:2");
+
+        state.handle_buffer_commands(":b log:2");
+
+        assert_eq!(state.opened_buffers, vec!["log:2".to_string()]);
+        assert!(state.opened_lines.is_empty());
+    }
+
+    #[test]
+    fn append_option_fills_a_named_buffer_from_a_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("source.txt");
+        std::fs::write(&file, "first\nsecond\n").unwrap();
+        let mut state = make_state();
+
+        state.handle_buffer_commands(&format!(":b -a compare {}", file.display()));
+
+        assert_eq!(state.opened_buffers, vec!["compare".to_string()]);
+        let store = state.buffers.lock().unwrap();
+        let buffer = store.get("compare").expect("buffer should exist");
+        assert_eq!(buffer.lines(), &["first", "second"]);
+        assert_eq!(buffer.storage_path(), "compare");
+        assert!(store.get(file.to_str().unwrap()).is_none());
+    }
+
+    #[test]
+    fn append_option_requires_a_readable_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let missing = temp.path().join("missing.txt");
+        let mut state = make_state();
+
+        state.handle_buffer_commands(":b -a compare");
+        state.handle_buffer_commands(&format!(":b -a compare {}", missing.display()));
+
+        assert!(state.opened_buffers.is_empty());
+        assert_eq!(state.editor_sessions, 0);
+    }
+
+    #[test]
+    fn alias_with_trailing_space_expands_the_next_word() {
+        let state = make_state();
+        define_aliases(&state, &["sudo=sudo ", "ll=ls -l", "run=sudo"]);
+
+        assert_eq!(
+            alias_parser(&state.builtin_map, parse_tokens("sudo ll /tmp")),
+            vec!["sudo", "ls", "-l", "/tmp"]
+        );
+        assert_eq!(
+            alias_parser(&state.builtin_map, parse_tokens("ll sudo")),
+            vec!["ls", "-l", "sudo"]
+        );
+        assert_eq!(
+            alias_parser(&state.builtin_map, parse_tokens("run ll")),
+            vec!["sudo", "ll"]
+        );
+        assert_eq!(
+            alias_parser(&state.builtin_map, parse_tokens("sudo sudo ll")),
+            vec!["sudo", "sudo", "ls", "-l"]
+        );
+    }
+
+    #[test]
+    fn prompt_rename_updates_the_editor_session() {
+        let mut state = make_state();
+        let old_name = format!("rename-{}", Uuid::new_v4());
+        let new_name = format!("{old_name}-renamed");
+        state.handle_buffer_commands(&format!(":b {old_name}"));
+        BufferEditor::instance()
+            .lock()
+            .unwrap()
+            .open_tabs(std::slice::from_ref(&old_name));
+
+        state.handle_buffer_commands(&format!(":b -r {old_name} {new_name}"));
+
+        let editor = BufferEditor::instance().lock().unwrap();
+        assert_eq!(editor.active_buffer(), new_name);
+        assert_eq!(editor.tabs(), std::slice::from_ref(&new_name));
+        let store = state.buffers.lock().unwrap();
+        assert!(store.get(&new_name).is_some());
+        assert!(store.get(&old_name).is_none());
     }
 
     #[test]
@@ -502,6 +1246,34 @@ mod tests {
         assert!(store.requires_name(buffer_name));
     }
 
+    #[test]
+    fn close_all_and_reopen_all_via_options() {
+        let mut state = make_state();
+        {
+            let mut store = state.buffers.lock().unwrap();
+            store.open("alpha");
+            store.open("beta").append("unsaved".into());
+            store.open("gamma");
+            store.save_in_memory("alpha");
+            store.save_in_memory("gamma");
+        }
+
+        assert_eq!(state.handle_buffer_commands(":b -L"), ControlFlow::CONTINUE);
+        assert_eq!(
+            state.buffers.lock().unwrap().open_buffers(),
+            vec!["beta".to_string()]
+        );
+
+        state.handle_buffer_commands(":b -Lf");
+        assert!(state.buffers.lock().unwrap().open_buffers().is_empty());
+
+        state.handle_buffer_commands(":b -O");
+        let mut open = state.buffers.lock().unwrap().open_buffers();
+        open.sort();
+        assert_eq!(open, vec!["alpha", "beta", "gamma"]);
+        assert!(state.opened_buffers.is_empty());
+    }
+
     #[test]
     fn deletes_buffers_via_option() {
         let mut state = make_state();
@@ -607,6 +1379,239 @@ mod tests {
         assert_eq!(names, vec!["alpha".to_string(), "beta".to_string()]);
     }
 
+    #[test]
+    fn write_option_saves_dirty_named_buffers_only() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dirty = temp.path().join("dirty.txt").to_string_lossy().into_owned();
+        let clean = temp.path().join("clean.txt").to_string_lossy().into_owned();
+        let mut state = make_state();
+        {
+            let mut store = state.buffers.lock().unwrap();
+            store.open(dirty.as_str()).append("saved".to_string());
+            store.open(clean.as_str());
+            store
+                .open_untitled("untitled-1")
+                .append("pending".to_string());
+        }
+
+        let flow = state.handle_buffer_commands(":b -w");
+
+        assert_eq!(flow, ControlFlow::CONTINUE);
+        assert!(state.opened_buffers.is_empty());
+        assert_eq!(std::fs::read_to_string(&dirty).unwrap(), "saved\n");
+        assert!(!std::path::Path::new(&clean).exists());
+
+        let mut store = state.buffers.lock().unwrap();
+        assert!(!store.is_dirty(&dirty));
+        assert!(store.is_dirty("untitled-1"));
+        assert!(!std::path::Path::new("untitled-1").exists());
+        assert_eq!(store.save_all().unwrap(), 0);
+    }
+
+    #[test]
+    fn write_option_skips_read_only_and_changed_on_disk_buffers() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let locked = temp
+            .path()
+            .join("locked.txt")
+            .to_string_lossy()
+            .into_owned();
+        let changed = temp.path().join("changed.txt");
+        std::fs::write(&changed, "original\n").unwrap();
+        let changed = changed.to_string_lossy().into_owned();
+        let mut state = make_state();
+        {
+            let mut store = state.buffers.lock().unwrap();
+            store.open(locked.as_str()).append("locked".to_string());
+            store.set_read_only(&locked, true);
+            store.open(changed.as_str()).append("edited".to_string());
+        }
+        // Another program rewrites the file after it was loaded.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(&changed, "theirs\n").unwrap();
+
+        state.handle_buffer_commands(":b -w");
+
+        assert!(!std::path::Path::new(&locked).exists());
+        assert_eq!(std::fs::read_to_string(&changed).unwrap(), "theirs\n");
+        let store = state.buffers.lock().unwrap();
+        assert!(store.is_dirty(&locked));
+        assert!(store.is_dirty(&changed));
+    }
+
+    #[test]
+    fn open_recent_option_picks_newest_saved_buffer() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let config = PersistenceConfig::with_path(temp.path().join("buffers.db"));
+        let older = UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let newer = UNIX_EPOCH + std::time::Duration::from_secs(2_000);
+        PersistenceManager::new(config.clone())
+            .store(&[
+                BufferSnapshot::new("newer".into(), vec!["new".into()], false, true, false)
+                    .with_modified(Some(newer)),
+                BufferSnapshot::new("older".into(), vec!["old".into()], false, true, false)
+                    .with_modified(Some(older)),
+                BufferSnapshot::new("never".into(), vec![], false, true, false),
+            ])
+            .unwrap();
+        let mut state = make_state();
+        state.persistence = PersistenceManager::new(config);
+
+        let flow = state.handle_buffer_commands(":b -o");
+
+        assert_eq!(flow, ControlFlow::CONTINUE);
+        assert_eq!(state.opened_buffers, vec!["newer".to_string()]);
+        let store = state.buffers.lock().unwrap();
+        assert_eq!(store.get("newer").unwrap().lines(), &["new".to_string()]);
+        assert_eq!(store.get("newer").unwrap().last_modified(), Some(newer));
+        assert!(store.get("older").is_none());
+    }
+
+    #[test]
+    fn open_recent_option_reports_empty_database() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut state = make_state();
+        state.persistence =
+            PersistenceManager::new(PersistenceConfig::with_path(temp.path().join("buffers.db")));
+
+        let flow = state.handle_buffer_commands(":b -o");
+
+        assert_eq!(flow, ControlFlow::CONTINUE);
+        assert!(state.opened_buffers.is_empty());
+        assert!(state.buffers.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn persistence_autosave_rewrites_database_once_interval_elapses() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let config = PersistenceConfig::with_path(temp.path().join("buffers.db"));
+        let mut state = make_state();
+        state.persistence = PersistenceManager::new(config.clone());
+        state.persistence_autosave = Some(Duration::from_secs(10));
+        let start = state.last_persistence_autosave;
+        state.buffers.lock().unwrap().open_untitled("notes");
+        state
+            .buffers
+            .lock()
+            .unwrap()
+            .insert_char("notes", 0, 0, 'a');
+
+        assert!(
+            !state
+                .autosave_persistence_at(start + Duration::from_secs(5))
+                .unwrap()
+        );
+        assert!(
+            PersistenceManager::new(config.clone())
+                .load()
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(
+            state
+                .autosave_persistence_at(start + Duration::from_secs(10))
+                .unwrap()
+        );
+        let stored = PersistenceManager::new(config.clone()).load().unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].name, "notes");
+        assert_eq!(stored[0].lines, vec!["a".to_string()]);
+
+        state
+            .buffers
+            .lock()
+            .unwrap()
+            .insert_char("notes", 0, 1, 'b');
+        assert!(
+            !state
+                .autosave_persistence_at(start + Duration::from_secs(15))
+                .unwrap()
+        );
+        assert!(
+            state
+                .autosave_persistence_at(start + Duration::from_secs(20))
+                .unwrap()
+        );
+        let stored = PersistenceManager::new(config).load().unwrap();
+        assert_eq!(stored[0].lines, vec!["ab".to_string()]);
+    }
+
+    #[test]
+    fn pipeline_stats_report_the_last_compression_ratio() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut state = make_state();
+        assert_eq!(state.pipeline_stats_message(), "Persistence is disabled");
+
+        state.persistence =
+            PersistenceManager::new(PersistenceConfig::with_path(temp.path().join("buffers.db")));
+        assert_eq!(
+            state.pipeline_stats_message(),
+            "No buffers written to the persistence database yet"
+        );
+
+        state.buffers.lock().unwrap().open_untitled("notes");
+        state
+            .buffers
+            .lock()
+            .unwrap()
+            .apply_edit("notes", |lines| *lines = vec!["repeat ".repeat(200)]);
+        state.persistence_flushed = false;
+        state.flush_persistence().unwrap();
+
+        let stats = state.persistence.last_compression().unwrap();
+        assert!(stats.compressed < stats.uncompressed);
+        assert_eq!(
+            state.pipeline_stats_message(),
+            format!(
+                "Last write: {} bytes compressed to {} ({:.2}:1)",
+                stats.uncompressed,
+                stats.compressed,
+                stats.ratio()
+            )
+        );
+        assert_eq!(
+            state.handle_prompt_command(":p stats"),
+            ControlFlow::CONTINUE
+        );
+    }
+
+    #[test]
+    fn persistence_autosave_is_off_without_an_interval() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut state = make_state();
+        state.persistence =
+            PersistenceManager::new(PersistenceConfig::with_path(temp.path().join("buffers.db")));
+        state.buffers.lock().unwrap().open_untitled("notes");
+
+        let later = state.last_persistence_autosave + Duration::from_secs(3600);
+        assert!(!state.autosave_persistence_at(later).unwrap());
+        assert!(!temp.path().join("buffers.db").exists());
+    }
+
+    #[test]
+    fn buffer_command_refuses_buffers_past_the_open_limit() {
+        let mut state = make_state();
+        state.buffers.lock().unwrap().set_max_open_buffers(Some(2));
+
+        state.handle_buffer_commands(":b alpha beta");
+        assert_eq!(
+            state.opened_buffers,
+            vec!["alpha".to_string(), "beta".to_string()]
+        );
+
+        let flow = state.handle_buffer_commands(":b gamma");
+        assert_eq!(flow, ControlFlow::CONTINUE);
+        assert_eq!(state.opened_buffers.len(), 2);
+        assert!(state.buffers.lock().unwrap().get("gamma").is_none());
+
+        state.handle_buffer_commands(":b beta");
+        assert_eq!(
+            state.opened_buffers.last().map(String::as_str),
+            Some("beta")
+        );
+    }
+
     #[test]
     fn opens_each_named_buffer_in_list() {
         let mut state = make_state();
@@ -628,13 +1633,177 @@ mod tests {
     }
 
     #[test]
-    fn quit_all_stops_opening_additional_buffers() {
+    fn cwd_is_unchanged_without_home() {
+        assert_eq!(
+            abbreviate_home("/home/user/src", Some("/home/user")),
+            "~/src"
+        );
+        assert_eq!(abbreviate_home("/home/user/src", None), "/home/user/src");
+        assert_eq!(abbreviate_home("/srv/app", Some("")), "/srv/app");
+    }
+
+    fn alias_listing(state: &ControlState) -> String {
+        let mut sink = Vec::new();
+        state
+            .builtin_map
+            .invoke_with_output("alias", &[], &mut sink);
+        String::from_utf8(sink).unwrap()
+    }
+
+    #[test]
+    fn fc_runs_the_edited_history_command_once_written() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let history = dir.path().join(".iridium_history");
+        fs::write(&history, "100:0:ls\n101:0:alias fc_probe=before\n").unwrap();
+
+        let mut state = make_state();
+        state.history_file = Some(history.clone());
+        state.session_edit = Some(|store, name| {
+            assert_eq!(store.get(name).unwrap().lines(), ["alias fc_probe=before"]);
+            store.replace_lines(name, vec!["alias fc_probe=after".to_string()]);
+            store.save(name).unwrap();
+        });
+
+        assert_eq!(state.handle_line(":fc"), ControlFlow::CONTINUE);
+        assert_eq!(state.editor_sessions, 1);
+        assert!(alias_listing(&state).contains("fc_probe='after'"));
+        assert!(
+            state.buffers.lock().unwrap().is_empty(),
+            "the temporary buffer is discarded"
+        );
+        let recorded = fs::read_to_string(&history).unwrap();
+        assert!(recorded.ends_with(":0:alias fc_probe=after\n"));
+    }
+
+    #[test]
+    fn history_expansion_reruns_earlier_commands() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let history = dir.path().join(".iridium_history");
+        fs::write(&history, "100:0:alias bang_probe=first\n101:0:cd .\n").unwrap();
+
+        let mut state = make_state();
+        state.history_file = Some(history.clone());
+        assert_eq!(state.expand_history("!!"), Ok(Some("cd .".to_string())));
+        assert_eq!(
+            state.expand_history("!al"),
+            Ok(Some("alias bang_probe=first".to_string()))
+        );
+
+        state.handle_line("!al");
+        assert!(alias_listing(&state).contains("bang_probe='first'"));
+        let recorded = fs::read_to_string(&history).unwrap();
+        assert!(
+            recorded.ends_with(":0:alias bang_probe=first\n"),
+            "the expanded command is what gets recorded"
+        );
+
+        state.handle_line("!missing");
+        assert_eq!(state.status, Some(1));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prompt_reaps_finished_background_jobs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut state = make_state();
+        state.history_file = Some(dir.path().join(".iridium_history"));
+
+        state.handle_line("true &");
+        assert_eq!(state.builtin_map.jobs().borrow().len(), 1);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !state.builtin_map.jobs().borrow().is_empty() && Instant::now() < deadline {
+            state.prompt();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(state.builtin_map.jobs().borrow().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn jobs_prompt_token_counts_running_background_jobs() {
+        let mut state = make_state();
+        state.config.control.prompt = Some("jobs={jobs}".to_string());
+        assert!(state.prompt().contains("jobs=0"));
+
+        // `cat` keeps running until the stdin held here is closed.
+        let mut blocked = std::process::Command::new("cat")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdin = blocked.stdin.take().unwrap();
+        state.builtin_map.jobs().borrow_mut().add(blocked, "cat");
+        assert!(state.prompt().contains("jobs=1"));
+
+        drop(stdin);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut prompt = state.prompt();
+        while !prompt.contains("jobs=0") && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+            prompt = state.prompt();
+        }
+        assert!(prompt.contains("jobs=0"), "the finished job is reaped");
+    }
+
+    #[test]
+    fn conditional_lists_short_circuit_and_record_the_last_status() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let history = dir.path().join(".iridium_history");
+        let mut state = make_state();
+        state.history_file = Some(history.clone());
+
+        state.handle_line("alias list_probe=first || alias list_probe=second");
+        assert_eq!(state.status, Some(0));
+        assert!(alias_listing(&state).contains("list_probe='first'"));
+
+        state.handle_line("cd /nonexistent-list-dir && alias list_probe=third");
+        assert_eq!(state.status, Some(1));
+        assert!(alias_listing(&state).contains("list_probe='first'"));
+
+        state.handle_line("cd /nonexistent-list-dir || alias list_probe=fourth");
+        assert_eq!(state.status, Some(0));
+        assert!(alias_listing(&state).contains("list_probe='fourth'"));
+
+        let recorded = fs::read_to_string(&history).unwrap();
+        assert!(recorded.contains(":1:cd /nonexistent-list-dir && alias list_probe=third\n"));
+        assert!(recorded.ends_with(":0:cd /nonexistent-list-dir || alias list_probe=fourth\n"));
+    }
+
+    #[test]
+    fn fc_runs_nothing_unless_the_buffer_is_written() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let history = dir.path().join(".iridium_history");
+        fs::write(&history, "100:0:alias fc_probe=before\n").unwrap();
+
+        let mut state = make_state();
+        state.history_file = Some(history.clone());
+        state.session_edit = Some(|store, name| {
+            store.replace_lines(name, vec!["alias fc_probe=after".to_string()]);
+        });
+        state.handle_line(":fc");
+        assert!(!alias_listing(&state).contains("fc_probe"));
+
+        fs::write(&history, "").unwrap();
+        state.handle_line(":fc");
+        assert_eq!(state.editor_sessions, 1, "an empty history opens no editor");
+    }
+
+    #[test]
+    fn multiple_targets_share_one_editor_session() {
         let mut state = make_state();
         state.force_quit_all = true;
 
         let flow = state.handle_buffer_commands(":b first second");
 
         assert_eq!(flow, ControlFlow::CONTINUE);
-        assert_eq!(state.opened_buffers, vec!["first".to_string()]);
+        assert_eq!(state.editor_sessions, 1);
+        assert_eq!(
+            state.opened_buffers,
+            vec!["first".to_string(), "second".to_string()]
+        );
+
+        state.handle_buffer_commands(":b third");
+        assert_eq!(state.editor_sessions, 2);
     }
 }
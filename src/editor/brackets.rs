@@ -0,0 +1,216 @@
+//! Bracket matching for `:set showmatch` and `:set matchpairs`.
+
+/// Bracket pairs recognised until `:set matchpairs` changes them, opening bracket first.
+pub const BRACKET_PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Lines searched on either side of the bracket before it counts as unmatched.
+pub const MATCH_WINDOW_LINES: usize = 100;
+
+/// Outcome of looking for the partner of the bracket at the cursor, positions as `(row, col)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketMatch {
+    /// The bracket at the first position pairs with the one at the second.
+    Matched((usize, usize), (usize, usize)),
+    /// No partner was found within [`MATCH_WINDOW_LINES`].
+    Unmatched((usize, usize)),
+}
+
+/// The bracket pairs in effect, set with `:set matchpairs=(:),[:]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPairs(Vec<(char, char)>);
+
+impl MatchPairs {
+    /// Parse a comma-separated list of `open:close` pairs such as `(:),<:>`.
+    ///
+    /// Each side must be a single character and the two must differ, since a bracket that
+    /// closes itself has no nesting to follow.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid matchpairs: {value}");
+        let pairs = value
+            .split(',')
+            .map(|pair| {
+                let mut chars = pair.chars();
+                match (chars.next(), chars.next(), chars.next(), chars.next()) {
+                    (Some(open), Some(':'), Some(close), None) if open != close => {
+                        Ok((open, close))
+                    }
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(pairs))
+    }
+
+    pub fn pairs(&self) -> &[(char, char)] {
+        &self.0
+    }
+}
+
+impl Default for MatchPairs {
+    fn default() -> Self {
+        Self(BRACKET_PAIRS.to_vec())
+    }
+}
+
+/// Match the bracket under the cursor at `(row, col)`, or else the one just before it.
+pub fn match_at_cursor(
+    lines: &[String],
+    row: usize,
+    col: usize,
+    pairs: &[(char, char)],
+) -> Option<BracketMatch> {
+    let line = lines.get(row)?;
+    let col = [Some(col), col.checked_sub(1)]
+        .into_iter()
+        .flatten()
+        .find(|col| {
+            line.chars()
+                .nth(*col)
+                .is_some_and(|ch| is_bracket(ch, pairs))
+        })?;
+    Some(match find_match(lines, row, col, pairs) {
+        Some(partner) => BracketMatch::Matched((row, col), partner),
+        None => BracketMatch::Unmatched((row, col)),
+    })
+}
+
+/// Position of the bracket pairing with the one at `(row, col)`, skipping nested pairs.
+///
+/// Opening brackets are matched forwards and closing ones backwards; only brackets of the same
+/// kind affect the nesting depth.
+pub fn find_match(
+    lines: &[String],
+    row: usize,
+    col: usize,
+    pairs: &[(char, char)],
+) -> Option<(usize, usize)> {
+    let bracket = lines.get(row)?.chars().nth(col)?;
+    if let Some(&(open, close)) = pairs.iter().find(|(open, _)| *open == bracket) {
+        let last = (row + MATCH_WINDOW_LINES).min(lines.len() - 1);
+        let mut depth = 0usize;
+        for (r, line) in lines.iter().enumerate().take(last + 1).skip(row) {
+            let skip = if r == row { col + 1 } else { 0 };
+            for (c, ch) in line.chars().enumerate().skip(skip) {
+                if ch == open {
+                    depth += 1;
+                } else if ch == close {
+                    if depth == 0 {
+                        return Some((r, c));
+                    }
+                    depth -= 1;
+                }
+            }
+        }
+        return None;
+    }
+
+    let &(open, close) = pairs.iter().find(|(_, close)| *close == bracket)?;
+    let first = row.saturating_sub(MATCH_WINDOW_LINES);
+    let mut depth = 0usize;
+    for r in (first..=row).rev() {
+        let chars: Vec<char> = lines[r].chars().collect();
+        let end = if r == row { col } else { chars.len() };
+        for c in (0..end).rev() {
+            if chars[c] == close {
+                depth += 1;
+            } else if chars[c] == open {
+                if depth == 0 {
+                    return Some((r, c));
+                }
+                depth -= 1;
+            }
+        }
+    }
+    None
+}
+
+fn is_bracket(ch: char, pairs: &[(char, char)]) -> bool {
+    pairs
+        .iter()
+        .any(|(open, close)| ch == *open || ch == *close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn opening_brackets_match_across_nesting_and_lines() {
+        let lines = lines(&["fn f(a: [u8; (2)]) {", "    if x { y(); }", "}"]);
+        assert_eq!(find_match(&lines, 0, 4, &BRACKET_PAIRS), Some((0, 17)));
+        assert_eq!(find_match(&lines, 0, 8, &BRACKET_PAIRS), Some((0, 16)));
+        assert_eq!(find_match(&lines, 0, 19, &BRACKET_PAIRS), Some((2, 0)));
+        assert_eq!(find_match(&lines, 1, 9, &BRACKET_PAIRS), Some((1, 16)));
+    }
+
+    #[test]
+    fn closing_brackets_match_backwards() {
+        let lines = lines(&["{ (a) [b]", "  (c)", "}"]);
+        assert_eq!(find_match(&lines, 2, 0, &BRACKET_PAIRS), Some((0, 0)));
+        assert_eq!(find_match(&lines, 1, 4, &BRACKET_PAIRS), Some((1, 2)));
+        assert_eq!(find_match(&lines, 0, 8, &BRACKET_PAIRS), Some((0, 6)));
+    }
+
+    #[test]
+    fn cursor_just_after_a_bracket_matches_it() {
+        let lines = lines(&["call(x)"]);
+        assert_eq!(
+            match_at_cursor(&lines, 0, 7, &BRACKET_PAIRS),
+            Some(BracketMatch::Matched((0, 6), (0, 4)))
+        );
+        assert_eq!(
+            match_at_cursor(&lines, 0, 4, &BRACKET_PAIRS),
+            Some(BracketMatch::Matched((0, 4), (0, 6)))
+        );
+        assert_eq!(match_at_cursor(&lines, 0, 2, &BRACKET_PAIRS), None);
+    }
+
+    #[test]
+    fn matchpairs_parse_and_drive_matching() {
+        let pairs = MatchPairs::parse("(:),[:],{:},<:>").unwrap();
+        assert_eq!(
+            pairs.pairs(),
+            &[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')]
+        );
+
+        let lines = lines(&["Vec<Option<u8>>"]);
+        assert_eq!(
+            match_at_cursor(&lines, 0, 3, pairs.pairs()),
+            Some(BracketMatch::Matched((0, 3), (0, 14)))
+        );
+        assert_eq!(match_at_cursor(&lines, 0, 3, &BRACKET_PAIRS), None);
+    }
+
+    #[test]
+    fn malformed_matchpairs_are_rejected() {
+        for spec in ["", "(:),", "(-)", "((:))", "|:|"] {
+            assert_eq!(
+                MatchPairs::parse(spec),
+                Err(format!("Invalid matchpairs: {spec}"))
+            );
+        }
+    }
+
+    #[test]
+    fn unmatched_and_out_of_window_brackets_are_reported() {
+        let unbalanced = lines(&["(a]"]);
+        assert_eq!(
+            match_at_cursor(&unbalanced, 0, 0, &BRACKET_PAIRS),
+            Some(BracketMatch::Unmatched((0, 0)))
+        );
+
+        let mut distant = vec!["{".to_string()];
+        distant.extend(std::iter::repeat_n(String::new(), MATCH_WINDOW_LINES));
+        distant.push("}".to_string());
+        assert_eq!(find_match(&distant, 0, 0, &BRACKET_PAIRS), None);
+        distant.remove(1);
+        assert_eq!(
+            find_match(&distant, 0, 0, &BRACKET_PAIRS),
+            Some((MATCH_WINDOW_LINES, 0))
+        );
+    }
+}
@@ -1,12 +1,22 @@
-use crate::editor::input::{InputAction, InputHandler, NavigationCommand};
-use crate::editor::terminal::{Position, Size, Terminal};
-use crate::editor::view::View;
+use crate::conf::expand_path;
+use crate::editor::clipboard::{ClipboardMode, SharedClipboard};
+use crate::editor::config::EditorConfig;
+use crate::editor::input::{InputAction, InputHandler, NavigationCommand, parse_keys};
+use crate::editor::register::{RegisterKind, Registers, UNNAMED_REGISTER};
+use crate::editor::settings::{EditorSettings, MODELINE_LINES};
+use crate::editor::snapshots::SnapshotRing;
+use crate::editor::syntax::FileType;
+use crate::editor::terminal::{FrameBuffer, Position, Size, Terminal};
+use crate::editor::view::{SplitLayout, View};
+use crate::store::buffer::{Alignment, SCRATCH_SAVE_MESSAGE, substitute_in};
 use core::cmp::min;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyModifiers;
 use crossterm::event::read;
 use crossterm::event::{Event, poll};
+use std::collections::HashMap;
 use std::io::{Error, ErrorKind};
+use std::path::Path;
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
@@ -27,6 +37,38 @@ pub struct BufferEditor {
     status_message: Option<String>,
     cursor_blink_visible: bool,
     cursor_last_toggle: Instant,
+    /// Where the last full refresh left the caret, so a blink can redraw just the glyph there.
+    caret: Position,
+    /// Revision of the buffer as of the last full refresh, or `None` before one.
+    drawn_revision: Option<(u64, u64)>,
+    /// When the current buffer was last considered for `:set autosave`.
+    last_autosave: Instant,
+    /// Draw the blinking `‸` glyph instead of the terminal's own cursor (`editor.custom_cursor`).
+    custom_cursor: bool,
+    /// Mode buffers open in (`editor.start_mode`).
+    start_mode: EditorMode,
+    /// Open untitled and empty new buffers in insert mode (`editor.new_buffer_insert`).
+    new_buffer_insert: bool,
+    /// `:q` on the last open buffer opens a new untitled one (`editor.quit_last_opens_new`).
+    quit_last_opens_new: bool,
+    settings: EditorSettings,
+    registers: Registers,
+    /// OS clipboard behind `:set clipboard=system`, opened the first time it is needed.
+    clipboard: Option<SharedClipboard>,
+    tabs: Vec<String>,
+    snapshots: HashMap<String, SnapshotRing>,
+    frame_buffer: FrameBuffer,
+    replaying_macro: bool,
+    /// Pattern of the last `:/` search, kept highlighted while `:set hlsearch` is on.
+    last_search: Option<String>,
+    /// Cursor and scroll offset to return to when an `incsearch` preview is cancelled.
+    incsearch_origin: Option<(Location, usize)>,
+    /// One-based lines to place the cursor on the next time each buffer is opened.
+    start_lines: HashMap<String, usize>,
+    /// Substitutions and changed lines gathered while `:g` runs `:s`, reported once at the end.
+    substitution_tally: Option<(usize, usize)>,
+    /// Buffer `:compare` shows read-only below this one, scrolled in step with it.
+    compare: Option<String>,
 }
 
 #[derive(Debug, Copy, Clone, Default)]
@@ -38,6 +80,8 @@ struct Location {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SaveIntent {
     BufferOnly,
+    /// `:w!`, which writes even when the file changed on disk since it was read.
+    ForcedWrite,
     WriteAndQuit,
     ConditionalQuit,
 }
@@ -46,6 +90,8 @@ enum SaveIntent {
 enum PendingCommand {
     Save(SaveIntent),
     QuitAll,
+    /// `:set autosave` on a buffer that still needs a name to be written under.
+    Autosave,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -61,7 +107,12 @@ enum WordDirection {
 }
 
 const BUFFER_NAME_PROMPT: &str = "Buffer name: ";
+const DISK_CHANGED_MESSAGE: &str =
+    "File changed on disk since it was read; use :w! to overwrite or :reload to discard edits";
+const READ_ONLY_MESSAGE: &str = "Buffer is read-only; use :set noreadonly to allow edits";
 const DIRTY_BUFFER_STATUS: &str = "This buffer is required to be saved.";
+/// Spaces `>>` adds to and `<<` removes from the start of each line.
+const SHIFT_WIDTH: usize = 4;
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub enum EditorMode {
@@ -72,6 +123,17 @@ pub enum EditorMode {
     Navigation,
 }
 
+impl EditorMode {
+    /// Resolve an `editor.start_mode` value; only `read` and `insert` are valid starting modes.
+    pub fn from_start_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "read" => Some(EditorMode::Read),
+            "insert" => Some(EditorMode::Insert),
+            _ => None,
+        }
+    }
+}
+
 impl BufferEditor {
     const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(350);
     pub fn new(name: impl Into<String>) -> Self {
@@ -91,6 +153,25 @@ impl BufferEditor {
             status_message: None,
             cursor_blink_visible: true,
             cursor_last_toggle: Instant::now(),
+            caret: Position::default(),
+            drawn_revision: None,
+            last_autosave: Instant::now(),
+            custom_cursor: true,
+            start_mode: EditorMode::Read,
+            new_buffer_insert: false,
+            quit_last_opens_new: false,
+            settings: EditorSettings::default(),
+            registers: Registers::default(),
+            clipboard: None,
+            tabs: Vec::new(),
+            snapshots: HashMap::new(),
+            frame_buffer: FrameBuffer::default(),
+            replaying_macro: false,
+            last_search: None,
+            incsearch_origin: None,
+            start_lines: HashMap::new(),
+            substitution_tally: None,
+            compare: None,
         }
     }
 
@@ -103,54 +184,180 @@ impl BufferEditor {
         self.name = name.into();
         self.quit = false;
         self.quit_all = false;
-        self.mode = EditorMode::Read;
-        self.prev_mode = EditorMode::Read;
+        self.mode = self.initial_mode();
+        self.prev_mode = self.mode;
         self.location = Location::default();
         self.command_input.clear();
         self.scroll_offset = 0;
         self.view_height = 0;
         self.pending_command = None;
         self.status_message = None;
+        self.incsearch_origin = None;
         self.cursor_blink_visible = true;
         self.cursor_last_toggle = Instant::now();
+        self.settings.filetype = FileType::from_path(&self.name);
+        if self.settings.modeline {
+            self.apply_modelines();
+        }
+        if let Some(line) = self.start_lines.remove(&self.name) {
+            let last_row = self.buffer_line_count().saturating_sub(1);
+            self.location.y = line.saturating_sub(1).min(last_row);
+        }
+    }
+
+    /// Place the cursor on one-based `line` when `name` is next opened, as for `:b name:N`.
+    ///
+    /// Lines past the end of the buffer land on its last line.
+    #[cfg_attr(test, allow(dead_code))]
+    pub fn set_start_line(&mut self, name: impl Into<String>, line: usize) {
+        self.start_lines.insert(name.into(), line);
+    }
+
+    // Honour `iridium:` modelines in the first and last few lines of the buffer.
+    fn apply_modelines(&mut self) {
+        let candidates: Vec<String> = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            let Some(buffer) = store.get(self.name.as_str()) else {
+                return;
+            };
+            let lines = buffer.lines();
+            let tail_start = lines
+                .len()
+                .saturating_sub(MODELINE_LINES)
+                .max(MODELINE_LINES);
+            lines
+                .iter()
+                .take(MODELINE_LINES)
+                .chain(lines.iter().skip(tail_start))
+                .cloned()
+                .collect()
+        };
+        for line in &candidates {
+            self.settings.apply_modeline(line);
+        }
+    }
+
+    /// Open several buffers as tabs of one session, activating the first.
+    ///
+    /// `:n`/`:p` cycle through the tabs in the order given here.
+    pub fn open_tabs(&mut self, names: &[String]) {
+        self.tabs.clear();
+        for name in names {
+            if !self.tabs.contains(name) {
+                self.tabs.push(name.clone());
+            }
+        }
+        if let Some(first) = self.tabs.first().cloned() {
+            self.open(first);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn tabs(&self) -> &[String] {
+        &self.tabs
+    }
+
+    #[allow(dead_code)]
+    pub fn active_buffer(&self) -> &str {
+        &self.name
+    }
+
+    /// Follow a buffer renamed in the store from `old_name` to `new_name`.
+    ///
+    /// Updates the active buffer, its tab entry and any per-buffer state keyed by name so the
+    /// session keeps editing the same content. Returns whether anything referred to `old_name`.
+    pub fn rename_buffer_references(&mut self, old_name: &str, new_name: &str) -> bool {
+        let mut updated = false;
+        if self.name == old_name {
+            self.name = new_name.to_string();
+            updated = true;
+        }
+        for tab in self.tabs.iter_mut().filter(|tab| *tab == old_name) {
+            *tab = new_name.to_string();
+            updated = true;
+        }
+        if let Some(ring) = self.snapshots.remove(old_name) {
+            self.snapshots.insert(new_name.to_string(), ring);
+            updated = true;
+        }
+        if let Some(name) = self.compare.as_mut().filter(|name| *name == old_name) {
+            *name = new_name.to_string();
+            updated = true;
+        }
+        if let Some(line) = self.start_lines.remove(old_name) {
+            self.start_lines.insert(new_name.to_string(), line);
+            updated = true;
+        }
+        updated
     }
 
-    pub fn run(&mut self) {
+    fn tab_bar_rows(&self) -> usize {
+        usize::from(self.tabs.len() > 1)
+    }
+
+    // Text rows the active buffer gets on a terminal `height` rows tall: everything but the
+    // command line and tab bar, less the bottom pane while `:compare` is on.
+    fn content_height(&self, height: usize) -> usize {
+        let rows = height.saturating_sub(1 + self.tab_bar_rows());
+        SplitLayout::active_rows(rows, self.compare.is_some())
+    }
+
+    /// Run the editor session until the user quits.
+    ///
+    /// Returns an [`ErrorKind::Unsupported`] error without
+    /// touching the terminal when stdin/stdout is not a TTY.
+    pub fn run(&mut self) -> Result<(), Error> {
         self.quit = false;
-        self.term
-            .enter()
-            .expect("failed to prepare terminal session");
+        self.term.enter()?;
+        self.frame_buffer.invalidate();
         let result = self.repl();
-        Terminal::terminate().unwrap();
-        result.unwrap();
+        self.term.terminate()?;
+        result
     }
 
     fn repl(&mut self) -> Result<(), Error> {
         self.ensure_cursor_visible()?;
+        let mut redraw = true;
         loop {
-            self.refresh_screen()?;
+            // A poll that times out only advances the cursor blink, so unless the buffer was
+            // changed from elsewhere just the glyph is redrawn.
+            if redraw || self.buffer_revision() != self.drawn_revision {
+                self.refresh_screen()?;
+            } else {
+                self.refresh_cursor()?;
+            }
 
             if self.quit {
                 break;
             }
 
+            redraw = false;
             if let Some(event) = Self::poll_event_with_timeout(Self::CURSOR_BLINK_INTERVAL)? {
+                redraw = true;
                 if self.handle_prompt_input(&event)? {
                     continue;
                 }
 
-                if let Some(action) =
-                    self.input
-                        .process(&event, &self.mode, self.mode == EditorMode::Insert)
-                {
-                    self.apply_input_action(action)?;
-                }
+                self.dispatch(&event)?;
             }
+
+            redraw |= self.autosave_if_due(Instant::now());
         }
 
         Ok(())
     }
 
+    fn dispatch(&mut self, event: &Event) -> Result<(), Error> {
+        if let Some(action) = self
+            .input
+            .process(event, &self.mode, self.mode == EditorMode::Insert)
+        {
+            self.apply_input_action(action)?;
+        }
+        Ok(())
+    }
+
     fn poll_event_with_timeout(timeout: Duration) -> Result<Option<Event>, Error> {
         if poll(timeout)? {
             Ok(Some(read()?))
@@ -239,11 +446,13 @@ impl BufferEditor {
             return Ok(true);
         }
 
-        self.name = desired_name;
+        let old_name = self.name.clone();
+        self.rename_buffer_references(&old_name, &desired_name);
         self.command_input.clear();
         match intent {
             PendingCommand::Save(save_intent) => self.execute_save_intent(save_intent)?,
             PendingCommand::QuitAll => self.execute_quit_all()?,
+            PendingCommand::Autosave => self.report_autosave(),
         }
         self.refresh_screen()?;
         Ok(true)
@@ -252,68 +461,39 @@ impl BufferEditor {
     fn move_point(&mut self, key_code: KeyCode) -> Result<(), Error> {
         let Location { mut x, mut y } = self.location;
         let Size { width, height } = Terminal::size()?;
-        let content_height = height.saturating_sub(1);
+        let content_height = self.content_height(height);
         self.view_height = content_height.max(1);
 
-        let buffer_view = View::snapshot(&self.name);
-        let mut line_lengths = if buffer_view.line_count() == 0 {
-            vec![0]
-        } else {
-            (0..buffer_view.line_count())
-                .map(|row| buffer_view.char_count(row))
-                .collect::<Vec<_>>()
-        };
-        let mut line_count = line_lengths.len();
-        if line_count == 0 {
-            line_lengths.push(0);
-            line_count = 1;
-        }
-
         let store_handle = self.term.store_handle();
         let mut store = store_handle.lock().expect("buffer store lock poisoned");
-        if store.get(self.name.as_str()).is_none() {
-            store.open(self.name.clone());
+        let name = self.name.as_str();
+        if store.get(name).is_none() {
+            store.open(name);
         }
-
-        let line_length = |row: usize| -> usize { line_lengths.get(row).copied().unwrap_or(0) };
+        let line_count = store.line_count(name).max(1);
 
         match key_code {
             KeyCode::Up => {
                 if y > 0 {
                     y -= 1;
-                    x = min(x, line_length(y));
+                    x = self.clamp_column(x, store.char_count(name, y));
                 }
             }
-            KeyCode::Down => {
-                if y + 1 < line_count {
-                    y += 1;
-                    x = min(x, line_length(y));
-                } else if self.mode == EditorMode::Insert {
-                    let last_row = line_count.saturating_sub(1);
-                    let last_col = line_length(last_row);
-                    let target_x = x;
-                    let (new_row, _) = store.insert_newline(self.name.as_str(), last_row, last_col);
-                    store.pad_line(self.name.as_str(), new_row, target_x);
-                    line_lengths.push(target_x);
-                    y = new_row;
-                    x = target_x;
-                }
+            KeyCode::Down if y + 1 < line_count => {
+                y += 1;
+                x = self.clamp_column(x, store.char_count(name, y));
             }
             KeyCode::Left => {
                 if x > 0 {
                     x -= 1;
                 } else if y > 0 {
                     y -= 1;
-                    x = line_length(y);
+                    x = store.char_count(name, y);
                 }
             }
             KeyCode::Right => {
-                if x < line_length(y) {
-                    x += 1;
-                } else if self.mode == EditorMode::Insert {
-                    let current_len = line_length(y);
-                    store.insert_char(self.name.as_str(), y, current_len, ' ');
-                    line_lengths[y] = current_len + 1;
+                let current_len = store.char_count(name, y);
+                if x < current_len || self.settings.virtualedit {
                     x += 1;
                 }
             }
@@ -323,7 +503,7 @@ impl BufferEditor {
                 } else {
                     y = 0;
                 }
-                x = min(x, line_length(y));
+                x = self.clamp_column(x, store.char_count(name, y));
             }
             KeyCode::PageDown => {
                 if content_height > 0 {
@@ -332,13 +512,21 @@ impl BufferEditor {
                         y.saturating_add(content_height),
                     );
                 }
-                x = min(x, line_length(y));
+                x = self.clamp_column(x, store.char_count(name, y));
+            }
+            // Enter outside insert mode: the first non-blank of the next line, or of the last.
+            KeyCode::Enter => {
+                y = (y + 1).min(line_count - 1);
+                x = store
+                    .get(name)
+                    .and_then(|buffer| buffer.lines().get(y))
+                    .map_or(0, |line| first_non_blank(line));
             }
             KeyCode::Home => {
                 x = 0;
             }
             KeyCode::End => {
-                x = line_length(y);
+                x = store.char_count(name, y);
                 if width > 0 {
                     x = min(x, width.saturating_sub(1));
                 }
@@ -353,6 +541,22 @@ impl BufferEditor {
         Ok(())
     }
 
+    // Whether the cursor sits in virtual space beyond the end of its line.
+    fn past_line_end(&self) -> bool {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        self.location.x > store.char_count(&self.name, self.location.y)
+    }
+
+    // Under `:set virtualedit` the cursor keeps its column past the end of shorter lines.
+    fn clamp_column(&self, x: usize, line_len: usize) -> usize {
+        if self.settings.virtualedit {
+            x
+        } else {
+            min(x, line_len)
+        }
+    }
+
     fn navigate_line(&mut self, command: NavigationCommand) -> Result<(), Error> {
         match command {
             NavigationCommand::LineStart => self.move_point(KeyCode::Home),
@@ -365,20 +569,14 @@ impl BufferEditor {
     }
 
     fn navigate_page(&mut self, direction: PageDirection) -> Result<(), Error> {
-        let buffer_view = View::snapshot(&self.name);
-        let mut line_lengths = if buffer_view.line_count() == 0 {
-            vec![0]
-        } else {
-            (0..buffer_view.line_count())
-                .map(|row| buffer_view.char_count(row))
-                .collect::<Vec<_>>()
-        };
-        if line_lengths.is_empty() {
-            line_lengths.push(0);
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        let name = self.name.as_str();
+        if store.get(name).is_none() {
+            store.open(name);
         }
 
-        let line_count = line_lengths.len();
-        let line_length = |row: usize| -> usize { line_lengths.get(row).copied().unwrap_or(0) };
+        let line_count = store.line_count(name).max(1);
         let last_row = line_count.saturating_sub(1);
 
         let view_height = self.view_height.max(1);
@@ -387,7 +585,7 @@ impl BufferEditor {
             .scroll_offset
             .saturating_add(view_height.saturating_sub(1))
             .min(last_row);
-        let half_stride = (view_height / 2).max(1);
+        let half_stride = self.settings.scroll_stride(view_height);
 
         let target_y = match direction {
             PageDirection::Up => {
@@ -411,25 +609,7 @@ impl BufferEditor {
             }
         };
 
-        let desired_x = self.location.x;
-        let mut target_x = desired_x;
-
-        let store_handle = self.term.store_handle();
-        let mut store = store_handle.lock().expect("buffer store lock poisoned");
-        if store.get(self.name.as_str()).is_none() {
-            store.open(self.name.clone());
-        }
-
-        if target_y == 0 {
-            target_x = min(desired_x, line_length(target_y));
-        } else if desired_x > line_length(target_y) {
-            store.pad_line(self.name.as_str(), target_y, desired_x);
-            if let Some(len) = line_lengths.get_mut(target_y) {
-                *len = desired_x;
-            }
-        } else {
-            target_x = min(desired_x, line_length(target_y));
-        }
+        let target_x = self.clamp_column(self.location.x, store.char_count(name, target_y));
 
         drop(store);
 
@@ -523,6 +703,7 @@ impl BufferEditor {
             InputAction::EnterPreviousMode => {
                 self.clear_status_message();
                 self.command_input.clear();
+                self.cancel_incsearch();
                 self.enter_last_mode();
                 self.ensure_cursor_visible()?;
                 redraw = true;
@@ -531,6 +712,7 @@ impl BufferEditor {
             InputAction::ExitInsertMode => {
                 self.clear_status_message();
                 self.command_input.clear();
+                self.cancel_incsearch();
                 self.enter_last_mode();
                 self.ensure_cursor_visible()?;
                 redraw = true;
@@ -587,7 +769,12 @@ impl BufferEditor {
             }
             InputAction::DeleteChar => {
                 self.clear_status_message();
-                if self.mode == EditorMode::Insert {
+                if self.mode == EditorMode::Insert && self.past_line_end() {
+                    // Only virtual space lies behind the cursor, so there is nothing to delete.
+                    self.location.x -= 1;
+                    redraw = true;
+                    self.cursor_last_toggle = Instant::now();
+                } else if self.mode == EditorMode::Insert {
                     let position = Position {
                         col: self.location.x,
                         row: self.location.y,
@@ -605,13 +792,113 @@ impl BufferEditor {
                     }
                 }
             }
+            InputAction::DeleteForward => {
+                self.clear_status_message();
+                if self.mode == EditorMode::Insert {
+                    let store_handle = self.term.store_handle();
+                    let mut store = store_handle.lock().expect("buffer store lock poisoned");
+                    let (x, y) = (self.location.x, self.location.y);
+                    let removed = store.delete_range(self.name.as_str(), y, x, x + 1);
+                    drop(store);
+                    if removed.is_some_and(|text| !text.is_empty()) {
+                        redraw = true;
+                        self.cursor_last_toggle = Instant::now();
+                    }
+                }
+            }
+            InputAction::KillToLineStart | InputAction::KillToLineEnd => {
+                self.clear_status_message();
+                if self.mode == EditorMode::Insert {
+                    self.kill_line_segment(action == InputAction::KillToLineStart);
+                    self.ensure_cursor_visible()?;
+                    redraw = true;
+                    self.cursor_last_toggle = Instant::now();
+                }
+            }
+            InputAction::PasteRegister => {
+                self.clear_status_message();
+                if self.mode == EditorMode::Insert {
+                    self.paste_register();
+                    self.ensure_cursor_visible()?;
+                    redraw = true;
+                    self.cursor_last_toggle = Instant::now();
+                }
+            }
+            InputAction::YankLines { register, count } => {
+                self.clear_status_message();
+                if self.mode != EditorMode::Insert {
+                    self.yank_lines(register, count);
+                    redraw = true;
+                }
+            }
+            InputAction::PasteLines { register } => {
+                self.clear_status_message();
+                if self.mode != EditorMode::Insert {
+                    self.paste_lines(register);
+                    self.ensure_cursor_visible()?;
+                    redraw = true;
+                    self.cursor_last_toggle = Instant::now();
+                }
+            }
+            InputAction::StartRecording(_) => {
+                self.clear_status_message();
+                redraw = true;
+            }
+            InputAction::StopRecording { register, keys } => {
+                self.registers.record(register, keys);
+                redraw = true;
+            }
+            InputAction::ReplayMacro(register) => {
+                self.clear_status_message();
+                // Replays nested inside a macro are skipped so a macro can never recurse.
+                if !self.replaying_macro {
+                    match self.registers.get(Some(register)) {
+                        Some(lines) => {
+                            let keys = lines.join("\n");
+                            self.replaying_macro = true;
+                            let result = self.replay_keys(&keys);
+                            self.replaying_macro = false;
+                            result?;
+                        }
+                        None => self.set_status_message(format!("Macro @{register} is empty")),
+                    }
+                }
+                redraw = true;
+            }
+            InputAction::RepeatSearch { reverse } => {
+                self.clear_status_message();
+                if self.mode != EditorMode::Insert {
+                    self.repeat_search(reverse);
+                    self.ensure_cursor_visible()?;
+                    redraw = true;
+                }
+            }
+            InputAction::JoinLines(count) => {
+                self.clear_status_message();
+                if self.mode != EditorMode::Insert {
+                    self.join_lines(count);
+                    self.ensure_cursor_visible()?;
+                    redraw = true;
+                    self.cursor_last_toggle = Instant::now();
+                }
+            }
+            InputAction::ShiftLines { count, outdent } => {
+                self.clear_status_message();
+                if self.mode != EditorMode::Insert {
+                    self.shift_lines(count, outdent);
+                    redraw = true;
+                }
+            }
             InputAction::UpdateCommandBuffer(buffer) => {
                 self.clear_status_message();
                 self.command_input = format!(":{}", buffer);
+                self.preview_incsearch()?;
                 redraw = true;
             }
             InputAction::ExecuteCommand(command) => {
                 self.clear_status_message();
+                // The search itself starts over from where the preview began.
+                self.cancel_incsearch();
                 keep_command_text = self.process_colon_command(command.trim())?;
 
                 if !keep_command_text {
@@ -645,10 +932,18 @@ impl BufferEditor {
 
         if self.quit {
             Terminal::clear_screen()?;
+            self.frame_buffer.invalidate();
             let _ = Terminal::print("Closed editor.\r\n");
         } else {
-            let buffer_view = View::snapshot(&self.name);
-            View::render(
+            let buffer_view = View::snapshot(&self.name)
+                .with_settings(&self.settings)
+                .with_search(self.search_highlight(), &self.settings)
+                .with_showmatch((self.location.y, self.location.x), &self.settings)
+                .with_tabs(&self.tabs)
+                .with_recording(self.input.recording())
+                .with_compare(self.compare_pane());
+            let size = Terminal::size()?;
+            let frame = View::compose(
                 &buffer_view,
                 &self.name,
                 &self.mode,
@@ -659,8 +954,10 @@ impl BufferEditor {
                     self.location.y.saturating_add(1),
                     self.location.x.saturating_add(1),
                 ),
-            )?;
-            let Size { width, height } = Terminal::size()?;
+                size,
+            );
+            self.frame_buffer.present(frame)?;
+            let Size { width, height } = size;
             let cursor_position = if !self.command_input.is_empty() {
                 let column = self
                     .command_input
@@ -672,46 +969,63 @@ impl BufferEditor {
                     row: height.saturating_sub(1),
                 }
             } else {
-                let content_height = height.saturating_sub(1);
+                let tab_rows = buffer_view.tab_bar_rows();
+                let content_height = self.content_height(height);
                 let screen_row = self.location.y.saturating_sub(self.scroll_offset);
                 Position {
                     col: self.location.x.min(width.saturating_sub(1)),
-                    row: screen_row.min(content_height.saturating_sub(1)),
+                    row: tab_rows + screen_row.min(content_height.saturating_sub(1)),
                 }
             };
 
-            Terminal::move_caret_to(cursor_position)?;
+            self.caret = cursor_position;
+            self.drawn_revision = self.buffer_revision();
+            self.draw_cursor()?;
+        }
 
-            // Draw custom cursor glyph (U+2038: ‸) at the caret position.
-            let now = Instant::now();
-            if now.duration_since(self.cursor_last_toggle) >= Self::CURSOR_BLINK_INTERVAL {
-                self.cursor_blink_visible = !self.cursor_blink_visible;
-                self.cursor_last_toggle = now;
-            }
+        Terminal::execute()?;
+        Ok(())
+    }
 
-            let glyph = if self.cursor_blink_visible {
-                '\u{2038}'.to_string()
-            } else {
-                buffer_view
-                    .char_at(self.location.y, self.location.x)
-                    .map(|ch| ch.to_string())
-                    .unwrap_or_else(|| " ".to_string())
-            };
-            Terminal::print(&glyph)?;
-            Terminal::move_caret_to(cursor_position)?;
+    // Redraw only the cursor, at the caret position of the last full refresh.
+    fn refresh_cursor(&mut self) -> Result<(), Error> {
+        if std::env::var("IRIDIUM_SKIP_EDITOR").is_ok() || !self.custom_cursor {
+            return Ok(());
         }
-
+        Terminal::hide_caret()?;
+        self.draw_cursor()?;
         Terminal::execute()?;
         Ok(())
     }
 
+    fn draw_cursor(&mut self) -> Result<(), Error> {
+        Terminal::move_caret_to(self.caret)?;
+        match self.cursor_glyph(Instant::now()) {
+            Some(glyph) => {
+                Terminal::print(&glyph)?;
+                self.frame_buffer.invalidate_row(self.caret.row);
+                Terminal::move_caret_to(self.caret)?;
+            }
+            None => Terminal::show_caret()?,
+        }
+        Ok(())
+    }
+
+    fn buffer_revision(&self) -> Option<(u64, u64)> {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store
+            .get(self.name.as_str())
+            .map(|buffer| buffer.revision())
+    }
+
     fn ensure_cursor_visible(&mut self) -> Result<(), Error> {
         if std::env::var("IRIDIUM_SKIP_EDITOR").is_ok() {
             return Ok(());
         }
         let Size { width, height } = Terminal::size()?;
 
-        let content_height = height.saturating_sub(1);
+        let content_height = self.content_height(height);
         if content_height > 0 {
             if self.location.y < self.scroll_offset {
                 self.scroll_offset = self.location.y;
@@ -737,6 +1051,10 @@ impl BufferEditor {
     }
 
     fn enter_insert_mode(&mut self) {
+        if self.buffer_is_read_only() {
+            self.set_status_message(READ_ONLY_MESSAGE);
+            return;
+        }
         self.prev_mode = self.mode;
         self.mode = EditorMode::Insert;
     }
@@ -805,6 +1123,16 @@ impl BufferEditor {
     }
 
     fn handle_save_command(&mut self, intent: SaveIntent) -> Result<bool, Error> {
+        if intent != SaveIntent::ConditionalQuit && self.buffer_is_scratch() {
+            self.set_status_message(SCRATCH_SAVE_MESSAGE);
+            return Ok(false);
+        }
+
+        if intent != SaveIntent::ConditionalQuit && self.buffer_is_read_only() {
+            self.set_status_message(READ_ONLY_MESSAGE);
+            return Ok(false);
+        }
+
         if self.buffer_requires_name() {
             self.pending_command = Some(PendingCommand::Save(intent));
             self.command_input = BUFFER_NAME_PROMPT.to_string();
@@ -829,14 +1157,26 @@ impl BufferEditor {
     }
 
     fn execute_save_intent(&mut self, intent: SaveIntent) -> Result<(), Error> {
+        if matches!(intent, SaveIntent::BufferOnly | SaveIntent::WriteAndQuit)
+            && self.buffer_disk_changed()
+        {
+            self.set_status_message(DISK_CHANGED_MESSAGE);
+            self.pending_command = None;
+            return Ok(());
+        }
+
         match intent {
-            SaveIntent::BufferOnly => {
-                self.save_current_buffer()?;
-            }
-            SaveIntent::WriteAndQuit => {
-                self.save_current_buffer()?;
-                self.quit = true;
-            }
+            SaveIntent::BufferOnly | SaveIntent::ForcedWrite => match self.save_current_buffer() {
+                Ok(()) => self.record_save_snapshot(),
+                Err(err) => self.set_status_message(format!("Write failed: {err}")),
+            },
+            SaveIntent::WriteAndQuit => match self.save_current_buffer() {
+                Ok(()) => {
+                    self.record_save_snapshot();
+                    self.quit = true;
+                }
+                Err(err) => self.set_status_message(format!("Write failed: {err}")),
+            },
             SaveIntent::ConditionalQuit => {
                 if self.buffer_is_dirty() {
                     println!("Buffer has unsaved changes. Use :w or :wq.");
@@ -850,6 +1190,78 @@ impl BufferEditor {
         Ok(())
     }
 
+    // `:reload` discards edits and re-reads the file, e.g. after an external change.
+    fn reload_current_buffer(&mut self) {
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        let result = store.load_from_disk(self.name.as_str());
+        let last_row = store.line_count(self.name.as_str()).saturating_sub(1);
+        drop(store);
+
+        match result {
+            Ok(()) => {
+                self.location.y = self.location.y.min(last_row);
+                self.location.x = 0;
+                self.set_status_message("Reloaded from disk");
+            }
+            Err(err) => self.set_status_message(format!("Reload failed: {err}")),
+        }
+    }
+
+    // Every successful write keeps a copy of the buffer for `:restore`.
+    fn record_save_snapshot(&mut self) {
+        let lines = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            match store.get(self.name.as_str()) {
+                Some(buffer) => buffer.lines().to_vec(),
+                None => return,
+            }
+        };
+        self.snapshots
+            .entry(self.name.clone())
+            .or_default()
+            .push(lines, Instant::now());
+    }
+
+    fn list_snapshots(&mut self) {
+        let listing = self
+            .snapshots
+            .get(&self.name)
+            .filter(|ring| !ring.is_empty())
+            .map(|ring| ring.describe(Instant::now()));
+        match listing {
+            Some(listing) => self.set_status_message(listing),
+            None => self.set_status_message("No snapshots for this buffer"),
+        }
+    }
+
+    fn restore_snapshot(&mut self, argument: &str) {
+        let Ok(number) = argument.parse::<usize>() else {
+            self.set_status_message(format!("Invalid snapshot: {argument}"));
+            return;
+        };
+        let Some(lines) = self
+            .snapshots
+            .get(&self.name)
+            .and_then(|ring| ring.get(number))
+            .map(|snapshot| snapshot.lines.clone())
+        else {
+            self.set_status_message(format!("No snapshot {number}"));
+            return;
+        };
+
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        store.replace_lines(self.name.as_str(), lines);
+        let last_row = store.line_count(self.name.as_str()).saturating_sub(1);
+        drop(store);
+
+        self.location.y = self.location.y.min(last_row);
+        self.location.x = 0;
+        self.set_status_message(format!("Restored snapshot {number}"));
+    }
+
     fn execute_quit_all(&mut self) -> Result<(), Error> {
         self.quit = true;
         self.quit_all = true;
@@ -857,12 +1269,130 @@ impl BufferEditor {
         Ok(())
     }
 
+    // :w <path> writes a copy without touching the buffer's own name or dirty state.
+    fn write_buffer_copy(&mut self, path: &str) {
+        let result = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            store.write_copy(self.name.as_str(), Path::new(path))
+        };
+        match result {
+            Ok(()) => self.set_status_message(format!("Wrote {path}")),
+            Err(err) => self.set_status_message(format!("Write failed: {err}")),
+        }
+    }
+
+    /// `:A,Bw path`: write the one-based lines `first..=last` to `path`, leaving the buffer's
+    /// own save state alone.
+    fn write_range_copy(&mut self, first: usize, last: usize, path: &str) {
+        if path.is_empty() {
+            self.set_status_message("No file name");
+            return;
+        }
+        if first == 0 || first > last {
+            self.set_status_message(format!("Invalid range: {first},{last}"));
+            return;
+        }
+        let result = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            store.write_range_copy(self.name.as_str(), first - 1..last, Path::new(path))
+        };
+        match result {
+            Ok(()) => self.set_status_message(format!(
+                "Wrote {} to {path}",
+                plural(last - first + 1, "line")
+            )),
+            Err(err) => self.set_status_message(format!("Write failed: {err}")),
+        }
+    }
+
+    fn buffer_disk_changed(&self) -> bool {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store.disk_changed(self.name.as_str())
+    }
+
+    fn buffer_is_scratch(&self) -> bool {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store.is_scratch(self.name.as_str())
+    }
+
+    fn buffer_is_read_only(&self) -> bool {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store.is_read_only(self.name.as_str())
+    }
+
     fn buffer_requires_name(&self) -> bool {
         let store_handle = self.term.store_handle();
         let store = store_handle.lock().expect("buffer store lock poisoned");
         store.requires_name(self.name.as_str())
     }
 
+    /// Apply the startup options from the `editor` config section.
+    pub fn configure(&mut self, config: &EditorConfig) {
+        self.input.set_quit_key(config.quit_key);
+        self.custom_cursor = config.custom_cursor;
+        self.start_mode = config.start_mode;
+        self.new_buffer_insert = config.new_buffer_insert;
+        self.quit_last_opens_new = config.quit_last_opens_new;
+        self.settings.scroll = config.scroll;
+        self.settings.report = Some(config.report);
+        self.settings.modeline = config.modeline;
+        self.settings.autowriteall = config.autowriteall;
+        self.settings.hide_mode = !config.showmode;
+    }
+
+    // Untitled buffers and named buffers that are still empty and unedited count as new.
+    // Read-only buffers always start in read mode.
+    fn initial_mode(&self) -> EditorMode {
+        let mode = if self.new_buffer_insert && self.buffer_is_new() {
+            EditorMode::Insert
+        } else {
+            self.start_mode
+        };
+        if mode == EditorMode::Insert && self.buffer_is_read_only() {
+            return EditorMode::Read;
+        }
+        mode
+    }
+
+    fn buffer_is_new(&self) -> bool {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store.get(self.name.as_str()).is_none_or(|buffer| {
+            buffer.requires_name() || (buffer.lines().is_empty() && !buffer.is_dirty())
+        })
+    }
+
+    // What to draw at the caret: the custom glyph (U+2038: ‸) or, during the off phase of the
+    // blink, the character underneath, read straight from the store. `None` leaves the cursor to
+    // the terminal.
+    fn cursor_glyph(&mut self, now: Instant) -> Option<String> {
+        if !self.custom_cursor {
+            return None;
+        }
+
+        if now.duration_since(self.cursor_last_toggle) >= Self::CURSOR_BLINK_INTERVAL {
+            self.cursor_blink_visible = !self.cursor_blink_visible;
+            self.cursor_last_toggle = now;
+        }
+
+        let glyph = if self.cursor_blink_visible {
+            '\u{2038}'.to_string()
+        } else {
+            let store_handle = Terminal::instance().store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            store
+                .char_at(&self.name, self.location.y, self.location.x)
+                .map(|ch| ch.to_string())
+                .unwrap_or_else(|| " ".to_string())
+        };
+        Some(glyph)
+    }
+
     pub fn take_quit_all_request(&mut self) -> bool {
         let requested = self.quit_all;
         if requested {
@@ -888,11 +1418,17 @@ impl BufferEditor {
     fn cycle_buffer(&mut self, forward: bool) -> Result<(), Error> {
         let store_handle = self.term.store_handle();
         let store = store_handle.lock().expect("buffer store lock poisoned");
-        let mut buffers = store.list();
+        // A tab set opened together cycles in its own order; otherwise walk every buffer.
+        let buffers = if self.tabs.len() > 1 && self.tabs.contains(&self.name) {
+            self.tabs.clone()
+        } else {
+            let mut buffers = store.list();
+            buffers.sort();
+            buffers
+        };
         if buffers.len() <= 1 {
             return Ok(());
         }
-        buffers.sort();
         let Some(idx) = buffers.iter().position(|name| name == &self.name) else {
             return Ok(());
         };
@@ -904,6 +1440,9 @@ impl BufferEditor {
         };
         let next_name = buffers[next_idx].clone();
         drop(store);
+        if !self.autowrite_before_leaving() {
+            return Ok(());
+        }
 
         let previous_mode = self.mode;
         self.open(next_name);
@@ -919,16 +1458,28 @@ impl BufferEditor {
     }
 
     fn switch_to_buffer(&mut self, name: &str) -> Result<(), Error> {
-        let trimmed = name.trim();
-        if trimmed.is_empty() {
+        if name.trim().is_empty() {
             println!(":b requires a buffer name");
             return Ok(());
         }
+        if !self.autowrite_before_leaving() {
+            return Ok(());
+        }
+        self.enter_buffer(name)
+    }
+
+    // Make `name` the current buffer, opening it in the store if needed.
+    fn enter_buffer(&mut self, name: &str) -> Result<(), Error> {
+        let trimmed = name.trim();
 
         {
             let store_handle = self.term.store_handle();
             let mut store = store_handle.lock().expect("buffer store lock poisoned");
-            store.open(trimmed);
+            if let Err(err) = store.try_open(trimmed) {
+                drop(store);
+                self.set_status_message(err.to_string());
+                return Ok(());
+            }
         }
 
         let previous_mode = self.mode;
@@ -945,6 +1496,9 @@ impl BufferEditor {
     }
 
     fn close_current_buffer(&mut self, force: bool) -> Result<(), Error> {
+        if !force && !self.autowrite_before_leaving() {
+            return Ok(());
+        }
         let current_name = self.name.clone();
         let store_handle = self.term.store_handle();
         let mut store = store_handle.lock().expect("buffer store lock poisoned");
@@ -962,7 +1516,19 @@ impl BufferEditor {
         let _ = store.mark_closed(current_name.as_str());
         let mut remaining = store.open_buffers();
         drop(store);
-
+        self.tabs.retain(|name| name != &current_name);
+
+        if remaining.is_empty() && self.quit_last_opens_new {
+            let untitled = {
+                let store_handle = self.term.store_handle();
+                let mut store = store_handle.lock().expect("buffer store lock poisoned");
+                let name = store.generate_untitled_name();
+                store.try_open_untitled(name.clone()).map(|_| name)
+            };
+            if let Ok(name) = untitled {
+                return self.enter_buffer(&name);
+            }
+        }
         if remaining.is_empty() {
             self.quit = true;
             self.quit_all = true;
@@ -976,10 +1542,43 @@ impl BufferEditor {
             .cloned()
             .unwrap_or_else(|| remaining[0].clone());
 
-        self.switch_to_buffer(&next_name)?;
+        self.enter_buffer(&next_name)?;
         Ok(())
     }
 
+    // `:set autowriteall` — write the current buffer before leaving it. Untitled, scratch and
+    // read-only buffers are left for `:w`; returns false when a failed write should keep the
+    // cursor here.
+    fn autowrite_before_leaving(&mut self) -> bool {
+        if !self.settings.autowriteall
+            || self.buffer_requires_name()
+            || self.buffer_is_scratch()
+            || self.buffer_is_read_only()
+        {
+            return true;
+        }
+        if self.buffer_is_dirty() && self.buffer_disk_changed() {
+            self.set_status_message(format!("Autowrite skipped: {DISK_CHANGED_MESSAGE}"));
+            return false;
+        }
+        let written = {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.save_if_dirty(self.name.as_str())
+        };
+        match written {
+            Ok(true) => {
+                self.record_save_snapshot();
+                true
+            }
+            Ok(false) => true,
+            Err(err) => {
+                self.set_status_message(format!("Autowrite failed: {err}"));
+                false
+            }
+        }
+    }
+
     pub fn execute_colon_command(&mut self, command: &str) -> Result<(), Error> {
         self.process_colon_command(command.trim()).map(|_| ())
     }
@@ -988,383 +1587,3129 @@ impl BufferEditor {
         self.quit
     }
 
-    fn process_colon_command(&mut self, command: &str) -> Result<bool, Error> {
-        let mut keep_command_text = false;
-        if command.is_empty() {
-            self.restore_after_command();
-            return Ok(keep_command_text);
+    // Returns whether the command line is kept for a name prompt, which enabling autosave on an
+    // untitled buffer needs before anything can be written.
+    fn apply_set_command(&mut self, arguments: &str) -> Result<bool, Error> {
+        if self.apply_read_only_command(arguments) {
+            return Ok(false);
+        }
+        let previous_autosave = self.settings.autosave;
+        for argument in arguments.split_whitespace() {
+            if let Some(value) = ["undolevels=", "ul="]
+                .into_iter()
+                .find_map(|prefix| argument.strip_prefix(prefix))
+            {
+                self.apply_undo_levels(value);
+                continue;
+            }
+            if let Some(value) = ["backupdir=", "bdir="]
+                .into_iter()
+                .find_map(|prefix| argument.strip_prefix(prefix))
+            {
+                self.apply_backup_dir(value);
+                continue;
+            }
+            if let Some(enabled) = match argument {
+                "backup" | "bk" => Some(true),
+                "nobackup" | "nobk" => Some(false),
+                _ => None,
+            } {
+                self.apply_backup(enabled);
+                continue;
+            }
+            if let Err(message) = self.settings.apply(argument) {
+                self.set_status_message(message);
+                return Ok(false);
+            }
+        }
+        if self.settings.clipboard == ClipboardMode::System && self.system_clipboard().is_none() {
+            self.set_status_message("System clipboard unavailable; using the internal register");
+        }
+        if self.settings.autosave == previous_autosave {
+            return Ok(false);
         }
 
-        if command == "q" {
-            self.close_current_buffer(false)?;
-        } else if command == "q!" {
-            self.close_current_buffer(true)?;
-        } else if command == "i" {
-            self.enter_insert_mode();
-        } else if command == "r" {
-            self.enter_read_mode();
-        } else if let Some(rest) = command.strip_prefix('b') {
-            self.jump_to_buffer(rest.trim()).ok();
-        } else if command == "n" {
-            self.cycle_buffer(true)?;
-        } else if command == "p" {
-            self.cycle_buffer(false)?;
-        } else if command == "w" {
-            keep_command_text = self.handle_save_command(SaveIntent::BufferOnly)?;
-        } else if command == "wq" {
-            keep_command_text = self.handle_save_command(SaveIntent::WriteAndQuit)?;
-        } else if command == "x" {
-            keep_command_text = self.handle_save_command(SaveIntent::ConditionalQuit)?;
-        } else if command == "s" {
-            self.save_current_buffer_in_memory();
-        } else if command == "Q" {
-            keep_command_text = self.handle_quit_all_command()?;
+        self.last_autosave = Instant::now();
+        if self.settings.autosave > 0 && self.buffer_requires_name() {
+            self.pending_command = Some(PendingCommand::Autosave);
+            self.command_input = BUFFER_NAME_PROMPT.to_string();
+            self.refresh_screen()?;
+            return Ok(true);
         }
+        self.report_autosave();
+        Ok(false)
+    }
 
-        Ok(keep_command_text)
+    // `:set readonly` / `:set noreadonly`, optionally followed by `all` to cover every buffer.
+    // Returns whether `arguments` named the option at all.
+    fn apply_read_only_command(&mut self, arguments: &str) -> bool {
+        let mut words = arguments.split_whitespace();
+        let read_only = match words.next() {
+            Some("readonly" | "ro") => true,
+            Some("noreadonly" | "noro") => false,
+            _ => return false,
+        };
+        let all = match (words.next(), words.next()) {
+            (None, _) => false,
+            (Some("all"), None) => true,
+            (Some(other), _) => {
+                self.set_status_message(format!("Unknown option: {other}"));
+                return true;
+            }
+        };
+
+        {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            if all {
+                store.set_all_read_only(read_only);
+            } else {
+                store.set_read_only(self.name.as_str(), read_only);
+            }
+        }
+        if read_only {
+            for mode in [&mut self.mode, &mut self.prev_mode] {
+                if *mode == EditorMode::Insert {
+                    *mode = EditorMode::Read;
+                }
+            }
+        }
+        self.set_status_message(match (read_only, all) {
+            (true, true) => "All buffers read-only",
+            (true, false) => "Buffer read-only",
+            (false, true) => "All buffers writable",
+            (false, false) => "Buffer writable",
+        });
+        true
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::store::buffer_store::BufferStore;
-    use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+    // `:set undolevels=N` caps the undo history of every buffer; `0` disables undo.
+    fn apply_undo_levels(&mut self, value: &str) {
+        let Ok(levels) = value.parse::<usize>() else {
+            self.set_status_message(format!("Invalid undolevels: {value}"));
+            return;
+        };
+        {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.set_undo_levels(levels);
+        }
+        self.set_status_message(format!("Undo levels: {levels}"));
+    }
 
-    fn test_lock() -> MutexGuard<'static, ()> {
-        static GUARD: OnceLock<Mutex<()>> = OnceLock::new();
-        GUARD
-            .get_or_init(|| Mutex::new(()))
-            .lock()
-            .unwrap_or_else(|err| err.into_inner())
+    // `:set backup` / `:set nobackup` toggles backups of the file each save replaces.
+    fn apply_backup(&mut self, enabled: bool) {
+        {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.set_backup(enabled);
+        }
+        self.set_status_message(if enabled { "Backups on" } else { "Backups off" });
+    }
+
+    // `:set backupdir=DIR` collects backups in `DIR`; an empty value puts them beside each file.
+    fn apply_backup_dir(&mut self, value: &str) {
+        let dir = (!value.is_empty()).then(|| expand_path(value));
+        let message = match &dir {
+            Some(dir) => format!("Backup directory: {}", dir.display()),
+            None => "Backups written beside files".to_string(),
+        };
+        {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.set_backup_dir(dir);
+        }
+        self.set_status_message(message);
+    }
+
+    fn report_autosave(&mut self) {
+        match self.settings.autosave {
+            0 => self.set_status_message("Autosave off"),
+            ms => self.set_status_message(format!("Autosave every {ms} ms")),
+        }
+    }
+
+    // Write the buffer once the autosave interval has passed since the last attempt. Clean,
+    // untitled and scratch buffers are skipped without an error.
+    // Returns whether an autosave was attempted, which may change the status line.
+    fn autosave_if_due(&mut self, now: Instant) -> bool {
+        let interval = Duration::from_millis(self.settings.autosave);
+        if interval.is_zero() || now.saturating_duration_since(self.last_autosave) < interval {
+            return false;
+        }
+        self.last_autosave = now;
+        if !self.buffer_is_dirty()
+            || self.buffer_requires_name()
+            || self.buffer_is_scratch()
+            || self.buffer_is_read_only()
+        {
+            return false;
+        }
+        if self.buffer_disk_changed() {
+            self.set_status_message(format!("Autosave skipped: {DISK_CHANGED_MESSAGE}"));
+            return true;
+        }
+        match self.save_current_buffer() {
+            Ok(()) => self.record_save_snapshot(),
+            Err(err) => self.set_status_message(format!("Autosave failed: {err}")),
+        }
+        true
+    }
+
+    // `@<reg>` types the register's keys through a handler of their own, so a recording in
+    // progress captures the `@<reg>` rather than everything it replays.
+    fn replay_keys(&mut self, keys: &str) -> Result<(), Error> {
+        let mut input = self.input.for_replay();
+        for event in parse_keys(keys) {
+            if let Some(action) = input.process(&event, &self.mode, self.mode == EditorMode::Insert)
+            {
+                self.apply_input_action(action)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Ctrl+U kills back to column 0, Ctrl+K kills to the end of the line.
+    fn kill_line_segment(&mut self, to_start: bool) {
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        let name = self.name.as_str();
+        let row = self.location.y;
+        let col = self.location.x.min(store.char_count(name, row));
+        let killed = if to_start {
+            store.delete_range(name, row, 0, col)
+        } else {
+            store.delete_range(name, row, col, usize::MAX)
+        };
+        drop(store);
+
+        if let Some(text) = killed {
+            self.registers.set_unnamed(text);
+            self.export_clipboard();
+        }
+        self.location.x = if to_start { 0 } else { col };
+    }
+
+    // Ctrl+Y re-inserts the most recently killed text at the cursor.
+    fn paste_register(&mut self) {
+        self.import_clipboard();
+        if let Some(text) = self.registers.unnamed() {
+            self.insert_text(&text);
+        }
+    }
+
+    // Type `text` in at the cursor, leaving the cursor just past it.
+    fn insert_text(&mut self, text: &str) {
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        for ch in text.chars() {
+            if ch == '\n' {
+                let (y, x) =
+                    store.insert_newline(self.name.as_str(), self.location.y, self.location.x);
+                self.location = Location { x, y };
+            } else {
+                store.insert_char(self.name.as_str(), self.location.y, self.location.x, ch);
+                self.location.x += 1;
+            }
+        }
+    }
+
+    // `["x]Nyy` copies N lines starting at the cursor into a register.
+    fn yank_lines(&mut self, register: Option<char>, count: usize) {
+        let lines: Vec<String> = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            let Some(buffer) = store.get(self.name.as_str()) else {
+                return;
+            };
+            buffer
+                .lines()
+                .iter()
+                .skip(self.location.y)
+                .take(count.max(1))
+                .cloned()
+                .collect()
+        };
+        if lines.is_empty() {
+            return;
+        }
+        let yanked = lines.len();
+        self.registers.store(register, lines);
+        self.export_clipboard();
+        self.set_status_message(format!("{yanked} lines yanked"));
+    }
+
+    // `["x]p` puts a register's lines below the cursor line, or a charwise kill just after the
+    // cursor with the cursor left on its last character.
+    fn paste_lines(&mut self, register: Option<char>) {
+        if register.is_none_or(|name| name == UNNAMED_REGISTER) {
+            self.import_clipboard();
+        }
+        let Some(lines) = self.registers.get(register).map(<[String]>::to_vec) else {
+            let name = register.unwrap_or(UNNAMED_REGISTER);
+            self.set_status_message(format!("Nothing in register {name}"));
+            return;
+        };
+        if self.registers.kind(register) == Some(RegisterKind::Charwise) {
+            let line_chars = {
+                let store_handle = self.term.store_handle();
+                let store = store_handle.lock().expect("buffer store lock poisoned");
+                store.char_count(self.name.as_str(), self.location.y)
+            };
+            self.location.x = (self.location.x + 1).min(line_chars);
+            self.insert_text(&lines.join("\n"));
+            self.location.x = self.location.x.saturating_sub(1);
+            return;
+        }
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        let name = self.name.as_str();
+        let row = if store.line_count(name) == 0 {
+            0
+        } else {
+            self.location.y + 1
+        };
+        if store.insert_lines(name, row, &lines) {
+            self.location = Location { x: 0, y: row };
+        }
+    }
+
+    // The OS clipboard while `:set clipboard=system` is on and it can be opened.
+    fn system_clipboard(&mut self) -> Option<SharedClipboard> {
+        if self.settings.clipboard != ClipboardMode::System {
+            return None;
+        }
+        if self.clipboard.is_none() {
+            self.clipboard = SharedClipboard::system();
+        }
+        self.clipboard.clone()
+    }
+
+    // Copy the unnamed register to the system clipboard after a yank or kill.
+    fn export_clipboard(&mut self) {
+        if let Some(clipboard) = self.system_clipboard()
+            && let Some(text) = self.registers.unnamed()
+        {
+            clipboard.set_text(&text);
+        }
+    }
+
+    // Before an unnamed paste, take in text another application put on the system clipboard.
+    fn import_clipboard(&mut self) {
+        let Some(text) = self
+            .system_clipboard()
+            .and_then(|clipboard| clipboard.get_text())
+        else {
+            return;
+        };
+        let text = text.strip_suffix('\n').unwrap_or(&text);
+        if self.registers.unnamed().as_deref() != Some(text) {
+            self.registers
+                .store(None, text.split('\n').map(str::to_string).collect());
+        }
+    }
+
+    // `J` / `NJ`: a count of N joins N lines, so anything below two still performs one join.
+    fn join_lines(&mut self, count: usize) {
+        let joins = count.saturating_sub(1).max(1);
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        for _ in 0..joins {
+            let Some(col) = store.join_lines(self.name.as_str(), self.location.y) else {
+                break;
+            };
+            self.location.x = col;
+        }
+    }
+
+    // `>>` and `<<` move `count` lines from the cursor by `SHIFT_WIDTH` spaces; blank lines are
+    // not indented and lines are only outdented by the leading spaces they have.
+    fn shift_lines(&mut self, count: usize, outdent: bool) {
+        if self.buffer_is_read_only() {
+            self.set_status_message(READ_ONLY_MESSAGE);
+            return;
+        }
+        let start = self.location.y;
+        let mut shifted = 0;
+        let current = {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.apply_edit(self.name.as_str(), |lines| {
+                let end = start.saturating_add(count.max(1)).min(lines.len());
+                for line in lines.iter_mut().take(end).skip(start) {
+                    let blanks = line.chars().take_while(|ch| *ch == ' ').count();
+                    if outdent && blanks > 0 {
+                        line.drain(..blanks.min(SHIFT_WIDTH));
+                    } else if !outdent && !line.is_empty() {
+                        line.insert_str(0, &" ".repeat(SHIFT_WIDTH));
+                    } else {
+                        continue;
+                    }
+                    shifted += 1;
+                }
+            });
+            store
+                .get(self.name.as_str())
+                .and_then(|buffer| buffer.lines().get(start).cloned())
+        };
+        if let Some(line) = current {
+            self.location.x = first_non_blank(&line);
+        }
+        let direction = if outdent { '<' } else { '>' };
+        self.report_lines(
+            shifted,
+            format!("{} {direction}ed 1 time", plural(shifted, "line")),
+        );
+    }
+
+    fn buffer_line_count(&self) -> usize {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        store.line_count(self.name.as_str())
+    }
+
+    fn delete_current_line(&mut self) {
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        if store
+            .delete_line(self.name.as_str(), self.location.y)
+            .is_none()
+        {
+            return;
+        }
+        let last_row = store.line_count(self.name.as_str()).saturating_sub(1);
+        drop(store);
+
+        self.location = Location {
+            x: 0,
+            y: self.location.y.min(last_row),
+        };
+    }
+
+    // :undo and :redo step through the buffer's bounded edit history.
+    fn step_history(&mut self, forward: bool) {
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        if store.is_read_only(self.name.as_str()) {
+            drop(store);
+            self.set_status_message(READ_ONLY_MESSAGE);
+            return;
+        }
+        let changed = if forward {
+            store.redo(self.name.as_str())
+        } else {
+            store.undo(self.name.as_str())
+        };
+        let last_row = store.line_count(self.name.as_str()).saturating_sub(1);
+        drop(store);
+
+        if !changed {
+            self.set_status_message(if forward {
+                "Already at newest change"
+            } else {
+                "Already at oldest change"
+            });
+            return;
+        }
+        self.location = Location {
+            x: 0,
+            y: self.location.y.min(last_row),
+        };
+    }
+
+    // :sort [n]
+    fn sort_current_buffer(&mut self, options: &str) {
+        let numeric = match options {
+            "" => false,
+            "n" => true,
+            other => {
+                self.set_status_message(format!("Unknown sort option: {other}"));
+                return;
+            }
+        };
+
+        let sorted = {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            store.sort_lines(self.name.as_str(), numeric);
+            store.line_count(self.name.as_str())
+        };
+        self.report_lines(sorted, format!("{} sorted", plural(sorted, "line")));
+    }
+
+    // :center [width], :right [width], :left [indent]
+    fn align_current_line(&mut self, command: &str, argument: &str) {
+        let amount = if argument.is_empty() {
+            None
+        } else {
+            match argument.parse::<usize>() {
+                Ok(amount) => Some(amount),
+                Err(_) => {
+                    self.set_status_message(format!("Invalid width: {argument}"));
+                    return;
+                }
+            }
+        };
+        let width = || amount.unwrap_or_else(|| Terminal::size().map_or(80, |size| size.width));
+        let alignment = match command {
+            "left" => Alignment::Left(amount.unwrap_or(0)),
+            "center" => Alignment::Center(width()),
+            _ => Alignment::Right(width()),
+        };
+
+        let store_handle = self.term.store_handle();
+        let mut store = store_handle.lock().expect("buffer store lock poisoned");
+        store.align_line(self.name.as_str(), self.location.y, alignment);
+    }
+
+    // :s/pattern/replacement/[g] on the cursor line, or :%s/... on every line.
+    fn substitute(&mut self, arguments: &str, every_line: bool) {
+        let mut parts = arguments.splitn(3, '/');
+        let pattern = parts.next().unwrap_or_default();
+        let replacement = parts.next().unwrap_or_default();
+        let all = parts.next().is_some_and(|flags| flags.contains('g'));
+        if pattern.is_empty() {
+            self.set_status_message("Substitute requires a pattern");
+            return;
+        }
+
+        let mut tally = (0, 0);
+        {
+            let store_handle = self.term.store_handle();
+            let mut store = store_handle.lock().expect("buffer store lock poisoned");
+            let (first, count) = match every_line {
+                true => (0, usize::MAX),
+                false => (self.location.y, 1),
+            };
+            store.apply_edit(self.name.as_str(), |lines| {
+                for line in lines.iter_mut().skip(first).take(count) {
+                    let occurrences = substitute_in(line, pattern, replacement, all);
+                    if occurrences > 0 {
+                        tally.0 += occurrences;
+                        tally.1 += 1;
+                    }
+                }
+            });
+        }
+        let (substitutions, lines) = tally;
+        if lines == 0 {
+            self.set_status_message(format!("Pattern not found: {pattern}"));
+            return;
+        }
+        match self.substitution_tally.as_mut() {
+            Some(tally) => {
+                tally.0 += substitutions;
+                tally.1 += lines;
+            }
+            None => self.report_substitutions(substitutions, lines),
+        }
+    }
+
+    // `:set report`: "5 substitutions on 3 lines" once more than `report` lines changed.
+    fn report_substitutions(&mut self, substitutions: usize, lines: usize) {
+        if self.settings.reports(lines) {
+            self.set_status_message(format!(
+                "{} on {}",
+                plural(substitutions, "substitution"),
+                plural(lines, "line")
+            ));
+        }
+    }
+
+    // `:set report`: "4 lines sorted", "3 fewer lines" and the like for bulk edits.
+    fn report_lines(&mut self, lines: usize, message: String) {
+        if self.settings.reports(lines) {
+            self.set_status_message(message);
+        }
+    }
+
+    // `:compare other` stacks `other` read-only below the active buffer, showing the same lines
+    // as the active buffer scrolls; repeating it, or a bare `:compare`, removes the pane.
+    fn toggle_compare(&mut self, other: &str) {
+        let showing = self.compare.take();
+        if other.is_empty() || showing.is_some_and(|name| name == other) {
+            return;
+        }
+        let exists = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            store.get(other).is_some()
+        };
+        if !exists {
+            self.set_status_message(format!("No buffer named {other}"));
+            return;
+        }
+        self.compare = Some(other.to_string());
+    }
+
+    // The compared buffer and the line its pane starts at, kept level with the active pane.
+    fn compare_pane(&self) -> Option<(&str, usize)> {
+        self.compare
+            .as_deref()
+            .map(|name| (name, self.scroll_offset))
+    }
+
+    // :/pattern — move to the next line containing the pattern, or the previous one when
+    // `backward` (`N`).
+    fn search(&mut self, pattern: &str, backward: bool) {
+        if pattern.is_empty() {
+            self.set_status_message("Search requires a pattern");
+            return;
+        }
+
+        self.last_search = Some(pattern.to_string());
+        match self.find_next_match(self.location.y, pattern, backward) {
+            Ok((row, col)) => self.location = Location { x: col, y: row },
+            Err(message) => self.set_status_message(message),
+        }
+    }
+
+    // `n`/`N` — repeat the last search forwards, or backwards when `reverse`.
+    fn repeat_search(&mut self, reverse: bool) {
+        match self.last_search.clone() {
+            Some(pattern) => self.search(&pattern, reverse),
+            None => self.set_status_message("No previous search pattern"),
+        }
+    }
+
+    // Row and column of the first match of `pattern` on a line after `row`, or before it when
+    // `backward`. The search wraps past the buffer boundary under `wrapscan` and otherwise stops
+    // there, describing which end it hit.
+    fn find_next_match(
+        &self,
+        row: usize,
+        pattern: &str,
+        backward: bool,
+    ) -> Result<(usize, usize), String> {
+        let store_handle = self.term.store_handle();
+        let store = store_handle.lock().expect("buffer store lock poisoned");
+        let lines = store
+            .get(self.name.as_str())
+            .map(|buffer| buffer.lines())
+            .unwrap_or_default();
+        let len = lines.len();
+        let wraps = |offset: usize| match backward {
+            true => offset > row,
+            false => row + offset >= len,
+        };
+        (1..=len)
+            .filter(|&offset| !self.settings.nowrapscan || !wraps(offset))
+            .map(|offset| match backward {
+                true => (row + len - offset) % len,
+                false => (row + offset) % len,
+            })
+            .find_map(|row| {
+                self.settings
+                    .find_match(&lines[row], pattern)
+                    .map(|col| (row, col))
+            })
+            .ok_or_else(|| match (self.settings.nowrapscan, backward) {
+                (false, _) => format!("Pattern not found: {pattern}"),
+                (true, false) => format!("Search hit BOTTOM without match for: {pattern}"),
+                (true, true) => format!("Search hit TOP without match for: {pattern}"),
+            })
+    }
+
+    // `:set incsearch` — while a `:/pattern` is typed, show the first match of what has been
+    // entered so far, remembering where the search started.
+    fn preview_incsearch(&mut self) -> Result<(), Error> {
+        let pattern = match self.command_input.strip_prefix(":/") {
+            Some(pattern) if self.settings.incsearch => pattern.to_string(),
+            _ => {
+                self.cancel_incsearch();
+                return Ok(());
+            }
+        };
+
+        let (origin, scroll_offset) = *self
+            .incsearch_origin
+            .get_or_insert((self.location, self.scroll_offset));
+        self.location = origin;
+        self.scroll_offset = scroll_offset;
+        if let Some((row, col)) = Some(pattern.as_str())
+            .filter(|pattern| !pattern.is_empty())
+            .and_then(|pattern| self.find_next_match(origin.y, pattern, false).ok())
+        {
+            self.location = Location { x: col, y: row };
+        }
+        self.ensure_cursor_visible()
+    }
+
+    // Return to where an incremental search started, if one is being previewed.
+    fn cancel_incsearch(&mut self) {
+        if let Some((location, scroll_offset)) = self.incsearch_origin.take() {
+            self.location = location;
+            self.scroll_offset = scroll_offset;
+        }
+    }
+
+    // The pattern being typed during an incsearch preview, else the last search under hlsearch.
+    fn search_highlight(&self) -> Option<&str> {
+        if self.incsearch_origin.is_some() {
+            return self
+                .command_input
+                .strip_prefix(":/")
+                .filter(|pattern| !pattern.is_empty());
+        }
+        self.last_search
+            .as_deref()
+            .filter(|_| self.settings.hlsearch)
+    }
+
+    // :g/pattern/<colon-command>, or :v/pattern/<colon-command> for non-matching lines
+    fn execute_global_command(&mut self, arguments: &str, invert: bool) -> Result<(), Error> {
+        let usage = if invert {
+            "Usage: :v/pattern/command"
+        } else {
+            "Usage: :g/pattern/command"
+        };
+        let Some((pattern, subcommand)) = arguments.split_once('/') else {
+            self.set_status_message(usage);
+            return Ok(());
+        };
+        let subcommand = subcommand.trim();
+        if pattern.is_empty() || subcommand.is_empty() {
+            self.set_status_message(usage);
+            return Ok(());
+        }
+        if subcommand.starts_with("g/") || subcommand.starts_with("v/") {
+            self.set_status_message("Cannot nest :g commands");
+            return Ok(());
+        }
+
+        // Collect matches up front so commands that add or remove lines don't disturb iteration.
+        let matches: Vec<usize> = {
+            let store_handle = self.term.store_handle();
+            let store = store_handle.lock().expect("buffer store lock poisoned");
+            store
+                .get(self.name.as_str())
+                .map(|buffer| {
+                    buffer
+                        .lines()
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| self.settings.matches(line, pattern) != invert)
+                        .map(|(row, _)| row)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        if matches.is_empty() {
+            self.set_status_message(format!("Pattern not found: {pattern}"));
+            return Ok(());
+        }
+
+        let name = self.name.clone();
+        let original_lines = self.buffer_line_count();
+        self.substitution_tally = Some((0, 0));
+        // Every edit the loop makes is undone together, as one step.
+        self.term
+            .store_handle()
+            .lock()
+            .expect("buffer store lock poisoned")
+            .begin_undo_group(&name);
+        let result = self.run_global_subcommand(&name, &matches, subcommand);
+        self.term
+            .store_handle()
+            .lock()
+            .expect("buffer store lock poisoned")
+            .end_undo_group(&name);
+        result?;
+
+        if let Some((substitutions, lines)) = self.substitution_tally.take()
+            && lines > 0
+        {
+            self.report_substitutions(substitutions, lines);
+        }
+        if self.name == name {
+            let remaining = self.buffer_line_count();
+            if remaining < original_lines {
+                let fewer = original_lines - remaining;
+                self.report_lines(fewer, format!("{fewer} fewer {}", noun(fewer, "line")));
+            } else if remaining > original_lines {
+                let more = remaining - original_lines;
+                self.report_lines(more, format!("{more} more {}", noun(more, "line")));
+            }
+        }
+        self.ensure_cursor_visible()
+    }
+
+    // Run `subcommand` with the cursor on each matched row, shifted by lines added or removed.
+    fn run_global_subcommand(
+        &mut self,
+        name: &str,
+        matches: &[usize],
+        subcommand: &str,
+    ) -> Result<(), Error> {
+        let mut shift: isize = 0;
+        for &row in matches {
+            let before = self.buffer_line_count();
+            self.location = Location {
+                x: 0,
+                y: row.saturating_add_signed(shift),
+            };
+            self.process_colon_command(subcommand)?;
+            if self.quit || self.name != name {
+                break;
+            }
+            shift += self.buffer_line_count() as isize - before as isize;
+        }
+        Ok(())
+    }
+
+    fn process_colon_command(&mut self, command: &str) -> Result<bool, Error> {
+        let mut keep_command_text = false;
+        if command.is_empty() {
+            self.restore_after_command();
+            return Ok(keep_command_text);
+        }
+
+        if command == "q" {
+            self.close_current_buffer(false)?;
+        } else if command == "q!" {
+            self.close_current_buffer(true)?;
+        } else if command == "i" {
+            self.enter_insert_mode();
+        } else if command == "r" {
+            self.enter_read_mode();
+        } else if let Some(rest) = command.strip_prefix('b') {
+            self.jump_to_buffer(rest.trim()).ok();
+        } else if command == "n" {
+            self.cycle_buffer(true)?;
+        } else if command == "p" {
+            self.cycle_buffer(false)?;
+        } else if command == "w" {
+            keep_command_text = self.handle_save_command(SaveIntent::BufferOnly)?;
+        } else if command == "w!" {
+            keep_command_text = self.handle_save_command(SaveIntent::ForcedWrite)?;
+        } else if let Some(path) = command.strip_prefix("w ") {
+            self.write_buffer_copy(path.trim());
+        } else if let Some((first, last, path)) = parse_range_write(command) {
+            self.write_range_copy(first, last, path);
+        } else if command == "wq" {
+            keep_command_text = self.handle_save_command(SaveIntent::WriteAndQuit)?;
+        } else if command == "x" {
+            keep_command_text = self.handle_save_command(SaveIntent::ConditionalQuit)?;
+        } else if command == "s" {
+            self.save_current_buffer_in_memory();
+        } else if command == "Q" {
+            keep_command_text = self.handle_quit_all_command()?;
+        } else if command == "registers" || command == "reg" {
+            let listing = self.registers.describe();
+            if listing.is_empty() {
+                self.set_status_message("All registers are empty");
+            } else {
+                self.set_status_message(listing);
+            }
+        } else if command == "reload" {
+            self.reload_current_buffer();
+        } else if command == "snapshots" {
+            self.list_snapshots();
+        } else if command == "restore" || command.starts_with("restore ") {
+            self.restore_snapshot(command["restore".len()..].trim());
+        } else if let Some(rest) = command.strip_prefix("set ") {
+            keep_command_text = self.apply_set_command(rest)?;
+        } else if command == "sort" || command.starts_with("sort ") {
+            self.sort_current_buffer(command["sort".len()..].trim());
+        } else if command == "compare" || command.starts_with("compare ") {
+            self.toggle_compare(command["compare".len()..].trim());
+        } else if let Some((name, argument)) = ["center", "left", "right"]
+            .into_iter()
+            .find_map(|name| Some((name, command.strip_prefix(name)?)))
+            .filter(|(_, argument)| argument.is_empty() || argument.starts_with(' '))
+        {
+            self.align_current_line(name, argument.trim());
+        } else if command == "d" {
+            self.delete_current_line();
+        } else if command == "u" || command == "undo" {
+            self.step_history(false);
+        } else if command == "redo" || command == "red" {
+            self.step_history(true);
+        } else if let Some(rest) = command.strip_prefix("s/") {
+            self.substitute(rest, false);
+        } else if let Some(rest) = command.strip_prefix("%s/") {
+            self.substitute(rest, true);
+        } else if let Some(rest) = command.strip_prefix("g/") {
+            self.execute_global_command(rest, false)?;
+        } else if let Some(rest) = command.strip_prefix("v/") {
+            self.execute_global_command(rest, true)?;
+        } else if let Some(pattern) = command.strip_prefix('/') {
+            self.search(pattern, false);
+        }
+
+        Ok(keep_command_text)
+    }
+}
+
+/// Split `:A,Bw path` (or `:Aw path` for one line) into its one-based bounds and path.
+fn parse_range_write(command: &str) -> Option<(usize, usize, &str)> {
+    let range_end = command.find(|ch: char| !ch.is_ascii_digit() && ch != ',')?;
+    let (range, rest) = command.split_at(range_end);
+    let path = rest
+        .strip_prefix("w ")
+        .or(rest.strip_prefix('w').filter(|rest| rest.is_empty()))?;
+    let (first, last) = range.split_once(',').unwrap_or((range, range));
+    Some((first.parse().ok()?, last.parse().ok()?, path.trim()))
+}
+
+/// `count` followed by `word`, pluralised unless `count` is one.
+pub(super) fn plural(count: usize, word: &str) -> String {
+    format!("{count} {}", noun(count, word))
+}
+
+/// `word`, with an `s` appended unless `count` is one.
+fn noun(count: usize, word: &str) -> String {
+    match count {
+        1 => word.to_string(),
+        _ => format!("{word}s"),
+    }
+}
+
+/// Column of the first non-whitespace character of `line`, or its end when it is all blank.
+fn first_non_blank(line: &str) -> usize {
+    line.chars().take_while(|ch| ch.is_whitespace()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::terminal::TerminalMode;
+    use crate::store::buffer_store::BufferStore;
+    use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+    fn test_lock() -> MutexGuard<'static, ()> {
+        static GUARD: OnceLock<Mutex<()>> = OnceLock::new();
+        GUARD
+            .get_or_init(|| Mutex::new(()))
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn reset_store() -> (Arc<Mutex<BufferStore>>, MutexGuard<'static, ()>) {
+        let guard = test_lock();
+        unsafe {
+            std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
+        }
+
+        let terminal = Terminal::instance();
+        let candidate = Arc::new(Mutex::new(BufferStore::new()));
+        terminal.attach_store(Arc::clone(&candidate));
+        let handle = terminal.store_handle();
+        {
+            let mut store = handle.lock().unwrap();
+            *store = BufferStore::new();
+        }
+
+        (handle, guard)
+    }
+
+    fn populate_buffer(handle: &Arc<Mutex<BufferStore>>, name: &str, line_count: usize) {
+        let mut store = handle.lock().unwrap();
+        let buffer = store.open(name);
+        buffer.clear();
+        for idx in 0..line_count {
+            buffer.append(format!("line {idx}"));
+        }
+    }
+
+    #[test]
+    fn navigation_page_up_moves_to_view_top() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 20);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 3, y: 10 };
+        editor.scroll_offset = 8;
+        editor.view_height = 5;
+
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up navigation");
+        assert_eq!(editor.location.y, 8);
+        assert_eq!(editor.scroll_offset, 8);
+
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up scrolls");
+        assert_eq!(editor.scroll_offset, 6);
+        assert_eq!(editor.location.y, 6);
+    }
+
+    #[test]
+    fn navigation_page_down_moves_to_view_bottom_or_buffer_end() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 12);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 2, y: 8 };
+        editor.scroll_offset = 7;
+        editor.view_height = 6;
+
+        editor
+            .navigate_line(NavigationCommand::PageEnd)
+            .expect("page down navigation");
+        assert_eq!(editor.location.y, 11);
+        assert_eq!(editor.scroll_offset, 7);
+
+        editor
+            .navigate_line(NavigationCommand::PageEnd)
+            .expect("page down scrolls");
+        assert_eq!(editor.scroll_offset, 10);
+        assert_eq!(editor.location.y, 11);
+    }
+
+    #[test]
+    fn navigation_page_moves_by_the_scroll_setting() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 40);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor
+            .execute_colon_command("set scroll=7")
+            .expect(":set should succeed");
+        editor.view_height = 6;
+        editor.scroll_offset = 10;
+        editor.location = Location { x: 0, y: 15 };
+
+        editor
+            .navigate_line(NavigationCommand::PageEnd)
+            .expect("page down scrolls");
+        assert_eq!(editor.scroll_offset, 17);
+        assert_eq!(editor.location.y, 22);
+
+        editor.location = Location { x: 0, y: 17 };
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up scrolls");
+        assert_eq!(editor.scroll_offset, 10);
+        assert_eq!(editor.location.y, 10);
+    }
+
+    #[test]
+    fn navigation_page_up_clamps_column_without_padding() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            buffer.clear();
+            for len in [5usize, 3, 12, 4, 2, 1, 6, 2, 3, 4, 5, 6] {
+                buffer.append("x".repeat(len));
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 10, y: 10 };
+        editor.scroll_offset = 8;
+        editor.view_height = 5;
+
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up clamps x");
+        assert_eq!(editor.location.y, 8);
+        assert_eq!(editor.location.x, 3);
+
+        {
+            let store = handle.lock().unwrap();
+            let buffer = store.get("alpha").unwrap();
+            assert_eq!(buffer.lines()[8].chars().count(), 3);
+        }
+
+        // Move to front of buffer and ensure clamped column.
+        editor.location = Location { x: 10, y: 0 };
+        editor.scroll_offset = 0;
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up at front");
+        assert_eq!(editor.location.y, 0);
+        assert_eq!(editor.location.x, 5);
+    }
+
+    #[test]
+    fn start_line_positions_the_cursor_once() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 20);
+        populate_buffer(&handle, "beta", 3);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.set_start_line("alpha", 10);
+        editor.set_start_line("beta", 42);
+        editor.open_tabs(&["alpha".to_string(), "beta".to_string()]);
+        assert_eq!((editor.location.y, editor.location.x), (9, 0));
+
+        editor.open("beta");
+        assert_eq!(editor.location.y, 2, "clamped to the last line");
+        editor.open("alpha");
+        assert_eq!(editor.location.y, 0, "only the first open jumps");
+    }
+
+    #[test]
+    fn start_line_follows_a_renamed_buffer() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
+        populate_buffer(&handle, "beta", 20);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.set_start_line("beta", 7);
+        assert!(handle.lock().unwrap().rename("beta", "gamma"));
+        assert!(editor.rename_buffer_references("beta", "gamma"));
+
+        editor.open("gamma");
+        assert_eq!(editor.location.y, 6);
+    }
+
+    #[test]
+    fn store_counts_match_snapshot_counts() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            buffer.clear();
+            for line in ["short", "", "a longer line", "\u{e9}t\u{e9}"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let view = View::snapshot("alpha");
+        let store = handle.lock().unwrap();
+        assert_eq!(store.line_count("alpha"), view.line_count());
+        for row in 0..=view.line_count() {
+            assert_eq!(store.char_count("alpha", row), view.char_count(row));
+        }
+        for (row, col) in [
+            (0, 0),
+            (0, 4),
+            (0, 5),
+            (1, 0),
+            (2, 12),
+            (3, 1),
+            (3, 3),
+            (9, 0),
+        ] {
+            assert_eq!(
+                store.char_at("alpha", row, col),
+                view.line(row).and_then(|line| line.chars().nth(col)),
+                "char at {row},{col}"
+            );
+        }
+        assert_eq!(store.char_at("alpha", 3, 2), Some('\u{e9}'));
+        assert_eq!(store.char_at("missing", 0, 0), None);
+    }
+
+    #[test]
+    fn navigation_word_left_moves_to_previous_space() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            buffer.clear();
+            buffer.append("first second third".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 12, y: 0 };
+
+        editor
+            .navigate_line(NavigationCommand::WordLeft)
+            .expect("word left");
+        assert_eq!(editor.location.x, 11);
+
+        editor
+            .navigate_line(NavigationCommand::WordLeft)
+            .expect("word left again");
+        assert_eq!(editor.location.x, 5);
+    }
+
+    #[test]
+    fn navigation_word_right_moves_to_next_space_or_end() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            buffer.clear();
+            buffer.append("first second third".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 0, y: 0 };
+
+        editor
+            .navigate_line(NavigationCommand::WordRight)
+            .expect("word right");
+        assert_eq!(editor.location.x, 5);
+
+        editor
+            .navigate_line(NavigationCommand::WordRight)
+            .expect("word right again");
+        assert_eq!(editor.location.x, 11);
+    }
+
+    #[test]
+    fn quit_all_prompts_when_buffer_is_untitled() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open_untitled("Untitled-1");
+        }
+
+        let mut editor = BufferEditor::new("Untitled-1");
+        editor.open("Untitled-1");
+
+        let keep_prompt = editor
+            .handle_quit_all_command()
+            .expect("quit all command should succeed");
+        assert!(keep_prompt, "should keep command text until name provided");
+
+        let input = format!("{}named", BUFFER_NAME_PROMPT);
+        editor
+            .process_prompt_input(input)
+            .expect("prompt processing should succeed");
+
+        assert!(editor.take_quit_all_request());
+    }
+
+    #[test]
+    fn set_autosave_updates_the_interval_and_reports_it() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open("alpha");
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        editor
+            .execute_colon_command("set autosave=1500")
+            .expect(":set should succeed");
+        assert_eq!(editor.settings.autosave, 1500);
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Autosave every 1500 ms")
+        );
+        assert!(editor.pending_command.is_none());
+
+        editor
+            .execute_colon_command("set noautosave")
+            .expect(":set should succeed");
+        assert_eq!(editor.settings.autosave, 0);
+        assert_eq!(editor.status_message.as_deref(), Some("Autosave off"));
+    }
+
+    #[test]
+    fn enabling_autosave_on_untitled_buffer_prompts_for_a_name() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open_untitled("Untitled-1");
+        let mut editor = BufferEditor::new("Untitled-1");
+        editor.open("Untitled-1");
+
+        let keep_prompt = editor
+            .apply_set_command("autosave")
+            .expect(":set should succeed");
+        assert!(keep_prompt, "should keep command text until name provided");
+        assert!(matches!(
+            editor.pending_command,
+            Some(PendingCommand::Autosave)
+        ));
+        assert_eq!(editor.command_input, BUFFER_NAME_PROMPT);
+
+        editor
+            .process_prompt_input(format!("{BUFFER_NAME_PROMPT}named"))
+            .expect("prompt processing should succeed");
+        assert_eq!(editor.name, "named");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some(
+                format!(
+                    "Autosave every {} ms",
+                    crate::editor::settings::DEFAULT_AUTOSAVE_MS
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[test]
+    fn autowriteall_writes_buffers_when_cycling_away() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let first = temp.path().join("a.txt").to_str().unwrap().to_string();
+        let second = temp.path().join("b.txt").to_str().unwrap().to_string();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open(first.clone()).append("first".into());
+            store.open(second.clone()).append("second".into());
+        }
+
+        let mut editor = BufferEditor::new(first.clone());
+        editor.open(first.clone());
+        editor.cycle_buffer(true).expect("cycle next");
+        assert!(!std::path::Path::new(&first).exists(), "off by default");
+
+        editor.execute_colon_command("set autowriteall").unwrap();
+        editor.cycle_buffer(true).expect("cycle next");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second\n");
+        assert!(editor.prompt_string().contains("a.txt"));
+
+        editor.cycle_buffer(true).expect("cycle next");
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first\n");
+        let store = handle.lock().unwrap();
+        assert!(!store.is_dirty(&first) && !store.is_dirty(&second));
+    }
+
+    #[test]
+    fn autowriteall_leaves_untitled_buffers_to_the_name_prompt() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open_untitled("Untitled-1").append("draft".into());
+            store.open("beta");
+        }
+
+        let mut editor = BufferEditor::new("Untitled-1");
+        editor.open("Untitled-1");
+        editor.execute_colon_command("set awa").unwrap();
+        editor.execute_colon_command("b beta").unwrap();
+
+        assert!(editor.prompt_string().contains("[buffer:beta]"));
+        assert!(handle.lock().unwrap().is_dirty("Untitled-1"));
+    }
+
+    #[test]
+    fn autosave_writes_dirty_buffers_once_the_interval_passes() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("autosaved.txt");
+        let name = path.to_str().unwrap().to_string();
+        handle
+            .lock()
+            .unwrap()
+            .open(name.clone())
+            .append("draft".into());
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        editor
+            .execute_colon_command("set autosave=1000")
+            .expect(":set should succeed");
+        let start = editor.last_autosave;
+
+        editor.autosave_if_due(start + Duration::from_millis(500));
+        assert!(!path.exists());
+
+        editor.autosave_if_due(start + Duration::from_millis(1000));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "draft\n");
+        assert!(!handle.lock().unwrap().is_dirty(&name));
+    }
+
+    #[test]
+    fn write_refuses_to_clobber_a_file_changed_on_disk() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("shared.txt");
+        std::fs::write(&path, "original\n").unwrap();
+        let name = path.to_str().unwrap().to_string();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open(name.clone());
+            store.load_from_disk(&name).unwrap();
+            store.replace_lines(&name, vec!["ours".into()]);
+        }
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+
+        // Make sure the external write lands on a different mtime than the load recorded.
+        let later = std::time::SystemTime::now() + Duration::from_secs(5);
+        let file = std::fs::File::options()
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut &file, b"theirs\n").unwrap();
+        file.set_modified(later).unwrap();
+        drop(file);
+
+        editor.execute_colon_command("w").expect(":w");
+        assert_eq!(editor.status_message.as_deref(), Some(DISK_CHANGED_MESSAGE));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "theirs\n");
+        assert!(handle.lock().unwrap().is_dirty(&name));
+
+        editor.execute_colon_command("wq").expect(":wq");
+        assert!(!editor.is_quit());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "theirs\n");
+
+        editor.execute_colon_command("w!").expect(":w!");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "ours\n");
+        assert!(!handle.lock().unwrap().is_dirty(&name));
+
+        handle
+            .lock()
+            .unwrap()
+            .replace_lines(&name, vec!["again".into()]);
+        editor.execute_colon_command("w").expect(":w after :w!");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "again\n");
+    }
+
+    #[test]
+    fn quit_all_sets_flag_for_named_buffer() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("alpha");
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        let keep_prompt = editor
+            .handle_quit_all_command()
+            .expect("quit all command should succeed");
+        assert!(!keep_prompt, "no prompt needed for named buffer");
+        assert!(editor.take_quit_all_request());
+    }
+
+    #[test]
+    fn cycles_forward_and_wraps() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("beta");
+            store.open("alpha");
+            store.open("gamma");
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        editor.cycle_buffer(true).expect("cycle next");
+        assert!(editor.prompt_string().contains("[buffer:beta]"));
+
+        editor.cycle_buffer(true).expect("cycle next again");
+        assert!(editor.prompt_string().contains("[buffer:gamma]"));
+
+        editor.cycle_buffer(true).expect("cycle wraps to start");
+        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+    }
+
+    #[test]
+    fn tabs_open_together_and_cycle_in_order() {
+        let (handle, _guard) = reset_store();
+        let names: Vec<String> = ["c", "a", "b"].iter().map(|n| n.to_string()).collect();
+        {
+            let mut store = handle.lock().unwrap();
+            for name in &names {
+                store.open(name.clone());
+            }
+            store.open("unrelated");
+        }
+
+        let mut editor = BufferEditor::new("");
+        editor.open_tabs(&names);
+        {
+            let store = handle.lock().unwrap();
+            let open = store.open_buffers();
+            assert!(names.iter().all(|name| open.contains(name)));
+        }
+        assert_eq!(editor.tabs(), names.as_slice());
+        assert_eq!(editor.active_buffer(), "c");
+
+        editor
+            .execute_colon_command("n")
+            .expect(":n should succeed");
+        assert_eq!(editor.active_buffer(), "a");
+        editor
+            .execute_colon_command("n")
+            .expect(":n should succeed");
+        assert_eq!(editor.active_buffer(), "b");
+        editor.execute_colon_command("n").expect(":n wraps");
+        assert_eq!(editor.active_buffer(), "c");
+        editor
+            .execute_colon_command("p")
+            .expect(":p should succeed");
+        assert_eq!(editor.active_buffer(), "b");
+    }
+
+    #[test]
+    fn cycles_backward_and_wraps() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("beta");
+            store.open("alpha");
+            store.open("gamma");
+        }
+
+        let mut editor = BufferEditor::new("beta");
+        editor.open("beta");
+
+        editor.cycle_buffer(false).expect("cycle prev");
+        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+
+        editor.cycle_buffer(false).expect("cycle prev wraps");
+        assert!(editor.prompt_string().contains("[buffer:gamma]"));
+    }
+
+    #[test]
+    fn colon_command_switches_buffer() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("alpha");
+            store.open("beta");
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        editor
+            .apply_input_action(InputAction::ExecuteCommand("b beta".into()))
+            .expect("command should succeed");
+        assert!(editor.prompt_string().contains("[buffer:beta]"));
+    }
+
+    #[test]
+    fn colon_b_refuses_a_directory() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open("alpha");
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_str().unwrap().to_string();
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command(&format!("b {dir}"))
+            .expect(":b should not fail");
+
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some(format!("{dir} is a directory").as_str())
+        );
+        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+        assert!(handle.lock().unwrap().get(&dir).is_none());
+    }
+
+    #[test]
+    fn compare_toggles_a_read_only_pane_below_the_active_buffer() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 3);
+        populate_buffer(&handle, "beta", 3);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        assert_eq!(editor.content_height(24), 23);
+
+        editor.execute_colon_command("compare beta").unwrap();
+        assert_eq!(editor.compare_pane(), Some(("beta", 0)));
+        assert_eq!(editor.content_height(24), 11);
+        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+
+        editor.execute_colon_command("compare beta").unwrap();
+        assert_eq!(
+            editor.compare, None,
+            "repeating the command closes the pane"
+        );
+        assert_eq!(editor.content_height(24), 23);
+
+        editor.execute_colon_command("compare gamma").unwrap();
+        assert_eq!(editor.compare, None);
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("No buffer named gamma")
+        );
+    }
+
+    #[test]
+    fn quitting_the_last_buffer_leaves_the_editor_by_default() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open("alpha");
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.execute_colon_command("q").unwrap();
+
+        assert!(editor.is_quit());
+        assert!(handle.lock().unwrap().open_buffers().is_empty());
+    }
+
+    #[test]
+    fn quit_last_opens_new_keeps_the_editor_on_an_untitled_buffer() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open("alpha");
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.configure(&EditorConfig {
+            quit_last_opens_new: true,
+            ..Default::default()
+        });
+        editor.open("alpha");
+        editor.execute_colon_command("q").unwrap();
+
+        assert!(!editor.is_quit());
+        assert_ne!(editor.name, "alpha");
+        let store = handle.lock().unwrap();
+        assert_eq!(store.open_buffers(), vec![editor.name.clone()]);
+        assert!(store.get(&editor.name).unwrap().requires_name());
+    }
+
+    #[test]
+    fn close_current_buffer_moves_to_next() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("alpha");
+            store.open("beta");
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        editor.close_current_buffer(false).expect("close current");
+
+        {
+            let store = handle.lock().unwrap();
+            let alpha = store.get("alpha").expect("alpha should remain tracked");
+            assert!(!alpha.is_open(), "closed buffer should no longer be open");
+            let beta = store.get("beta").expect("beta should exist");
+            assert!(beta.is_open());
+        }
+
+        assert!(editor.prompt_string().contains("[buffer:beta]"));
+        assert!(!editor.quit);
+    }
+
+    #[test]
+    fn close_current_buffer_respects_dirty_flag() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("alpha").append("dirty".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        editor.close_current_buffer(false).expect("close current");
+        {
+            let store = handle.lock().unwrap();
+            assert!(store.get("alpha").is_some());
+        }
+        assert!(!editor.quit);
+
+        editor.close_current_buffer(true).expect("force close");
+        {
+            let store = handle.lock().unwrap();
+            let alpha = store
+                .get("alpha")
+                .expect("alpha should remain tracked after force close");
+            assert!(!alpha.is_open());
+        }
+        assert!(editor.quit);
+    }
+
+    #[test]
+    fn open_detects_filetype_and_set_overrides_it() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("main.rs");
+        }
+
+        let mut editor = BufferEditor::new("main.rs");
+        editor.open("main.rs");
+        assert_eq!(editor.settings.filetype, Some(FileType::Rust));
+
+        editor
+            .execute_colon_command("set filetype=toml")
+            .expect(":set should succeed");
+        assert_eq!(editor.settings.filetype, Some(FileType::Toml));
+
+        editor
+            .execute_colon_command("set filetype=cobol")
+            .expect(":set with a bad value should not error");
+        assert_eq!(editor.settings.filetype, Some(FileType::Toml));
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Unknown filetype: cobol")
+        );
+    }
+
+    fn buffer_lines(handle: &Arc<Mutex<BufferStore>>, name: &str) -> Vec<String> {
+        let store = handle.lock().unwrap();
+        store.get(name).unwrap().lines().to_vec()
+    }
+
+    #[test]
+    fn join_lines_merges_rows_and_places_cursor_at_join_point() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["one", "  two", "three", "four", "last"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .apply_input_action(InputAction::JoinLines(1))
+            .expect("J should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha")[0], "one two");
+        assert_eq!((editor.location.x, editor.location.y), (3, 0));
+
+        editor
+            .apply_input_action(InputAction::JoinLines(3))
+            .expect("3J should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["one two three four".to_string(), "last".to_string()]
+        );
+        assert_eq!((editor.location.x, editor.location.y), (13, 0));
+        assert!(handle.lock().unwrap().is_dirty("alpha"));
+
+        editor.location.y = 1;
+        editor
+            .apply_input_action(InputAction::JoinLines(1))
+            .expect("J on the last line should be a no-op");
+        assert_eq!(buffer_lines(&handle, "alpha").len(), 2);
+    }
+
+    #[test]
+    fn shifting_lines_indents_and_reports_above_the_report_threshold() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["one", "", "  three", "four"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .apply_input_action(InputAction::ShiftLines {
+                count: 4,
+                outdent: false,
+            })
+            .expect(">> should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["    one", "", "      three", "    four"]
+        );
+        assert_eq!(editor.location.x, 4);
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("3 lines >ed 1 time"),
+            "the blank line is left alone"
+        );
+
+        editor
+            .apply_input_action(InputAction::ShiftLines {
+                count: 2,
+                outdent: true,
+            })
+            .expect("<< should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha")[0], "one");
+        assert_eq!(editor.status_message, None, "1 line is not above report=2");
+
+        editor.location.y = 2;
+        editor
+            .apply_input_action(InputAction::ShiftLines {
+                count: 3,
+                outdent: true,
+            })
+            .expect("<< past the last line should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha")[2..], ["  three", "four"]);
+    }
+
+    #[test]
+    fn write_into_missing_directory_reports_status_when_mkdir_disabled() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("missing").join("notes.txt");
+        let name = path.to_string_lossy().to_string();
+        {
+            let mut store = handle.lock().unwrap();
+            store.set_mkdir_on_save(false);
+            store.open(name.clone()).append("data".into());
+        }
+
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        editor
+            .execute_colon_command("wq")
+            .expect(":wq should not error");
+
+        assert!(!editor.is_quit());
+        assert!(!path.exists());
+        let status = editor.status_message.clone().unwrap_or_default();
+        assert!(status.starts_with("Write failed: directory does not exist"));
+    }
+
+    #[test]
+    fn snapshots_are_taken_on_write_and_restorable() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let name = temp.path().join("notes.txt").to_string_lossy().to_string();
+        handle
+            .lock()
+            .unwrap()
+            .open(name.clone())
+            .append("one".into());
+
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        editor
+            .execute_colon_command("w")
+            .expect(":w should succeed");
+
+        handle
+            .lock()
+            .unwrap()
+            .open(name.clone())
+            .append("two".into());
+        editor
+            .execute_colon_command("w")
+            .expect(":w should succeed");
+        handle
+            .lock()
+            .unwrap()
+            .open(name.clone())
+            .append("three".into());
+
+        editor
+            .execute_colon_command("snapshots")
+            .expect(":snapshots should succeed");
+        let listing = editor.status_message.clone().unwrap_or_default();
+        assert!(listing.starts_with("1: 1 line ("), "{listing}");
+        assert!(listing.contains("| 2: 2 lines"), "{listing}");
+
+        editor
+            .execute_colon_command("restore 1")
+            .expect(":restore should succeed");
+        assert_eq!(buffer_lines(&handle, &name), vec!["one"]);
+        assert!(handle.lock().unwrap().is_dirty(&name));
+
+        editor
+            .execute_colon_command("restore 9")
+            .expect(":restore should not error");
+        assert_eq!(editor.status_message.as_deref(), Some("No snapshot 9"));
+
+        editor
+            .execute_colon_command("restore2")
+            .expect("unknown commands should not error");
+        assert_eq!(
+            buffer_lines(&handle, &name),
+            vec!["one"],
+            "only the whole word runs :restore"
+        );
+    }
+
+    #[test]
+    fn renamed_buffer_keeps_editing_same_content() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 2);
+        populate_buffer(&handle, "beta", 1);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open_tabs(&["alpha".to_string(), "beta".to_string()]);
+        assert!(handle.lock().unwrap().rename("alpha", "gamma"));
+        assert!(editor.rename_buffer_references("alpha", "gamma"));
+        assert!(!editor.rename_buffer_references("alpha", "gamma"));
+
+        assert_eq!(editor.active_buffer(), "gamma");
+        assert_eq!(editor.tabs(), ["gamma".to_string(), "beta".to_string()]);
+        editor.mode = EditorMode::Insert;
+        editor
+            .apply_input_action(InputAction::KillToLineEnd)
+            .expect("kill should succeed");
+        assert_eq!(buffer_lines(&handle, "gamma"), vec!["", "line 1"]);
+        assert!(handle.lock().unwrap().get("alpha").is_none());
+    }
+
+    #[test]
+    fn compare_pane_scrolls_with_the_active_buffer() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 40);
+        populate_buffer(&handle, "beta", 40);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.scroll_offset = 3;
+        editor.execute_colon_command("compare beta").unwrap();
+        assert_eq!(editor.compare_pane(), Some(("beta", 3)));
+
+        editor.scroll_offset = 17;
+        assert_eq!(
+            editor.compare_pane(),
+            Some(("beta", 17)),
+            "the lower pane follows the upper one"
+        );
+    }
+
+    #[test]
+    fn renaming_the_compared_buffer_keeps_the_pane_open() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 2);
+        populate_buffer(&handle, "beta", 2);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.execute_colon_command("compare beta").unwrap();
+        assert!(handle.lock().unwrap().rename("beta", "gamma"));
+        assert!(editor.rename_buffer_references("beta", "gamma"));
+        assert_eq!(editor.compare.as_deref(), Some("gamma"));
+
+        editor.execute_colon_command("compare gamma").unwrap();
+        assert_eq!(editor.compare, None, "the renamed pane still toggles off");
+    }
+
+    #[test]
+    fn alignment_commands_pad_the_current_line() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            buffer.append("other".into());
+            buffer.append("  hello ".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.location = Location { x: 0, y: 1 };
+        let mut run = |command: &str| {
+            editor.execute_colon_command(command).expect(command);
+            buffer_lines(&handle, "alpha")[1].clone()
+        };
+
+        assert_eq!(run("center 20"), "       hello");
+        assert!(handle.lock().unwrap().is_dirty("alpha"));
+        assert_eq!(run("right 20"), "               hello");
+        assert_eq!(run("left"), "hello");
+        assert_eq!(run("left 4"), "    hello");
+        assert_eq!(run("right 3"), "hello");
+        assert_eq!(run("center wide"), "hello");
+        assert_eq!(buffer_lines(&handle, "alpha")[0], "other");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Invalid width: wide")
+        );
+    }
+
+    #[test]
+    fn disabled_custom_cursor_bypasses_the_glyph() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        let later = editor.cursor_last_toggle + BufferEditor::CURSOR_BLINK_INTERVAL;
+
+        assert_eq!(editor.cursor_glyph(Instant::now()), Some("\u{2038}".into()));
+        assert_eq!(editor.cursor_glyph(later), Some("l".into()));
+
+        editor.configure(&EditorConfig {
+            custom_cursor: false,
+            ..Default::default()
+        });
+        let visible = editor.cursor_blink_visible;
+        let toggled = editor.cursor_last_toggle;
+        let much_later = later + BufferEditor::CURSOR_BLINK_INTERVAL * 4;
+        assert_eq!(editor.cursor_glyph(much_later), None);
+        assert_eq!(editor.cursor_blink_visible, visible);
+        assert_eq!(editor.cursor_last_toggle, toggled);
+    }
+
+    #[test]
+    fn scratch_buffer_write_warns_and_write_path_copies() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        let name = uuid::Uuid::new_v4().to_string();
+        handle
+            .lock()
+            .unwrap()
+            .open_scratch(name.clone())
+            .append("jotted".into());
+
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        for command in ["w", "wq"] {
+            editor.execute_colon_command(command).expect(command);
+            assert_eq!(editor.status_message.as_deref(), Some(SCRATCH_SAVE_MESSAGE));
+        }
+        assert!(!editor.is_quit());
+        assert!(!cwd.join(&name).exists());
+        assert!(handle.lock().unwrap().snapshots().is_empty());
+
+        let copy = temp.path().join("notes.txt");
+        editor
+            .execute_colon_command(&format!("w {}", copy.display()))
+            .expect(":w <path>");
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "jotted\n");
+        assert!(handle.lock().unwrap().is_dirty(&name));
+        assert!(handle.lock().unwrap().is_scratch(&name));
+    }
+
+    #[test]
+    fn range_write_copies_only_the_selected_lines() {
+        let (handle, _guard) = reset_store();
+        let temp = tempfile::tempdir().unwrap();
+        let name = uuid::Uuid::new_v4().to_string();
+        populate_buffer(&handle, &name, 6);
+        handle.lock().unwrap().save_in_memory(&name);
+
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        let copy = temp.path().join("range.txt");
+        editor
+            .execute_colon_command(&format!("2,4w {}", copy.display()))
+            .expect(":2,4w");
+        assert_eq!(
+            std::fs::read_to_string(&copy).unwrap(),
+            "line 1\nline 2\nline 3\n"
+        );
+        assert_eq!(
+            editor.status_message,
+            Some(format!("Wrote 3 lines to {}", copy.display()))
+        );
+        assert!(!handle.lock().unwrap().is_dirty(&name));
+        assert!(!std::path::Path::new(&name).exists());
+
+        editor
+            .execute_colon_command(&format!("6w {}", copy.display()))
+            .expect(":6w");
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "line 5\n");
+
+        editor
+            .execute_colon_command(&format!("4,2w {}", copy.display()))
+            .expect(":4,2w");
+        assert_eq!(editor.status_message.as_deref(), Some("Invalid range: 4,2"));
+        editor
+            .execute_colon_command(&format!("5,9w {}", copy.display()))
+            .expect(":5,9w");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Write failed: range extends past the end of the buffer")
+        );
+        editor.execute_colon_command("1,2w").expect(":1,2w");
+        assert_eq!(editor.status_message.as_deref(), Some("No file name"));
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "line 5\n");
+    }
+
+    #[test]
+    fn read_only_buffer_refuses_insert_and_write() {
+        let (handle, _guard) = reset_store();
+        let name = uuid::Uuid::new_v4().to_string();
+        handle
+            .lock()
+            .unwrap()
+            .open(name.clone())
+            .append("keep".into());
+
+        let mut editor = BufferEditor::new(name.clone());
+        editor.open(name.clone());
+        editor.mode = EditorMode::Insert;
+        editor.execute_colon_command("set readonly").expect(":set");
+        assert_eq!(editor.mode, EditorMode::Read);
+        assert_eq!(editor.status_message.as_deref(), Some("Buffer read-only"));
+
+        editor.execute_colon_command("i").expect(":i");
+        assert_eq!(editor.mode, EditorMode::Read);
+        assert_eq!(editor.status_message.as_deref(), Some(READ_ONLY_MESSAGE));
+        for command in ["w", "w!", "wq"] {
+            editor.status_message = None;
+            editor.execute_colon_command(command).expect(command);
+            assert_eq!(editor.status_message.as_deref(), Some(READ_ONLY_MESSAGE));
+        }
+        assert!(!editor.is_quit());
+        assert!(!std::path::Path::new(&name).exists());
+
+        editor.execute_colon_command("set noro").expect(":set");
+        editor.execute_colon_command("i").expect(":i");
+        assert_eq!(editor.mode, EditorMode::Insert);
+    }
+
+    #[test]
+    fn set_readonly_all_covers_every_buffer() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
+        populate_buffer(&handle, "beta", 1);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.configure(&EditorConfig {
+            start_mode: EditorMode::Insert,
+            ..Default::default()
+        });
+        editor.open_tabs(&["alpha".to_string(), "beta".to_string()]);
+        editor
+            .execute_colon_command("set readonly all")
+            .expect(":set");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("All buffers read-only")
+        );
+        assert!(handle.lock().unwrap().is_read_only("beta"));
+
+        editor.open("beta");
+        assert_eq!(editor.mode, EditorMode::Read);
+        editor
+            .execute_colon_command("set readonly everything")
+            .expect(":set");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Unknown option: everything")
+        );
+
+        editor
+            .execute_colon_command("set noreadonly all")
+            .expect(":set");
+        assert!(!handle.lock().unwrap().is_read_only("alpha"));
+        assert!(!handle.lock().unwrap().is_read_only("beta"));
+    }
+
+    #[test]
+    fn open_uses_the_configured_start_mode() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 2);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        assert_eq!(editor.mode, EditorMode::Read);
+
+        editor.configure(&EditorConfig {
+            start_mode: EditorMode::Insert,
+            ..Default::default()
+        });
+        editor.open("alpha");
+        assert_eq!(editor.mode, EditorMode::Insert);
+        editor.execute_colon_command("").expect("empty command");
+        assert_eq!(editor.mode, EditorMode::Insert);
+    }
+
+    #[test]
+    fn new_buffer_insert_only_applies_to_new_buffers() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 2);
+        handle.lock().unwrap().open_untitled("untitled");
+        handle.lock().unwrap().open("empty");
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.configure(&EditorConfig {
+            new_buffer_insert: true,
+            ..Default::default()
+        });
+        editor.open("alpha");
+        assert_eq!(editor.mode, EditorMode::Read);
+        editor.open("untitled");
+        assert_eq!(editor.mode, EditorMode::Insert);
+        editor.open("empty");
+        assert_eq!(editor.mode, EditorMode::Insert);
+    }
+
+    #[test]
+    fn sort_n_orders_lines_numerically() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["10", "2", "1"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("sort")
+            .expect(":sort should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["1", "10", "2"]);
+
+        editor
+            .execute_colon_command("sort n")
+            .expect(":sort n should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["1", "2", "10"]);
+
+        editor
+            .execute_colon_command("sort x")
+            .expect(":sort x should not error");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Unknown sort option: x")
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_respect_undolevels() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 3);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("set ul=2")
+            .expect(":set ul should succeed");
+        assert_eq!(editor.status_message.as_deref(), Some("Undo levels: 2"));
+
+        for _ in 0..2 {
+            editor
+                .execute_colon_command("d")
+                .expect(":d should succeed");
+        }
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["line 2"]);
+
+        editor
+            .execute_colon_command("u")
+            .expect(":u should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["line 1", "line 2"]);
+        editor
+            .execute_colon_command("undo")
+            .expect(":undo should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["line 0", "line 1", "line 2"]
+        );
+        editor
+            .execute_colon_command("u")
+            .expect(":u should succeed");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Already at oldest change")
+        );
+
+        editor
+            .execute_colon_command("redo")
+            .expect(":redo should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["line 1", "line 2"]);
+
+        editor
+            .execute_colon_command("set undolevels=lots")
+            .expect(":set undolevels should not error");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Invalid undolevels: lots")
+        );
+    }
+
+    #[test]
+    fn undolevels_does_not_end_a_set_command() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.execute_colon_command("set ul=5 ic").expect(":set");
+        assert!(editor.settings.ignorecase);
+        assert_eq!(editor.status_message.as_deref(), Some("Undo levels: 5"));
+    }
+
+    #[test]
+    fn line_kills_update_content_and_register() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            store.open("alpha").append("hello brave world".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Insert;
+        editor.location = Location { x: 6, y: 0 };
+
+        editor
+            .apply_input_action(InputAction::KillToLineStart)
+            .expect("Ctrl+U should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["brave world"]);
+        assert_eq!(editor.registers.unnamed().as_deref(), Some("hello "));
+        assert_eq!(editor.location.x, 0);
+
+        editor.location.x = 5;
+        editor
+            .apply_input_action(InputAction::KillToLineEnd)
+            .expect("Ctrl+K should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["brave"]);
+        assert_eq!(editor.registers.unnamed().as_deref(), Some(" world"));
+        assert_eq!(editor.location.x, 5);
+
+        editor.location.x = 0;
+        editor
+            .apply_input_action(InputAction::PasteRegister)
+            .expect("Ctrl+Y should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec![" worldbrave"]);
+        assert_eq!(editor.location.x, 6);
+        assert!(handle.lock().unwrap().is_dirty("alpha"));
+    }
+
+    #[test]
+    fn p_pastes_a_kill_inline_after_the_cursor() {
+        let (handle, _guard) = reset_store();
+        handle
+            .lock()
+            .unwrap()
+            .open("alpha")
+            .append("one two".into());
+        handle.lock().unwrap().open("alpha").append("three".into());
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Insert;
+        editor.location = Location { x: 3, y: 0 };
+        editor
+            .apply_input_action(InputAction::KillToLineEnd)
+            .expect("Ctrl+K should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["one", "three"]);
+
+        editor.mode = EditorMode::Read;
+        editor.location = Location { x: 1, y: 1 };
+        editor
+            .apply_input_action(InputAction::PasteLines { register: None })
+            .expect("p should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["one", "th tworee"]);
+        assert_eq!((editor.location.y, editor.location.x), (1, 5));
+    }
+
+    #[test]
+    fn delete_forward_removes_character_under_cursor() {
+        let (handle, _guard) = reset_store();
+        handle.lock().unwrap().open("alpha").append("abc".into());
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.mode = EditorMode::Insert;
+        editor.location = Location { x: 1, y: 0 };
+
+        editor
+            .apply_input_action(InputAction::DeleteForward)
+            .expect("Delete should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["ac"]);
+        assert_eq!(editor.location.x, 1);
+
+        editor.location.x = 2;
+        editor
+            .apply_input_action(InputAction::DeleteForward)
+            .expect("Delete at end of line is a no-op");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["ac"]);
+    }
+
+    #[test]
+    fn recorded_macro_replays_edits_on_another_buffer() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let alpha = store.open("alpha");
+            alpha.append("abc".into());
+            alpha.append("def".into());
+            let beta = store.open("beta");
+            beta.append("xyz".into());
+            beta.append("uvw".into());
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        type_keys(&mut editor, "qa:i<CR><Del><Esc>J");
+        assert_eq!(editor.input.recording(), Some('a'));
+        type_keys(&mut editor, "q");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["bc def"]);
+        assert_eq!(editor.input.recording(), None);
+        assert_eq!(
+            editor.registers.get(Some('a')).unwrap(),
+            [":i<CR><Del><Esc>J"]
+        );
+
+        editor.open("beta");
+        editor
+            .apply_input_action(InputAction::ReplayMacro('a'))
+            .expect("@a should succeed");
+        assert_eq!(buffer_lines(&handle, "beta"), vec!["yz uvw"]);
+
+        editor
+            .apply_input_action(InputAction::ReplayMacro('z'))
+            .expect("@z should succeed");
+        assert_eq!(editor.status_message.as_deref(), Some("Macro @z is empty"));
+    }
+
+    #[test]
+    fn macros_and_yanks_share_the_named_registers() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let alpha = store.open("alpha");
+            for line in ["J", "one", "two"] {
+                alpha.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        type_keys(&mut editor, "\"byy<Down>@b");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["J", "one two"]);
+
+        type_keys(&mut editor, "qcJq");
+        type_keys(&mut editor, "\"cp");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["J", "one Jtwo"]);
+    }
+
+    fn type_keys(editor: &mut BufferEditor, keys: &str) {
+        for event in parse_keys(keys) {
+            editor.dispatch(&event).expect("key should apply");
+        }
+    }
+
+    /// Clipboard backend whose contents the test can inspect and replace.
+    struct MockClipboard(Arc<Mutex<Option<String>>>);
+
+    impl crate::editor::clipboard::ClipboardBackend for MockClipboard {
+        fn get_text(&mut self) -> Option<String> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn set_text(&mut self, text: &str) -> bool {
+            *self.0.lock().unwrap() = Some(text.to_string());
+            true
+        }
+    }
+
+    #[test]
+    fn system_clipboard_receives_yanks_and_feeds_pastes() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["one", "two"] {
+                buffer.append(line.into());
+            }
+        }
+        let contents = Arc::new(Mutex::new(None));
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.clipboard = Some(SharedClipboard::new(MockClipboard(Arc::clone(&contents))));
+
+        editor
+            .apply_input_action(InputAction::YankLines {
+                register: None,
+                count: 2,
+            })
+            .expect("2yy should succeed");
+        assert_eq!(*contents.lock().unwrap(), None, "internal until enabled");
+
+        editor
+            .execute_colon_command("set clipboard=system")
+            .expect(":set");
+        editor
+            .apply_input_action(InputAction::YankLines {
+                register: Some('a'),
+                count: 2,
+            })
+            .expect("\"a2yy should succeed");
+        assert_eq!(contents.lock().unwrap().as_deref(), Some("one\ntwo"));
+
+        *contents.lock().unwrap() = Some("from elsewhere\n".to_string());
+        editor.location.y = 1;
+        editor
+            .apply_input_action(InputAction::PasteLines { register: None })
+            .expect("p should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["one", "two", "from elsewhere"]
+        );
+        assert_eq!(editor.registers.get(Some('a')).unwrap(), ["one", "two"]);
+    }
+
+    #[test]
+    fn unavailable_system_clipboard_falls_back_to_registers() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+
+        assert!(editor.execute_colon_command("set cb=primary").is_ok());
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Invalid clipboard: primary")
+        );
+        if cfg!(not(feature = "system-clipboard")) {
+            editor.execute_colon_command("set cb=system").expect(":set");
+            assert_eq!(
+                editor.status_message.as_deref(),
+                Some("System clipboard unavailable; using the internal register")
+            );
+        }
+        editor
+            .apply_input_action(InputAction::YankLines {
+                register: None,
+                count: 1,
+            })
+            .expect("yy should succeed");
+        editor
+            .apply_input_action(InputAction::PasteLines { register: None })
+            .expect("p should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["line 0", "line 0"]);
+    }
+
+    #[test]
+    fn named_registers_yank_and_paste_independently() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["one", "two", "three"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .apply_input_action(InputAction::YankLines {
+                register: Some('a'),
+                count: 1,
+            })
+            .expect("\"ayy should succeed");
+        editor.location.y = 1;
+        editor
+            .apply_input_action(InputAction::YankLines {
+                register: Some('b'),
+                count: 2,
+            })
+            .expect("\"b2yy should succeed");
+        assert_eq!(editor.registers.get(Some('a')).unwrap(), ["one"]);
+        assert_eq!(editor.registers.get(Some('b')).unwrap(), ["two", "three"]);
+
+        editor.location.y = 2;
+        editor
+            .apply_input_action(InputAction::PasteLines {
+                register: Some('a'),
+            })
+            .expect("\"ap should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["one", "two", "three", "one"]
+        );
+        assert_eq!(editor.location.y, 3);
+
+        editor.location.y = 0;
+        editor
+            .apply_input_action(InputAction::PasteLines { register: None })
+            .expect("p should succeed");
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["one", "two", "three", "two", "three", "one"]
+        );
+
+        editor
+            .execute_colon_command("registers")
+            .expect(":registers should succeed");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("\"\" two\\nthree | \"a one | \"b two\\nthree")
+        );
+    }
+
+    #[test]
+    fn global_delete_removes_every_matching_line() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["keep", "x1", "x2", "keep too", "x3"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("g/x/d")
+            .expect(":g/x/d should succeed");
+
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["keep".to_string(), "keep too".to_string()]
+        );
+        assert_eq!(editor.location.y, 1);
+    }
+
+    #[test]
+    fn global_delete_is_undone_in_one_step() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["keep", "x1", "x2", "keep too", "x3"] {
+                buffer.append(line.into());
+            }
+        }
+
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("g/x/d")
+            .expect(":g/x/d should succeed");
+        editor
+            .execute_colon_command("undo")
+            .expect(":undo should succeed");
+
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["keep", "x1", "x2", "keep too", "x3"]
+        );
+        editor
+            .execute_colon_command("redo")
+            .expect(":redo should succeed");
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["keep", "keep too"]);
+    }
+
+    #[test]
+    fn global_commands_follow_case_settings() {
+        let (handle, _guard) = reset_store();
+        let lines = ["Error: disk", "error: net", "ok", "ERROR: cpu"];
+        let mut editor = BufferEditor::new("alpha");
+        let mut run = |setting: &str, command: &str| {
+            let mut store = handle.lock().unwrap();
+            store.remove("alpha");
+            let buffer = store.open("alpha");
+            for line in lines {
+                buffer.append(line.into());
+            }
+            drop(store);
+            editor.open("alpha");
+            editor.settings = EditorSettings::default();
+            editor.apply_set_command(setting).expect(":set");
+            editor.execute_colon_command(command).expect(":g");
+            buffer_lines(&handle, "alpha")
+        };
+
+        assert_eq!(run("noic", "g/error/d"), lines_except(&lines, &[1]));
+        assert_eq!(run("ignorecase", "g/error/d"), vec!["ok"]);
+        assert_eq!(run("ignorecase", "g/Error/d"), vec!["ok"]);
+        assert_eq!(run("smartcase", "g/error/d"), vec!["ok"]);
+        assert_eq!(run("smartcase", "g/Error/d"), lines_except(&lines, &[0]));
+        assert_eq!(
+            run("smartcase", "v/Error/d"),
+            vec!["Error: disk".to_string()]
+        );
     }
 
-    fn reset_store() -> (Arc<Mutex<BufferStore>>, MutexGuard<'static, ()>) {
-        let guard = test_lock();
-        unsafe {
-            std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
-        }
+    fn lines_except(lines: &[&str], removed: &[usize]) -> Vec<String> {
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !removed.contains(idx))
+            .map(|(_, line)| line.to_string())
+            .collect()
+    }
 
-        let terminal = Terminal::instance();
-        let candidate = Arc::new(Mutex::new(BufferStore::new()));
-        terminal.attach_store(Arc::clone(&candidate));
-        let handle = terminal.store_handle();
+    #[test]
+    fn modelines_apply_only_when_enabled() {
+        let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            *store = BufferStore::new();
+            let buffer = store.open("notes.txt");
+            for idx in 0..12 {
+                buffer.append(format!("line {idx}"));
+            }
+            buffer.append("# iridium: tw=72 cc=80 ft=rust".into());
         }
 
-        (handle, guard)
+        let mut editor = BufferEditor::new("notes.txt");
+        editor.open("notes.txt");
+        assert_eq!(editor.settings.textwidth, 0);
+
+        editor.configure(&EditorConfig {
+            modeline: true,
+            ..Default::default()
+        });
+        editor.open("notes.txt");
+        assert_eq!(editor.settings.textwidth, 72);
+        assert_eq!(editor.settings.colorcolumn, vec![80]);
+        assert_eq!(editor.settings.filetype, Some(FileType::Rust));
     }
 
-    fn populate_buffer(handle: &Arc<Mutex<BufferStore>>, name: &str, line_count: usize) {
-        let mut store = handle.lock().unwrap();
-        let buffer = store.open(name);
-        buffer.clear();
-        for idx in 0..line_count {
-            buffer.append(format!("line {idx}"));
+    #[test]
+    fn modelines_outside_the_scanned_lines_or_malformed_are_ignored() {
+        let (handle, _guard) = reset_store();
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("notes");
+            buffer.append("# iridium: tw=oops".into());
+            for idx in 0..12 {
+                let line = if idx == 6 {
+                    "# iridium: tw=50".to_string()
+                } else {
+                    format!("line {idx}")
+                };
+                buffer.append(line);
+            }
         }
+
+        let mut editor = BufferEditor::new("notes");
+        editor.configure(&EditorConfig {
+            modeline: true,
+            ..Default::default()
+        });
+        editor.open("notes");
+        assert_eq!(editor.settings.textwidth, 0);
+        assert_eq!(editor.status_message, None);
     }
 
     #[test]
-    fn navigation_page_up_moves_to_view_top() {
+    fn incsearch_previews_the_first_match_and_restores_on_cancel() {
         let (handle, _guard) = reset_store();
-        populate_buffer(&handle, "alpha", 20);
+        {
+            let mut store = handle.lock().unwrap();
+            let buffer = store.open("alpha");
+            for line in ["start", "a tarp", "the target", "plain"] {
+                buffer.append(line.into());
+            }
+        }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-        editor.mode = EditorMode::Read;
-        editor.location = Location { x: 3, y: 10 };
-        editor.scroll_offset = 8;
-        editor.view_height = 5;
+        editor.execute_colon_command("set incsearch").expect(":set");
+        editor.location = Location { x: 2, y: 0 };
+
+        let mut typed = String::new();
+        for (ch, expected) in [('/', (0, 2)), ('t', (1, 2)), ('a', (1, 2)), ('r', (1, 2))] {
+            typed.push(ch);
+            editor
+                .apply_input_action(InputAction::UpdateCommandBuffer(typed.clone()))
+                .expect("update command");
+            assert_eq!((editor.location.y, editor.location.x), expected);
+        }
+        for (ch, expected) in [('g', (2, 4)), ('x', (0, 2))] {
+            typed.push(ch);
+            editor
+                .apply_input_action(InputAction::UpdateCommandBuffer(typed.clone()))
+                .expect("update command");
+            assert_eq!((editor.location.y, editor.location.x), expected);
+        }
+        assert_eq!(editor.search_highlight(), Some("targx"));
 
         editor
-            .navigate_line(NavigationCommand::PageStart)
-            .expect("page up navigation");
-        assert_eq!(editor.location.y, 8);
-        assert_eq!(editor.scroll_offset, 8);
+            .apply_input_action(InputAction::ExitInsertMode)
+            .expect("cancel");
+        assert_eq!((editor.location.y, editor.location.x), (0, 2));
+        assert_eq!(editor.search_highlight(), None);
 
         editor
-            .navigate_line(NavigationCommand::PageStart)
-            .expect("page up scrolls");
-        assert_eq!(editor.scroll_offset, 6);
-        assert_eq!(editor.location.y, 6);
+            .apply_input_action(InputAction::UpdateCommandBuffer("/targ".into()))
+            .expect("update command");
+        editor
+            .apply_input_action(InputAction::ExecuteCommand("/targ".into()))
+            .expect("search");
+        assert_eq!((editor.location.y, editor.location.x), (2, 4));
     }
 
     #[test]
-    fn navigation_page_down_moves_to_view_bottom_or_buffer_end() {
+    fn hlsearch_keeps_the_last_search_highlighted() {
         let (handle, _guard) = reset_store();
-        populate_buffer(&handle, "alpha", 12);
+        populate_buffer(&handle, "alpha", 3);
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-        editor.mode = EditorMode::Read;
-        editor.location = Location { x: 2, y: 8 };
-        editor.scroll_offset = 7;
-        editor.view_height = 6;
-
-        editor
-            .navigate_line(NavigationCommand::PageEnd)
-            .expect("page down navigation");
-        assert_eq!(editor.location.y, 11);
-        assert_eq!(editor.scroll_offset, 7);
+        editor.execute_colon_command("/line").expect("search");
+        assert_eq!(editor.search_highlight(), None);
 
+        editor.execute_colon_command("set hls").expect(":set");
+        assert_eq!(editor.search_highlight(), Some("line"));
         editor
-            .navigate_line(NavigationCommand::PageEnd)
-            .expect("page down scrolls");
-        assert_eq!(editor.scroll_offset, 10);
-        assert_eq!(editor.location.y, 11);
+            .execute_colon_command("set nohlsearch")
+            .expect(":set");
+        assert_eq!(editor.search_highlight(), None);
     }
 
     #[test]
-    fn navigation_page_up_preserves_horizontal_until_front() {
+    fn navigation_clamps_to_content_and_leaves_the_buffer_clean() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
             let buffer = store.open("alpha");
-            buffer.clear();
-            for len in [5usize, 3, 12, 4, 2, 1, 6, 2, 3, 4, 5, 6] {
-                buffer.append("x".repeat(len));
+            for line in ["a long first line", "", "mid", "the last line"] {
+                buffer.append(line.into());
             }
+            store.save_in_memory("alpha");
         }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-        editor.mode = EditorMode::Read;
-        editor.location = Location { x: 10, y: 10 };
-        editor.scroll_offset = 8;
-        editor.view_height = 5;
+        editor.view_height = 2;
+        for mode in [EditorMode::Read, EditorMode::Insert] {
+            editor.mode = mode;
+            editor.location = Location { x: 0, y: 0 };
+            let moves = [KeyCode::End, KeyCode::Right, KeyCode::Down, KeyCode::Right]
+                .into_iter()
+                .chain(std::iter::repeat_n(KeyCode::Down, 5))
+                .chain(std::iter::repeat_n(KeyCode::Right, 20))
+                .chain([KeyCode::Up, KeyCode::PageDown, KeyCode::PageUp]);
+            for key in moves {
+                editor
+                    .apply_input_action(InputAction::MoveCursor(key))
+                    .expect("move");
+                let len = buffer_lines(&handle, "alpha")[editor.location.y]
+                    .chars()
+                    .count();
+                assert!(editor.location.x <= len, "{key:?} left the line");
+            }
+            editor.location = Location { x: 13, y: 3 };
+            editor
+                .navigate_line(NavigationCommand::PageStart)
+                .expect("page up");
+            editor
+                .navigate_line(NavigationCommand::PageEnd)
+                .expect("page down");
+        }
 
-        editor
-            .navigate_line(NavigationCommand::PageStart)
-            .expect("page up maintains x");
-        assert_eq!(editor.location.y, 8);
-        assert_eq!(editor.location.x, 10);
+        let store = handle.lock().unwrap();
+        assert!(!store.is_dirty("alpha"));
+        assert_eq!(store.line_count("alpha"), 4);
+        assert_eq!(store.get("alpha").unwrap().lines()[1], "");
+    }
+
+    #[test]
+    fn set_backup_and_backupdir_configure_the_store() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 1);
 
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.execute_colon_command("set bk").expect(":set");
+        assert_eq!(editor.status_message.as_deref(), Some("Backups on"));
+        editor
+            .execute_colon_command("set backupdir=/tmp/iridium-backups")
+            .expect(":set");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Backup directory: /tmp/iridium-backups")
+        );
         {
             let store = handle.lock().unwrap();
-            let buffer = store.get("alpha").unwrap();
-            assert!(buffer.lines()[8].chars().count() >= 10);
+            let backup = store.backup_options();
+            assert!(backup.enabled);
+            assert_eq!(
+                backup.dir.as_deref(),
+                Some(Path::new("/tmp/iridium-backups"))
+            );
         }
 
-        // Move to front of buffer and ensure clamped column.
-        editor.location = Location { x: 10, y: 0 };
-        editor.scroll_offset = 0;
         editor
-            .navigate_line(NavigationCommand::PageStart)
-            .expect("page up at front");
-        assert_eq!(editor.location.y, 0);
-        assert_eq!(editor.location.x, 5);
+            .execute_colon_command("set backupdir=")
+            .expect(":set");
+        editor.execute_colon_command("set nobackup").expect(":set");
+        assert_eq!(editor.status_message.as_deref(), Some("Backups off"));
+        let store = handle.lock().unwrap();
+        assert_eq!(store.backup_options(), &Default::default());
     }
 
     #[test]
-    fn navigation_word_left_moves_to_previous_space() {
+    fn backup_options_do_not_end_a_set_command() {
         let (handle, _guard) = reset_store();
-        {
-            let mut store = handle.lock().unwrap();
-            let buffer = store.open("alpha");
-            buffer.clear();
-            buffer.append("first second third".into());
-        }
+        populate_buffer(&handle, "alpha", 1);
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-        editor.mode = EditorMode::Read;
-        editor.location = Location { x: 12, y: 0 };
-
-        editor
-            .navigate_line(NavigationCommand::WordLeft)
-            .expect("word left");
-        assert_eq!(editor.location.x, 11);
-
         editor
-            .navigate_line(NavigationCommand::WordLeft)
-            .expect("word left again");
-        assert_eq!(editor.location.x, 5);
+            .execute_colon_command("set bk bdir=/tmp/iridium-backups ic")
+            .expect(":set");
+        assert!(editor.settings.ignorecase);
+        let store = handle.lock().unwrap();
+        let backup = store.backup_options();
+        assert!(backup.enabled);
+        assert_eq!(
+            backup.dir.as_deref(),
+            Some(Path::new("/tmp/iridium-backups"))
+        );
     }
 
     #[test]
-    fn navigation_word_right_moves_to_next_space_or_end() {
+    fn enter_in_read_mode_moves_to_the_next_first_non_blank() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
             let buffer = store.open("alpha");
-            buffer.clear();
-            buffer.append("first second third".into());
+            for line in ["fn main() {", "    let x = 1;", "\tx", "   "] {
+                buffer.append(line.into());
+            }
         }
-
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-        editor.mode = EditorMode::Read;
-        editor.location = Location { x: 0, y: 0 };
-
-        editor
-            .navigate_line(NavigationCommand::WordRight)
-            .expect("word right");
-        assert_eq!(editor.location.x, 5);
-
-        editor
-            .navigate_line(NavigationCommand::WordRight)
-            .expect("word right again");
-        assert_eq!(editor.location.x, 11);
+        editor.location.x = 5;
+
+        let mut visited = Vec::new();
+        for _ in 0..4 {
+            editor
+                .apply_input_action(InputAction::MoveCursor(KeyCode::Enter))
+                .expect("enter");
+            visited.push((editor.location.y, editor.location.x));
+        }
+        assert_eq!(visited, vec![(1, 4), (2, 1), (3, 3), (3, 3)]);
+        assert_eq!(buffer_lines(&handle, "alpha").len(), 4);
     }
 
     #[test]
-    fn quit_all_prompts_when_buffer_is_untitled() {
+    fn virtualedit_moves_past_line_end_without_modifying_the_buffer() {
         let (handle, _guard) = reset_store();
-        {
-            let mut store = handle.lock().unwrap();
-            store.open_untitled("Untitled-1");
-        }
+        populate_buffer(&handle, "alpha", 3);
+        handle.lock().unwrap().save_in_memory("alpha");
 
-        let mut editor = BufferEditor::new("Untitled-1");
-        editor.open("Untitled-1");
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor.execute_colon_command("set ve").expect(":set");
+        editor.mode = EditorMode::Insert;
+        editor.location = Location { x: 6, y: 0 };
+        editor.view_height = 2;
+        for _ in 0..4 {
+            editor
+                .apply_input_action(InputAction::MoveCursor(KeyCode::Right))
+                .expect("right");
+        }
+        assert_eq!((editor.location.x, editor.location.y), (10, 0));
+        for _ in 0..4 {
+            editor
+                .apply_input_action(InputAction::MoveCursor(KeyCode::Down))
+                .expect("down");
+        }
+        assert_eq!((editor.location.x, editor.location.y), (10, 2));
+        editor
+            .navigate_line(NavigationCommand::PageStart)
+            .expect("page up");
+        assert_eq!(editor.location.x, 10);
+        editor
+            .apply_input_action(InputAction::DeleteChar)
+            .expect("backspace");
+        assert_eq!(editor.location.x, 9);
 
-        let keep_prompt = editor
-            .handle_quit_all_command()
-            .expect("quit all command should succeed");
-        assert!(keep_prompt, "should keep command text until name provided");
+        assert!(!handle.lock().unwrap().is_dirty("alpha"));
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["line 0", "line 1", "line 2"]
+        );
 
-        let input = format!("{}named", BUFFER_NAME_PROMPT);
+        editor.location = Location { x: 9, y: 0 };
         editor
-            .process_prompt_input(input)
-            .expect("prompt processing should succeed");
-
-        assert!(editor.take_quit_all_request());
+            .apply_input_action(InputAction::InsertChar('x'))
+            .expect("type");
+        assert_eq!(buffer_lines(&handle, "alpha")[0], "line 0   x");
+        assert!(handle.lock().unwrap().is_dirty("alpha"));
     }
 
     #[test]
-    fn quit_all_sets_flag_for_named_buffer() {
+    fn search_moves_to_next_match_and_wraps() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            store.open("alpha");
+            let buffer = store.open("alpha");
+            for line in ["Target one", "plain", "the target"] {
+                buffer.append(line.into());
+            }
         }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-
-        let keep_prompt = editor
-            .handle_quit_all_command()
-            .expect("quit all command should succeed");
-        assert!(!keep_prompt, "no prompt needed for named buffer");
-        assert!(editor.take_quit_all_request());
+        editor.execute_colon_command("/target").expect("search");
+        assert_eq!((editor.location.y, editor.location.x), (2, 4));
+        editor.execute_colon_command("/target").expect("search");
+        assert_eq!((editor.location.y, editor.location.x), (2, 4));
+
+        editor.execute_colon_command("set smartcase").expect(":set");
+        editor.execute_colon_command("/target").expect("search");
+        assert_eq!((editor.location.y, editor.location.x), (0, 0));
+        editor.execute_colon_command("/Target").expect("search");
+        assert_eq!((editor.location.y, editor.location.x), (0, 0));
+        editor.execute_colon_command("/missing").expect("search");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Pattern not found: missing")
+        );
     }
 
     #[test]
-    fn cycles_forward_and_wraps() {
+    fn wrapscan_controls_whether_n_wraps_past_the_end() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            store.open("beta");
-            store.open("alpha");
-            store.open("gamma");
+            let buffer = store.open("alpha");
+            for line in ["plain", "plain", "plain", "needle", "plain"] {
+                buffer.append(line.into());
+            }
         }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
+        editor.execute_colon_command("/needle").expect("search");
+        assert_eq!(editor.location.y, 3);
 
-        editor.cycle_buffer(true).expect("cycle next");
-        assert!(editor.prompt_string().contains("[buffer:beta]"));
-
-        editor.cycle_buffer(true).expect("cycle next again");
-        assert!(editor.prompt_string().contains("[buffer:gamma]"));
+        editor
+            .apply_input_action(InputAction::RepeatSearch { reverse: false })
+            .expect("n");
+        assert_eq!(editor.location.y, 3, "n wraps round to the only match");
+        assert_eq!(editor.status_message, None);
 
-        editor.cycle_buffer(true).expect("cycle wraps to start");
-        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+        editor
+            .execute_colon_command("set nowrapscan")
+            .expect(":set");
+        editor
+            .apply_input_action(InputAction::RepeatSearch { reverse: false })
+            .expect("n");
+        assert_eq!(editor.location.y, 3);
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Search hit BOTTOM without match for: needle")
+        );
+        editor
+            .apply_input_action(InputAction::RepeatSearch { reverse: true })
+            .expect("N");
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Search hit TOP without match for: needle")
+        );
+
+        editor.location.y = 0;
+        editor
+            .apply_input_action(InputAction::RepeatSearch { reverse: false })
+            .expect("n");
+        assert_eq!(editor.location.y, 3, "matches ahead are still found");
     }
 
     #[test]
-    fn cycles_backward_and_wraps() {
+    fn global_substitute_applies_to_each_matching_line() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            store.open("beta");
-            store.open("alpha");
-            store.open("gamma");
+            let buffer = store.open("alpha");
+            for line in ["foo a", "bar", "foo foo"] {
+                buffer.append(line.into());
+            }
         }
 
-        let mut editor = BufferEditor::new("beta");
-        editor.open("beta");
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("g/foo/s/foo/baz/")
+            .expect(":g substitute should succeed");
+
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec![
+                "baz a".to_string(),
+                "bar".to_string(),
+                "baz foo".to_string()
+            ]
+        );
+        assert_eq!(editor.location.y, 2);
+    }
 
-        editor.cycle_buffer(false).expect("cycle prev");
-        assert!(editor.prompt_string().contains("[buffer:alpha]"));
+    #[test]
+    fn global_without_matches_reports_status() {
+        let (handle, _guard) = reset_store();
+        populate_buffer(&handle, "alpha", 3);
 
-        editor.cycle_buffer(false).expect("cycle prev wraps");
-        assert!(editor.prompt_string().contains("[buffer:gamma]"));
+        let mut editor = BufferEditor::new("alpha");
+        editor.open("alpha");
+        editor
+            .execute_colon_command("g/missing/d")
+            .expect(":g should succeed");
+
+        assert_eq!(buffer_lines(&handle, "alpha").len(), 3);
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("Pattern not found: missing")
+        );
     }
 
     #[test]
-    fn colon_command_switches_buffer() {
+    fn percent_substitute_reports_changes_above_the_report_threshold() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            store.open("alpha");
-            store.open("beta");
+            let buffer = store.open("alpha");
+            for line in ["foo foo", "bar", "foo", "foo foo"] {
+                buffer.append(line.into());
+            }
         }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-
         editor
-            .apply_input_action(InputAction::ExecuteCommand("b beta".into()))
-            .expect("command should succeed");
-        assert!(editor.prompt_string().contains("[buffer:beta]"));
+            .execute_colon_command("%s/foo/baz/g")
+            .expect(":%s should succeed");
+
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["baz baz", "bar", "baz", "baz baz"]
+        );
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("5 substitutions on 3 lines")
+        );
+
+        editor.execute_colon_command("u").unwrap();
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["foo foo", "bar", "foo", "foo foo"],
+            "one undo reverts the whole substitution"
+        );
+        editor.execute_colon_command("%s/foo/baz/g").unwrap();
+
+        editor.clear_status_message();
+        editor.execute_colon_command("set report=3").unwrap();
+        editor.execute_colon_command("%s/baz/qux/").unwrap();
+        assert_eq!(
+            buffer_lines(&handle, "alpha"),
+            vec!["qux baz", "bar", "qux", "qux baz"]
+        );
+        assert_eq!(editor.status_message, None, "3 lines is not above report=3");
     }
 
     #[test]
-    fn close_current_buffer_moves_to_next() {
+    fn global_commands_summarise_bulk_edits() {
         let (handle, _guard) = reset_store();
         {
             let mut store = handle.lock().unwrap();
-            store.open("alpha");
-            store.open("beta");
+            let buffer = store.open("alpha");
+            for line in ["x1", "keep", "x2", "x3", "x4"] {
+                buffer.append(line.into());
+            }
         }
 
         let mut editor = BufferEditor::new("alpha");
         editor.open("alpha");
-
-        editor.close_current_buffer(false).expect("close current");
-
-        {
-            let store = handle.lock().unwrap();
-            let alpha = store.get("alpha").expect("alpha should remain tracked");
-            assert!(!alpha.is_open(), "closed buffer should no longer be open");
-            let beta = store.get("beta").expect("beta should exist");
-            assert!(beta.is_open());
-        }
-
-        assert!(editor.prompt_string().contains("[buffer:beta]"));
-        assert!(!editor.quit);
+        editor.execute_colon_command("g/x/s/x/y/").unwrap();
+        assert_eq!(
+            editor.status_message.as_deref(),
+            Some("4 substitutions on 4 lines")
+        );
+
+        editor.execute_colon_command("g/y/d").unwrap();
+        assert_eq!(buffer_lines(&handle, "alpha"), vec!["keep"]);
+        assert_eq!(editor.status_message.as_deref(), Some("4 fewer lines"));
     }
 
     #[test]
-    fn close_current_buffer_respects_dirty_flag() {
+    fn run_without_tty_returns_error_instead_of_panicking() {
         let (handle, _guard) = reset_store();
-        {
-            let mut store = handle.lock().unwrap();
-            store.open("alpha").append("dirty".into());
-        }
+        populate_buffer(&handle, "alpha", 1);
 
-        let mut editor = BufferEditor::new("alpha");
+        let line_terminal: &'static Terminal =
+            Box::leak(Box::new(Terminal::with_mode(TerminalMode::Line)));
+        let mut editor = BufferEditor {
+            term: line_terminal,
+            ..BufferEditor::new("alpha")
+        };
         editor.open("alpha");
 
-        editor.close_current_buffer(false).expect("close current");
-        {
-            let store = handle.lock().unwrap();
-            assert!(store.get("alpha").is_some());
-        }
+        let err = editor
+            .run()
+            .expect_err("editor must refuse to start without a TTY");
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
         assert!(!editor.quit);
-
-        editor.close_current_buffer(true).expect("force close");
-        {
-            let store = handle.lock().unwrap();
-            let alpha = store
-                .get("alpha")
-                .expect("alpha should remain tracked after force close");
-            assert!(!alpha.is_open());
-        }
-        assert!(editor.quit);
     }
 
     #[test]
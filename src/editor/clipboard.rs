@@ -0,0 +1,91 @@
+//! OS clipboard access for `:set clipboard=system`.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Where yanks go and pastes come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClipboardMode {
+    /// Only the editor's own registers.
+    #[default]
+    Internal,
+    /// The unnamed register is mirrored to and refreshed from the OS clipboard.
+    System,
+}
+
+impl ClipboardMode {
+    /// Parse a `:set clipboard=` value; an empty value selects the internal register.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "" | "internal" => Ok(Self::Internal),
+            "system" => Ok(Self::System),
+            other => Err(format!("Invalid clipboard: {other}")),
+        }
+    }
+}
+
+/// A clipboard the editor can read and write text through.
+pub trait ClipboardBackend: Send {
+    /// Current clipboard text, or `None` when it holds none or cannot be read.
+    fn get_text(&mut self) -> Option<String>;
+
+    /// Replace the clipboard text, returning whether it was written.
+    fn set_text(&mut self, text: &str) -> bool;
+}
+
+/// A clipboard backend shared between clones of the editor.
+#[derive(Clone)]
+pub struct SharedClipboard(Arc<Mutex<dyn ClipboardBackend>>);
+
+impl SharedClipboard {
+    #[cfg_attr(not(feature = "system-clipboard"), allow(dead_code))]
+    pub fn new(backend: impl ClipboardBackend + 'static) -> Self {
+        Self(Arc::new(Mutex::new(backend)))
+    }
+
+    /// The OS clipboard, or `None` when it cannot be opened or the `system-clipboard` feature
+    /// is disabled.
+    pub fn system() -> Option<Self> {
+        #[cfg(feature = "system-clipboard")]
+        {
+            arboard::Clipboard::new()
+                .ok()
+                .map(|clipboard| Self::new(SystemClipboard(clipboard)))
+        }
+        #[cfg(not(feature = "system-clipboard"))]
+        {
+            None
+        }
+    }
+
+    pub fn get_text(&self) -> Option<String> {
+        self.0.lock().ok()?.get_text()
+    }
+
+    pub fn set_text(&self, text: &str) -> bool {
+        self.0
+            .lock()
+            .map(|mut backend| backend.set_text(text))
+            .unwrap_or(false)
+    }
+}
+
+impl fmt::Debug for SharedClipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedClipboard")
+    }
+}
+
+#[cfg(feature = "system-clipboard")]
+struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "system-clipboard")]
+impl ClipboardBackend for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.0.get_text().ok()
+    }
+
+    fn set_text(&mut self, text: &str) -> bool {
+        self.0.set_text(text.to_string()).is_ok()
+    }
+}
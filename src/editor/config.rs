@@ -0,0 +1,95 @@
+//! Startup options the shell hands every editor session.
+
+use crate::conf::section::EditorConfigSection;
+use crate::editor::buffer_editor::EditorMode;
+use crate::editor::input::KeyBinding;
+
+/// Options from the `editor` config section that each editor session starts with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditorConfig {
+    /// Key that closes the editor immediately (`editor.quit_key`).
+    pub quit_key: KeyBinding,
+    /// Draw the blinking `‸` glyph instead of the terminal's own cursor (`editor.custom_cursor`).
+    pub custom_cursor: bool,
+    /// Mode buffers open in (`editor.start_mode`).
+    pub start_mode: EditorMode,
+    /// Open untitled and empty new buffers in insert mode (`editor.new_buffer_insert`).
+    pub new_buffer_insert: bool,
+    /// Half-page navigation stride; `0` scrolls half the view (`editor.scroll`).
+    pub scroll: usize,
+    /// Lines a bulk edit must exceed before it is summarised (`editor.report`).
+    pub report: usize,
+    /// Apply `iridium:` modelines when buffers are opened (`editor.modeline`).
+    pub modeline: bool,
+    /// Write the current buffer before leaving it for another (`editor.autowriteall`).
+    pub autowriteall: bool,
+    /// `:q` on the last open buffer opens a new untitled one (`editor.quit_last_opens_new`).
+    pub quit_last_opens_new: bool,
+    /// Show the `[MODE]` label in the status line (`editor.showmode`).
+    pub showmode: bool,
+}
+
+impl EditorConfig {
+    /// Read the options from `section`, warning about and replacing values that do not parse.
+    pub fn from_section(section: &EditorConfigSection) -> Self {
+        let quit_key = KeyBinding::parse(section.quit_key()).unwrap_or_else(|err| {
+            eprintln!("Warning: editor.quit_key: {err}; using Ctrl+C");
+            KeyBinding::default()
+        });
+        let start_mode = EditorMode::from_start_name(section.start_mode()).unwrap_or_else(|| {
+            eprintln!(
+                "Warning: editor.start_mode: unknown mode '{}'; using read",
+                section.start_mode()
+            );
+            EditorMode::Read
+        });
+        Self {
+            quit_key,
+            custom_cursor: section.custom_cursor(),
+            start_mode,
+            new_buffer_insert: section.new_buffer_insert(),
+            scroll: section.scroll(),
+            report: section.report(),
+            modeline: section.modeline(),
+            autowriteall: section.autowriteall(),
+            quit_last_opens_new: section.quit_last_opens_new(),
+            showmode: section.showmode(),
+        }
+    }
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self::from_section(&EditorConfigSection::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conf::ConfigurationModel;
+    use crate::editor::settings::DEFAULT_REPORT;
+
+    #[test]
+    fn reads_the_editor_section() {
+        let config: ConfigurationModel = serde_yaml::from_str(
+            "editor:\n  quit_key: ctrl+q\n  start_mode: insert\n  report: 0\n  showmode: false\n",
+        )
+        .unwrap();
+        let editor = EditorConfig::from_section(&config.editor);
+        assert_eq!(editor.quit_key, KeyBinding::parse("ctrl+q").unwrap());
+        assert_eq!(editor.start_mode, EditorMode::Insert);
+        assert_eq!(editor.report, 0);
+        assert!(!editor.showmode);
+        assert!(editor.custom_cursor);
+    }
+
+    #[test]
+    fn defaults_match_an_empty_section() {
+        let editor = EditorConfig::default();
+        assert_eq!(editor.quit_key, KeyBinding::default());
+        assert_eq!(editor.start_mode, EditorMode::Read);
+        assert_eq!(editor.report, DEFAULT_REPORT);
+        assert!(editor.showmode);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::editor::buffer_editor::EditorMode;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum InputAction {
@@ -9,14 +10,115 @@ pub enum InputAction {
     ExitInsertMode,
     InsertChar(char),
     DeleteChar,
+    DeleteForward,
     InsertNewLine,
+    KillToLineStart,
+    KillToLineEnd,
+    PasteRegister,
+    YankLines {
+        register: Option<char>,
+        count: usize,
+    },
+    PasteLines {
+        register: Option<char>,
+    },
+    JoinLines(usize),
+    /// `>>`/`<<`: indent `count` lines from the cursor, or outdent them when `outdent`.
+    ShiftLines {
+        count: usize,
+        outdent: bool,
+    },
+    /// `n`/`N`: repeat the last search forwards, or backwards when `reverse`.
+    RepeatSearch {
+        reverse: bool,
+    },
+    /// `q<reg>`: start capturing actions into a macro register.
+    StartRecording(char),
+    /// `q` while recording, with the keys typed since `q<reg>` in [`key_notation`] form.
+    StopRecording {
+        register: char,
+        keys: String,
+    },
+    /// `@<reg>`: replay a recorded macro.
+    ReplayMacro(char),
     MoveCursor(KeyCode),
     Navigation(NavigationCommand),
     UpdateCommandBuffer(String),
     ExecuteCommand(String),
+    /// Leave the editor at once via the quit key, without the unsaved-changes checks of `:q`.
     Quit,
 }
 
+/// A modified key such as `Ctrl+Q`, used for the configurable `editor.quit_key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Parse a binding like `ctrl+q`, `Ctrl-C` or `alt+x`.
+    ///
+    /// A Ctrl or Alt modifier is required so the binding cannot swallow ordinary typing.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid key binding '{spec}'");
+        let parts: Vec<&str> = spec.trim().split(['+', '-']).collect();
+        let (key, modifier_names) = parts.split_last().ok_or_else(invalid)?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for name in modifier_names {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" | "c" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "m" => KeyModifiers::ALT,
+                _ => return Err(invalid()),
+            };
+        }
+        if modifiers.is_empty() {
+            return Err(format!("key binding '{spec}' needs a Ctrl or Alt modifier"));
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch.to_ascii_lowercase()),
+            _ => return Err(invalid()),
+        };
+        Ok(Self { code, modifiers })
+    }
+
+    fn matches(&self, code: &KeyCode, modifiers: KeyModifiers) -> bool {
+        let code = match code {
+            KeyCode::Char(ch) => KeyCode::Char(ch.to_ascii_lowercase()),
+            other => *other,
+        };
+        self.code == code && self.modifiers == modifiers
+    }
+}
+
+impl Default for KeyBinding {
+    fn default() -> Self {
+        Self::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(ch) => write!(f, "{}", ch.to_ascii_uppercase()),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NavigationCommand {
     LineStart,
@@ -30,6 +132,15 @@ pub enum NavigationCommand {
 #[derive(Debug, Default, Clone)]
 pub struct InputHandler {
     colon_buffer: Option<String>,
+    pending_count: Option<usize>,
+    /// Set after `"` until the register name arrives.
+    awaiting_register: bool,
+    pending_register: Option<char>,
+    /// First key of a two-key operator such as `yy` or `>>`.
+    pending_operator: Option<char>,
+    /// Register and keys of the macro being recorded; `q` stops it rather than starting one.
+    recording: Option<(char, String)>,
+    quit_key: KeyBinding,
 }
 
 impl InputHandler {
@@ -37,7 +148,43 @@ impl InputHandler {
         Self::default()
     }
 
+    /// Emit [`InputAction::Quit`] for `key` instead of the default Ctrl+C.
+    pub fn set_quit_key(&mut self, key: KeyBinding) {
+        self.quit_key = key;
+    }
+
+    /// Register a `q<reg>` recording is capturing keys into, if any.
+    pub fn recording(&self) -> Option<char> {
+        self.recording.as_ref().map(|(register, _)| *register)
+    }
+
+    /// A fresh handler with the same quit key, for feeding a macro's keys through `@<reg>`.
+    pub fn for_replay(&self) -> Self {
+        Self {
+            quit_key: self.quit_key,
+            ..Self::default()
+        }
+    }
+
     pub fn process(
+        &mut self,
+        event: &Event,
+        mode: &EditorMode,
+        in_insert_mode: bool,
+    ) -> Option<InputAction> {
+        let action = self.interpret(event, mode, in_insert_mode);
+        if !matches!(
+            action,
+            Some(InputAction::StartRecording(_) | InputAction::StopRecording { .. })
+        ) && let (Some((_, keys)), Event::Key(key)) = (self.recording.as_mut(), event)
+            && let Some(notation) = key_notation(key)
+        {
+            keys.push_str(&notation);
+        }
+        action
+    }
+
+    fn interpret(
         &mut self,
         event: &Event,
         _mode: &EditorMode,
@@ -50,7 +197,7 @@ impl InputHandler {
                 kind: KeyEventKind::Press,
                 ..
             }) => {
-                if *modifiers == KeyModifiers::CONTROL && matches!(code, KeyCode::Char('c')) {
+                if self.quit_key.matches(code, *modifiers) {
                     return Some(InputAction::Quit);
                 }
 
@@ -85,6 +232,11 @@ impl InputHandler {
                             }
                             return Some(InputAction::UpdateCommandBuffer(buffer.clone()));
                         }
+                        // The command cursor always sits at the end, so there is nothing
+                        // ahead of it to delete; keep the command rather than aborting it.
+                        KeyCode::Delete => {
+                            return Some(InputAction::UpdateCommandBuffer(buffer.clone()));
+                        }
                         KeyCode::Enter => {
                             let command = buffer.clone();
                             self.reset_colon();
@@ -104,13 +256,125 @@ impl InputHandler {
                     }
                 }
 
+                if in_insert_mode && *modifiers == KeyModifiers::CONTROL {
+                    match code {
+                        KeyCode::Char('u') => return Some(InputAction::KillToLineStart),
+                        KeyCode::Char('k') => return Some(InputAction::KillToLineEnd),
+                        KeyCode::Char('y') => return Some(InputAction::PasteRegister),
+                        _ => {}
+                    }
+                }
+
+                if !in_insert_mode {
+                    // Ctrl chords such as Ctrl+Y are not the plain `y`, `p` or `J` keys; they
+                    // only cancel a count or operator being typed.
+                    if matches!(code, KeyCode::Char(_)) && modifiers.contains(KeyModifiers::CONTROL)
+                    {
+                        self.awaiting_register = false;
+                        self.pending_register = None;
+                        self.pending_operator = None;
+                        self.pending_count = None;
+                        return None;
+                    }
+
+                    if self.awaiting_register {
+                        self.awaiting_register = false;
+                        if let KeyCode::Char(name @ 'a'..='z') = code {
+                            self.pending_register = Some(*name);
+                        } else {
+                            self.pending_register = None;
+                            self.pending_count = None;
+                        }
+                        return None;
+                    }
+
+                    if let Some(operator @ ('q' | '@')) = self.pending_operator {
+                        self.pending_operator = None;
+                        self.pending_count = None;
+                        let KeyCode::Char(name @ 'a'..='z') = code else {
+                            return None;
+                        };
+                        if operator == '@' {
+                            return Some(InputAction::ReplayMacro(*name));
+                        }
+                        self.recording = Some((*name, String::new()));
+                        return Some(InputAction::StartRecording(*name));
+                    }
+
+                    if matches!(code, KeyCode::Char('q'))
+                        && let Some((register, keys)) = self.recording.take()
+                    {
+                        return Some(InputAction::StopRecording { register, keys });
+                    }
+
+                    if matches!(code, KeyCode::Char('q' | '@')) && self.pending_operator.is_none() {
+                        if let KeyCode::Char(operator) = code {
+                            self.pending_operator = Some(*operator);
+                        }
+                        return None;
+                    }
+
+                    if let KeyCode::Char(digit @ '0'..='9') = code
+                        && (*digit != '0' || self.pending_count.is_some())
+                    {
+                        let value = digit.to_digit(10).unwrap_or_default() as usize;
+                        let count = self.pending_count.unwrap_or_default();
+                        self.pending_count = Some(count.saturating_mul(10).saturating_add(value));
+                        return None;
+                    }
+
+                    if matches!(code, KeyCode::Char('"')) && self.pending_operator.is_none() {
+                        self.awaiting_register = true;
+                        return None;
+                    }
+
+                    if matches!(code, KeyCode::Char('y')) {
+                        if self.pending_operator.take() == Some('y') {
+                            return Some(InputAction::YankLines {
+                                register: self.pending_register.take(),
+                                count: self.pending_count.take().unwrap_or(1),
+                            });
+                        }
+                        self.pending_operator = Some('y');
+                        return None;
+                    }
+
+                    if let KeyCode::Char(key @ ('>' | '<')) = code {
+                        if self.pending_operator.take() == Some(*key) {
+                            self.pending_register = None;
+                            return Some(InputAction::ShiftLines {
+                                count: self.pending_count.take().unwrap_or(1),
+                                outdent: *key == '<',
+                            });
+                        }
+                        self.pending_operator = Some(*key);
+                        return None;
+                    }
+
+                    self.pending_operator = None;
+                    let count = self.pending_count.take();
+                    let register = self.pending_register.take();
+                    if matches!(code, KeyCode::Char('J')) {
+                        return Some(InputAction::JoinLines(count.unwrap_or(1)));
+                    }
+                    if matches!(code, KeyCode::Char('p')) {
+                        return Some(InputAction::PasteLines { register });
+                    }
+                    if let KeyCode::Char(key @ ('n' | 'N')) = code {
+                        return Some(InputAction::RepeatSearch {
+                            reverse: *key == 'N',
+                        });
+                    }
+                }
+
                 match code {
                     KeyCode::Esc if in_insert_mode => Some(InputAction::ExitInsertMode),
                     KeyCode::Backspace if in_insert_mode => Some(InputAction::DeleteChar),
+                    KeyCode::Delete if in_insert_mode => Some(InputAction::DeleteForward),
                     KeyCode::Enter if in_insert_mode => Some(InputAction::InsertNewLine),
                     KeyCode::Char(ch) if in_insert_mode => Some(InputAction::InsertChar(*ch)),
-                    KeyCode::Enter if in_insert_mode => None,
-                    KeyCode::Up
+                    KeyCode::Enter
+                    | KeyCode::Up
                     | KeyCode::Down
                     | KeyCode::Left
                     | KeyCode::Right
@@ -130,6 +394,101 @@ impl InputHandler {
     }
 }
 
+/// Vim-style text for a key press such as `x`, `<CR>`, `<C-k>` or `<M-Left>`, the form macro
+/// registers hold keys in.
+pub fn key_notation(key: &KeyEvent) -> Option<String> {
+    if key.kind != KeyEventKind::Press {
+        return None;
+    }
+    let mut prefix = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        prefix.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        prefix.push_str("M-");
+    }
+    let name = match key.code {
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(ch) if prefix.is_empty() => return Some(ch.to_string()),
+        KeyCode::Char(ch) => ch.to_string(),
+        code => {
+            if key.modifiers.contains(KeyModifiers::SHIFT) {
+                prefix.push_str("S-");
+            }
+            NAMED_KEYS
+                .iter()
+                .find(|(_, named)| *named == code)
+                .map(|(name, _)| name.to_string())?
+        }
+    };
+    Some(format!("<{prefix}{name}>"))
+}
+
+/// Key presses spelled by `text` in [`key_notation`] form; a newline is read as Enter and a `<`
+/// that starts no known key name stands for itself.
+pub fn parse_keys(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut rest = text;
+    while let Some(ch) = rest.chars().next() {
+        let (code, modifiers, len) = match ch {
+            '<' => rest
+                .find('>')
+                .and_then(|end| Some((parse_key_name(&rest[1..end])?, end + 1)))
+                .map(|((code, modifiers), len)| (code, modifiers, len))
+                .unwrap_or((KeyCode::Char('<'), KeyModifiers::NONE, 1)),
+            '\n' => (KeyCode::Enter, KeyModifiers::NONE, 1),
+            ch => (KeyCode::Char(ch), KeyModifiers::NONE, ch.len_utf8()),
+        };
+        events.push(Event::Key(KeyEvent::new(code, modifiers)));
+        rest = &rest[len..];
+    }
+    events
+}
+
+/// Keys [`key_notation`] writes by name, as `<Name>`.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("CR", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("BS", KeyCode::Backspace),
+    ("Del", KeyCode::Delete),
+    ("Tab", KeyCode::Tab),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+// The inside of a `<...>` key name such as `C-k` or `S-M-Left`.
+fn parse_key_name(name: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    loop {
+        let modifier = match rest.get(..2) {
+            Some("C-") => KeyModifiers::CONTROL,
+            Some("M-") => KeyModifiers::ALT,
+            Some("S-") => KeyModifiers::SHIFT,
+            _ => break,
+        };
+        modifiers |= modifier;
+        rest = &rest[2..];
+    }
+    if rest == "lt" {
+        return Some((KeyCode::Char('<'), modifiers));
+    }
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(named, _)| *named == rest) {
+        return Some((*code, modifiers));
+    }
+    let mut chars = rest.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if !modifiers.is_empty() => Some((KeyCode::Char(ch), modifiers)),
+        _ => None,
+    }
+}
+
 fn navigation_action_for_key(code: KeyCode) -> Option<NavigationCommand> {
     match code {
         KeyCode::Left => Some(NavigationCommand::LineStart),
@@ -181,6 +540,328 @@ mod tests {
         })
     }
 
+    fn key_event(code: KeyCode) -> Event {
+        Event::Key(KeyEvent {
+            code,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    fn ctrl_event(ch: char) -> Event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::CONTROL,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    #[test]
+    fn q_records_and_at_replays_macros() {
+        let mut handler = InputHandler::new();
+        let read = |handler: &mut InputHandler, ch| {
+            handler.process(&key_event(KeyCode::Char(ch)), &EditorMode::Read, false)
+        };
+
+        assert_eq!(read(&mut handler, 'q'), None);
+        assert_eq!(
+            read(&mut handler, 'a'),
+            Some(InputAction::StartRecording('a'))
+        );
+        assert_eq!(handler.recording(), Some('a'));
+        assert_eq!(read(&mut handler, 'J'), Some(InputAction::JoinLines(1)));
+        assert_eq!(
+            read(&mut handler, 'q'),
+            Some(InputAction::StopRecording {
+                register: 'a',
+                keys: "J".into()
+            })
+        );
+        assert_eq!(handler.recording(), None);
+
+        assert_eq!(read(&mut handler, '@'), None);
+        assert_eq!(read(&mut handler, 'a'), Some(InputAction::ReplayMacro('a')));
+
+        assert_eq!(read(&mut handler, '@'), None);
+        assert_eq!(read(&mut handler, '!'), None);
+        assert_eq!(read(&mut handler, 'J'), Some(InputAction::JoinLines(1)));
+    }
+
+    #[test]
+    fn recordings_spell_special_keys_by_name() {
+        let mut handler = InputHandler::new();
+        feed(&mut handler, "qb");
+        handler.process(&ctrl_event('k'), &EditorMode::Read, false);
+        for code in [KeyCode::Enter, KeyCode::Char('<'), KeyCode::Esc] {
+            handler.process(&key_event(code), &EditorMode::Read, false);
+        }
+        assert_eq!(
+            feed(&mut handler, "xq"),
+            Some(InputAction::StopRecording {
+                register: 'b',
+                keys: "<C-k><CR><lt><Esc>x".into()
+            })
+        );
+    }
+
+    #[test]
+    fn parse_keys_reads_back_key_notation() {
+        let events = parse_keys("a<CR><C-k><lt><S-M-Left>\n<nope>");
+        let expected = [
+            KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('k'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Left, KeyModifiers::SHIFT | KeyModifiers::ALT),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        ];
+        assert_eq!(&events[..6], expected.map(Event::Key));
+        assert_eq!(events.len(), 12, "an unknown <name> is typed literally");
+        for event in &events[..5] {
+            let Event::Key(key) = event else {
+                unreachable!()
+            };
+            let notation = key_notation(key).unwrap();
+            assert_eq!(parse_keys(&notation), std::slice::from_ref(event));
+        }
+    }
+
+    #[test]
+    fn ctrl_c_quits_by_default() {
+        let mut handler = InputHandler::new();
+        let action = handler.process(&ctrl_event('c'), &EditorMode::Insert, true);
+        assert_eq!(action, Some(InputAction::Quit));
+    }
+
+    #[test]
+    fn configured_quit_key_replaces_ctrl_c() {
+        let mut handler = InputHandler::new();
+        handler.set_quit_key(KeyBinding::parse("ctrl+q").unwrap());
+
+        for in_insert_mode in [false, true] {
+            let action = handler.process(&ctrl_event('q'), &EditorMode::Read, in_insert_mode);
+            assert_eq!(action, Some(InputAction::Quit));
+            let action = handler.process(&ctrl_event('c'), &EditorMode::Read, in_insert_mode);
+            assert_ne!(action, Some(InputAction::Quit));
+        }
+    }
+
+    #[test]
+    fn key_bindings_parse_and_display() {
+        assert_eq!(KeyBinding::parse("Ctrl-Q").unwrap().to_string(), "Ctrl+Q");
+        assert_eq!(
+            KeyBinding::parse("alt+x").unwrap(),
+            KeyBinding::new(KeyCode::Char('x'), KeyModifiers::ALT)
+        );
+        assert_eq!(KeyBinding::default().to_string(), "Ctrl+C");
+        assert!(KeyBinding::parse("q").is_err());
+        assert!(KeyBinding::parse("ctrl+").is_err());
+        assert!(KeyBinding::parse("hyper+q").is_err());
+    }
+
+    #[test]
+    fn shift_j_joins_lines_with_optional_count() {
+        let mut handler = InputHandler::new();
+        let action = handler.process(&key_event(KeyCode::Char('J')), &EditorMode::Read, false);
+        assert_eq!(action, Some(InputAction::JoinLines(1)));
+
+        for digit in ['1', '2'] {
+            assert_eq!(
+                handler.process(&key_event(KeyCode::Char(digit)), &EditorMode::Read, false),
+                None
+            );
+        }
+        let action = handler.process(&key_event(KeyCode::Char('J')), &EditorMode::Read, false);
+        assert_eq!(action, Some(InputAction::JoinLines(12)));
+    }
+
+    #[test]
+    fn doubled_angle_brackets_shift_lines_with_optional_count() {
+        let mut handler = InputHandler::new();
+        assert_eq!(feed(&mut handler, ">"), None);
+        assert_eq!(
+            feed(&mut handler, ">"),
+            Some(InputAction::ShiftLines {
+                count: 1,
+                outdent: false
+            })
+        );
+
+        assert_eq!(feed(&mut handler, "3"), None);
+        assert_eq!(feed(&mut handler, "<"), None);
+        assert_eq!(
+            feed(&mut handler, "<"),
+            Some(InputAction::ShiftLines {
+                count: 3,
+                outdent: true
+            })
+        );
+
+        assert_eq!(feed(&mut handler, ">"), None);
+        assert_eq!(feed(&mut handler, "<"), None, "a different key starts over");
+    }
+
+    #[test]
+    fn n_and_shift_n_repeat_the_last_search() {
+        let mut handler = InputHandler::new();
+        let action = handler.process(&key_event(KeyCode::Char('n')), &EditorMode::Read, false);
+        assert_eq!(action, Some(InputAction::RepeatSearch { reverse: false }));
+        let action = handler.process(&key_event(KeyCode::Char('N')), &EditorMode::Read, false);
+        assert_eq!(action, Some(InputAction::RepeatSearch { reverse: true }));
+
+        let action = handler.process(&key_event(KeyCode::Char('n')), &EditorMode::Insert, true);
+        assert_eq!(action, Some(InputAction::InsertChar('n')));
+    }
+
+    #[test]
+    fn count_is_discarded_by_other_keys_and_ignored_in_insert_mode() {
+        let mut handler = InputHandler::new();
+        handler.process(&key_event(KeyCode::Char('3')), &EditorMode::Read, false);
+        handler.process(&key_event(KeyCode::Down), &EditorMode::Read, false);
+        let action = handler.process(&key_event(KeyCode::Char('J')), &EditorMode::Read, false);
+        assert_eq!(action, Some(InputAction::JoinLines(1)));
+
+        let action = handler.process(&key_event(KeyCode::Char('J')), &EditorMode::Insert, true);
+        assert_eq!(action, Some(InputAction::InsertChar('J')));
+    }
+
+    #[test]
+    fn ctrl_u_k_y_are_line_kill_shortcuts_in_insert_mode() {
+        let ctrl = |ch| {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(ch),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            })
+        };
+        let mut handler = InputHandler::new();
+        assert_eq!(
+            handler.process(&ctrl('u'), &EditorMode::Insert, true),
+            Some(InputAction::KillToLineStart)
+        );
+        assert_eq!(
+            handler.process(&ctrl('k'), &EditorMode::Insert, true),
+            Some(InputAction::KillToLineEnd)
+        );
+        assert_eq!(
+            handler.process(&ctrl('y'), &EditorMode::Insert, true),
+            Some(InputAction::PasteRegister)
+        );
+        assert_eq!(handler.process(&ctrl('u'), &EditorMode::Read, false), None);
+    }
+
+    #[test]
+    fn ctrl_y_in_read_mode_is_not_a_yank() {
+        let mut handler = InputHandler::new();
+        assert_eq!(
+            handler.process(&ctrl_event('y'), &EditorMode::Read, false),
+            None
+        );
+        assert_eq!(feed(&mut handler, "y"), None);
+        assert_eq!(
+            handler.process(&ctrl_event('y'), &EditorMode::Read, false),
+            None
+        );
+        assert_eq!(feed(&mut handler, "y"), None, "Ctrl+Y cancels a pending y");
+        assert_eq!(
+            feed(&mut handler, "y"),
+            Some(InputAction::YankLines {
+                register: None,
+                count: 1
+            })
+        );
+    }
+
+    #[test]
+    fn delete_key_deletes_forward_only_in_insert_mode() {
+        let mut handler = InputHandler::new();
+        let delete = key_event(KeyCode::Delete);
+        assert_eq!(
+            handler.process(&delete, &EditorMode::Insert, true),
+            Some(InputAction::DeleteForward)
+        );
+        assert_eq!(handler.process(&delete, &EditorMode::Read, false), None);
+    }
+
+    #[test]
+    fn delete_key_keeps_colon_command_being_typed() {
+        let mut handler = InputHandler::new();
+        handler.process(&char_event(':'), &EditorMode::Read, false);
+        handler.process(&char_event('w'), &EditorMode::Read, false);
+
+        assert_eq!(
+            handler.process(&key_event(KeyCode::Delete), &EditorMode::Command, false),
+            Some(InputAction::UpdateCommandBuffer("w".into()))
+        );
+        handler.process(&char_event('q'), &EditorMode::Command, false);
+        assert_eq!(
+            handler.process(&key_event(KeyCode::Enter), &EditorMode::Command, false),
+            Some(InputAction::ExecuteCommand("wq".into()))
+        );
+    }
+
+    #[test]
+    fn enter_moves_the_cursor_outside_insert_mode() {
+        let mut handler = InputHandler::new();
+        assert_eq!(
+            handler.process(&key_event(KeyCode::Enter), &EditorMode::Read, false),
+            Some(InputAction::MoveCursor(KeyCode::Enter))
+        );
+        assert_eq!(
+            handler.process(&key_event(KeyCode::Enter), &EditorMode::Insert, true),
+            Some(InputAction::InsertNewLine)
+        );
+    }
+
+    fn char_event(ch: char) -> Event {
+        Event::Key(KeyEvent {
+            code: KeyCode::Char(ch),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+            state: KeyEventState::NONE,
+        })
+    }
+
+    fn feed(handler: &mut InputHandler, keys: &str) -> Option<InputAction> {
+        let mut last = None;
+        for ch in keys.chars() {
+            last = handler.process(&char_event(ch), &EditorMode::Read, false);
+        }
+        last
+    }
+
+    #[test]
+    fn register_prefix_applies_to_yank_and_paste() {
+        let mut handler = InputHandler::new();
+        assert_eq!(
+            feed(&mut handler, "\"ayy"),
+            Some(InputAction::YankLines {
+                register: Some('a'),
+                count: 1
+            })
+        );
+        assert_eq!(
+            feed(&mut handler, "3yy"),
+            Some(InputAction::YankLines {
+                register: None,
+                count: 3
+            })
+        );
+        assert_eq!(
+            feed(&mut handler, "\"bp"),
+            Some(InputAction::PasteLines {
+                register: Some('b')
+            })
+        );
+        assert_eq!(
+            feed(&mut handler, "p"),
+            Some(InputAction::PasteLines { register: None })
+        );
+    }
+
     #[test]
     fn shift_alt_right_enters_navigation_line_end() {
         let mut handler = InputHandler::new();
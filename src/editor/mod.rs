@@ -1,4 +1,12 @@
+pub mod brackets;
 pub mod buffer_editor;
+pub mod clipboard;
+pub mod config;
 pub mod input;
+pub mod register;
+pub mod settings;
+pub mod snapshots;
+pub mod spell;
+pub mod syntax;
 pub mod terminal;
 pub mod view;
@@ -0,0 +1,138 @@
+//! Text registers used for kill/yank style editing.
+
+use std::collections::HashMap;
+
+/// Register written by every yank or kill and read when no `"x` prefix is given.
+pub const UNNAMED_REGISTER: char = '"';
+
+/// Whether a register holds whole lines (`yy`) or a run of characters (Ctrl+U/Ctrl+K kills).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterKind {
+    Charwise,
+    Linewise,
+}
+
+#[derive(Debug, Clone)]
+struct Register {
+    lines: Vec<String>,
+    kind: RegisterKind,
+}
+
+/// Holds yanked or killed text, line by line, keyed by register name.
+#[derive(Debug, Clone, Default)]
+pub struct Registers {
+    slots: HashMap<char, Register>,
+}
+
+impl Registers {
+    /// Store whole `lines` in register `name` (when given) and always in the unnamed register.
+    ///
+    /// Empty content (no lines, or a single empty line) leaves every register untouched.
+    pub fn store(&mut self, name: Option<char>, lines: Vec<String>) {
+        self.put(name, lines, RegisterKind::Linewise);
+    }
+
+    /// Lines held by register `name`, or by the unnamed register for `None`.
+    pub fn get(&self, name: Option<char>) -> Option<&[String]> {
+        self.slot(name).map(|register| register.lines.as_slice())
+    }
+
+    /// Whether register `name` (or the unnamed register for `None`) holds lines or characters.
+    pub fn kind(&self, name: Option<char>) -> Option<RegisterKind> {
+        self.slot(name).map(|register| register.kind)
+    }
+
+    /// Store killed `text` in the unnamed (paste) register; empty kills leave it untouched.
+    pub fn set_unnamed(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        let lines = text.split('\n').map(str::to_string).collect();
+        self.put(None, lines, RegisterKind::Charwise);
+    }
+
+    /// Keep the keys of a `q<reg>` recording in register `name` alone, leaving the unnamed
+    /// register as it is; an empty recording clears the register.
+    pub fn record(&mut self, name: char, keys: String) {
+        if keys.is_empty() {
+            self.slots.remove(&name);
+            return;
+        }
+        let register = Register {
+            lines: vec![keys],
+            kind: RegisterKind::Charwise,
+        };
+        self.slots.insert(name, register);
+    }
+
+    fn put(&mut self, name: Option<char>, lines: Vec<String>, kind: RegisterKind) {
+        if lines.is_empty() || (lines.len() == 1 && lines[0].is_empty()) {
+            return;
+        }
+        let register = Register { lines, kind };
+        if let Some(name) = name.filter(|name| *name != UNNAMED_REGISTER) {
+            self.slots.insert(name, register.clone());
+        }
+        self.slots.insert(UNNAMED_REGISTER, register);
+    }
+
+    fn slot(&self, name: Option<char>) -> Option<&Register> {
+        self.slots.get(&name.unwrap_or(UNNAMED_REGISTER))
+    }
+
+    /// Unnamed register contents with lines joined by `\n`.
+    pub fn unnamed(&self) -> Option<String> {
+        self.get(None).map(|lines| lines.join("\n"))
+    }
+
+    /// One-line `:registers` listing, unnamed first and then alphabetical.
+    pub fn describe(&self) -> String {
+        let mut names: Vec<char> = self.slots.keys().copied().collect();
+        names.sort_by_key(|name| (*name != UNNAMED_REGISTER, *name));
+        names
+            .into_iter()
+            .map(|name| format!("\"{name} {}", self.slots[&name].lines.join("\\n")))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_text_does_not_clobber_register() {
+        let mut registers = Registers::default();
+        assert_eq!(registers.unnamed(), None);
+
+        registers.set_unnamed("killed");
+        registers.set_unnamed("");
+        assert_eq!(registers.unnamed().as_deref(), Some("killed"));
+    }
+
+    #[test]
+    fn kills_are_charwise_and_yanks_linewise() {
+        let mut registers = Registers::default();
+        registers.set_unnamed("killed");
+        assert_eq!(registers.kind(None), Some(RegisterKind::Charwise));
+
+        registers.store(Some('a'), vec!["line".into()]);
+        assert_eq!(registers.kind(None), Some(RegisterKind::Linewise));
+        assert_eq!(registers.kind(Some('a')), Some(RegisterKind::Linewise));
+        assert_eq!(registers.kind(Some('b')), None);
+    }
+
+    #[test]
+    fn named_store_also_fills_unnamed() {
+        let mut registers = Registers::default();
+        registers.store(Some('a'), vec!["one".into(), "two".into()]);
+        registers.store(Some('b'), vec!["three".into()]);
+
+        assert_eq!(registers.get(Some('a')).unwrap(), ["one", "two"]);
+        assert_eq!(registers.get(None).unwrap(), ["three"]);
+        assert!(registers.get(Some('z')).is_none());
+        assert_eq!(
+            registers.describe(),
+            "\"\" three | \"a one\\ntwo | \"b three"
+        );
+    }
+}
@@ -0,0 +1,531 @@
+//! Editor options adjustable through `:set`.
+
+use crate::editor::brackets::MatchPairs;
+use crate::editor::clipboard::ClipboardMode;
+use crate::editor::syntax::FileType;
+
+/// Interval `:set autosave` uses when no value is given.
+pub const DEFAULT_AUTOSAVE_MS: u64 = 5000;
+
+/// Lines a bulk edit must exceed before its summary is shown when `report` is not set.
+pub const DEFAULT_REPORT: usize = 2;
+
+/// Lines at the start and at the end of a buffer searched for an `iridium:` modeline.
+pub const MODELINE_LINES: usize = 5;
+
+/// Marker that introduces a modeline, e.g. `# iridium: tw=72 ic`.
+const MODELINE_MARKER: &str = "iridium:";
+
+/// Options a modeline may not set: they write files, reach the OS clipboard, or change how
+/// modelines are read.
+const MODELINE_FORBIDDEN: &[&str] = &[
+    "autosave",
+    "noautosave",
+    "modeline",
+    "ml",
+    "nomodeline",
+    "noml",
+    "autowriteall",
+    "awa",
+    "noautowriteall",
+    "noawa",
+    "clipboard",
+    "cb",
+];
+
+/// Options applied to the active buffer session.
+#[derive(Debug, Clone, Default)]
+pub struct EditorSettings {
+    pub filetype: Option<FileType>,
+    /// Preferred maximum line length; `0` disables the column ruler.
+    pub textwidth: usize,
+    /// One-based screen columns to highlight, sorted and without duplicates.
+    pub colorcolumn: Vec<usize>,
+    /// Match search and `:g`/`:v` patterns regardless of case.
+    pub ignorecase: bool,
+    /// Ignore case only for all-lowercase patterns; takes precedence over `ignorecase`.
+    pub smartcase: bool,
+    /// Lines moved by half-page navigation; `0` scrolls half the view.
+    pub scroll: usize,
+    /// Lines a bulk edit must exceed before its summary is shown; `None` uses
+    /// [`DEFAULT_REPORT`].
+    pub report: Option<usize>,
+    /// Milliseconds between automatic writes of a dirty buffer; `0` disables autosave.
+    pub autosave: u64,
+    /// Write the current named buffer before switching to, cycling to or closing into another.
+    pub autowriteall: bool,
+    /// Keep every match of the last search highlighted.
+    pub hlsearch: bool,
+    /// Jump to and highlight the first match while a `:/pattern` search is being typed.
+    pub incsearch: bool,
+    /// Apply `iridium:` modelines found near the start or end of opened buffers.
+    pub modeline: bool,
+    /// Highlight the bracket matching the one at the cursor, or flag it when unmatched.
+    pub showmatch: bool,
+    /// Leave the `[MODE]` label out of the status line (`:set noshowmode`).
+    pub hide_mode: bool,
+    /// Stop searches at the end or start of the buffer instead of wrapping (`:set nowrapscan`).
+    pub nowrapscan: bool,
+    /// Bracket pairs `showmatch` pairs up.
+    pub matchpairs: MatchPairs,
+    /// Let the cursor move past the end of a line without padding it; spaces are only added
+    /// once text is typed there.
+    pub virtualedit: bool,
+    /// Underline words missing from the word list.
+    pub spell: bool,
+    /// Mirror yanks to the OS clipboard and paste from it (`clipboard=system`).
+    pub clipboard: ClipboardMode,
+}
+
+impl EditorSettings {
+    /// Apply a single `:set` argument such as `filetype=rust`.
+    pub fn apply(&mut self, argument: &str) -> Result<(), String> {
+        let (name, value) = match argument.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (argument.trim(), None),
+        };
+
+        match name {
+            "filetype" | "ft" => {
+                let value = value.unwrap_or_default();
+                self.filetype = match value {
+                    "" | "none" => None,
+                    other => Some(
+                        FileType::from_name(other)
+                            .ok_or_else(|| format!("Unknown filetype: {other}"))?,
+                    ),
+                };
+                Ok(())
+            }
+            "ignorecase" | "ic" => Self::set_flag(&mut self.ignorecase, true, value),
+            "noignorecase" | "noic" => Self::set_flag(&mut self.ignorecase, false, value),
+            "smartcase" | "scs" => Self::set_flag(&mut self.smartcase, true, value),
+            "nosmartcase" | "noscs" => Self::set_flag(&mut self.smartcase, false, value),
+            "hlsearch" | "hls" => Self::set_flag(&mut self.hlsearch, true, value),
+            "nohlsearch" | "nohls" => Self::set_flag(&mut self.hlsearch, false, value),
+            "incsearch" | "is" => Self::set_flag(&mut self.incsearch, true, value),
+            "noincsearch" | "nois" => Self::set_flag(&mut self.incsearch, false, value),
+            "modeline" | "ml" => Self::set_flag(&mut self.modeline, true, value),
+            "nomodeline" | "noml" => Self::set_flag(&mut self.modeline, false, value),
+            "showmatch" | "sm" => Self::set_flag(&mut self.showmatch, true, value),
+            "noshowmatch" | "nosm" => Self::set_flag(&mut self.showmatch, false, value),
+            "showmode" | "smd" => Self::set_flag(&mut self.hide_mode, false, value),
+            "noshowmode" | "nosmd" => Self::set_flag(&mut self.hide_mode, true, value),
+            "wrapscan" | "ws" => Self::set_flag(&mut self.nowrapscan, false, value),
+            "nowrapscan" | "nows" => Self::set_flag(&mut self.nowrapscan, true, value),
+            "matchpairs" | "mps" => {
+                self.matchpairs = MatchPairs::parse(value.unwrap_or_default())?;
+                Ok(())
+            }
+            "virtualedit" | "ve" => Self::set_flag(&mut self.virtualedit, true, value),
+            "novirtualedit" | "nove" => Self::set_flag(&mut self.virtualedit, false, value),
+            "clipboard" | "cb" => {
+                self.clipboard = ClipboardMode::parse(value.unwrap_or_default())?;
+                Ok(())
+            }
+            "spell" => Self::set_flag(&mut self.spell, true, value),
+            "nospell" => Self::set_flag(&mut self.spell, false, value),
+            "textwidth" | "tw" => {
+                let value = value.unwrap_or_default();
+                self.textwidth = value
+                    .parse()
+                    .map_err(|_| format!("Invalid textwidth: {value}"))?;
+                Ok(())
+            }
+            "colorcolumn" | "cc" => {
+                self.colorcolumn = Self::parse_columns(value.unwrap_or_default())?;
+                Ok(())
+            }
+            "autosave" => {
+                self.autosave = match value {
+                    None => DEFAULT_AUTOSAVE_MS,
+                    Some(value) => value
+                        .parse()
+                        .map_err(|_| format!("Invalid autosave: {value}"))?,
+                };
+                Ok(())
+            }
+            "autowriteall" | "awa" => Self::set_flag(&mut self.autowriteall, true, value),
+            "noautowriteall" | "noawa" => Self::set_flag(&mut self.autowriteall, false, value),
+            "noautosave" => match value {
+                Some(value) => Err(format!("Option takes no value: {value}")),
+                None => {
+                    self.autosave = 0;
+                    Ok(())
+                }
+            },
+            "report" => {
+                let value = value.unwrap_or_default();
+                self.report = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid report: {value}"))?,
+                );
+                Ok(())
+            }
+            "scroll" | "scr" => {
+                let value = value.unwrap_or_default();
+                self.scroll = value
+                    .parse()
+                    .map_err(|_| format!("Invalid scroll: {value}"))?;
+                Ok(())
+            }
+            other => Err(format!("Unknown option: {other}")),
+        }
+    }
+
+    /// Apply the options of an `iridium:` modeline in `line`, returning whether one was applied.
+    ///
+    /// The marker must start the line or follow whitespace. Options are separated by spaces or
+    /// `:` and run to the end of the line, or with `iridium: set ... :` up to the closing colon
+    /// so a comment terminator may follow. A modeline with an unknown option, an invalid value
+    /// or an option in [`MODELINE_FORBIDDEN`] is ignored as a whole.
+    pub fn apply_modeline(&mut self, line: &str) -> bool {
+        let Some(start) = line.match_indices(MODELINE_MARKER).find_map(|(index, _)| {
+            let preceded_by_space = line[..index]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace);
+            preceded_by_space.then_some(index + MODELINE_MARKER.len())
+        }) else {
+            return false;
+        };
+
+        let options = line[start..].trim_start();
+        let options = match options.strip_prefix("set ") {
+            Some(rest) => match rest.split_once(':') {
+                Some((options, _)) => options,
+                None => return false,
+            },
+            None => options,
+        };
+
+        let mut updated = self.clone();
+        let mut applied = false;
+        for option in options
+            .split(|ch: char| ch.is_whitespace() || ch == ':')
+            .filter(|option| !option.is_empty())
+        {
+            let name = option.split_once('=').map_or(option, |(name, _)| name);
+            if MODELINE_FORBIDDEN.contains(&name) || updated.apply(option).is_err() {
+                return false;
+            }
+            applied = true;
+        }
+        if applied {
+            *self = updated;
+        }
+        applied
+    }
+
+    fn set_flag(flag: &mut bool, enabled: bool, value: Option<&str>) -> Result<(), String> {
+        if let Some(value) = value {
+            return Err(format!("Option takes no value: {value}"));
+        }
+        *flag = enabled;
+        Ok(())
+    }
+
+    /// Parse a comma-separated column list such as `80,100,120`; an empty list clears it.
+    fn parse_columns(value: &str) -> Result<Vec<usize>, String> {
+        let mut columns = value
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(|column| match column.parse::<usize>() {
+                Ok(number) if number > 0 => Ok(number),
+                _ => Err(format!("Invalid colorcolumn: {column}")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        columns.sort_unstable();
+        columns.dedup();
+        Ok(columns)
+    }
+
+    /// Whether an edit touching `lines` lines is large enough to summarise.
+    pub fn reports(&self, lines: usize) -> bool {
+        lines > self.report.unwrap_or(DEFAULT_REPORT)
+    }
+
+    /// Lines a half-page motion moves in a view `view_height` rows tall.
+    pub fn scroll_stride(&self, view_height: usize) -> usize {
+        match self.scroll {
+            0 => (view_height / 2).max(1),
+            lines => lines,
+        }
+    }
+
+    /// Whether `pattern` should be matched case-insensitively under the current settings.
+    pub fn ignores_case(&self, pattern: &str) -> bool {
+        if self.smartcase {
+            !pattern.chars().any(char::is_uppercase)
+        } else {
+            self.ignorecase
+        }
+    }
+
+    /// Character column of the first occurrence of `pattern` in `line`, if any.
+    pub fn find_match(&self, line: &str, pattern: &str) -> Option<usize> {
+        if !self.ignores_case(pattern) {
+            return line.find(pattern).map(|byte| line[..byte].chars().count());
+        }
+
+        let needle: Vec<char> = pattern.chars().collect();
+        let haystack: Vec<char> = line.chars().collect();
+        if needle.is_empty() {
+            return Some(0);
+        }
+        haystack
+            .windows(needle.len())
+            .position(|window| chars_match(window, &needle, true))
+    }
+
+    /// Character ranges of every non-overlapping occurrence of `pattern` in `line`.
+    pub fn match_ranges(&self, line: &str, pattern: &str) -> Vec<(usize, usize)> {
+        let needle: Vec<char> = pattern.chars().collect();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+        let haystack: Vec<char> = line.chars().collect();
+        let ignore_case = self.ignores_case(pattern);
+
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        while start + needle.len() <= haystack.len() {
+            let end = start + needle.len();
+            if chars_match(&haystack[start..end], &needle, ignore_case) {
+                ranges.push((start, end));
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        ranges
+    }
+
+    /// Whether `line` contains `pattern` under the current case settings.
+    pub fn matches(&self, line: &str, pattern: &str) -> bool {
+        self.find_match(line, pattern).is_some()
+    }
+}
+
+fn chars_match(window: &[char], needle: &[char], ignore_case: bool) -> bool {
+    if !ignore_case {
+        return window == needle;
+    }
+    window
+        .iter()
+        .zip(needle)
+        .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_and_clears_filetype() {
+        let mut settings = EditorSettings::default();
+        settings.apply("filetype=rust").unwrap();
+        assert_eq!(settings.filetype, Some(FileType::Rust));
+
+        settings.apply("ft=").unwrap();
+        assert_eq!(settings.filetype, None);
+    }
+
+    #[test]
+    fn sets_textwidth() {
+        let mut settings = EditorSettings::default();
+        assert_eq!(settings.textwidth, 0);
+        settings.apply("textwidth=80").unwrap();
+        assert_eq!(settings.textwidth, 80);
+        settings.apply("tw=0").unwrap();
+        assert_eq!(settings.textwidth, 0);
+        assert_eq!(
+            settings.apply("tw=wide"),
+            Err("Invalid textwidth: wide".to_string())
+        );
+    }
+
+    #[test]
+    fn wrapscan_is_on_by_default() {
+        let mut settings = EditorSettings::default();
+        assert!(!settings.nowrapscan);
+        settings.apply("nows").unwrap();
+        assert!(settings.nowrapscan);
+        settings.apply("wrapscan").unwrap();
+        assert!(!settings.nowrapscan);
+        assert_eq!(
+            settings.apply("ws=1"),
+            Err("Option takes no value: 1".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_matchpairs_keep_the_current_pairs() {
+        let mut settings = EditorSettings::default();
+        settings.apply("matchpairs=(:),<:>").unwrap();
+        assert_eq!(settings.matchpairs.pairs(), &[('(', ')'), ('<', '>')]);
+        assert_eq!(
+            settings.apply("mps=<>"),
+            Err("Invalid matchpairs: <>".to_string())
+        );
+        assert_eq!(settings.matchpairs.pairs(), &[('(', ')'), ('<', '>')]);
+    }
+
+    #[test]
+    fn colorcolumn_is_sorted_and_deduplicated() {
+        let mut settings = EditorSettings::default();
+        assert!(settings.colorcolumn.is_empty());
+
+        settings.apply("colorcolumn=120,80,100,80").unwrap();
+        assert_eq!(settings.colorcolumn, vec![80, 100, 120]);
+        settings.apply("cc=").unwrap();
+        assert!(settings.colorcolumn.is_empty());
+        assert_eq!(
+            settings.apply("cc=80,0"),
+            Err("Invalid colorcolumn: 0".to_string())
+        );
+        assert_eq!(
+            settings.apply("cc=80,wide"),
+            Err("Invalid colorcolumn: wide".to_string())
+        );
+    }
+
+    #[test]
+    fn scroll_defaults_to_half_the_view() {
+        let mut settings = EditorSettings::default();
+        assert_eq!(settings.scroll_stride(10), 5);
+        assert_eq!(settings.scroll_stride(1), 1);
+
+        settings.apply("scroll=3").unwrap();
+        assert_eq!(settings.scroll_stride(10), 3);
+        settings.apply("scr=0").unwrap();
+        assert_eq!(settings.scroll_stride(10), 5);
+        assert_eq!(
+            settings.apply("scroll=-1"),
+            Err("Invalid scroll: -1".to_string())
+        );
+    }
+
+    #[test]
+    fn autosave_takes_an_optional_interval() {
+        let mut settings = EditorSettings::default();
+        assert_eq!(settings.autosave, 0);
+
+        settings.apply("autosave").unwrap();
+        assert_eq!(settings.autosave, DEFAULT_AUTOSAVE_MS);
+        settings.apply("autosave=250").unwrap();
+        assert_eq!(settings.autosave, 250);
+        settings.apply("noautosave").unwrap();
+        assert_eq!(settings.autosave, 0);
+        assert_eq!(
+            settings.apply("autosave=soon"),
+            Err("Invalid autosave: soon".to_string())
+        );
+    }
+
+    #[test]
+    fn ignorecase_matches_any_case() {
+        let mut settings = EditorSettings::default();
+        assert!(!settings.matches("Hello World", "hello"));
+        assert!(settings.matches("Hello World", "World"));
+
+        settings.apply("ignorecase").unwrap();
+        assert!(settings.matches("Hello World", "hello"));
+        assert!(settings.matches("hello world", "WoRlD"));
+        assert_eq!(settings.find_match("say HELLO", "hello"), Some(4));
+
+        settings.apply("noic").unwrap();
+        assert!(!settings.ignorecase);
+    }
+
+    #[test]
+    fn smartcase_only_ignores_case_for_lowercase_patterns() {
+        let mut settings = EditorSettings::default();
+        settings.apply("smartcase").unwrap();
+        assert!(settings.matches("Hello World", "hello"));
+        assert!(settings.matches("Hello World", "World"));
+        assert!(!settings.matches("hello world", "World"));
+
+        settings.apply("ic").unwrap();
+        assert!(!settings.matches("hello world", "World"));
+        settings.apply("noscs").unwrap();
+        assert!(settings.matches("hello world", "World"));
+        assert_eq!(
+            settings.apply("ic=1"),
+            Err("Option takes no value: 1".to_string())
+        );
+    }
+
+    #[test]
+    fn match_ranges_finds_every_occurrence() {
+        let mut settings = EditorSettings::default();
+        settings.apply("hlsearch").unwrap();
+        settings.apply("is").unwrap();
+        assert!(settings.hlsearch && settings.incsearch);
+
+        assert_eq!(
+            settings.match_ranges("aaa Aa aa", "aa"),
+            vec![(0, 2), (7, 9)]
+        );
+        settings.apply("ignorecase").unwrap();
+        assert_eq!(
+            settings.match_ranges("aaa Aa aa", "aa"),
+            vec![(0, 2), (4, 6), (7, 9)]
+        );
+        assert!(settings.match_ranges("abc", "").is_empty());
+
+        settings.apply("nohls").unwrap();
+        settings.apply("noincsearch").unwrap();
+        assert!(!settings.hlsearch && !settings.incsearch);
+    }
+
+    #[test]
+    fn modeline_applies_recognised_options() {
+        let mut settings = EditorSettings::default();
+        assert!(settings.apply_modeline("# iridium: tw=72 ic:cc=80,100"));
+        assert_eq!(settings.textwidth, 72);
+        assert!(settings.ignorecase);
+        assert_eq!(settings.colorcolumn, vec![80, 100]);
+
+        assert!(settings.apply_modeline("/* iridium: set ft=rust noic : */"));
+        assert_eq!(settings.filetype, Some(FileType::Rust));
+        assert!(!settings.ignorecase);
+    }
+
+    #[test]
+    fn malformed_modelines_are_ignored() {
+        let mut settings = EditorSettings::default();
+        for line in [
+            "plain text",
+            "# iridium:",
+            "# xiridium: tw=72",
+            "# iridium: tw=72 bogus",
+            "# iridium: tw=wide",
+            "# iridium: tw=72 autosave=10",
+            "# iridium: ml",
+            "# iridium: clipboard=system",
+            "# iridium: tw=72 cb=system",
+            "/* iridium: set tw=72 */",
+        ] {
+            assert!(!settings.apply_modeline(line), "{line} should be ignored");
+        }
+        assert_eq!(settings.textwidth, 0);
+        assert_eq!(settings.autosave, 0);
+        assert_eq!(settings.clipboard, ClipboardMode::default());
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_values() {
+        let mut settings = EditorSettings::default();
+        assert_eq!(
+            settings.apply("filetype=cobol"),
+            Err("Unknown filetype: cobol".to_string())
+        );
+        assert_eq!(
+            settings.apply("bogus"),
+            Err("Unknown option: bogus".to_string())
+        );
+    }
+}
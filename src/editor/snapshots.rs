@@ -0,0 +1,139 @@
+//! Coarse whole-buffer snapshots captured on `:w`, restorable with `:restore N`.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::buffer_editor::plural;
+
+/// Number of snapshots kept per buffer before the oldest is dropped.
+pub const DEFAULT_SNAPSHOT_CAPACITY: usize = 10;
+
+/// Buffer contents as they were at one save.
+#[derive(Debug, Clone)]
+pub struct SaveSnapshot {
+    pub lines: Vec<String>,
+    pub taken_at: Instant,
+}
+
+/// Bounded ring of snapshots for a single buffer, oldest first.
+#[derive(Debug, Clone)]
+pub struct SnapshotRing {
+    capacity: usize,
+    entries: VecDeque<SaveSnapshot>,
+}
+
+impl SnapshotRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: VecDeque::new(),
+        }
+    }
+
+    /// Record `lines`, skipping saves that did not change anything since the last snapshot.
+    pub fn push(&mut self, lines: Vec<String>, now: Instant) {
+        if self
+            .entries
+            .back()
+            .is_some_and(|latest| latest.lines == lines)
+        {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(SaveSnapshot {
+            lines,
+            taken_at: now,
+        });
+    }
+
+    /// Snapshot `number`, counting from 1 for the oldest kept.
+    pub fn get(&self, number: usize) -> Option<&SaveSnapshot> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.entries.get(index))
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// One-line listing such as `1: 3 lines (12s ago) | 2: 4 lines (2s ago)`.
+    pub fn describe(&self, now: Instant) -> String {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, snapshot)| {
+                let age = now.saturating_duration_since(snapshot.taken_at);
+                format!(
+                    "{}: {} ({} ago)",
+                    index + 1,
+                    plural(snapshot.lines.len(), "line"),
+                    format_age(age)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+impl Default for SnapshotRing {
+    fn default() -> Self {
+        Self::new(DEFAULT_SNAPSHOT_CAPACITY)
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn ring_drops_oldest_and_skips_duplicates() {
+        let now = Instant::now();
+        let mut ring = SnapshotRing::new(2);
+        ring.push(lines(&["a"]), now);
+        ring.push(lines(&["a"]), now);
+        assert_eq!(ring.len(), 1);
+
+        ring.push(lines(&["a", "b"]), now);
+        ring.push(lines(&["a", "b", "c"]), now);
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.get(1).unwrap().lines, lines(&["a", "b"]));
+        assert!(ring.get(0).is_none());
+        assert!(ring.get(3).is_none());
+    }
+
+    #[test]
+    fn describe_lists_line_counts_and_age() {
+        let start = Instant::now();
+        let mut ring = SnapshotRing::default();
+        ring.push(lines(&["a"]), start);
+        ring.push(lines(&["a", "b"]), start + Duration::from_secs(100));
+
+        assert_eq!(
+            ring.describe(start + Duration::from_secs(125)),
+            "1: 1 line (2m ago) | 2: 2 lines (25s ago)"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Word lists and misspelling detection for `:set spell`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::sync::OnceLock;
+
+/// System word list read the first time spell checking is needed.
+pub const SYSTEM_WORD_LIST: &str = "/usr/share/dict/words";
+
+/// Common English words used when [`SYSTEM_WORD_LIST`] is missing or empty.
+const BUILTIN_WORDS: &[&str] = &[
+    "a", "about", "after", "again", "all", "also", "an", "and", "any", "are", "as", "at", "back",
+    "be", "because", "been", "before", "being", "both", "but", "by", "can", "could", "day", "did",
+    "do", "does", "done", "down", "each", "even", "first", "for", "from", "get", "give", "go",
+    "good", "had", "has", "have", "he", "her", "here", "him", "his", "how", "i", "if", "in",
+    "into", "is", "it", "its", "just", "know", "last", "like", "line", "little", "long", "look",
+    "make", "man", "many", "may", "me", "more", "most", "much", "must", "my", "new", "no", "not",
+    "now", "of", "off", "old", "on", "one", "only", "or", "other", "our", "out", "over", "own",
+    "people", "said", "same", "say", "see", "she", "should", "so", "some", "still", "such", "take",
+    "text", "than", "that", "the", "their", "them", "then", "there", "these", "they", "thing",
+    "think", "this", "those", "through", "time", "to", "too", "two", "under", "up", "us", "use",
+    "very", "was", "way", "we", "well", "were", "what", "when", "where", "which", "while", "who",
+    "why", "will", "with", "word", "work", "world", "would", "write", "year", "yes", "you", "your",
+];
+
+/// A case-insensitive set of known words.
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+}
+
+impl Dictionary {
+    /// Build a dictionary from `words`, ignoring case.
+    pub fn from_words<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words
+                .into_iter()
+                .map(|word| word.as_ref().trim().to_lowercase())
+                .filter(|word| !word.is_empty())
+                .collect(),
+        }
+    }
+
+    /// The dictionary shared by every view, loaded from [`SYSTEM_WORD_LIST`] or, failing that,
+    /// the built-in list.
+    pub fn shared() -> &'static Dictionary {
+        static DICTIONARY: OnceLock<Dictionary> = OnceLock::new();
+        DICTIONARY.get_or_init(|| {
+            fs::read_to_string(SYSTEM_WORD_LIST)
+                .map(|contents| Self::from_words(contents.lines()))
+                .ok()
+                .filter(|dictionary| !dictionary.is_empty())
+                .unwrap_or_else(|| Self::from_words(BUILTIN_WORDS))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(&word.to_lowercase())
+    }
+
+    /// Character ranges of the words in `line` that are not in the dictionary.
+    ///
+    /// Words are runs of letters with inner apostrophes (`don't`). Tokens that also contain
+    /// digits or underscores, such as identifiers, are not checked. An empty dictionary flags
+    /// nothing.
+    pub fn misspelled(&self, line: &str) -> Vec<(usize, usize)> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        tokens(line)
+            .into_iter()
+            .filter(|(_, _, word)| !self.contains(word))
+            .map(|(start, end, _)| (start, end))
+            .collect()
+    }
+}
+
+/// Checkable words of `line` as `(start, end, word)` with character offsets.
+fn tokens(line: &str) -> Vec<(usize, usize, String)> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        if !is_token_char(chars[index]) {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < chars.len() && is_token_char(chars[index]) {
+            index += 1;
+        }
+        let token = &chars[start..index];
+        if !token.iter().all(|ch| ch.is_alphabetic() || *ch == '\'') {
+            continue;
+        }
+        let leading = token.iter().take_while(|ch| **ch == '\'').count();
+        let trailing = token.iter().rev().take_while(|ch| **ch == '\'').count();
+        if leading + trailing < token.len() {
+            let (start, end) = (start + leading, index - trailing);
+            tokens.push((start, end, chars[start..end].iter().collect()));
+        }
+    }
+    tokens
+}
+
+fn is_token_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '\''
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> Dictionary {
+        Dictionary::from_words(["the", "quick", "brown", "fox", "don't", "Paris"])
+    }
+
+    #[test]
+    fn flags_words_missing_from_the_dictionary() {
+        let dictionary = dictionary();
+        assert_eq!(
+            dictionary.misspelled("The quikc brown fx"),
+            vec![(4, 9), (16, 18)]
+        );
+        assert!(dictionary.misspelled("paris, the FOX!").is_empty());
+    }
+
+    #[test]
+    fn apostrophes_and_identifiers() {
+        let dictionary = dictionary();
+        assert!(dictionary.misspelled("'don't' the").is_empty());
+        assert_eq!(dictionary.misspelled("won't"), vec![(0, 5)]);
+        assert!(dictionary.misspelled("fox_id 42 abc123").is_empty());
+    }
+
+    #[test]
+    fn an_empty_dictionary_flags_nothing() {
+        assert!(Dictionary::default().misspelled("anything goes").is_empty());
+    }
+}
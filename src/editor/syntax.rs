@@ -0,0 +1,255 @@
+//! Minimal line-based syntax highlighting for the buffer editor.
+
+use std::path::Path;
+
+const KEYWORD_COLOR: &str = "\u{1b}[34m";
+const STRING_COLOR: &str = "\u{1b}[32m";
+const COMMENT_COLOR: &str = "\u{1b}[90m";
+const END_COLOR: &str = "\u{1b}[39m";
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+const TOML_KEYWORDS: &[&str] = &["true", "false"];
+
+/// Languages the editor knows how to colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    Rust,
+    Toml,
+}
+
+impl FileType {
+    /// Resolve a `:set filetype=<name>` value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(FileType::Rust),
+            "toml" => Some(FileType::Toml),
+            _ => None,
+        }
+    }
+
+    /// Detect the file type from a buffer name's extension.
+    pub fn from_path(name: &str) -> Option<Self> {
+        let extension = Path::new(name).extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "rs" => Some(FileType::Rust),
+            "toml" => Some(FileType::Toml),
+            _ => None,
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            FileType::Rust => RUST_KEYWORDS,
+            FileType::Toml => TOML_KEYWORDS,
+        }
+    }
+
+    fn comment_prefix(self) -> &'static str {
+        match self {
+            FileType::Rust => "//",
+            FileType::Toml => "#",
+        }
+    }
+
+    fn is_quote(self, ch: char) -> bool {
+        match self {
+            FileType::Rust => ch == '"',
+            FileType::Toml => ch == '"' || ch == '\'',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Keyword,
+    String,
+    Comment,
+}
+
+impl TokenKind {
+    fn color(self) -> &'static str {
+        match self {
+            TokenKind::Keyword => KEYWORD_COLOR,
+            TokenKind::String => STRING_COLOR,
+            TokenKind::Comment => COMMENT_COLOR,
+        }
+    }
+}
+
+/// A coloured region of a line, measured in characters (`start..end`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: TokenKind,
+}
+
+/// Split a single line into coloured spans for the given file type.
+pub fn tokenize(line: &str, filetype: FileType) -> Vec<Span> {
+    let chars: Vec<char> = line.chars().collect();
+    let comment: Vec<char> = filetype.comment_prefix().chars().collect();
+    let mut spans = Vec::new();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let ch = chars[idx];
+
+        if chars[idx..].starts_with(&comment) {
+            spans.push(Span {
+                start: idx,
+                end: chars.len(),
+                kind: TokenKind::Comment,
+            });
+            break;
+        }
+
+        if filetype.is_quote(ch) {
+            let start = idx;
+            idx += 1;
+            while idx < chars.len() && chars[idx] != ch {
+                if chars[idx] == '\\' {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            idx = (idx + 1).min(chars.len());
+            spans.push(Span {
+                start,
+                end: idx,
+                kind: TokenKind::String,
+            });
+            continue;
+        }
+
+        if is_word_char(ch) {
+            let start = idx;
+            while idx < chars.len() && is_word_char(chars[idx]) {
+                idx += 1;
+            }
+            let word: String = chars[start..idx].iter().collect();
+            if filetype.keywords().contains(&word.as_str()) {
+                spans.push(Span {
+                    start,
+                    end: idx,
+                    kind: TokenKind::Keyword,
+                });
+            }
+            continue;
+        }
+
+        idx += 1;
+    }
+
+    spans
+}
+
+/// Render the visible portion of `line`, wrapping highlighted spans in SGR colour codes.
+///
+/// Only the first `width` characters are emitted; escape sequences do not count toward the width.
+pub fn render_line(line: &str, width: usize, filetype: Option<FileType>) -> String {
+    let visible: Vec<char> = line.chars().take(width).collect();
+    let Some(filetype) = filetype else {
+        return visible.into_iter().collect();
+    };
+
+    let mut output = String::with_capacity(line.len());
+    let mut cursor = 0;
+    for span in tokenize(line, filetype) {
+        if span.start >= visible.len() {
+            break;
+        }
+        let end = span.end.min(visible.len());
+        output.extend(&visible[cursor..span.start]);
+        output.push_str(span.kind.color());
+        output.extend(&visible[span.start..end]);
+        output.push_str(END_COLOR);
+        cursor = end;
+    }
+    output.extend(&visible[cursor..]);
+    output
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_filetype_from_extension() {
+        assert_eq!(FileType::from_path("src/main.rs"), Some(FileType::Rust));
+        assert_eq!(FileType::from_path("Cargo.TOML"), Some(FileType::Toml));
+        assert_eq!(FileType::from_path("notes.txt"), None);
+        assert_eq!(FileType::from_path("Makefile"), None);
+    }
+
+    #[test]
+    fn rust_keyword_is_wrapped_in_keyword_color() {
+        let rendered = render_line("fn main() {}", 80, Some(FileType::Rust));
+        assert_eq!(
+            rendered,
+            format!("{KEYWORD_COLOR}fn{END_COLOR} main() {{}}")
+        );
+    }
+
+    #[test]
+    fn keywords_inside_identifiers_are_not_highlighted() {
+        assert!(
+            tokenize("let format = 1;", FileType::Rust)
+                .iter()
+                .all(|span| span.start == 0)
+        );
+    }
+
+    #[test]
+    fn strings_and_comments_are_tokenized() {
+        let spans = tokenize("let s = \"a \\\" b\"; // done", FileType::Rust);
+        assert_eq!(
+            spans,
+            vec![
+                Span {
+                    start: 0,
+                    end: 3,
+                    kind: TokenKind::Keyword
+                },
+                Span {
+                    start: 8,
+                    end: 16,
+                    kind: TokenKind::String
+                },
+                Span {
+                    start: 18,
+                    end: 25,
+                    kind: TokenKind::Comment
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn toml_comments_and_booleans() {
+        let spans = tokenize("enabled = true # note", FileType::Toml);
+        assert_eq!(spans[0].kind, TokenKind::Keyword);
+        assert_eq!(spans[1].kind, TokenKind::Comment);
+    }
+
+    #[test]
+    fn highlighting_preserves_visible_width() {
+        let rendered = render_line("pub fn long_name()", 6, Some(FileType::Rust));
+        let stripped = rendered.replace(KEYWORD_COLOR, "").replace(END_COLOR, "");
+        assert_eq!(stripped, "pub fn");
+    }
+
+    #[test]
+    fn no_filetype_renders_plain_text() {
+        assert_eq!(render_line("fn main()", 4, None), "fn m");
+    }
+}
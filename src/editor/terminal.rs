@@ -3,7 +3,7 @@ use crossterm::cursor::{Hide, MoveTo, Show};
 use crossterm::style::Print;
 use crossterm::terminal::{Clear, ClearType, disable_raw_mode, enable_raw_mode, size};
 use crossterm::{Command, queue};
-use std::io::{Error, Write, stdout};
+use std::io::{Error, ErrorKind, IsTerminal, Write, stdin, stdout};
 use std::sync::{Arc, Mutex, OnceLock};
 
 #[derive(Copy, Clone)]
@@ -12,21 +12,55 @@ pub struct Size {
     pub height: usize,
 }
 
-#[derive(Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct Position {
     pub col: usize,
     pub row: usize,
 }
 
+const NON_TTY_MESSAGE: &str =
+    "the buffer editor requires an interactive terminal on stdin and stdout";
+
+/// How the process' standard streams can be driven.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TerminalMode {
+    /// Both stdin and stdout are TTYs, so raw mode and full-screen drawing are available.
+    Raw,
+    /// Input is piped or output is redirected (CI, scripts); only line-based I/O is possible.
+    #[default]
+    Line,
+}
+
+impl TerminalMode {
+    /// Inspect the real standard streams. `IRIDIUM_SKIP_EDITOR` forces line mode.
+    pub fn detect() -> Self {
+        if std::env::var("IRIDIUM_SKIP_EDITOR").is_ok() {
+            return TerminalMode::Line;
+        }
+        Self::from_streams(stdin().is_terminal(), stdout().is_terminal())
+    }
+
+    pub fn from_streams(stdin_is_tty: bool, stdout_is_tty: bool) -> Self {
+        if stdin_is_tty && stdout_is_tty {
+            TerminalMode::Raw
+        } else {
+            TerminalMode::Line
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Terminal {
     store: OnceLock<Arc<Mutex<BufferStore>>>,
+    mode: TerminalMode,
 }
 
 impl Terminal {
-    fn new() -> Self {
+    /// Build a terminal handle for an explicit mode without touching the real TTY.
+    pub fn with_mode(mode: TerminalMode) -> Self {
         Self {
             store: OnceLock::new(),
+            mode,
         }
     }
 
@@ -49,12 +83,20 @@ impl Terminal {
             .expect("Buffer store has not been attached to the terminal")
     }
 
+    pub fn is_interactive(&self) -> bool {
+        self.mode == TerminalMode::Raw
+    }
+
+    /// Switch into raw mode for a full-screen editor session.
+    ///
+    /// Fails with [`ErrorKind::Unsupported`] when stdin/stdout is not a TTY.
     pub fn enter(&self) -> Result<(), Error> {
-        if std::env::var("IRIDIUM_SKIP_EDITOR").is_err() {
-            enable_raw_mode()?;
-            Self::clear_screen()?;
-            Self::execute()?;
+        if !self.is_interactive() {
+            return Err(Error::new(ErrorKind::Unsupported, NON_TTY_MESSAGE));
         }
+        enable_raw_mode()?;
+        Self::clear_screen()?;
+        Self::execute()?;
         Ok(())
     }
 
@@ -70,9 +112,6 @@ impl Terminal {
             store.insert_char(buffer_name, position.row, position.col, ch);
         }
 
-        Self::move_caret_to(position)?;
-        Self::print(&ch.to_string())?;
-
         let Size { width, height: _ } = Self::size()?;
         let mut next = Position {
             col: position.col.saturating_add(1),
@@ -84,9 +123,6 @@ impl Terminal {
             next.row = position.row.saturating_add(1);
         }
 
-        Self::move_caret_to(next)?;
-        Self::execute()?;
-
         Ok(next)
     }
 
@@ -97,14 +133,7 @@ impl Terminal {
             store.insert_newline(buffer_name, position.row, position.col)
         };
 
-        Self::move_caret_to(position)?;
-        Self::print("\r\n")?;
-
-        let next = Position { col, row };
-        Self::move_caret_to(next)?;
-        Self::execute()?;
-
-        Ok(next)
+        Ok(Position { col, row })
     }
 
     pub fn delete_char(
@@ -129,8 +158,8 @@ impl Terminal {
         }
     }
 
-    pub fn terminate() -> Result<(), Error> {
-        if std::env::var("IRIDIUM_SKIP_EDITOR").is_err() {
+    pub fn terminate(&self) -> Result<(), Error> {
+        if self.is_interactive() {
             Self::execute()?;
             disable_raw_mode()?;
         }
@@ -138,11 +167,10 @@ impl Terminal {
     }
 
     fn initialize() -> Result<Terminal, Error> {
-        let term = Terminal::new();
-        if std::env::var("IRIDIUM_SKIP_EDITOR").is_err() {
-            enable_raw_mode()?;
-            Self::clear_screen()?;
-            Self::execute()?;
+        let mut term = Terminal::with_mode(TerminalMode::detect());
+        if term.is_interactive() && term.enter().is_err() {
+            // A TTY that refuses raw mode is treated like a pipe rather than aborting the shell.
+            term.mode = TerminalMode::Line;
         }
         Ok(term)
     }
@@ -152,11 +180,6 @@ impl Terminal {
         Ok(())
     }
 
-    pub fn clear_line() -> Result<(), Error> {
-        queue_command(Clear(ClearType::CurrentLine))?;
-        Ok(())
-    }
-
     pub fn move_caret_to(position: Position) -> Result<(), Error> {
         queue_command(MoveTo(position.col as u16, position.row as u16))?;
         Ok(())
@@ -190,7 +213,156 @@ impl Terminal {
     }
 }
 
+/// A fully composed screen, one rendered string per terminal row.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    width: usize,
+    rows: Vec<String>,
+}
+
+impl Frame {
+    pub fn new(width: usize) -> Self {
+        Self {
+            width,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push_row(&mut self, row: String) {
+        self.rows.push(row);
+    }
+
+    #[allow(dead_code)]
+    pub fn rows(&self) -> &[String] {
+        &self.rows
+    }
+}
+
+/// Back buffer holding the last frame drawn, so a refresh only re-sends rows that changed.
+///
+/// Rows are the unit of comparison because rendered lines carry SGR sequences; a changed row is
+/// rewritten in place and cleared to the end of the line rather than clearing the whole screen.
+#[derive(Debug, Clone, Default)]
+pub struct FrameBuffer {
+    width: usize,
+    /// What each screen row currently shows; `None` when it is unknown and must be redrawn.
+    rows: Vec<Option<String>>,
+}
+
+impl FrameBuffer {
+    /// Forget the screen contents, forcing the next frame to be drawn in full.
+    pub fn invalidate(&mut self) {
+        self.rows.clear();
+    }
+
+    /// Mark a single row as overwritten outside the frame (e.g. by the cursor glyph).
+    pub fn invalidate_row(&mut self, row: usize) {
+        if let Some(slot) = self.rows.get_mut(row) {
+            *slot = None;
+        }
+    }
+
+    /// Draw `frame` to stdout, queueing commands only for rows that differ from the last frame.
+    pub fn present(&mut self, frame: Frame) -> Result<(), Error> {
+        self.write_diff(frame, &mut stdout())
+    }
+
+    /// Queue the commands that turn the previous frame into `frame` onto `out`.
+    pub fn write_diff(&mut self, frame: Frame, out: &mut impl Write) -> Result<(), Error> {
+        if frame.width != self.width {
+            self.invalidate();
+            self.width = frame.width;
+        }
+
+        for (idx, row) in frame.rows.iter().enumerate() {
+            if self
+                .rows
+                .get(idx)
+                .is_some_and(|shown| shown.as_ref() == Some(row))
+            {
+                continue;
+            }
+            queue!(
+                out,
+                MoveTo(0, idx as u16),
+                Print(row),
+                Clear(ClearType::UntilNewLine)
+            )?;
+        }
+        for idx in frame.rows.len()..self.rows.len() {
+            queue!(out, MoveTo(0, idx as u16), Clear(ClearType::CurrentLine))?;
+        }
+
+        self.rows = frame.rows.into_iter().map(Some).collect();
+        Ok(())
+    }
+}
+
 fn queue_command<T: Command>(command: T) -> Result<(), Error> {
     queue!(stdout(), command)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(rows: &[&str]) -> Frame {
+        let mut frame = Frame::new(20);
+        rows.iter().for_each(|row| frame.push_row(row.to_string()));
+        frame
+    }
+
+    #[test]
+    fn identical_frames_produce_no_output() {
+        let mut buffer = FrameBuffer::default();
+        let mut first = Vec::new();
+        buffer
+            .write_diff(frame(&["one", "two"]), &mut first)
+            .unwrap();
+        assert!(!first.is_empty());
+
+        let mut second = Vec::new();
+        buffer
+            .write_diff(frame(&["one", "two"]), &mut second)
+            .unwrap();
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn only_changed_rows_are_redrawn() {
+        let mut buffer = FrameBuffer::default();
+        buffer
+            .write_diff(frame(&["one", "two"]), &mut Vec::new())
+            .unwrap();
+
+        let mut out = Vec::new();
+        buffer.write_diff(frame(&["one", "TWO"]), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("TWO"));
+        assert!(!out.contains("one"));
+    }
+
+    #[test]
+    fn invalidated_rows_and_resizes_force_a_redraw() {
+        let mut buffer = FrameBuffer::default();
+        buffer
+            .write_diff(frame(&["one", "two"]), &mut Vec::new())
+            .unwrap();
+
+        buffer.invalidate_row(0);
+        let mut out = Vec::new();
+        buffer.write_diff(frame(&["one", "two"]), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("one"));
+        assert!(!out.contains("two"));
+
+        let mut wider = Frame::new(40);
+        wider.push_row("one".to_string());
+        wider.push_row("two".to_string());
+        let mut out = Vec::new();
+        buffer.write_diff(wider, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("one") && out.contains("two"));
+    }
+}
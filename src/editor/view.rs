@@ -1,12 +1,85 @@
-use std::io::Error;
-
+use crate::editor::brackets::{self, BracketMatch};
 use crate::editor::buffer_editor::EditorMode;
+use crate::editor::settings::EditorSettings;
+use crate::editor::spell::Dictionary;
+use crate::editor::syntax::{self, FileType};
+
+use super::terminal::{Frame, Size, Terminal};
+use std::ops::Range;
 
-use super::terminal::{Size, Terminal};
+const RULER_GLYPH: &str = "\u{1b}[90m\u{2502}\u{1b}[39m";
+const COLORCOLUMN_GLYPH: &str = "\u{1b}[100m \u{1b}[49m";
+const OVERFLOW_BACKGROUND: &str = "\u{1b}[41m";
+const END_BACKGROUND: &str = "\u{1b}[49m";
+const SEARCH_BACKGROUND: &str = "\u{1b}[43m";
+const BRACKET_BACKGROUND: &str = "\u{1b}[46m";
+const SPELL_START: &str = "\u{1b}[4m";
+const SPELL_END: &str = "\u{1b}[24m";
+const ACTIVE_TAB_START: &str = "\u{1b}[7m";
+const ACTIVE_TAB_END: &str = "\u{1b}[27m";
 
 #[derive(Debug, Clone)]
 pub struct BufferView {
     lines: Vec<String>,
+    filetype: Option<FileType>,
+    textwidth: usize,
+    colorcolumn: Vec<usize>,
+    /// Character ranges to highlight as search matches, indexed by line.
+    search_matches: Vec<Vec<(usize, usize)>>,
+    /// `showmatch` cells as `(row, col, background)`.
+    bracket_marks: Vec<(usize, usize, &'static str)>,
+    /// Word list for `spell`; misspellings are found per visible line while composing.
+    dictionary: Option<&'static Dictionary>,
+    tabs: Vec<String>,
+    recording: Option<char>,
+    /// Whether the status line names the mode, from `showmode`.
+    showmode: bool,
+    /// Buffer `:compare` stacks below this one.
+    compare: Option<ComparePane>,
+}
+
+/// A buffer shown read-only beneath the active one, drawn from its own scroll offset.
+#[derive(Debug, Clone)]
+struct ComparePane {
+    name: String,
+    lines: Vec<String>,
+    filetype: Option<FileType>,
+    scroll_offset: usize,
+}
+
+/// Screen rows of the two panes `:compare` stacks in the text area.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitLayout {
+    /// Rows showing the active buffer.
+    pub top: Range<usize>,
+    /// Row naming the buffer below.
+    pub divider: usize,
+    /// Rows showing the compared buffer.
+    pub bottom: Range<usize>,
+}
+
+impl SplitLayout {
+    /// Split the `rows` text rows starting at screen row `first` around a divider, giving the top
+    /// pane the odd row out. Returns `None` when each pane cannot get at least one row.
+    pub fn new(first: usize, rows: usize) -> Option<Self> {
+        if rows < 3 {
+            return None;
+        }
+        let divider = first + (rows - 1).div_ceil(2);
+        Some(Self {
+            top: first..divider,
+            divider,
+            bottom: divider + 1..first + rows,
+        })
+    }
+
+    /// Rows left to the active buffer out of `rows`, whether or not a second pane fits.
+    pub fn active_rows(rows: usize, comparing: bool) -> usize {
+        match comparing {
+            true => Self::new(0, rows).map_or(rows, |layout| layout.top.len()),
+            false => rows,
+        }
+    }
 }
 
 impl BufferView {
@@ -20,13 +93,109 @@ impl BufferView {
                 .unwrap_or_default()
         };
 
-        Self { lines }
+        Self {
+            lines,
+            filetype: None,
+            textwidth: 0,
+            colorcolumn: Vec::new(),
+            search_matches: Vec::new(),
+            bracket_marks: Vec::new(),
+            dictionary: None,
+            tabs: Vec::new(),
+            recording: None,
+            showmode: true,
+            compare: None,
+        }
+    }
+
+    /// Highlight rendered lines using the provided file type.
+    pub fn with_filetype(mut self, filetype: Option<FileType>) -> Self {
+        self.filetype = filetype;
+        self
+    }
+
+    /// Apply every `:set` option that affects rendering.
+    pub fn with_settings(self, settings: &EditorSettings) -> Self {
+        let mut view = self.with_filetype(settings.filetype);
+        view.textwidth = settings.textwidth;
+        view.colorcolumn = settings.colorcolumn.clone();
+        view.dictionary = settings.spell.then(Dictionary::shared);
+        view.showmode = !settings.hide_mode;
+        view
+    }
+
+    /// Highlight every occurrence of `pattern`, matched under the case rules of `settings`.
+    pub fn with_search(mut self, pattern: Option<&str>, settings: &EditorSettings) -> Self {
+        self.search_matches = match pattern {
+            Some(pattern) => self
+                .lines
+                .iter()
+                .map(|line| settings.match_ranges(line, pattern))
+                .collect(),
+            None => Vec::new(),
+        };
+        self
     }
 
+    /// Mark the bracket at `cursor` and its partner, or the bracket alone in red when it has
+    /// none, if `settings.showmatch` is on.
+    pub fn with_showmatch(mut self, cursor: (usize, usize), settings: &EditorSettings) -> Self {
+        self.bracket_marks = match settings
+            .showmatch
+            .then(|| {
+                brackets::match_at_cursor(
+                    &self.lines,
+                    cursor.0,
+                    cursor.1,
+                    settings.matchpairs.pairs(),
+                )
+            })
+            .flatten()
+        {
+            Some(BracketMatch::Matched(at, partner)) => vec![
+                (at.0, at.1, BRACKET_BACKGROUND),
+                (partner.0, partner.1, BRACKET_BACKGROUND),
+            ],
+            Some(BracketMatch::Unmatched(at)) => vec![(at.0, at.1, OVERFLOW_BACKGROUND)],
+            None => Vec::new(),
+        };
+        self
+    }
+
+    /// Show a tab bar listing `tabs`; it is only drawn when more than one buffer is open.
+    pub fn with_tabs(mut self, tabs: &[String]) -> Self {
+        self.tabs = tabs.to_vec();
+        self
+    }
+
+    /// Show `recording @<reg>` beside the mode while a macro is being recorded.
+    pub fn with_recording(mut self, register: Option<char>) -> Self {
+        self.recording = register;
+        self
+    }
+
+    /// Stack the buffer `other` below this one, starting at its line `scroll_offset`.
+    pub fn with_compare(mut self, other: Option<(&str, usize)>) -> Self {
+        self.compare = other.map(|(name, scroll_offset)| ComparePane {
+            lines: BufferView::new(name).lines,
+            name: name.to_string(),
+            filetype: FileType::from_path(name),
+            scroll_offset,
+        });
+        self
+    }
+
+    /// Rows reserved above the text for the tab bar.
+    pub fn tab_bar_rows(&self) -> usize {
+        usize::from(self.tabs.len() > 1)
+    }
+
+    #[allow(dead_code)]
     pub fn line_count(&self) -> usize {
         self.lines.len()
     }
 
+    #[allow(dead_code)]
     pub fn char_count(&self, row: usize) -> usize {
         self.lines
             .get(row)
@@ -37,10 +206,6 @@ impl BufferView {
     pub fn line(&self, row: usize) -> Option<&str> {
         self.lines.get(row).map(|line| line.as_str())
     }
-
-    pub fn char_at(&self, row: usize, col: usize) -> Option<char> {
-        self.line(row).and_then(|line| line.chars().nth(col))
-    }
 }
 
 pub struct View;
@@ -50,7 +215,9 @@ impl View {
         BufferView::new(buffer_name)
     }
 
-    pub fn render(
+    /// Compose the whole screen for `size` in memory; drawing it is left to a back buffer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn compose(
         view: &BufferView,
         buffer_name: &str,
         mode: &EditorMode,
@@ -58,52 +225,296 @@ impl View {
         status_message: Option<&str>,
         scroll_offset: usize,
         cursor_position: (usize, usize),
-    ) -> Result<(), Error> {
-        let Size { width, height } = Terminal::size()?;
+        size: Size,
+    ) -> Frame {
+        let Size { width, height } = size;
         let command_row = height.saturating_sub(1);
+        let tab_rows = view.tab_bar_rows().min(command_row);
+        let mut frame = Frame::new(width);
 
-        let mut edge_rendered = false;
+        if tab_rows > 0 {
+            frame.push_row(build_tab_bar(&view.tabs, buffer_name, width));
+        }
 
-        for row in 0..command_row {
-            Terminal::clear_line()?;
+        let text_rows = command_row - tab_rows;
+        let layout = view
+            .compare
+            .as_ref()
+            .and_then(|_| SplitLayout::new(tab_rows, text_rows));
+        let active_rows = layout.as_ref().map_or(text_rows, |layout| layout.top.len());
+        let mut edge_rendered = false;
 
+        for row in 0..active_rows {
             if let Some(line) = view.line(scroll_offset + row) {
-                let display: String = if width > 0 {
-                    line.chars().take(width).collect()
-                } else {
-                    String::new()
-                };
-                Terminal::print(&display)?;
+                let mut rendered = render_text_line(
+                    line,
+                    width,
+                    view.filetype,
+                    view.textwidth,
+                    &view.colorcolumn,
+                );
+                if let Some(ranges) = view.search_matches.get(scroll_offset + row)
+                    && !ranges.is_empty()
+                {
+                    rendered = highlight_matches(&rendered, ranges, SEARCH_BACKGROUND);
+                }
+                if let Some(dictionary) = view.dictionary {
+                    let misspelled = dictionary.misspelled(line);
+                    if !misspelled.is_empty() {
+                        rendered = underline_ranges(&rendered, &misspelled);
+                    }
+                }
+                for (_, col, background) in view
+                    .bracket_marks
+                    .iter()
+                    .filter(|(mark_row, _, _)| *mark_row == scroll_offset + row)
+                {
+                    rendered = highlight_matches(&rendered, &[(*col, col + 1)], background);
+                }
+                frame.push_row(rendered);
             } else if !edge_rendered {
                 edge_rendered = true;
-                let edge_line = "\u{2015}".repeat(width.max(1));
-                Terminal::print(&edge_line)?;
+                frame.push_row("\u{2015}".repeat(width.max(1)));
+            } else {
+                frame.push_row(String::new());
             }
-
-            Terminal::print("\r\n")?;
         }
-        Terminal::clear_line()?;
-        let command_line = build_command_line(
+        if let (Some(layout), Some(pane)) = (layout, &view.compare) {
+            frame.push_row(build_divider(&pane.name, width));
+            push_compare_rows(&mut frame, pane, layout.bottom.len(), width);
+        }
+        frame.push_row(build_command_line(
             width,
             command_input,
             buffer_name,
-            mode,
+            view.showmode.then_some(mode),
             cursor_position,
             status_message,
-        );
-        Terminal::print(&command_line)?;
+            view.recording,
+        ));
 
-        Ok(())
+        frame
     }
 }
 
+/// Render one buffer line, adding the `textwidth` ruler, `colorcolumn` markers and overflow
+/// highlighting when enabled.
+///
+/// The ruler occupies the first column past the limit (column `textwidth + 1`) and is only drawn
+/// when that cell would otherwise be blank; characters beyond the limit get a red background.
+/// Each `colorcolumn` entry is a one-based screen column marked on blank cells the same way.
+fn render_text_line(
+    line: &str,
+    width: usize,
+    filetype: Option<FileType>,
+    textwidth: usize,
+    colorcolumn: &[usize],
+) -> String {
+    let textwidth = if textwidth >= width { 0 } else { textwidth };
+    let line_len = line.chars().count();
+    let limit = if textwidth == 0 { width } else { textwidth };
+
+    let mut display = syntax::render_line(line, limit, filetype);
+    let mut column = line_len.min(limit);
+    let mut markers = Vec::new();
+    if textwidth > 0 && line_len > textwidth {
+        let overflow: String = line.chars().take(width).skip(textwidth).collect();
+        display.push_str(OVERFLOW_BACKGROUND);
+        display.push_str(&overflow);
+        display.push_str(END_BACKGROUND);
+        column = line_len.min(width);
+    } else if textwidth > 0 {
+        markers.push((textwidth, RULER_GLYPH));
+    }
+    markers.extend(
+        colorcolumn
+            .iter()
+            .map(|number| number - 1)
+            .filter(|index| *index >= column && *index < width)
+            .map(|index| (index, COLORCOLUMN_GLYPH)),
+    );
+    // A stable sort keeps the ruler ahead of a colorcolumn on the same cell.
+    markers.sort_by_key(|(index, _)| *index);
+    markers.dedup_by_key(|(index, _)| *index);
+
+    for (index, glyph) in markers {
+        display.push_str(&" ".repeat(index - column));
+        display.push_str(glyph);
+        column = index + 1;
+    }
+    display
+}
+
+/// Give the visible characters in `ranges` the `highlight` background within an already rendered
+/// line.
+///
+/// Escape sequences take no columns. A background the line sets itself, such as the overflow
+/// colour, is held back inside a match and restored when the match ends.
+fn highlight_matches(rendered: &str, ranges: &[(usize, usize)], highlight: &str) -> String {
+    let mut output = String::with_capacity(rendered.len() + ranges.len() * 10);
+    let mut background = END_BACKGROUND.to_string();
+    let mut ranges = ranges.iter().peekable();
+    let mut in_match = false;
+    let mut column = 0;
+    let mut chars = rendered.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            let mut sequence = String::from(ch);
+            for next in chars.by_ref() {
+                sequence.push(next);
+                if next.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            if is_background_sequence(&sequence) {
+                background = sequence;
+                if !in_match {
+                    output.push_str(&background);
+                }
+            } else {
+                output.push_str(&sequence);
+            }
+            continue;
+        }
+
+        while ranges.peek().is_some_and(|(_, end)| *end <= column) {
+            ranges.next();
+        }
+        let inside = ranges
+            .peek()
+            .is_some_and(|(start, end)| (*start..*end).contains(&column));
+        if inside && !in_match {
+            output.push_str(highlight);
+        } else if !inside && in_match {
+            output.push_str(&background);
+        }
+        in_match = inside;
+        output.push(ch);
+        column += 1;
+    }
+    if in_match {
+        output.push_str(&background);
+    }
+    output
+}
+
+/// Underline the visible characters in `ranges` within an already rendered line.
+///
+/// Escape sequences take no columns, so syntax and background colours are left as they are.
+fn underline_ranges(rendered: &str, ranges: &[(usize, usize)]) -> String {
+    let mut output = String::with_capacity(rendered.len() + ranges.len() * 10);
+    let mut ranges = ranges.iter().peekable();
+    let mut in_word = false;
+    let mut column = 0;
+    let mut chars = rendered.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            output.push(ch);
+            for next in chars.by_ref() {
+                output.push(next);
+                if next.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        while ranges.peek().is_some_and(|(_, end)| *end <= column) {
+            ranges.next();
+        }
+        let inside = ranges
+            .peek()
+            .is_some_and(|(start, end)| (*start..*end).contains(&column));
+        if inside != in_word {
+            output.push_str(if inside { SPELL_START } else { SPELL_END });
+            in_word = inside;
+        }
+        output.push(ch);
+        column += 1;
+    }
+    if in_word {
+        output.push_str(SPELL_END);
+    }
+    output
+}
+
+// `ESC[4Nm` and `ESC[10Nm` select a background colour; `ESC[49m` resets it.
+fn is_background_sequence(sequence: &str) -> bool {
+    let Some(code) = sequence
+        .strip_prefix("\u{1b}[")
+        .and_then(|rest| rest.strip_suffix('m'))
+    else {
+        return false;
+    };
+    let colour = match code.len() {
+        2 => code.strip_prefix('4'),
+        3 => code.strip_prefix("10"),
+        _ => None,
+    };
+    colour.is_some_and(|digit| digit.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+/// Render the open buffer names, highlighting `active` in reverse video.
+///
+/// Tabs that do not fit in `width` columns are dropped from the end.
+fn build_tab_bar(tabs: &[String], active: &str, width: usize) -> String {
+    let mut bar = String::new();
+    let mut used = 0;
+    for name in tabs {
+        let label = format!(" {name} ");
+        let label_width = label.chars().count();
+        if used + label_width > width {
+            break;
+        }
+        used += label_width;
+        if name == active {
+            bar.push_str(ACTIVE_TAB_START);
+            bar.push_str(&label);
+            bar.push_str(ACTIVE_TAB_END);
+        } else {
+            bar.push_str(&label);
+        }
+    }
+    bar
+}
+
+/// Reverse-video bar naming the read-only buffer in the bottom pane.
+fn build_divider(name: &str, width: usize) -> String {
+    let label: String = format!(" {name} [compare] ").chars().take(width).collect();
+    let padding = width.saturating_sub(label.chars().count());
+    format!(
+        "{ACTIVE_TAB_START}{label}{}{ACTIVE_TAB_END}",
+        " ".repeat(padding)
+    )
+}
+
+/// Draw `rows` lines of `pane` from its own scroll offset, closing it with the end-of-buffer edge.
+fn push_compare_rows(frame: &mut Frame, pane: &ComparePane, rows: usize, width: usize) {
+    let mut edge_rendered = false;
+    for row in 0..rows {
+        if let Some(line) = pane.lines.get(pane.scroll_offset + row) {
+            frame.push_row(render_text_line(line, width, pane.filetype, 0, &[]));
+        } else if !edge_rendered {
+            edge_rendered = true;
+            frame.push_row("\u{2015}".repeat(width.max(1)));
+        } else {
+            frame.push_row(String::new());
+        }
+    }
+}
+
+/// Status row: command input or message on the left, buffer name and cursor centred, and the
+/// mode label on the right. Without `mode` (`noshowmode`) only a recording register is shown.
 fn build_command_line(
     width: usize,
     command_input: &str,
     buffer_name: &str,
-    mode: &EditorMode,
+    mode: Option<&EditorMode>,
     cursor_position: (usize, usize),
     status_message: Option<&str>,
+    recording: Option<char>,
 ) -> String {
     if width == 0 {
         return String::new();
@@ -111,7 +522,12 @@ fn build_command_line(
 
     let mut line: Vec<char> = vec![' '; width];
 
-    let mode_label = format!("[{}]", mode_name(mode));
+    let mode_label = match (recording, mode) {
+        (Some(register), Some(mode)) => format!("recording @{register} [{}]", mode_name(mode)),
+        (Some(register), None) => format!("recording @{register}"),
+        (None, Some(mode)) => format!("[{}]", mode_name(mode)),
+        (None, None) => String::new(),
+    };
     let mode_chars: Vec<char> = mode_label.chars().collect();
     let (row, col) = cursor_position;
     let cursor_label = format!("{},{}", row, col);
@@ -192,9 +608,286 @@ fn mode_name(mode: &EditorMode) -> &'static str {
 mod tests {
     use super::*;
 
+    #[test]
+    fn textwidth_ruler_is_drawn_after_the_limit() {
+        let rendered = render_text_line("short", 40, None, 10, &[]);
+        assert_eq!(rendered, format!("short{}{RULER_GLYPH}", " ".repeat(5)));
+
+        let ruler_column = rendered.find(RULER_GLYPH).unwrap();
+        assert_eq!(ruler_column, 10);
+    }
+
+    #[test]
+    fn textwidth_highlights_overflow_instead_of_ruler() {
+        let rendered = render_text_line("0123456789abc", 12, None, 10, &[]);
+        assert_eq!(
+            rendered,
+            format!("0123456789{OVERFLOW_BACKGROUND}ab{END_BACKGROUND}")
+        );
+    }
+
+    #[test]
+    fn textwidth_zero_or_wider_than_screen_renders_plain_line() {
+        assert_eq!(render_text_line("plain", 40, None, 0, &[]), "plain");
+        assert_eq!(render_text_line("plain", 40, None, 40, &[]), "plain");
+    }
+
+    #[test]
+    fn colorcolumns_mark_each_blank_column() {
+        let rendered = render_text_line("abc", 20, None, 0, &[6, 10]);
+        assert_eq!(
+            rendered,
+            format!(
+                "abc{}{COLORCOLUMN_GLYPH}{}{COLORCOLUMN_GLYPH}",
+                " ".repeat(2),
+                " ".repeat(3)
+            )
+        );
+    }
+
+    #[test]
+    fn colorcolumns_skip_text_and_columns_past_the_screen() {
+        let rendered = render_text_line("abcdefgh", 10, None, 0, &[4, 9, 12]);
+        assert_eq!(rendered, format!("abcdefgh{COLORCOLUMN_GLYPH}"));
+    }
+
+    #[test]
+    fn colorcolumns_combine_with_textwidth_ruler() {
+        let rendered = render_text_line("ab", 20, None, 4, &[3, 5, 8]);
+        assert_eq!(
+            rendered,
+            format!("ab{COLORCOLUMN_GLYPH} {RULER_GLYPH}  {COLORCOLUMN_GLYPH}")
+        );
+    }
+
+    #[test]
+    fn search_matches_get_a_background() {
+        assert_eq!(
+            highlight_matches("a cat, a cat", &[(2, 5), (9, 12)], SEARCH_BACKGROUND),
+            format!(
+                "a {SEARCH_BACKGROUND}cat{END_BACKGROUND}, a {SEARCH_BACKGROUND}cat{END_BACKGROUND}"
+            )
+        );
+
+        let keyword = syntax::render_line("fn main", 20, Some(FileType::Rust));
+        assert_eq!(
+            highlight_matches(&keyword, &[(0, 2)], SEARCH_BACKGROUND),
+            format!("\u{1b}[34m{SEARCH_BACKGROUND}fn\u{1b}[39m{END_BACKGROUND} main")
+        );
+    }
+
+    #[test]
+    fn search_match_restores_the_overflow_background() {
+        let rendered = render_text_line("0123456789abc", 12, None, 10, &[]);
+        assert_eq!(
+            highlight_matches(&rendered, &[(9, 11)], SEARCH_BACKGROUND),
+            format!("012345678{SEARCH_BACKGROUND}9a{OVERFLOW_BACKGROUND}b{END_BACKGROUND}")
+        );
+    }
+
+    #[test]
+    fn showmatch_marks_brackets_only_when_enabled() {
+        let view = BufferView {
+            lines: vec!["f(a[0])".to_string(), "(".to_string()],
+            filetype: None,
+            textwidth: 0,
+            colorcolumn: Vec::new(),
+            search_matches: Vec::new(),
+            bracket_marks: Vec::new(),
+            dictionary: None,
+            tabs: Vec::new(),
+            recording: None,
+            showmode: true,
+            compare: None,
+        };
+        let mut settings = EditorSettings::default();
+        assert!(
+            view.clone()
+                .with_showmatch((0, 1), &settings)
+                .bracket_marks
+                .is_empty()
+        );
+
+        settings.apply("showmatch").unwrap();
+        let matched = view.clone().with_showmatch((0, 1), &settings);
+        assert_eq!(
+            matched.bracket_marks,
+            vec![(0, 1, BRACKET_BACKGROUND), (0, 6, BRACKET_BACKGROUND)]
+        );
+        let unmatched = view.clone().with_showmatch((1, 1), &settings);
+        assert_eq!(unmatched.bracket_marks, vec![(1, 0, OVERFLOW_BACKGROUND)]);
+
+        let generic = BufferView {
+            lines: vec!["Vec<u8>".to_string()],
+            ..view
+        };
+        assert!(
+            generic
+                .clone()
+                .with_showmatch((0, 3), &settings)
+                .bracket_marks
+                .is_empty()
+        );
+        settings.apply("matchpairs=(:),<:>").unwrap();
+        assert_eq!(
+            generic.with_showmatch((0, 3), &settings).bracket_marks,
+            vec![(0, 3, BRACKET_BACKGROUND), (0, 6, BRACKET_BACKGROUND)]
+        );
+    }
+
+    #[test]
+    fn misspellings_are_underlined_around_escapes() {
+        let dictionary = Dictionary::from_words(["fn", "main"]);
+        let misspelled = dictionary.misspelled("fn mian");
+        assert_eq!(
+            underline_ranges("fn mian", &misspelled),
+            format!("fn {SPELL_START}mian{SPELL_END}")
+        );
+
+        let keyword = syntax::render_line("mian fn", 20, Some(FileType::Rust));
+        assert_eq!(
+            underline_ranges(&keyword, &[(0, 4)]),
+            format!("{SPELL_START}mian{SPELL_END} \u{1b}[34mfn\u{1b}[39m")
+        );
+    }
+
+    #[test]
+    fn tab_bar_marks_active_buffer() {
+        let tabs = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(
+            build_tab_bar(&tabs, "b", 80),
+            format!(" a {ACTIVE_TAB_START} b {ACTIVE_TAB_END} c ")
+        );
+        assert_eq!(
+            build_tab_bar(&tabs, "a", 7),
+            format!("{ACTIVE_TAB_START} a {ACTIVE_TAB_END} b ")
+        );
+    }
+
+    #[test]
+    fn tab_bar_is_only_reserved_for_multiple_buffers() {
+        let view = BufferView {
+            lines: Vec::new(),
+            filetype: None,
+            textwidth: 0,
+            colorcolumn: Vec::new(),
+            search_matches: Vec::new(),
+            bracket_marks: Vec::new(),
+            dictionary: None,
+            tabs: vec!["solo".to_string()],
+            recording: None,
+            showmode: true,
+            compare: None,
+        };
+        assert_eq!(view.tab_bar_rows(), 0);
+        let view = view.with_tabs(&["a".to_string(), "b".to_string()]);
+        assert_eq!(view.tab_bar_rows(), 1);
+    }
+
+    #[test]
+    fn split_layout_stacks_two_panes_around_a_divider() {
+        assert_eq!(
+            SplitLayout::new(0, 23),
+            Some(SplitLayout {
+                top: 0..11,
+                divider: 11,
+                bottom: 12..23,
+            })
+        );
+        assert_eq!(
+            SplitLayout::new(1, 10),
+            Some(SplitLayout {
+                top: 1..6,
+                divider: 6,
+                bottom: 7..11,
+            }),
+            "the top pane takes the odd row and rows start below the tab bar"
+        );
+        assert_eq!(
+            SplitLayout::new(0, 3),
+            Some(SplitLayout {
+                top: 0..1,
+                divider: 1,
+                bottom: 2..3,
+            })
+        );
+        assert_eq!(SplitLayout::new(0, 2), None);
+    }
+
+    #[test]
+    fn active_rows_shrink_only_while_comparing() {
+        assert_eq!(SplitLayout::active_rows(23, false), 23);
+        assert_eq!(SplitLayout::active_rows(23, true), 11);
+        assert_eq!(SplitLayout::active_rows(2, true), 2, "too small to split");
+    }
+
+    #[test]
+    fn compare_pane_renders_below_a_divider_from_its_own_offset() {
+        let view = BufferView {
+            lines: vec!["top 0".to_string(), "top 1".to_string()],
+            filetype: None,
+            textwidth: 0,
+            colorcolumn: Vec::new(),
+            search_matches: Vec::new(),
+            bracket_marks: Vec::new(),
+            dictionary: None,
+            tabs: Vec::new(),
+            recording: None,
+            showmode: true,
+            compare: Some(ComparePane {
+                name: "other".to_string(),
+                lines: vec!["low 0".to_string(), "low 1".to_string()],
+                filetype: None,
+                scroll_offset: 1,
+            }),
+        };
+        let frame = View::compose(
+            &view,
+            "active",
+            &EditorMode::Read,
+            "",
+            None,
+            1,
+            (2, 1),
+            Size {
+                width: 20,
+                height: 8,
+            },
+        );
+
+        let rows = frame.rows();
+        assert_eq!(rows.len(), 8);
+        assert_eq!(rows[0], "top 1");
+        assert_eq!(rows[1], "\u{2015}".repeat(20));
+        assert_eq!(rows[3], build_divider("other", 20));
+        assert_eq!(rows[4], "low 1");
+        assert_eq!(rows[5], "\u{2015}".repeat(20));
+    }
+
+    #[test]
+    fn divider_names_the_compared_buffer_across_the_width() {
+        let divider = build_divider("notes.txt", 24);
+        assert_eq!(
+            divider,
+            format!("{ACTIVE_TAB_START} notes.txt [compare]    {ACTIVE_TAB_END}")
+        );
+        assert_eq!(
+            build_divider("notes.txt", 6),
+            format!("{ACTIVE_TAB_START} notes{ACTIVE_TAB_END}")
+        );
+    }
+
     #[test]
     fn command_line_includes_buffer_name_cursor_and_mode() {
-        let line = build_command_line(40, "", "test.rs", &EditorMode::Insert, (3, 5), None);
+        let line = build_command_line(
+            40,
+            "",
+            "test.rs",
+            Some(&EditorMode::Insert),
+            (3, 5),
+            None,
+            None,
+        );
 
         assert!(line.starts_with(":"));
         assert!(line.ends_with("[INSERT]"));
@@ -207,7 +900,15 @@ mod tests {
 
     #[test]
     fn command_line_respects_command_input_and_mode() {
-        let line = build_command_line(40, ":w", "buffer", &EditorMode::Read, (1, 1), None);
+        let line = build_command_line(
+            40,
+            ":w",
+            "buffer",
+            Some(&EditorMode::Read),
+            (1, 1),
+            None,
+            None,
+        );
 
         assert!(line.starts_with(":w"));
         assert!(line.ends_with("[READ]"));
@@ -216,23 +917,114 @@ mod tests {
 
     #[test]
     fn cursor_position_changes_are_reflected() {
-        let first = build_command_line(30, ":", "file", &EditorMode::Command, (2, 4), None);
-        let second = build_command_line(30, ":", "file", &EditorMode::Command, (5, 10), None);
+        let first = build_command_line(
+            30,
+            ":",
+            "file",
+            Some(&EditorMode::Command),
+            (2, 4),
+            None,
+            None,
+        );
+        let second = build_command_line(
+            30,
+            ":",
+            "file",
+            Some(&EditorMode::Command),
+            (5, 10),
+            None,
+            None,
+        );
 
         assert!(first.contains("file 2,4"));
         assert!(second.contains("file 5,10"));
         assert_ne!(first, second);
     }
 
+    #[test]
+    fn recording_register_is_shown_beside_mode() {
+        let line = build_command_line(
+            60,
+            "",
+            "buf",
+            Some(&EditorMode::Read),
+            (1, 1),
+            None,
+            Some('a'),
+        );
+        assert!(line.ends_with("recording @a [READ]"));
+    }
+
+    #[test]
+    fn noshowmode_omits_the_mode_label() {
+        let mut settings = EditorSettings::default();
+        settings.apply("noshowmode").unwrap();
+        let view = BufferView {
+            lines: vec!["text".to_string()],
+            filetype: None,
+            textwidth: 0,
+            colorcolumn: Vec::new(),
+            search_matches: Vec::new(),
+            bracket_marks: Vec::new(),
+            dictionary: None,
+            tabs: Vec::new(),
+            recording: None,
+            showmode: true,
+            compare: None,
+        }
+        .with_settings(&settings);
+        let size = Size {
+            width: 30,
+            height: 4,
+        };
+
+        let frame = View::compose(
+            &view,
+            "notes",
+            &EditorMode::Insert,
+            "",
+            None,
+            0,
+            (1, 1),
+            size,
+        );
+        let line = frame.rows().last().unwrap();
+        assert!(!line.contains("[INSERT]"));
+        assert_eq!(line.trim_end(), format!(":{}notes 1,1", " ".repeat(9)));
+
+        settings.apply("showmode").unwrap();
+        let frame = View::compose(
+            &view.with_settings(&settings),
+            "notes",
+            &EditorMode::Insert,
+            "",
+            None,
+            0,
+            (1, 1),
+            size,
+        );
+        assert!(frame.rows().last().unwrap().ends_with("[INSERT]"));
+    }
+
+    #[test]
+    fn command_line_without_a_mode_keeps_the_recording_register() {
+        let line = build_command_line(30, "", "buf", None, (1, 1), None, Some('q'));
+        assert!(line.ends_with("recording @q"));
+
+        let line = build_command_line(30, ":w", "buf", None, (1, 1), Some("written"), None);
+        assert_eq!(line.trim_end(), format!("written{}buf 1,1", " ".repeat(15)));
+    }
+
     #[test]
     fn status_message_overrides_command_input() {
         let line = build_command_line(
             80,
             ":w",
             "buffer",
-            &EditorMode::Command,
+            Some(&EditorMode::Command),
             (1, 1),
             Some("This buffer is required to be saved."),
+            None,
         );
 
         assert!(line.starts_with("This buffer is required to be saved"));
@@ -8,8 +8,20 @@ mod process;
 mod store;
 
 use rustyline::Result;
+use std::env;
 
 /// Entry point that starts the control loop.
 fn main() -> Result<()> {
-    control::control_loop()
+    if env::args().skip(1).any(|arg| arg == "--print-config") {
+        match conf::effective_config() {
+            Ok(report) => print!("{report}"),
+            Err(err) => {
+                eprintln!("iridium: {err}");
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    control::control_loop(control::LaunchOptions::from_args(env::args().skip(1)))
 }
@@ -38,6 +38,12 @@ impl Default for AliasSink {
     }
 }
 
+/// Characters that cannot appear in an alias name because the shell would treat them specially.
+const INVALID_NAME_CHARS: &[char] = &[
+    '=', '|', '&', ';', '<', '>', '(', ')', '$', '`', '\\', '"', '\'', '*', '?', '[', ']', '#',
+    '~', '{', '}',
+];
+
 // ENV variables used:
 // LANG
 // LC_ALL
@@ -47,6 +53,9 @@ impl Default for AliasSink {
 // man page: https://www.man7.org/linux/man-pages/man1/alias.1p.html
 
 /// Stores command aliases and exposes the POSIX `alias` builtin behaviour.
+///
+/// `alias -s [name...]` lists definitions sorted by name, one `alias name='value'` line each, so
+/// the output can be sourced back from an rc file.
 pub struct Alias {
     alias_map: HashMap<String, String>,
     stdout: AliasSink,
@@ -58,8 +67,14 @@ impl Builtin for Alias {
     fn call(&mut self, args: &[String]) -> Option<i32> {
         let mut queries = Vec::new();
         let mut definitions = Vec::new();
+        let mut sourceable = false;
 
         for arg in args {
+            if arg == "-s" {
+                sourceable = true;
+                continue;
+            }
+
             if arg.starts_with('-') && !arg.contains('=') {
                 let message = format!("alias: {}: invalid option", arg);
                 self.stderr.write_line(&message);
@@ -75,17 +90,28 @@ impl Builtin for Alias {
             }
         }
 
+        let mut status = 0;
+
         for (name, value) in definitions {
+            if !is_valid_alias_name(&name) {
+                let message = format!("alias: {}: invalid alias name", name);
+                self.stderr.write_line(&message);
+                status = 1;
+                continue;
+            }
             self.insert_alias(&name, &value);
         }
 
+        if sourceable {
+            let listed = self.write_sourceable(queries);
+            return Some(status.max(listed));
+        }
+
         if args.is_empty() {
             self.write_all_definitions();
             return Some(0);
         }
 
-        let mut status = 0;
-
         for name in queries {
             if let Some(value) = self.alias_map.get(&name).cloned() {
                 let line = format_definition(&name, &value);
@@ -99,6 +125,16 @@ impl Builtin for Alias {
 
         Some(status)
     }
+
+    /// Run the builtin with listings written to `out` rather than the configured stdout sink.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let captured = Rc::new(RefCell::new(Vec::new()));
+        let previous = std::mem::replace(&mut self.stdout, AliasSink::Buffer(Rc::clone(&captured)));
+        let status = self.call(args);
+        self.stdout = previous;
+        let _ = out.write_all(&captured.borrow());
+        status
+    }
 }
 
 impl Alias {
@@ -144,6 +180,47 @@ impl Alias {
         self.alias_map.get(alias_name)
     }
 
+    /// Every alias as a sourceable `alias name='value'` line, sorted by name.
+    ///
+    /// Each line, including the last, ends with a newline so the text can be written straight
+    /// to an rc file.
+    pub fn sourceable_definitions(&self) -> String {
+        let mut names: Vec<&String> = self.alias_map.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| format!("{}\n", format_definition(name, &self.alias_map[name])))
+            .collect()
+    }
+
+    // `alias -s`: all aliases, or just `names`, in sorted sourceable form.
+    fn write_sourceable(&mut self, mut names: Vec<String>) -> i32 {
+        if names.is_empty() {
+            for line in self.sourceable_definitions().lines() {
+                self.stdout.write_line(line);
+            }
+            return 0;
+        }
+
+        names.sort();
+        names.dedup();
+        let mut status = 0;
+        for name in names {
+            match self.alias_map.get(&name) {
+                Some(value) => {
+                    let line = format_definition(&name, value);
+                    self.stdout.write_line(&line);
+                }
+                None => {
+                    let message = format!("alias: {}: not found", name);
+                    self.stderr.write_line(&message);
+                    status = 1;
+                }
+            }
+        }
+        status
+    }
+
     fn write_all_definitions(&mut self) {
         let mut names: Vec<String> = self.alias_map.keys().cloned().collect();
         names.sort();
@@ -157,6 +234,14 @@ impl Alias {
     }
 }
 
+/// Whether `name` could be typed as a command word and therefore used as an alias.
+fn is_valid_alias_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control() || INVALID_NAME_CHARS.contains(&ch))
+}
+
 /// Render an alias definition using POSIX-compliant quoting rules.
 pub fn format_definition(name: &str, value: &str) -> String {
     format!("alias {}={}", name, single_quote(value))
@@ -201,6 +286,18 @@ mod tests {
         assert!(matches!(AliasSink::default(), AliasSink::Stdout));
     }
 
+    #[test]
+    fn sourceable_definitions_are_sorted_and_newline_terminated() {
+        let (mut alias, _, _) = setup_alias();
+        assert_eq!(alias.sourceable_definitions(), "");
+
+        let _ = alias.call(&["zz=echo 'z'".into(), "aa=ls".into()]);
+        assert_eq!(
+            alias.sourceable_definitions(),
+            "alias aa='ls'\nalias zz='echo '\\''z'\\'''\n"
+        );
+    }
+
     #[test]
     fn contains_alias_returns_true_for_existing_alias() {
         let mut alias = Alias::new();
@@ -218,6 +315,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn trailing_space_in_value_is_preserved() {
+        let (mut alias, stdout, _) = setup_alias();
+        let _ = alias.call(&["sudo=sudo ".into()]);
+        assert_eq!(
+            alias.get_alias_expansion("sudo").map(|s| s.as_str()),
+            Some("sudo ")
+        );
+        let _ = alias.call(&["sudo".into()]);
+        assert_eq!(buffer_to_string(&stdout), "alias sudo='sudo '\n");
+    }
+
     #[test]
     fn alias_sink_writes_to_stdout() {
         let buffer = Rc::new(RefCell::new(Vec::new()));
@@ -310,6 +419,28 @@ mod tests {
         assert_eq!(buffer_to_string(&stderr), "alias: -p: invalid option\n");
     }
 
+    #[test]
+    fn rejects_invalid_alias_names_but_keeps_valid_ones() {
+        let (mut alias, stdout, stderr) = setup_alias();
+        let status = alias.call(&["ll=ls -al".into(), "bad name=ls".into(), "a|b=ls".into()]);
+        assert_eq!(status, Some(1));
+        assert!(buffer_to_string(&stdout).is_empty());
+        assert_eq!(
+            buffer_to_string(&stderr),
+            "alias: bad name: invalid alias name\nalias: a|b: invalid alias name\n"
+        );
+        assert!(alias.contains_alias("ll"));
+        assert!(!alias.contains_alias("bad name"));
+        assert!(!alias.contains_alias("a|b"));
+    }
+
+    #[test]
+    fn rejects_empty_alias_name() {
+        let (mut alias, _, stderr) = setup_alias();
+        assert_eq!(alias.call(&["=ls".into()]), Some(1));
+        assert_eq!(buffer_to_string(&stderr), "alias: : invalid alias name\n");
+    }
+
     #[test]
     fn quotes_single_quotes_in_values() {
         let (mut alias, stdout, stderr) = setup_alias();
@@ -1,23 +1,36 @@
 use super::Builtin;
 use crate::process::alias::Alias;
 use crate::process::cd::Cd;
+use crate::process::compress::{Compress, Direction};
+use crate::process::date::Date;
+use crate::process::dirs::{DirStack, DirStackHandle, Dirs};
+use crate::process::environment::{EnvironmentHandle, ShellEnvironment};
 use crate::process::exit::Exit;
 use crate::process::help::Help;
 use crate::process::history::History;
-use crate::process::pushd::Pushd;
+use crate::process::jobs::{Jobs, JobsBuiltin, JobsHandle};
+use crate::process::options::{OptionsHandle, ShellOptions};
+use crate::process::pushd::{Popd, Pushd};
 use crate::process::pwd::Pwd;
+use crate::process::set::Set;
 use crate::process::r#type::Type;
+use crate::process::umask::Umask;
 use crate::process::welcome::Welcome;
 use crate::process::which::Which;
+use crate::store::buffer_store::BufferStore;
 use std::any::Any;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::io::Write;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 /// Shared interface that lets [`BuiltinMap`] invoke builtins without knowing their concrete types.
 trait BuiltinAdapter: Any {
     /// Execute the builtin with the provided argument list, returning its exit status when available.
     fn call(&self, args: &[String]) -> Option<i32>;
+    /// Execute the builtin with its standard output routed into `out`.
+    fn call_with_output(&self, args: &[String], out: &mut dyn Write) -> Option<i32>;
     /// Allow downcasting back to the underlying builtin wrapper when handles are needed.
     fn as_any(&self) -> &dyn Any;
 }
@@ -50,6 +63,10 @@ impl<T: Builtin + 'static> BuiltinAdapter for BuiltinWrapper<T> {
         self.inner.borrow_mut().call(args)
     }
 
+    fn call_with_output(&self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        self.inner.borrow_mut().call_with_output(args, out)
+    }
+
     /// Expose the wrapper as [`Any`] to enable downcasting by name.
     fn as_any(&self) -> &dyn Any {
         self
@@ -62,18 +79,38 @@ struct BuiltinHandles {
     alias: Option<Rc<RefCell<Alias>>>,
     pwd: Option<Rc<RefCell<Pwd>>>,
     which: Option<Rc<RefCell<Which>>>,
+    r#type: Option<Rc<RefCell<Type>>>,
 }
 
 /// Populate a builtin map using a set of builtin names and capture selected handles for later use.
 macro_rules! register_builtins {
-    ($map:expr, $names:expr) => {{
+    ($map:expr, $names:expr, $environment:expr, $options:expr, $dir_stack:expr, $jobs:expr) => {{
         let mut handles = BuiltinHandles::default();
         for name in $names {
             match name.as_str() {
                 "alias" => handles.alias = Some(insert_builtin($map, "alias", Alias::new())),
-                "pwd" => handles.pwd = Some(insert_builtin($map, "pwd", Pwd::new())),
+                "pwd" => {
+                    let pwd = Pwd::with_environment(Rc::clone($environment));
+                    handles.pwd = Some(insert_builtin($map, "pwd", pwd));
+                }
                 "cd" => {
-                    insert_builtin($map, "cd", Cd::new());
+                    insert_builtin($map, "cd", Cd::with_environment(Rc::clone($environment)));
+                }
+                "compress" | "decompress" => {
+                    let direction = if name == "compress" {
+                        Direction::Compress
+                    } else {
+                        Direction::Decompress
+                    };
+                    let builtin = Compress::with_environment(direction, Rc::clone($environment));
+                    insert_builtin($map, name.as_str(), builtin);
+                }
+                "date" => {
+                    insert_builtin($map, "date", Date::new());
+                }
+                "dirs" => {
+                    let dirs = Dirs::new(Rc::clone($environment), Rc::clone($dir_stack));
+                    insert_builtin($map, "dirs", dirs);
                 }
                 "exit" => {
                     insert_builtin($map, "exit", Exit::new());
@@ -84,17 +121,33 @@ macro_rules! register_builtins {
                 "history" => {
                     insert_builtin($map, "history", History::new());
                 }
+                "jobs" => {
+                    insert_builtin($map, "jobs", JobsBuiltin::new(Rc::clone($jobs)));
+                }
+                "popd" => {
+                    let popd = Popd::new(Rc::clone($environment), Rc::clone($dir_stack));
+                    insert_builtin($map, "popd", popd);
+                }
                 "pushd" => {
-                    insert_builtin($map, "pushd", Pushd::new());
+                    let pushd = Pushd::new(Rc::clone($environment), Rc::clone($dir_stack));
+                    insert_builtin($map, "pushd", pushd);
+                }
+                "set" => {
+                    insert_builtin($map, "set", Set::with_options(Rc::clone($options)));
                 }
                 "type" => {
-                    insert_builtin($map, "type", Type::new());
+                    let r#type = Type::with_environment(Rc::clone($environment));
+                    handles.r#type = Some(insert_builtin($map, "type", r#type));
+                }
+                "umask" => {
+                    insert_builtin($map, "umask", Umask::with_options(Rc::clone($options)));
                 }
                 "welcome" => {
                     insert_builtin($map, "welcome", Welcome::new());
                 }
                 "which" => {
-                    handles.which = Some(insert_builtin($map, "which", Which::new()));
+                    let which = Which::with_environment(Rc::clone($environment));
+                    handles.which = Some(insert_builtin($map, "which", which));
                 }
                 other => panic!("unsupported builtin name: {}", other),
             }
@@ -106,38 +159,85 @@ macro_rules! register_builtins {
 /// Concrete mapping between builtin names and runtime adapters.
 pub struct BuiltinMap {
     func_map: HashMap<String, Rc<dyn BuiltinAdapter>>,
+    environment: EnvironmentHandle,
+    options: OptionsHandle,
+    dir_stack: DirStackHandle,
+    jobs: JobsHandle,
+    /// Buffers that `tee @name` pipeline stages write into, when the shell has a store.
+    buffers: Option<Arc<Mutex<BufferStore>>>,
 }
 
 impl BuiltinMap {
-    /// Register the default set of builtins and wire up their interdependencies.
+    /// Register the default set of builtins operating on the process environment.
     pub fn new() -> Self {
+        Self::with_environment(ShellEnvironment::Process.into_handle())
+    }
+
+    /// Register the default set of builtins and wire up their interdependencies, sharing
+    /// `environment` between builtins and spawned commands.
+    pub fn with_environment(environment: EnvironmentHandle) -> Self {
         let mut func_map: HashMap<String, Rc<dyn BuiltinAdapter>> = HashMap::new();
+        let options = ShellOptions::default().into_handle();
+        let dir_stack = DirStack::default().into_handle();
+        let jobs = Jobs::default().into_handle();
 
-        let BuiltinHandles { alias, pwd, which } = register_builtins!(
+        let BuiltinHandles {
+            alias,
+            pwd,
+            which,
+            r#type,
+        } = register_builtins!(
             &mut func_map,
             vec![
                 "alias".to_string(),
                 "pwd".to_string(),
                 "cd".to_string(),
+                "compress".to_string(),
+                "decompress".to_string(),
+                "date".to_string(),
+                "dirs".to_string(),
                 "exit".to_string(),
                 "help".to_string(),
                 "history".to_string(),
+                "jobs".to_string(),
+                "popd".to_string(),
                 "pushd".to_string(),
+                "set".to_string(),
                 "type".to_string(),
+                "umask".to_string(),
                 "welcome".to_string(),
                 "which".to_string(),
-            ]
+            ],
+            &environment,
+            &options,
+            &dir_stack,
+            &jobs
         );
 
         let alias = alias.expect("alias builtin not registered");
         let pwd = pwd.expect("pwd builtin not registered");
         let which = which.expect("which builtin not registered");
+        let r#type = r#type.expect("type builtin not registered");
 
         which.borrow_mut().set_aliases(alias.clone());
+        r#type.borrow_mut().set_aliases(alias.clone());
         let builtin_names: Vec<String> = func_map.keys().cloned().collect();
-        which.borrow_mut().set_builtin_names(builtin_names);
+        which.borrow_mut().set_builtin_names(builtin_names.clone());
+        r#type.borrow_mut().set_builtin_names(builtin_names);
 
-        Self { func_map }
+        Self {
+            func_map,
+            environment,
+            options,
+            dir_stack,
+            jobs,
+            buffers: None,
+        }
+    }
+
+    /// Whether `func_name` names a registered builtin.
+    pub fn contains(&self, func_name: &str) -> bool {
+        self.func_map.contains_key(func_name)
     }
 
     /// Attempt to invoke a builtin by name, returning its status if the builtin exists.
@@ -147,6 +247,18 @@ impl BuiltinMap {
             .map(|adapter| adapter.call(args))
     }
 
+    /// Like [`BuiltinMap::invoke`], but the builtin's standard output is written to `out`.
+    pub fn invoke_with_output(
+        &self,
+        func_name: &str,
+        args: &[String],
+        out: &mut dyn Write,
+    ) -> Option<Option<i32>> {
+        self.func_map
+            .get(func_name)
+            .map(|adapter| adapter.call_with_output(args, out))
+    }
+
     /// Retrieve the shared alias handle so other components can mutate the alias map.
     pub fn get_alias(&self) -> Rc<RefCell<Alias>> {
         self.get_handle("alias")
@@ -160,6 +272,37 @@ impl BuiltinMap {
             .unwrap_or_default()
     }
 
+    /// Shared environment used by builtins and external commands.
+    pub fn environment(&self) -> EnvironmentHandle {
+        Rc::clone(&self.environment)
+    }
+
+    /// Shell option flags shared with `set` and command execution.
+    pub fn options(&self) -> OptionsHandle {
+        Rc::clone(&self.options)
+    }
+
+    /// Directory stack shared by `pushd`, `popd` and `dirs`.
+    #[allow(dead_code)]
+    pub fn dir_stack(&self) -> DirStackHandle {
+        Rc::clone(&self.dir_stack)
+    }
+
+    /// Background jobs started with a trailing `&`.
+    pub fn jobs(&self) -> JobsHandle {
+        Rc::clone(&self.jobs)
+    }
+
+    /// Give `tee @name` pipeline stages a buffer store to write into.
+    pub fn set_buffer_store(&mut self, buffers: Arc<Mutex<BufferStore>>) {
+        self.buffers = Some(buffers);
+    }
+
+    /// The buffer store `tee @name` writes into, if one was attached.
+    pub fn buffer_store(&self) -> Option<Arc<Mutex<BufferStore>>> {
+        self.buffers.clone()
+    }
+
     /// Downcast the stored adapter to recover the concrete builtin handle for the requested name.
     fn get_handle<T: Builtin + 'static>(&self, name: &str) -> Option<Rc<RefCell<T>>> {
         self.func_map.get(name).and_then(|adapter| {
@@ -1,7 +1,12 @@
 pub mod map;
 
+use std::io::Write;
+
 /// Trait implemented by all builtins so they can be invoked through [`BuiltinMap`].
 pub trait Builtin {
     /// Execute the builtin with the provided arguments, returning an optional status code.
     fn call(&mut self, args: &[String]) -> Option<i32>;
+
+    /// Execute the builtin with its standard output written to `out` instead of stdout.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32>;
 }
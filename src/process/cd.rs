@@ -1,20 +1,31 @@
 use crate::process::builtin::Builtin;
+use crate::process::environment::{EnvironmentHandle, ShellEnvironment};
 use std::cell::RefCell;
-use std::env;
 use std::fs;
+use std::io::{self, ErrorKind, Write};
 use std::path::{Component, Path, PathBuf};
 use std::rc::Rc;
 
 /// POSIX-compliant `cd` builtin supporting logical/physical modes and CDPATH resolution.
 pub struct Cd {
     output: CdOutput,
+    errors: CdOutput,
+    environment: EnvironmentHandle,
 }
 
 impl Cd {
     /// Construct a `cd` builtin that writes path announcements to stdout.
+    #[allow(dead_code)]
     pub fn new() -> Self {
+        Self::with_environment(ShellEnvironment::Process.into_handle())
+    }
+
+    /// Construct a `cd` builtin that reads and updates the provided environment.
+    pub fn with_environment(environment: EnvironmentHandle) -> Self {
         Self {
             output: CdOutput::Stdout,
+            errors: CdOutput::Stderr,
+            environment,
         }
     }
 
@@ -22,11 +33,18 @@ impl Cd {
     pub fn capture_output_buffer(&mut self, buffer: Rc<RefCell<Vec<u8>>>) {
         self.output = CdOutput::Buffer(buffer);
     }
+
+    /// Route error messages into the provided buffer instead of stderr.
+    #[allow(dead_code)]
+    pub fn capture_error_buffer(&mut self, buffer: Rc<RefCell<Vec<u8>>>) {
+        self.errors = CdOutput::Buffer(buffer);
+    }
 }
 
 impl Builtin for Cd {
     fn call(&mut self, args: &[String]) -> Option<i32> {
-        match execute_cd(args) {
+        let result = execute_cd(args, &mut self.environment.borrow_mut());
+        match result {
             Ok(print) => {
                 if let Some(path) = print {
                     self.output.println(&path);
@@ -34,44 +52,57 @@ impl Builtin for Cd {
                 Some(0)
             }
             Err(err) => {
-                eprintln!("{err}");
+                self.errors.println(&err);
                 Some(1)
             }
         }
     }
+
+    /// Run `cd`, writing any announced directory to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let captured = Rc::new(RefCell::new(Vec::new()));
+        let previous = std::mem::replace(&mut self.output, CdOutput::Buffer(Rc::clone(&captured)));
+        let status = self.call(args);
+        self.output = previous;
+        let _ = out.write_all(&captured.borrow());
+        status
+    }
 }
 
-fn execute_cd(args: &[String]) -> Result<Option<String>, String> {
+pub(crate) fn execute_cd(
+    args: &[String],
+    env: &mut ShellEnvironment,
+) -> Result<Option<String>, String> {
     let (mode, operand) = parse_arguments(args)?;
     let mut should_print = false;
     let operand = match operand {
         Some(val) => val,
-        None => env::var("HOME").map_err(|_| "cd: HOME not set".to_string())?,
+        None => env
+            .var("HOME")
+            .ok_or_else(|| "cd: HOME not set".to_string())?,
     };
 
     let operand = if operand == "-" {
         should_print = true;
-        env::var("OLDPWD").map_err(|_| "cd: OLDPWD not set".to_string())?
+        env.var("OLDPWD")
+            .ok_or_else(|| "cd: OLDPWD not set".to_string())?
     } else {
         operand
     };
 
-    let operand = expand_tilde(&operand)?;
-    let cdpath_result = resolve_with_cdpath(&operand)?;
+    let operand = expand_tilde(&operand, env)?;
+    let cdpath_result = resolve_with_cdpath(&operand, env)?;
 
-    let previous_pwd = env::var("PWD")
-        .ok()
-        .unwrap_or_else(|| env::current_dir().unwrap().to_string_lossy().to_string());
+    let previous_pwd = env
+        .var("PWD")
+        .unwrap_or_else(|| env.current_dir().unwrap().to_string_lossy().to_string());
 
-    if let Err(err) = env::set_current_dir(&cdpath_result.actual_path) {
-        return Err(format!(
-            "cd: {}: {}",
-            operand,
-            err.kind().to_string().replace('_', " ").to_lowercase()
-        ));
+    if let Err(err) = env.set_current_dir(&cdpath_result.actual_path) {
+        return Err(format!("cd: {}: {}", operand, describe_io_error(&err)));
     }
 
-    let new_physical = env::current_dir()
+    let new_physical = env
+        .current_dir()
         .map_err(|err| format!("cd: unable to determine current directory: {err}"))?;
 
     let new_pwd = match mode {
@@ -79,10 +110,8 @@ fn execute_cd(args: &[String]) -> Result<Option<String>, String> {
         ResolveMode::Physical => new_physical.to_string_lossy().to_string(),
     };
 
-    unsafe {
-        env::set_var("OLDPWD", previous_pwd);
-        env::set_var("PWD", new_pwd.clone());
-    }
+    env.set_var("OLDPWD", previous_pwd);
+    env.set_var("PWD", new_pwd.clone());
 
     let mut print_path = should_print;
     if cdpath_result.print_on_success {
@@ -132,14 +161,18 @@ fn parse_arguments(args: &[String]) -> Result<(ResolveMode, Option<String>), Str
     Ok((mode, operands.into_iter().next()))
 }
 
-fn expand_tilde(input: &str) -> Result<String, String> {
+fn expand_tilde(input: &str, env: &ShellEnvironment) -> Result<String, String> {
     if let Some(stripped) = input.strip_prefix("~/") {
-        let home = env::var("HOME").map_err(|_| "cd: HOME not set".to_string())?;
+        let home = env
+            .var("HOME")
+            .ok_or_else(|| "cd: HOME not set".to_string())?;
         return Ok(format!("{home}/{stripped}"));
     }
 
     if input == "~" {
-        let home = env::var("HOME").map_err(|_| "cd: HOME not set".to_string())?;
+        let home = env
+            .var("HOME")
+            .ok_or_else(|| "cd: HOME not set".to_string())?;
         return Ok(home);
     }
 
@@ -152,15 +185,17 @@ struct CdpathResolution {
     print_on_success: bool,
 }
 
-fn resolve_with_cdpath(dir: &str) -> Result<CdpathResolution, String> {
+fn resolve_with_cdpath(dir: &str, env: &ShellEnvironment) -> Result<CdpathResolution, String> {
     let mut attempted = Vec::new();
     if eligible_for_cdpath(dir) {
-        if let Ok(cdpath) = env::var("CDPATH") {
+        if let Some(cdpath) = env.var("CDPATH") {
             for entry in cdpath.split(':') {
-                let base = if entry.is_empty() { "." } else { entry };
+                // An entry such as `~/projects` is expanded; without HOME it is used as written.
+                let expanded = expand_tilde(entry, env).unwrap_or_else(|_| entry.to_string());
+                let base = if entry.is_empty() { "." } else { &expanded };
                 let candidate = Path::new(base).join(dir);
                 if let Some(resolution) =
-                    accept_candidate(&candidate, entry != "." && !entry.is_empty())
+                    accept_candidate(&candidate, entry != "." && !entry.is_empty(), env)
                 {
                     return Ok(resolution);
                 }
@@ -169,22 +204,47 @@ fn resolve_with_cdpath(dir: &str) -> Result<CdpathResolution, String> {
         }
     }
 
-    if let Some(resolution) = accept_candidate(&PathBuf::from(dir), false) {
+    if let Some(resolution) = accept_candidate(&PathBuf::from(dir), false, env) {
         return Ok(resolution);
     }
 
-    Err(format!("cd: no such file or directory: {}", dir))
+    // Anything else that exists (a regular file, say) is handed to `set_current_dir` so the
+    // reported reason comes from the real io error.
+    if resolve_against_cwd(Path::new(dir), env).is_some_and(|path| path.exists()) {
+        return Ok(CdpathResolution {
+            actual_path: PathBuf::from(dir),
+            logical_operand: dir.to_string(),
+            print_on_success: false,
+        });
+    }
+
+    Err(format!("cd: {}: no such file or directory", dir))
 }
 
-fn accept_candidate(path: &PathBuf, print_on_success: bool) -> Option<CdpathResolution> {
-    let absolute = if path.is_absolute() {
-        path.clone()
+/// Human-readable reason for a failed directory change, e.g. `not a directory`.
+fn describe_io_error(err: &io::Error) -> String {
+    match err.kind() {
+        ErrorKind::NotFound => "no such file or directory".to_string(),
+        ErrorKind::NotADirectory => "not a directory".to_string(),
+        ErrorKind::PermissionDenied => "permission denied".to_string(),
+        kind => kind.to_string().replace('_', " ").to_lowercase(),
+    }
+}
+
+fn resolve_against_cwd(path: &Path, env: &ShellEnvironment) -> Option<PathBuf> {
+    if path.is_absolute() {
+        Some(path.to_path_buf())
     } else {
-        match env::current_dir() {
-            Ok(dir) => dir.join(path),
-            Err(_) => return None,
-        }
-    };
+        env.current_dir().ok().map(|dir| dir.join(path))
+    }
+}
+
+fn accept_candidate(
+    path: &Path,
+    print_on_success: bool,
+    env: &ShellEnvironment,
+) -> Option<CdpathResolution> {
+    let absolute = resolve_against_cwd(path, env)?;
 
     match fs::metadata(&absolute) {
         Ok(meta) if meta.is_dir() => Some(CdpathResolution {
@@ -254,6 +314,7 @@ enum ResolveMode {
 
 enum CdOutput {
     Stdout,
+    Stderr,
     Buffer(Rc<RefCell<Vec<u8>>>),
 }
 
@@ -263,6 +324,9 @@ impl CdOutput {
             CdOutput::Stdout => {
                 println!("{value}");
             }
+            CdOutput::Stderr => {
+                eprintln!("{value}");
+            }
             CdOutput::Buffer(buffer) => {
                 let mut buf = buffer.borrow_mut();
                 buf.extend_from_slice(value.as_bytes());
@@ -276,6 +340,7 @@ impl CdOutput {
 mod tests {
     use super::*;
     use std::collections::HashMap;
+    use std::env;
     use tempfile::TempDir;
 
     use once_cell::sync::Lazy;
@@ -0,0 +1,180 @@
+use super::builtin::Builtin;
+use super::environment::{EnvironmentHandle, ShellEnvironment};
+use crate::store::compress::{self, CompressionAlgorithm};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Which way the builtin transforms files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Compress,
+    Decompress,
+}
+
+impl Direction {
+    fn name(self) -> &'static str {
+        match self {
+            Direction::Compress => "compress",
+            Direction::Decompress => "decompress",
+        }
+    }
+}
+
+/// The `compress` and `decompress` builtins, backed by the persistence compression code.
+///
+/// # Synopsis
+/// compress [-a algorithm] file...
+/// decompress [-a algorithm] file...
+///
+/// # Description
+/// `compress` writes `file.<ext>` next to each input (`.lz4` for the default LZ4 algorithm);
+/// `decompress` reverses it, writing the input path with the extension stripped. Inputs are
+/// left in place and existing outputs are never overwritten. Without `-a`, `decompress` picks
+/// the algorithm from the file extension.
+pub struct Compress {
+    direction: Direction,
+    environment: EnvironmentHandle,
+}
+
+impl Builtin for Compress {
+    /// Process each file in turn, returning `1` if any of them failed.
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        let name = self.direction.name();
+        let mut algorithm = None;
+        let mut files = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-a" {
+                let Some(value) = iter.next() else {
+                    eprintln!("{name}: -a requires an algorithm");
+                    return Some(2);
+                };
+                match CompressionAlgorithm::from_name(value) {
+                    Some(alg) => algorithm = Some(alg),
+                    None => {
+                        eprintln!("{name}: unknown algorithm: {value}");
+                        return Some(2);
+                    }
+                }
+            } else {
+                files.push(arg);
+            }
+        }
+
+        if files.is_empty() {
+            eprintln!("usage: {name} [-a algorithm] file...");
+            return Some(2);
+        }
+
+        let mut status = 0;
+        for file in files {
+            let result = match self.direction {
+                Direction::Compress => {
+                    self.compress_file(file, algorithm.unwrap_or(CompressionAlgorithm::default()))
+                }
+                Direction::Decompress => self.decompress_file(file, algorithm),
+            };
+            if let Err(message) = result {
+                eprintln!("{name}: {message}");
+                status = 1;
+            }
+        }
+        Some(status)
+    }
+
+    /// Only errors are reported, on stderr, so `out` is left untouched.
+    fn call_with_output(&mut self, args: &[String], _out: &mut dyn Write) -> Option<i32> {
+        self.call(args)
+    }
+}
+
+impl Compress {
+    /// Construct the builtin resolving relative paths against the process directory.
+    #[allow(dead_code)]
+    pub fn new(direction: Direction) -> Self {
+        Self::with_environment(direction, ShellEnvironment::Process.into_handle())
+    }
+
+    /// Construct the builtin resolving relative paths against `environment`.
+    pub fn with_environment(direction: Direction, environment: EnvironmentHandle) -> Self {
+        Self {
+            direction,
+            environment,
+        }
+    }
+
+    fn resolve(&self, file: &str) -> PathBuf {
+        match self.environment.borrow().current_dir() {
+            Ok(cwd) => cwd.join(file),
+            Err(_) => PathBuf::from(file),
+        }
+    }
+
+    fn compress_file(&self, file: &str, algorithm: CompressionAlgorithm) -> Result<(), String> {
+        let input = self.resolve(file);
+        let mut output = input.clone().into_os_string();
+        output.push(".");
+        output.push(algorithm.extension());
+        let output = PathBuf::from(output);
+
+        let data = read_input(file, &input)?;
+        let encoded =
+            compress::compress(&data, algorithm).map_err(|err| format!("{file}: {err}"))?;
+        write_output(&output, &encoded)
+    }
+
+    fn decompress_file(
+        &self,
+        file: &str,
+        algorithm: Option<CompressionAlgorithm>,
+    ) -> Result<(), String> {
+        let input = self.resolve(file);
+        let extension = input
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(CompressionAlgorithm::from_extension);
+        let algorithm = match (algorithm, extension) {
+            (Some(requested), Some(found)) if requested != found => {
+                return Err(format!(
+                    "{file}: extension does not match {}",
+                    requested.name()
+                ));
+            }
+            (_, Some(found)) => found,
+            (_, None) => return Err(format!("{file}: unknown compressed file extension")),
+        };
+        let output = input.with_extension("");
+
+        let data = read_input(file, &input)?;
+        let decoded =
+            compress::decompress(&data, algorithm).map_err(|err| format!("{file}: {err}"))?;
+        write_output(&output, &decoded)
+    }
+}
+
+fn read_input(file: &str, path: &Path) -> Result<Vec<u8>, String> {
+    if !path.exists() {
+        return Err(format!("{file}: No such file or directory"));
+    }
+    if path.is_dir() {
+        return Err(format!("{file}: Is a directory"));
+    }
+    fs::read(path).map_err(|err| format!("{file}: {err}"))
+}
+
+/// Write `data` to a new file at `path`, refusing to replace one that already exists.
+fn write_output(path: &Path, data: &[u8]) -> Result<(), String> {
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(format!("{}: already exists", path.display()));
+        }
+        Err(err) => return Err(format!("{}: {err}", path.display())),
+    };
+    file.write_all(data).map_err(|err| {
+        // Leave no truncated output behind for the next attempt to trip over.
+        let _ = fs::remove_file(path);
+        format!("{}: {err}", path.display())
+    })
+}
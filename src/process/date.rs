@@ -0,0 +1,135 @@
+use super::builtin::Builtin;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeZone};
+use std::fmt::{Display, Write as _};
+use std::io::{self, Write};
+
+/// Format used when `date` is called without a `+format` operand.
+pub const DEFAULT_FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+
+/// The `date` builtin.
+///
+/// # Synopsis
+/// date [+format]
+///
+/// # Description
+/// Print the current local time. `format` is a strftime-style string such as `%Y-%m-%d`; without
+/// it the time is written as `2024-05-01 13:45:00 +02:00`.
+pub struct Date {
+    clock: fn() -> DateTime<Local>,
+}
+
+impl Date {
+    pub fn new() -> Self {
+        Self { clock: Local::now }
+    }
+}
+
+impl Default for Date {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Builtin for Date {
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Write the formatted time to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let format = match args {
+            [] => DEFAULT_FORMAT,
+            [operand] => match operand.strip_prefix('+') {
+                Some(format) => format,
+                None => {
+                    eprintln!("date: invalid argument: {operand}");
+                    eprintln!("usage: date [+format]");
+                    return Some(1);
+                }
+            },
+            _ => {
+                eprintln!("date: too many arguments");
+                return Some(1);
+            }
+        };
+
+        match format_time(&(self.clock)(), format) {
+            Ok(text) => {
+                let _ = writeln!(out, "{text}");
+                Some(0)
+            }
+            Err(message) => {
+                eprintln!("date: {message}");
+                Some(1)
+            }
+        }
+    }
+}
+
+/// Render `time` with the strftime-style `format`, rejecting unknown specifiers.
+pub fn format_time<Tz>(time: &DateTime<Tz>, format: &str) -> Result<String, String>
+where
+    Tz: TimeZone,
+    Tz::Offset: Display,
+{
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid format: {format}"));
+    }
+
+    let mut text = String::new();
+    write!(text, "{}", time.format_with_items(items.into_iter()))
+        .map_err(|_| format!("invalid format: {format}"))?;
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn fixed_time() -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(2 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2024, 5, 1, 13, 45, 9)
+            .unwrap()
+    }
+
+    #[test]
+    fn formats_with_strftime_specifiers() {
+        let time = fixed_time();
+        assert_eq!(format_time(&time, "%Y-%m-%d").unwrap(), "2024-05-01");
+        assert_eq!(format_time(&time, "at %H:%M").unwrap(), "at 13:45");
+        assert_eq!(
+            format_time(&time, DEFAULT_FORMAT).unwrap(),
+            "2024-05-01 13:45:09 +02:00"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_specifiers() {
+        assert_eq!(
+            format_time(&fixed_time(), "%Q"),
+            Err("invalid format: %Q".to_string())
+        );
+    }
+
+    #[test]
+    fn uses_the_injected_clock() {
+        let mut date = Date {
+            clock: || {
+                Local
+                    .with_ymd_and_hms(1999, 12, 31, 23, 59, 58)
+                    .single()
+                    .unwrap()
+            },
+        };
+        let mut out = Vec::new();
+        assert_eq!(
+            date.call_with_output(&["+%Y/%m/%d %T".to_string()], &mut out),
+            Some(0)
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), "1999/12/31 23:59:58\n");
+    }
+}
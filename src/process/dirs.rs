@@ -0,0 +1,153 @@
+//! Directory stack shared by `pushd`, `popd` and `dirs`.
+
+use super::builtin::Builtin;
+use super::environment::{EnvironmentHandle, ShellEnvironment};
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// Shared handle so `pushd`, `popd` and `dirs` observe the same stack.
+pub type DirStackHandle = Rc<RefCell<DirStack>>;
+
+/// Directories saved by `pushd`, most recent first.
+///
+/// The current directory is always the implicit top of the stack and is not stored here.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirStack {
+    saved: Vec<String>,
+}
+
+impl DirStack {
+    /// Wrap the stack in a shareable handle.
+    pub fn into_handle(self) -> DirStackHandle {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// Save `dir` just below the current directory.
+    pub fn push(&mut self, dir: impl Into<String>) {
+        self.saved.insert(0, dir.into());
+    }
+
+    /// Remove and return the most recently saved directory.
+    pub fn pop(&mut self) -> Option<String> {
+        (!self.saved.is_empty()).then(|| self.saved.remove(0))
+    }
+
+    pub fn clear(&mut self) {
+        self.saved.clear();
+    }
+
+    #[allow(dead_code)]
+    pub fn saved(&self) -> &[String] {
+        &self.saved
+    }
+
+    /// Every stack entry starting with the current directory of `env`.
+    ///
+    /// Paths under `HOME` are abbreviated to `~` unless `long` is set.
+    pub fn entries(&self, env: &ShellEnvironment, long: bool) -> Vec<String> {
+        let current = current_pwd(env);
+        let home = if long { None } else { env.var("HOME") };
+        std::iter::once(&current)
+            .chain(&self.saved)
+            .map(|dir| abbreviate_home(dir, home.as_deref()))
+            .collect()
+    }
+}
+
+/// The logical current directory: `PWD` when set, else the directory the environment reports.
+pub(crate) fn current_pwd(env: &ShellEnvironment) -> String {
+    env.var("PWD").unwrap_or_else(|| {
+        env.current_dir()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn abbreviate_home(dir: &str, home: Option<&str>) -> String {
+    let Some(home) = home.filter(|home| !home.is_empty() && *home != "/") else {
+        return dir.to_string();
+    };
+    match dir.strip_prefix(home) {
+        Some("") => "~".to_string(),
+        Some(rest) if rest.starts_with('/') => format!("~{rest}"),
+        _ => dir.to_string(),
+    }
+}
+
+/// The `dirs` builtin.
+///
+/// # Synopsis
+/// dirs [-clpv]
+///
+/// # Description
+/// Print the directory stack on one line, current directory first. Directories under `HOME` are
+/// shown with a leading `~`.
+///
+/// # Options
+/// - `-c` clears the stack, leaving only the current directory.
+/// - `-l` prints full paths instead of abbreviating `HOME`.
+/// - `-p` prints one entry per line.
+/// - `-v` prints one entry per line, prefixed with its index in the stack.
+pub struct Dirs {
+    environment: EnvironmentHandle,
+    stack: DirStackHandle,
+}
+
+impl Dirs {
+    pub fn new(environment: EnvironmentHandle, stack: DirStackHandle) -> Self {
+        Self { environment, stack }
+    }
+}
+
+impl Builtin for Dirs {
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Write the stack listing to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let (mut clear, mut long, mut per_line, mut verbose) = (false, false, false, false);
+        for arg in args {
+            let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+                eprintln!("dirs: too many arguments");
+                return Some(1);
+            };
+            for flag in flags.chars() {
+                match flag {
+                    'c' => clear = true,
+                    'l' => long = true,
+                    'p' => per_line = true,
+                    'v' => verbose = true,
+                    other => {
+                        eprintln!("dirs: bad option: -{other}");
+                        eprintln!("usage: dirs [-clpv]");
+                        return Some(1);
+                    }
+                }
+            }
+        }
+
+        if clear {
+            self.stack.borrow_mut().clear();
+            return Some(0);
+        }
+
+        let entries = self
+            .stack
+            .borrow()
+            .entries(&self.environment.borrow(), long);
+        if verbose {
+            for (index, dir) in entries.iter().enumerate() {
+                let _ = writeln!(out, "{index:2}  {dir}");
+            }
+        } else if per_line {
+            for dir in &entries {
+                let _ = writeln!(out, "{dir}");
+            }
+        } else {
+            let _ = writeln!(out, "{}", entries.join(" "));
+        }
+        Some(0)
+    }
+}
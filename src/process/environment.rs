@@ -0,0 +1,143 @@
+//! Variable and working-directory storage shared by builtins and spawned commands.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::rc::Rc;
+
+/// Shared handle so every builtin observes the same environment.
+pub type EnvironmentHandle = Rc<RefCell<ShellEnvironment>>;
+
+/// Where the shell reads and writes variables such as `PWD` and its working directory.
+#[derive(Debug, Clone, Default)]
+pub enum ShellEnvironment {
+    /// Operate directly on the process environment (normal CLI use).
+    #[default]
+    Process,
+    /// Keep a private copy so an embedding host never sees shell state changes.
+    Isolated {
+        vars: HashMap<String, String>,
+        cwd: PathBuf,
+    },
+}
+
+impl ShellEnvironment {
+    /// Snapshot the current process variables and directory into an isolated environment.
+    pub fn isolated() -> Self {
+        let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+        ShellEnvironment::Isolated {
+            vars: env::vars().collect(),
+            cwd,
+        }
+    }
+
+    /// Wrap the environment in a shareable handle.
+    pub fn into_handle(self) -> EnvironmentHandle {
+        Rc::new(RefCell::new(self))
+    }
+
+    #[allow(dead_code)]
+    pub fn is_isolated(&self) -> bool {
+        matches!(self, ShellEnvironment::Isolated { .. })
+    }
+
+    pub fn var(&self, key: &str) -> Option<String> {
+        match self {
+            ShellEnvironment::Process => env::var(key).ok(),
+            ShellEnvironment::Isolated { vars, .. } => vars.get(key).cloned(),
+        }
+    }
+
+    pub fn set_var(&mut self, key: &str, value: impl Into<String>) {
+        match self {
+            ShellEnvironment::Process => unsafe {
+                env::set_var(key, value.into());
+            },
+            ShellEnvironment::Isolated { vars, .. } => {
+                vars.insert(key.to_string(), value.into());
+            }
+        }
+    }
+
+    pub fn current_dir(&self) -> Result<PathBuf, Error> {
+        match self {
+            ShellEnvironment::Process => env::current_dir(),
+            ShellEnvironment::Isolated { cwd, .. } => Ok(cwd.clone()),
+        }
+    }
+
+    /// Change directory, resolving relative paths against the current directory.
+    ///
+    /// Isolated environments store the physical (symlink-free) path, mirroring what
+    /// `env::current_dir` reports after a real `chdir`.
+    pub fn set_current_dir(&mut self, path: &Path) -> Result<(), Error> {
+        match self {
+            ShellEnvironment::Process => env::set_current_dir(path),
+            ShellEnvironment::Isolated { cwd, .. } => {
+                let physical = cwd.join(path).canonicalize()?;
+                if !fs::metadata(&physical)?.is_dir() {
+                    return Err(Error::from(ErrorKind::NotADirectory));
+                }
+                // Entering a directory needs search permission, which `stat` on `dir/.` checks.
+                fs::metadata(physical.join("."))?;
+                *cwd = physical;
+                Ok(())
+            }
+        }
+    }
+
+    /// Pass the environment to a child process; process mode simply inherits.
+    pub fn configure(&self, command: &mut Command) {
+        if let ShellEnvironment::Isolated { vars, cwd } = self {
+            command.env_clear().envs(vars).current_dir(cwd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn isolated_variables_do_not_touch_process_env() {
+        let key = "IRIDIUM_ISOLATED_ENV_TEST";
+        let mut environment = ShellEnvironment::isolated();
+        environment.set_var(key, "inside");
+
+        assert_eq!(environment.var(key).as_deref(), Some("inside"));
+        assert!(env::var(key).is_err());
+    }
+
+    #[test]
+    fn isolated_directory_changes_stay_private() {
+        let temp = TempDir::new().unwrap();
+        let original = env::current_dir().unwrap();
+        let mut environment = ShellEnvironment::isolated();
+
+        environment.set_current_dir(temp.path()).unwrap();
+
+        assert_eq!(
+            environment.current_dir().unwrap(),
+            temp.path().canonicalize().unwrap()
+        );
+        assert_eq!(env::current_dir().unwrap(), original);
+    }
+
+    #[test]
+    fn isolated_directory_change_rejects_missing_and_file_targets() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("file.txt");
+        fs::write(&file, "contents").unwrap();
+        let mut environment = ShellEnvironment::isolated();
+
+        let missing = environment.set_current_dir(&temp.path().join("missing"));
+        assert_eq!(missing.unwrap_err().kind(), ErrorKind::NotFound);
+        let not_dir = environment.set_current_dir(&file);
+        assert_eq!(not_dir.unwrap_err().kind(), ErrorKind::NotADirectory);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::process::builtin::Builtin;
+use std::io::Write;
 
 /// Sentinel exit code used to signal the control loop to terminate.
 pub const EXIT_CODE: i32 = 1000;
@@ -11,6 +12,11 @@ impl Builtin for Exit {
     fn call(&mut self, _args: &[String]) -> Option<i32> {
         Some(EXIT_CODE)
     }
+
+    /// `exit` prints nothing, so `out` is left untouched.
+    fn call_with_output(&mut self, args: &[String], _out: &mut dyn Write) -> Option<i32> {
+        self.call(args)
+    }
 }
 
 impl Exit {
@@ -1,4 +1,5 @@
 use crate::process::builtin::Builtin;
+use std::io::Write;
 
 /// Builtin that prints contextual help for the shell.
 pub struct Help {}
@@ -8,6 +9,11 @@ impl Builtin for Help {
     fn call(&mut self, _args: &[String]) -> Option<i32> {
         Some(0)
     }
+
+    /// Help has no content yet, so nothing is written to `out`.
+    fn call_with_output(&mut self, args: &[String], _out: &mut dyn Write) -> Option<i32> {
+        self.call(args)
+    }
 }
 
 impl Help {
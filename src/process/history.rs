@@ -1,10 +1,15 @@
+use crate::complete::history::parse_history_command;
 use crate::process::builtin::Builtin;
 use rev_lines::RevLines;
+use std::cell::RefCell;
 use std::env;
+use std::ffi::OsString;
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Once;
 
 #[cfg(windows)]
 /// Platform-specific newline used when persisting history entries.
@@ -13,13 +18,23 @@ const LINE_ENDING: &'static str = "\r\n";
 /// Platform-specific newline used when persisting history entries.
 const LINE_ENDING: &'static str = "\n";
 
+/// Most recent commands `history` lists and `!N` numbers.
+pub const HISTORY_LIST_LIMIT: usize = 1000;
+
 /// Implements the `history` builtin which prints recent commands.
-pub struct History {}
+///
+/// `history [-r] [substring]` lists at most the last 1000 commands, optionally keeping only those
+/// whose command text contains `substring` and printing newest first with `-r`.
+pub struct History {
+    path: Option<PathBuf>,
+    output: HistoryOutput,
+}
 
 impl Builtin for History {
-    /// Dump at most the last 1000 persisted commands to stdout.
-    fn call(&mut self, _args: &[String]) -> Option<i32> {
-        let file = match File::open(history_file_path()) {
+    /// Dump the (optionally filtered) persisted commands to stdout.
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        let path = self.path.clone().unwrap_or_else(history_file_path);
+        let file = match File::open(path) {
             Ok(file) => file,
             Err(e) => {
                 eprintln!("Unable to read history file: {}", e);
@@ -27,38 +42,128 @@ impl Builtin for History {
             }
         };
 
-        let mut lines = lines_from_file(&file, 1000);
+        let mut reverse = false;
+        let mut terms: Vec<&str> = Vec::new();
+        for arg in args {
+            if arg == "-r" {
+                reverse = true;
+            } else {
+                terms.push(arg);
+            }
+        }
+        let filter = terms.join(" ");
+
+        let mut lines = lines_from_file(&file, HISTORY_LIST_LIMIT);
         lines.reverse();
-        for (i, line) in lines.into_iter().enumerate() {
-            let cmd: &str = line.split(":").last().unwrap();
-            println!("{} {}", i, cmd);
+        let mut entries: Vec<(usize, String)> = lines
+            .iter()
+            .filter_map(|line| parse_history_command(line))
+            .enumerate()
+            .filter(|(_, cmd)| cmd.contains(filter.as_str()))
+            .collect();
+        if reverse {
+            entries.reverse();
+        }
+
+        for (i, cmd) in entries {
+            self.output.println(&format!("{} {}", i, cmd));
         }
 
         Some(0)
     }
+
+    /// List the commands into `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let captured = Rc::new(RefCell::new(Vec::new()));
+        let previous = std::mem::replace(
+            &mut self.output,
+            HistoryOutput::Buffer(Rc::clone(&captured)),
+        );
+        let status = self.call(args);
+        self.output = previous;
+        let _ = out.write_all(&captured.borrow());
+        status
+    }
 }
 
 impl History {
     /// Construct a history builtin instance.
     pub fn new() -> Self {
-        History {}
+        History {
+            path: None,
+            output: HistoryOutput::Stdout,
+        }
+    }
+
+    /// Read entries from `path` instead of `~/.iridium_history`.
+    #[allow(dead_code)]
+    pub fn with_history_file(path: impl Into<PathBuf>) -> Self {
+        History {
+            path: Some(path.into()),
+            output: HistoryOutput::Stdout,
+        }
+    }
+
+    /// Route command output into the provided buffer (useful for tests).
+    #[allow(dead_code)]
+    pub fn capture_output_buffer(&mut self, buffer: Rc<RefCell<Vec<u8>>>) {
+        self.output = HistoryOutput::Buffer(buffer);
+    }
+}
+
+enum HistoryOutput {
+    Stdout,
+    Buffer(Rc<RefCell<Vec<u8>>>),
+}
+
+impl HistoryOutput {
+    fn println(&mut self, value: &str) {
+        match self {
+            HistoryOutput::Stdout => {
+                println!("{value}");
+            }
+            HistoryOutput::Buffer(buffer) => {
+                let mut buf = buffer.borrow_mut();
+                buf.extend_from_slice(value.as_bytes());
+                buf.push(b'\n');
+            }
+        }
     }
 }
 
 /// Append an entry to the on-disk history log, creating the file if needed.
+#[allow(dead_code)]
 pub fn append_history(timestamp: u64, status: Option<i32>, line: &str) {
-    let history_file_path = history_file_path();
+    append_history_to(&history_file_path(), timestamp, status, line);
+}
 
+/// Append an entry to the history log at `history_file_path`, creating the file if needed.
+pub fn append_history_to(
+    history_file_path: &Path,
+    timestamp: u64,
+    status: Option<i32>,
+    line: &str,
+) {
     let status_code = match status {
         Some(val) => val,
         None => 1,
     };
 
-    let mut file = OpenOptions::new()
+    let mut file = match OpenOptions::new()
         .create(true)
         .append(true)
-        .open(&history_file_path)
-        .unwrap();
+        .open(history_file_path)
+    {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!(
+                "Unable to open history file '{}': {}",
+                history_file_path.display(),
+                e
+            );
+            return;
+        }
+    };
 
     if line.ends_with(LINE_ENDING) {
         if let Err(e) = write!(file, "{}:{}:{}", timestamp, status_code, line) {
@@ -72,11 +177,90 @@ pub fn append_history(timestamp: u64, status: Option<i32>, line: &str) {
     }
 }
 
+/// Expand bash-style event designators in `line` against `entries`, oldest first.
+///
+/// `!!` is the previous command, `!N` the entry `history` lists as `N` and `!prefix` the most
+/// recent command starting with `prefix`. A `!` escaped as `\!`, inside single quotes or
+/// followed by a space, `=` or `(` is left alone. Returns `Ok(None)` when nothing was expanded
+/// and an `event not found` error naming the designator when an event does not exist.
+pub fn expand_history(line: &str, entries: &[String]) -> Result<Option<String>, String> {
+    let mut expanded = String::with_capacity(line.len());
+    let mut changed = false;
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' if !quoted => {
+                expanded.push(ch);
+                expanded.extend(chars.next());
+                continue;
+            }
+            '\'' => quoted = !quoted,
+            '!' if !quoted => {
+                let designator: String = match chars.peek() {
+                    Some('!') => chars.next().map(String::from).unwrap_or_default(),
+                    Some(next) if next.is_ascii_digit() => {
+                        std::iter::from_fn(|| chars.next_if(char::is_ascii_digit)).collect()
+                    }
+                    _ => std::iter::from_fn(|| chars.next_if(|ch| !ends_designator(*ch))).collect(),
+                };
+                if designator.is_empty() {
+                    expanded.push(ch);
+                    continue;
+                }
+
+                let event = match designator.as_str() {
+                    "!" => entries.last(),
+                    number if number.starts_with(|ch: char| ch.is_ascii_digit()) => number
+                        .parse()
+                        .ok()
+                        .and_then(|index: usize| entries.get(index)),
+                    prefix => entries.iter().rev().find(|entry| entry.starts_with(prefix)),
+                };
+                let event = event.ok_or_else(|| format!("!{designator}: event not found"))?;
+                expanded.push_str(event);
+                changed = true;
+                continue;
+            }
+            _ => {}
+        }
+        expanded.push(ch);
+    }
+
+    Ok(changed.then_some(expanded))
+}
+
+/// Whether `ch` ends a `!prefix` designator (or, straight after `!`, leaves the `!` literal).
+fn ends_designator(ch: char) -> bool {
+    ch.is_whitespace()
+        || matches!(
+            ch,
+            '=' | '(' | ')' | ';' | '|' | '&' | '<' | '>' | '"' | '\''
+        )
+}
+
 /// Return the fully qualified path to the shell history file.
+///
+/// Falls back to the system temp directory, warning once, when `HOME` is unset.
 pub fn history_file_path() -> PathBuf {
-    let home =
-        env::var("HOME").expect("Expected HOME environment variable to be set, aborting now.");
-    Path::new(&home).join(".iridium_history")
+    static HOME_WARNING: Once = Once::new();
+    let home = env::var_os("HOME").filter(|home| !home.is_empty());
+    if home.is_none() {
+        HOME_WARNING.call_once(|| {
+            eprintln!(
+                "Warning: HOME is not set; keeping history in {}",
+                env::temp_dir().display()
+            );
+        });
+    }
+    history_path_for_home(home)
+}
+
+/// Resolve the history file under `home`, or under the temp directory without one.
+fn history_path_for_home(home: Option<OsString>) -> PathBuf {
+    let base = home.map(PathBuf::from).unwrap_or_else(env::temp_dir);
+    Path::new(&base).join(".iridium_history")
 }
 
 // Need to clean this up... very rough impl
@@ -99,3 +283,80 @@ fn lines_from_file(file: &File, limit: usize) -> Vec<String> {
     }
     return vec;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_path_uses_home_when_set() {
+        assert_eq!(
+            history_path_for_home(Some(OsString::from("/home/user"))),
+            PathBuf::from("/home/user/.iridium_history")
+        );
+    }
+
+    fn entries() -> Vec<String> {
+        ["ls -la", "git status", "echo one", "git log"]
+            .map(String::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn expands_previous_numbered_and_prefix_events() {
+        let entries = entries();
+        assert_eq!(
+            expand_history("!!", &entries),
+            Ok(Some("git log".to_string()))
+        );
+        assert_eq!(
+            expand_history("sudo !!", &entries),
+            Ok(Some("sudo git log".to_string()))
+        );
+        assert_eq!(
+            expand_history("!0 /tmp", &entries),
+            Ok(Some("ls -la /tmp".to_string()))
+        );
+        assert_eq!(
+            expand_history("!git", &entries),
+            Ok(Some("git log".to_string()))
+        );
+        assert_eq!(
+            expand_history("!ec; !ls", &entries),
+            Ok(Some("echo one; ls -la".to_string()))
+        );
+    }
+
+    #[test]
+    fn escaped_quoted_and_bare_bangs_are_left_alone() {
+        let entries = entries();
+        assert_eq!(expand_history("echo hi", &entries), Ok(None));
+        assert_eq!(expand_history("echo \\!!", &entries), Ok(None));
+        assert_eq!(expand_history("echo '!!'", &entries), Ok(None));
+        assert_eq!(expand_history("echo ! x!=y", &entries), Ok(None));
+    }
+
+    #[test]
+    fn missing_events_are_reported() {
+        assert_eq!(
+            expand_history("!!", &[]),
+            Err("!!: event not found".to_string())
+        );
+        assert_eq!(
+            expand_history("!9", &entries()),
+            Err("!9: event not found".to_string())
+        );
+        assert_eq!(
+            expand_history("!cargo", &entries()),
+            Err("!cargo: event not found".to_string())
+        );
+    }
+
+    #[test]
+    fn history_path_falls_back_to_temp_dir_without_home() {
+        assert_eq!(
+            history_path_for_home(None),
+            env::temp_dir().join(".iridium_history")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Background jobs started by ending a command line with `&`, and the `jobs` builtin.
+
+use super::builtin::Builtin;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::process::Child;
+use std::rc::Rc;
+
+/// Shared handle so command execution and the prompt see the same job table.
+pub type JobsHandle = Rc<RefCell<Jobs>>;
+
+/// A command running in the background.
+#[derive(Debug)]
+pub struct Job {
+    /// Number printed as `[N]` when the job starts.
+    pub id: usize,
+    pub pid: u32,
+    /// The command line that started the job, without the trailing `&`.
+    pub command: String,
+    child: Child,
+}
+
+/// Background jobs that have not yet been seen to finish, oldest first.
+#[derive(Debug, Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+}
+
+impl Jobs {
+    /// Wrap the table in a shareable handle.
+    pub fn into_handle(self) -> JobsHandle {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// Track `child` as a new job, returning its job number.
+    ///
+    /// Numbers continue from the highest running job, so they restart at 1 once every job has
+    /// been reaped.
+    pub fn add(&mut self, child: Child, command: impl Into<String>) -> usize {
+        let id = self.jobs.last().map_or(1, |job| job.id + 1);
+        self.jobs.push(Job {
+            id,
+            pid: child.id(),
+            command: command.into(),
+            child,
+        });
+        id
+    }
+
+    /// Collect every job that has exited, returning each with its exit status.
+    ///
+    /// A job killed by a signal has no status.
+    pub fn reap(&mut self) -> Vec<(Job, Option<i32>)> {
+        let mut finished = Vec::new();
+        let mut index = 0;
+        while index < self.jobs.len() {
+            match self.jobs[index].child.try_wait() {
+                Ok(Some(status)) => finished.push((self.jobs.remove(index), status.code())),
+                // A job that cannot be polled is dropped rather than retried forever.
+                Err(_) => finished.push((self.jobs.remove(index), None)),
+                Ok(None) => index += 1,
+            }
+        }
+        finished
+    }
+
+    /// Reap finished jobs, writing a `[N]  Done  command` line for each to `out`.
+    pub fn report_finished(&mut self, out: &mut dyn Write) {
+        for (job, status) in self.reap() {
+            let _ = writeln!(out, "[{}]  {}\t{}", job.id, describe(status), job.command);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+/// How a finished job is reported: `Done`, `Exit N`, or `Terminated` when a signal ended it.
+fn describe(status: Option<i32>) -> String {
+    match status {
+        Some(0) => "Done".to_string(),
+        Some(code) => format!("Exit {code}"),
+        None => "Terminated".to_string(),
+    }
+}
+
+/// The `jobs` builtin.
+///
+/// # Synopsis
+/// jobs [-lp]
+///
+/// # Description
+/// Report background jobs that have finished since they were last reported, then list those
+/// still running, oldest first.
+///
+/// # Options
+/// - `-l` includes each job's process id.
+/// - `-p` prints only the process ids of running jobs.
+pub struct JobsBuiltin {
+    jobs: JobsHandle,
+}
+
+impl JobsBuiltin {
+    pub fn new(jobs: JobsHandle) -> Self {
+        Self { jobs }
+    }
+}
+
+impl Builtin for JobsBuiltin {
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Write the job listing to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let (mut long, mut pids) = (false, false);
+        for arg in args {
+            let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+                eprintln!("jobs: {arg}: no such job");
+                return Some(1);
+            };
+            for flag in flags.chars() {
+                match flag {
+                    'l' => long = true,
+                    'p' => pids = true,
+                    other => {
+                        eprintln!("jobs: bad option: -{other}");
+                        eprintln!("usage: jobs [-lp]");
+                        return Some(2);
+                    }
+                }
+            }
+        }
+
+        let mut jobs = self.jobs.borrow_mut();
+        if !pids {
+            jobs.report_finished(out);
+        }
+        for job in jobs.iter() {
+            let _ = if pids {
+                writeln!(out, "{}", job.pid)
+            } else if long {
+                writeln!(out, "[{}]  {} Running\t{}", job.id, job.pid, job.command)
+            } else {
+                writeln!(out, "[{}]  Running\t{}", job.id, job.command)
+            };
+        }
+        Some(0)
+    }
+}
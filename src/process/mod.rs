@@ -3,44 +3,879 @@
 pub mod alias;
 pub mod builtin;
 pub mod cd;
+pub mod compress;
+pub mod date;
+pub mod dirs;
+pub mod environment;
 pub mod exit;
 pub mod help;
 pub mod history;
+pub mod jobs;
+pub mod options;
 pub mod pushd;
 pub mod pwd;
+pub mod set;
 pub mod r#type;
+pub mod umask;
 pub mod welcome;
 pub mod which;
 use crate::process::builtin::map::BuiltinMap;
-use std::process::Command;
+use crate::process::environment::ShellEnvironment;
+use crate::process::jobs::JobsHandle;
+use crate::process::options::ShellOptions;
+use crate::store::buffer_store::BufferStore;
+use std::fs::{File, OpenOptions};
+use std::io::{self, PipeReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Output redirection requested by `> file`, `>| file` or `>> file`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Redirect {
+    path: PathBuf,
+    /// Set by `>|`, which overwrites the target even under `noclobber`.
+    force: bool,
+    /// Set by `>>`, which appends to the target instead of truncating it.
+    append: bool,
+}
+
+/// Redirections split out of a command's argv; the last of each kind wins.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Redirections {
+    output: Option<Redirect>,
+    /// File named by `< file`, read as the command's standard input.
+    input: Option<PathBuf>,
+}
+
+/// Words the shell reads as operators when they appear unquoted.
+const OPERATORS: [&str; 8] = ["&&", "||", "|", "&", ">", ">|", ">>", "<"];
+
+/// Prefix marking a quoted word spelled like an operator, so `grep '|' file` passes `|` on as
+/// an argument. NUL cannot occur in an argument, so the mark never collides with real text; it
+/// is removed when argv is built.
+const QUOTED_MARK: char = '\0';
+
+/// Split `line` into words with shell quoting rules, or `None` when a quote is left open.
+///
+/// Quoted words that read as operators (`'|'`, `"&&"`, `\>`) are marked so that `split_list`,
+/// pipelines and redirections treat them as plain arguments.
+pub fn split_words(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    for raw in raw_words(line) {
+        let quoted = raw.contains(['\'', '"', '\\']);
+        for word in shlex::split(raw)? {
+            match quoted && OPERATORS.contains(&word.as_str()) {
+                true => words.push(format!("{QUOTED_MARK}{word}")),
+                false => words.push(word),
+            }
+        }
+    }
+    Some(words)
+}
+
+/// The words of `line` as typed, split on whitespace outside quotes and stopping at a comment.
+fn raw_words(line: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, ch) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match (quote, ch) {
+            (Some('\''), '\'') | (Some('"'), '"') => quote = None,
+            (Some('"'), '\\') | (None, '\\') => escaped = true,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(ch),
+            (None, _) if ch.is_whitespace() => {
+                if let Some(begin) = start.take() {
+                    words.push(&line[begin..index]);
+                }
+                continue;
+            }
+            (None, '#') if start.is_none() => return words,
+            (None, _) => {}
+        }
+        start.get_or_insert(index);
+    }
+    if let Some(begin) = start {
+        words.push(&line[begin..]);
+    }
+    words
+}
+
+/// `arg` with the mark [`split_words`] puts on quoted operators removed.
+fn unmarked(arg: &str) -> &str {
+    arg.strip_prefix(QUOTED_MARK).unwrap_or(arg)
+}
+
+/// Operator joining a command to the one before it in an `&&`/`||` list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connector {
+    /// `&&`: run only when the previous command succeeded.
+    And,
+    /// `||`: run only when the previous command failed.
+    Or,
+}
+
+/// One command of an `&&`/`||` list with the operator before it; the first has none.
+pub type ListCommand<'a> = (Option<Connector>, &'a [String]);
+
+/// Split `tokens` on `&&` and `||` into commands, each paired with the operator before it.
+///
+/// Returns an error naming the operator when one has no command on either side.
+pub fn split_list(tokens: &[String]) -> Result<Vec<ListCommand<'_>>, String> {
+    let mut commands = Vec::new();
+    let mut connector = None;
+    let mut start = 0;
+    for (index, token) in tokens.iter().enumerate() {
+        let next = match token.as_str() {
+            "&&" => Connector::And,
+            "||" => Connector::Or,
+            _ => continue,
+        };
+        if index == start || index + 1 == tokens.len() {
+            return Err(format!("syntax error near unexpected token '{token}'"));
+        }
+        commands.push((connector, &tokens[start..index]));
+        connector = Some(next);
+        start = index + 1;
+    }
+    commands.push((connector, &tokens[start..]));
+    Ok(commands)
+}
+
+/// Run the `&&`/`||` list `tokens`, passing each command the statuses so far allow to `run`.
+///
+/// As in POSIX shells the operators have equal precedence and associate left: a skipped
+/// command leaves the status of the last one run in place, and that status is returned. The
+/// list stops early once a command asks the shell to exit.
+pub fn execute_list(
+    tokens: &[String],
+    mut run: impl FnMut(&[String]) -> Option<i32>,
+) -> Option<i32> {
+    let commands = match split_list(tokens) {
+        Ok(commands) => commands,
+        Err(message) => {
+            eprintln!("iridium: {message}");
+            return Some(2);
+        }
+    };
+
+    let mut status = Some(0);
+    for (connector, command) in commands {
+        let runs = match connector {
+            None => true,
+            Some(Connector::And) => status == Some(0),
+            Some(Connector::Or) => status != Some(0),
+        };
+        if runs {
+            status = run(command);
+            if status == Some(exit::EXIT_CODE) {
+                break;
+            }
+        }
+    }
+    status
+}
 
 /// Execute a command, dispatching to builtins or spawning external processes.
-pub fn execute(builtin_map: &BuiltinMap, args: &Vec<String>) -> Option<i32> {
-    if args.len() == 0 {
+pub fn execute(builtin_map: &BuiltinMap, args: &[String]) -> Option<i32> {
+    run(builtin_map, args, None)
+}
+
+/// Execute a command like [`execute`], writing its standard output to `out`.
+///
+/// Used when the caller needs the output rather than having it appear on the process' stdout,
+/// such as a builtin starting a pipeline. An explicit `> file` redirection still wins.
+pub fn execute_with_output(
+    builtin_map: &BuiltinMap,
+    args: &[String],
+    out: &mut dyn Write,
+) -> Option<i32> {
+    run(builtin_map, args, Some(out))
+}
+
+/// Run `args`, sending its standard output to `output` or, without one, to the shell's stdout.
+fn run(
+    builtin_map: &BuiltinMap,
+    args: &[String],
+    mut output: Option<&mut dyn Write>,
+) -> Option<i32> {
+    if args.is_empty() {
         return Some(0);
     }
 
+    if args.last().is_some_and(|arg| arg == "&") {
+        let args = &args[..args.len() - 1];
+        return match output {
+            Some(out) => run_in_background(builtin_map, args, out),
+            None => run_in_background(builtin_map, args, &mut io::stdout()),
+        };
+    }
+
+    if args.iter().any(|arg| arg == "|") {
+        return run_pipeline(builtin_map, args, output);
+    }
+
     // Determine if command is builtin, and call function
-    if let Some(result) = builtin_map.invoke(&args[0], &args[1..]) {
+    let sink = output.as_mut().map(|out| &mut **out as &mut dyn Write);
+    if let Some(result) = run_builtin(builtin_map, args, sink) {
         return result;
     }
 
     // Attempt to exec external process
-    launch(&args)
+    let environment = builtin_map.environment();
+    let options = *builtin_map.options().borrow();
+    launch(args, &environment.borrow(), &options, output)
 }
 
-/// Spawn a child process for external commands and wait for its exit status.
-fn launch(args: &Vec<String>) -> Option<i32> {
-    let res = Command::new(&args[0]).args(&args[1..]).spawn();
+/// Pipe `input` through the external command `args`, returning the lines it prints.
+///
+/// This is the `:b -p` (vim's `:%!`) filter. A command that cannot be started or exits with a
+/// non-zero status yields an error describing why, so callers can leave their text untouched.
+pub fn filter_lines(
+    args: &[String],
+    environment: &ShellEnvironment,
+    input: &[String],
+) -> Result<Vec<String>, String> {
+    let Some(program) = args.first() else {
+        return Err("no filter command given".to_string());
+    };
 
-    let mut child = match res {
-        Ok(child) => child,
-        Err(_e) => {
-            eprintln!("iridium: command not found: {}", &args[0]);
-            return None;
+    let mut command = Command::new(program);
+    command
+        .args(&args[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped());
+    environment.configure(&mut command);
+    let mut child = command
+        .spawn()
+        .map_err(|_| format!("command not found: {program}"))?;
+
+    // Feed stdin from another thread so a filter that writes before it finishes reading cannot
+    // fill its stdout pipe and deadlock against us.
+    let mut stdin = child.stdin.take().expect("child stdin was piped");
+    let text: String = input.iter().map(|line| format!("{line}\n")).collect();
+    let writer = std::thread::spawn(move || stdin.write_all(text.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .map_err(|err| format!("{program}: {err}"))?;
+    // A filter may exit without draining its input; that is not an error on our side.
+    let _ = writer.join();
+
+    if !output.status.success() {
+        return Err(match output.status.code() {
+            Some(code) => format!("{program}: exited with status {code}"),
+            None => format!("{program}: terminated by signal"),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Start the external command `args` without waiting for it, recording it as a job and
+/// writing `[N] <pid>` to `out`.
+///
+/// Builtins run in the shell itself, so they are run in the foreground as if `&` were absent.
+/// Background pipelines are not supported yet.
+fn run_in_background(
+    builtin_map: &BuiltinMap,
+    args: &[String],
+    out: &mut dyn Write,
+) -> Option<i32> {
+    if args.is_empty() {
+        eprintln!("iridium: syntax error near unexpected token '&'");
+        return Some(2);
+    }
+    if args.iter().any(|arg| arg == "|") {
+        eprintln!("iridium: pipelines cannot be run in the background");
+        return Some(2);
+    }
+    if let Some(result) = run_builtin(builtin_map, args, Some(out)) {
+        return result;
+    }
+
+    // Finished jobs are collected first so their numbers can be reused.
+    let jobs = builtin_map.jobs();
+    jobs.borrow_mut().report_finished(out);
+    let environment = builtin_map.environment();
+    let options = *builtin_map.options().borrow();
+    launch_background(args, &environment.borrow(), &options, &jobs, out)
+}
+
+/// Run `args` as a pipeline, connecting each stage's stdout to the next stage's stdin.
+///
+/// The status is that of the last stage, as in POSIX shells. A builtin may only start a
+/// pipeline: it runs in the shell itself and its captured output feeds the second stage.
+/// Builtins read no input, so one in any later stage is rejected. Only the last stage may
+/// redirect its output with `> file` or `>> file`, and only the first may read `< file`.
+///
+/// A `tee @name` stage fills buffer `name` with its input as it streams past and passes that
+/// input on, as `ls | tee @listing | wc -l` does; it runs in the shell, like a builtin.
+fn run_pipeline(
+    builtin_map: &BuiltinMap,
+    args: &[String],
+    output: Option<&mut dyn Write>,
+) -> Option<i32> {
+    let stages: Vec<&[String]> = args.split(|arg| arg == "|").collect();
+    if stages.iter().any(|stage| stage.is_empty()) {
+        eprintln!("iridium: syntax error near unexpected token '|'");
+        return Some(2);
+    }
+    if let Some(stage) = stages[1..]
+        .iter()
+        .find(|stage| builtin_map.contains(&stage[0]))
+    {
+        eprintln!("iridium: {}: builtins can only start a pipeline", stage[0]);
+        return Some(2);
+    }
+
+    let mut externals = &stages[..];
+    let mut input = None;
+    if builtin_map.contains(&stages[0][0]) {
+        let mut captured = Vec::new();
+        execute_with_output(builtin_map, stages[0], &mut captured);
+        input = Some(captured);
+        externals = &stages[1..];
+    }
+
+    let environment = builtin_map.environment();
+    let options = *builtin_map.options().borrow();
+    let buffers = builtin_map.buffer_store();
+    launch_pipeline(
+        externals,
+        &environment.borrow(),
+        &options,
+        buffers.as_ref(),
+        input,
+        output,
+    )
+}
+
+/// The buffer named by a `tee @name` stage, which copies its input into that buffer.
+fn buffer_tee_target(stage: &[String]) -> Option<&str> {
+    match stage {
+        [tee, target] if tee == "tee" => target.strip_prefix('@').filter(|name| !name.is_empty()),
+        _ => None,
+    }
+}
+
+/// Open buffer `name` for a `tee @name` stage, refusing read-only buffers.
+fn open_tee_buffer(
+    buffers: Option<&Arc<Mutex<BufferStore>>>,
+    name: &str,
+) -> Result<Arc<Mutex<BufferStore>>, String> {
+    let Some(buffers) = buffers else {
+        return Err(format!("@{name}: no buffers are available"));
+    };
+    let mut store = buffers.lock().expect("buffer store lock poisoned");
+    store.try_open(name).map_err(|err| err.to_string())?;
+    if store.is_read_only(name) {
+        return Err(format!("@{name}: buffer is read-only"));
+    }
+    Ok(Arc::clone(buffers))
+}
+
+/// Replace buffer `name` with what streams from `reader` to `writer`, chunk by chunk, appending
+/// each complete line as it arrives. The whole tee is a single undo step.
+///
+/// Reading stops once `writer` is closed, e.g. by a `head` downstream, so the stages feeding
+/// the tee see a broken pipe instead of running forever.
+fn stream_into_buffer(
+    buffers: &Mutex<BufferStore>,
+    name: &str,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) {
+    {
+        let mut store = buffers.lock().expect("buffer store lock poisoned");
+        store.begin_undo_group(name);
+        store.replace_lines(name, Vec::new());
+    }
+    let mut chunk = [0u8; 8192];
+    let mut partial = Vec::new();
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => break,
+        };
+        partial.extend_from_slice(&chunk[..read]);
+        if let Some(end) = partial.iter().rposition(|&byte| byte == b'\n') {
+            let complete: Vec<u8> = partial.drain(..=end).collect();
+            append_to_buffer(buffers, name, &complete);
+        }
+        if writer.write_all(&chunk[..read]).is_err() {
+            break;
+        }
+    }
+    append_to_buffer(buffers, name, &partial);
+    buffers
+        .lock()
+        .expect("buffer store lock poisoned")
+        .end_undo_group(name);
+}
+
+fn append_to_buffer(buffers: &Mutex<BufferStore>, name: &str, bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    let lines: Vec<String> = String::from_utf8_lossy(bytes)
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let mut store = buffers.lock().expect("buffer store lock poisoned");
+    let end = store.line_count(name);
+    store.insert_lines(name, end, &lines);
+}
+
+/// Run `args` as a builtin when it names one, applying its redirections first.
+///
+/// A `> file` redirection receives the builtin's output in place of `output` (or stdout); a
+/// `< file` source must exist but is not read, as no builtin takes input. Returns `None` when
+/// `args` does not name a builtin.
+fn run_builtin(
+    builtin_map: &BuiltinMap,
+    args: &[String],
+    output: Option<&mut dyn Write>,
+) -> Option<Option<i32>> {
+    if !builtin_map.contains(&args[0]) {
+        return None;
+    }
+    let (argv, redirections) = match parse_redirection(args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("iridium: {message}");
+            return Some(Some(2));
         }
     };
 
+    // The environment borrow ends here, as builtins such as `cd` update it.
+    let mut file = {
+        let environment = builtin_map.environment();
+        let environment = environment.borrow();
+        let options = *builtin_map.options().borrow();
+        if let Some(path) = &redirections.input
+            && let Err(message) = open_input(path, &environment)
+        {
+            eprintln!("{message}");
+            return Some(Some(1));
+        }
+        match &redirections.output {
+            Some(redirect) => match open_redirect(redirect, &environment, &options) {
+                Ok(file) => Some(file),
+                Err(message) => {
+                    eprintln!("{message}");
+                    return Some(Some(1));
+                }
+            },
+            None => None,
+        }
+    };
+
+    match (file.as_mut(), output) {
+        (Some(file), _) => builtin_map.invoke_with_output(&argv[0], &argv[1..], file),
+        (None, Some(out)) => builtin_map.invoke_with_output(&argv[0], &argv[1..], out),
+        (None, None) => builtin_map.invoke(&argv[0], &argv[1..]),
+    }
+}
+
+/// One stage of a pipeline, ready to start.
+enum Stage {
+    Command(Command, String),
+    /// A `tee @name` stage, copying its input into the buffer store as well as on.
+    Tee(Arc<Mutex<BufferStore>>, String),
+}
+
+/// What a started stage hands to the next one.
+enum StageOutput {
+    Child(ChildStdout),
+    Tee(PipeReader),
+}
+
+impl StageOutput {
+    fn into_reader(self) -> Box<dyn Read + Send> {
+        match self {
+            StageOutput::Child(stdout) => Box::new(stdout),
+            StageOutput::Tee(reader) => Box::new(reader),
+        }
+    }
+}
+
+impl From<StageOutput> for Stdio {
+    fn from(output: StageOutput) -> Self {
+        match output {
+            StageOutput::Child(stdout) => stdout.into(),
+            StageOutput::Tee(reader) => reader.into(),
+        }
+    }
+}
+
+/// A started stage, waited for once every stage is running.
+enum Running {
+    Child(Child),
+    Tee(JoinHandle<()>),
+    /// A command that could not be started.
+    Failed,
+}
+
+/// Spawn every stage of a pipeline and wait for all of them.
+///
+/// `input` is written to the first stage's stdin when a builtin produced it. Every stage is
+/// parsed and its redirections or `tee @name` buffer opened before anything is spawned, so a bad stage fails the whole pipeline without leaving children behind. A stage
+/// that cannot be started is reported and the next stage reads an empty stdin instead.
+fn launch_pipeline(
+    stages: &[&[String]],
+    environment: &ShellEnvironment,
+    options: &ShellOptions,
+    buffers: Option<&Arc<Mutex<BufferStore>>>,
+    mut input: Option<Vec<u8>>,
+    output: Option<&mut dyn Write>,
+) -> Option<i32> {
+    let last = stages.len() - 1;
+    let mut output = output;
+    let mut pipeline = Vec::with_capacity(stages.len());
+
+    for (index, stage) in stages.iter().enumerate() {
+        if let Some(name) = buffer_tee_target(stage) {
+            match open_tee_buffer(buffers, name) {
+                Ok(store) => pipeline.push(Stage::Tee(store, name.to_string())),
+                Err(message) => {
+                    eprintln!("iridium: tee: {message}");
+                    return Some(1);
+                }
+            }
+            continue;
+        }
+        let (argv, redirections) = match parse_redirection(stage) {
+            Ok(parsed) => parsed,
+            Err(message) => {
+                eprintln!("iridium: {message}");
+                return Some(2);
+            }
+        };
+        if argv.is_empty()
+            || (redirections.output.is_some() && index != last)
+            || (redirections.input.is_some() && (index != 0 || input.is_some()))
+        {
+            eprintln!(
+                "iridium: syntax error: only the first pipeline stage may redirect input and \
+                 only the last may redirect output"
+            );
+            return Some(2);
+        }
+
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]);
+        environment.configure(&mut command);
+        if let Some(path) = &redirections.input {
+            match open_input(path, environment) {
+                Ok(file) => {
+                    command.stdin(file);
+                }
+                Err(message) => {
+                    eprintln!("{message}");
+                    return Some(1);
+                }
+            }
+        } else if index == 0 && input.is_some() {
+            command.stdin(Stdio::piped());
+        }
+        if index != last {
+            command.stdout(Stdio::piped());
+        } else if let Some(redirect) = &redirections.output {
+            match open_redirect(redirect, environment, options) {
+                Ok(file) => {
+                    command.stdout(file);
+                    output = None;
+                }
+                Err(message) => {
+                    eprintln!("{message}");
+                    return Some(1);
+                }
+            }
+        } else if output.is_some() {
+            command.stdout(Stdio::piped());
+        }
+        pipeline.push(Stage::Command(command, argv[0].clone()));
+    }
+
+    let mut running = Vec::with_capacity(pipeline.len());
+    let mut previous: Option<StageOutput> = None;
+    let mut writer = None;
+    for (index, stage) in pipeline.into_iter().enumerate() {
+        let (mut command, name) = match stage {
+            Stage::Command(command, name) => (command, name),
+            Stage::Tee(store, name) => {
+                let reader: Box<dyn Read + Send> = match previous.take() {
+                    Some(upstream) => upstream.into_reader(),
+                    None if index == 0 => {
+                        Box::new(io::Cursor::new(input.take().unwrap_or_default()))
+                    }
+                    None => Box::new(io::empty()),
+                };
+                let (downstream, sink) = match io::pipe() {
+                    Ok(pipe) => pipe,
+                    Err(err) => {
+                        eprintln!("iridium: tee: {err}");
+                        running.push(Running::Failed);
+                        continue;
+                    }
+                };
+                let tee =
+                    std::thread::spawn(move || stream_into_buffer(&store, &name, reader, sink));
+                previous = Some(StageOutput::Tee(downstream));
+                running.push(Running::Tee(tee));
+                continue;
+            }
+        };
+        match previous.take() {
+            Some(upstream) => {
+                command.stdin(upstream);
+            }
+            None if index > 0 => {
+                command.stdin(Stdio::null());
+            }
+            None => {}
+        }
+
+        let Ok(mut child) = command.spawn() else {
+            eprintln!("iridium: command not found: {name}");
+            running.push(Running::Failed);
+            continue;
+        };
+        if let (Some(bytes), Some(mut stdin)) = (input.take(), child.stdin.take()) {
+            // Written from another thread so a stage that prints before draining its input
+            // cannot deadlock against us.
+            writer = Some(std::thread::spawn(move || stdin.write_all(&bytes)));
+        }
+        if index != last {
+            previous = child.stdout.take().map(StageOutput::Child);
+        }
+        running.push(Running::Child(child));
+    }
+
+    // A tee that ends the pipeline passes its input on to our own output.
+    let final_output = match (previous.take(), running.last_mut()) {
+        (Some(tee), _) => Some(tee.into_reader()),
+        (None, Some(Running::Child(child))) if output.is_some() => child
+            .stdout
+            .take()
+            .map(|stdout| Box::new(stdout) as Box<dyn Read + Send>),
+        _ => None,
+    };
+    if let Some(mut reader) = final_output {
+        let copied = match output {
+            Some(out) => io::copy(&mut reader, out),
+            None => io::copy(&mut reader, &mut io::stdout()),
+        };
+        if let Err(err) = copied {
+            eprintln!("iridium: unable to capture pipeline output: {err}");
+        }
+    }
+
+    let mut status = None;
+    for stage in running {
+        status = match stage {
+            Running::Child(mut child) => Some(
+                child
+                    .wait()
+                    .expect("Failed to wait on child process, aborting now.")
+                    .code()
+                    // A stage killed by a signal (e.g. SIGPIPE) has no code; report it as failed.
+                    .unwrap_or(1),
+            ),
+            Running::Tee(tee) => {
+                let _ = tee.join();
+                Some(0)
+            }
+            Running::Failed => None,
+        };
+    }
+    if let Some(writer) = writer {
+        // The first stage may exit without reading its input; that is not an error here.
+        let _ = writer.join();
+    }
+    status
+}
+
+/// Split `> file`, `>| file`, `>> file` and `< file` operators out of `args`, returning the
+/// remaining argv.
+fn parse_redirection(args: &[String]) -> Result<(Vec<String>, Redirections), String> {
+    let mut argv = Vec::with_capacity(args.len());
+    let mut redirections = Redirections::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (force, append) = match arg.as_str() {
+            ">" => (false, false),
+            ">|" => (true, false),
+            ">>" => (false, true),
+            "<" => (false, false),
+            _ => {
+                argv.push(unmarked(arg).to_string());
+                continue;
+            }
+        };
+        let Some(target) = iter.next().map(|target| unmarked(target)) else {
+            return Err(format!("syntax error: expected a file after '{arg}'"));
+        };
+        if arg == "<" {
+            redirections.input = Some(PathBuf::from(target));
+        } else {
+            redirections.output = Some(Redirect {
+                path: PathBuf::from(target),
+                force,
+                append,
+            });
+        }
+    }
+    Ok((argv, redirections))
+}
+
+/// Resolve a redirect target against the shell's working directory.
+fn resolve_target(path: &Path, environment: &ShellEnvironment) -> PathBuf {
+    match environment.current_dir() {
+        Ok(cwd) => cwd.join(path),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Open the redirect target for writing, honouring `noclobber` unless `>|` was used.
+///
+/// Only existing regular files are protected, so `> /dev/null` keeps working. `>>` appends and
+/// never clobbers, creating the file when it is missing.
+fn open_redirect(
+    redirect: &Redirect,
+    environment: &ShellEnvironment,
+    options: &ShellOptions,
+) -> Result<File, String> {
+    let path = resolve_target(&redirect.path, environment);
+    if options.noclobber && !redirect.force && !redirect.append && path.is_file() {
+        return Err(format!(
+            "iridium: {}: cannot overwrite existing file",
+            redirect.path.display()
+        ));
+    }
+    let file = if redirect.append {
+        OpenOptions::new().append(true).create(true).open(&path)
+    } else {
+        File::create(&path)
+    };
+    file.map_err(|err| format!("iridium: {}: {err}", redirect.path.display()))
+}
+
+/// Open the `< file` source for reading.
+fn open_input(path: &Path, environment: &ShellEnvironment) -> Result<File, String> {
+    File::open(resolve_target(path, environment))
+        .map_err(|err| format!("iridium: {}: {err}", path.display()))
+}
+
+/// How [`spawn`] connects a child to the shell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placement {
+    /// Share the shell's terminal; with `capture` stdout is piped back to the shell.
+    Foreground { capture: bool },
+    /// A job started with `&`: without job control it reads `/dev/null` unless given `< file`,
+    /// and runs in its own process group so keyboard signals meant for the shell miss it.
+    Background,
+}
+
+/// Build `args` with its redirections applied and spawn it.
+///
+/// A `> file` redirection claims stdout even when `placement` captures it. When nothing is
+/// spawned the error holds the status to report.
+fn spawn(
+    args: &[String],
+    environment: &ShellEnvironment,
+    options: &ShellOptions,
+    placement: Placement,
+) -> Result<Child, Option<i32>> {
+    let (args, redirections) = match parse_redirection(args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("iridium: {message}");
+            return Err(Some(2));
+        }
+    };
+    if args.is_empty() {
+        return Err(Some(0));
+    }
+
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]);
+    environment.configure(&mut command);
+    if let Some(path) = &redirections.input {
+        match open_input(path, environment) {
+            Ok(file) => {
+                command.stdin(file);
+            }
+            Err(message) => {
+                eprintln!("{message}");
+                return Err(Some(1));
+            }
+        }
+    } else if placement == Placement::Background {
+        command.stdin(Stdio::null());
+    }
+    if placement == Placement::Background {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+    }
+    if let Some(redirect) = &redirections.output {
+        match open_redirect(redirect, environment, options) {
+            Ok(file) => {
+                command.stdout(file);
+            }
+            Err(message) => {
+                eprintln!("{message}");
+                return Err(Some(1));
+            }
+        }
+    } else if placement == (Placement::Foreground { capture: true }) {
+        command.stdout(Stdio::piped());
+    }
+
+    command.spawn().map_err(|_| {
+        eprintln!("iridium: command not found: {}", &args[0]);
+        None
+    })
+}
+
+/// Spawn a child process for external commands and wait for its exit status.
+///
+/// When `output` is given and no redirection was requested, the child's stdout is copied into it.
+fn launch(
+    args: &[String],
+    environment: &ShellEnvironment,
+    options: &ShellOptions,
+    output: Option<&mut dyn Write>,
+) -> Option<i32> {
+    let placement = Placement::Foreground {
+        capture: output.is_some(),
+    };
+    let mut child = match spawn(args, environment, options, placement) {
+        Ok(child) => child,
+        Err(status) => return status,
+    };
+
+    if let (Some(out), Some(mut stdout)) = (output, child.stdout.take())
+        && let Err(err) = io::copy(&mut stdout, out)
+    {
+        eprintln!("iridium: {}: unable to capture output: {err}", &args[0]);
+    }
+
     let ecode = child
         .wait()
         .expect("Failed to wait on child process, aborting now.");
@@ -50,3 +885,22 @@ fn launch(args: &Vec<String>) -> Option<i32> {
             .expect("Expected an exit code from spawned child process, aborting now."),
     )
 }
+
+/// Spawn `args` without waiting for it, adding it to `jobs` and writing `[N] <pid>` to `out`.
+fn launch_background(
+    args: &[String],
+    environment: &ShellEnvironment,
+    options: &ShellOptions,
+    jobs: &JobsHandle,
+    out: &mut dyn Write,
+) -> Option<i32> {
+    let child = match spawn(args, environment, options, Placement::Background) {
+        Ok(child) => child,
+        Err(status) => return status,
+    };
+    let pid = child.id();
+    let command: Vec<&str> = args.iter().map(|arg| unmarked(arg)).collect();
+    let id = jobs.borrow_mut().add(child, command.join(" "));
+    let _ = writeln!(out, "[{id}] {pid}");
+    Some(0)
+}
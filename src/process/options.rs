@@ -0,0 +1,23 @@
+//! Shell option flags toggled through `set` and the configuration file.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Shared handle so `set` and command execution observe the same flags.
+pub type OptionsHandle = Rc<RefCell<ShellOptions>>;
+
+/// Flags that change how the shell runs commands.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShellOptions {
+    /// Refuse to let `>` overwrite an existing file (`set -C`); `>|` still forces it.
+    pub noclobber: bool,
+    /// File mode creation mask last set with `umask`; `None` keeps the inherited one.
+    pub umask: Option<u32>,
+}
+
+impl ShellOptions {
+    /// Wrap the options in a shareable handle.
+    pub fn into_handle(self) -> OptionsHandle {
+        Rc::new(RefCell::new(self))
+    }
+}
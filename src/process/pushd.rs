@@ -1,19 +1,118 @@
 use crate::process::builtin::Builtin;
+use crate::process::cd::execute_cd;
+use crate::process::dirs::{DirStackHandle, current_pwd};
+use crate::process::environment::{EnvironmentHandle, ShellEnvironment};
+use std::io::{self, Write};
 
-/// Stub implementation of the `pushd` builtin.
-pub struct Pushd {}
+/// The `pushd` builtin.
+///
+/// # Synopsis
+/// pushd [dir]
+///
+/// # Description
+/// Save the current directory on the directory stack and change to `dir`. Without `dir`, swap
+/// the current directory with the one on top of the stack. The resulting stack is printed as
+/// `dirs` would.
+pub struct Pushd {
+    environment: EnvironmentHandle,
+    stack: DirStackHandle,
+}
+
+impl Pushd {
+    /// Construct a `pushd` builtin sharing `stack` with `popd` and `dirs`.
+    pub fn new(environment: EnvironmentHandle, stack: DirStackHandle) -> Self {
+        Self { environment, stack }
+    }
+}
 
 impl Builtin for Pushd {
-    /// Currently prints a placeholder message and exits successfully.
-    fn call(&mut self, _args: &[String]) -> Option<i32> {
-        println!("PUSHD!");
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Change directory and write the new stack to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let mut env = self.environment.borrow_mut();
+        let mut stack = self.stack.borrow_mut();
+        let previous = current_pwd(&env);
+
+        let (target, swapping) = match args {
+            [] => match stack.pop() {
+                Some(top) => (top, true),
+                None => {
+                    eprintln!("pushd: no other directory");
+                    return Some(1);
+                }
+            },
+            [dir] => (dir.clone(), false),
+            _ => {
+                eprintln!("pushd: too many arguments");
+                return Some(1);
+            }
+        };
+
+        if let Err(err) = change_directory(&target, &mut env) {
+            if swapping {
+                stack.push(target);
+            }
+            eprintln!("{}", err.replacen("cd:", "pushd:", 1));
+            return Some(1);
+        }
+        stack.push(previous);
+        let _ = writeln!(out, "{}", stack.entries(&env, false).join(" "));
         Some(0)
     }
 }
 
-impl Pushd {
-    /// Construct a new pushd builtin instance.
-    pub fn new() -> Self {
-        Pushd {}
+/// The `popd` builtin.
+///
+/// # Synopsis
+/// popd
+///
+/// # Description
+/// Remove the top directory from the directory stack and change to it, then print the
+/// resulting stack as `dirs` would.
+pub struct Popd {
+    environment: EnvironmentHandle,
+    stack: DirStackHandle,
+}
+
+impl Popd {
+    /// Construct a `popd` builtin sharing `stack` with `pushd` and `dirs`.
+    pub fn new(environment: EnvironmentHandle, stack: DirStackHandle) -> Self {
+        Self { environment, stack }
+    }
+}
+
+impl Builtin for Popd {
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Change to the saved directory and write the remaining stack to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        if !args.is_empty() {
+            eprintln!("popd: too many arguments");
+            return Some(1);
+        }
+
+        let mut env = self.environment.borrow_mut();
+        let mut stack = self.stack.borrow_mut();
+        let Some(target) = stack.pop() else {
+            eprintln!("popd: directory stack empty");
+            return Some(1);
+        };
+        if let Err(err) = change_directory(&target, &mut env) {
+            stack.push(target);
+            eprintln!("{}", err.replacen("cd:", "popd:", 1));
+            return Some(1);
+        }
+        let _ = writeln!(out, "{}", stack.entries(&env, false).join(" "));
+        Some(0)
     }
 }
+
+/// Change directory the way `cd` would, without printing the new path.
+fn change_directory(target: &str, env: &mut ShellEnvironment) -> Result<(), String> {
+    execute_cd(&["--".to_string(), target.to_string()], env).map(|_| ())
+}
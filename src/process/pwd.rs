@@ -1,5 +1,6 @@
 use super::builtin::Builtin;
-use std::env;
+use super::environment::{EnvironmentHandle, ShellEnvironment};
+use std::io::{self, Write};
 use std::path::Path;
 
 /// The 'pwd' built-in command follows the IEEE 1003.1-2017 standard.
@@ -36,11 +37,18 @@ use std::path::Path;
 ///
 /// If both -L and -P are specified, the last one shall apply. If neither -L nor -P is specified,
 /// the pwd utility shall behave as if -L had been specified.
-pub struct Pwd {}
+pub struct Pwd {
+    environment: EnvironmentHandle,
+}
 
 impl Builtin for Pwd {
     /// Print the current directory, resolving options according to POSIX `pwd` rules.
     fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Write the current directory to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
         let mut options: Vec<&String> = Vec::new();
 
         // Iterate through all arguments and categorize references into options and arguments
@@ -68,32 +76,30 @@ impl Builtin for Pwd {
                 Err(e) => panic!("Error canonicalizing path: {}, {}", pwd_val, e),
             };
 
-            println!("{}", pwd.to_str().unwrap());
+            let _ = writeln!(out, "{}", pwd.to_str().unwrap());
             return Some(0);
         }
 
         let pwd = self.get_pwd();
-        println!("{}", pwd);
+        let _ = writeln!(out, "{}", pwd);
         Some(0)
     }
 }
 
 impl Pwd {
     /// Construct a new `pwd` builtin.
+    #[allow(dead_code)]
     pub fn new() -> Self {
-        Pwd {}
+        Self::with_environment(ShellEnvironment::Process.into_handle())
     }
 
-    /// Return the `PWD` environment variable, canonicalised when necessary.
-    pub fn get_pwd(&self) -> String {
-        get_pwd()
+    /// Construct a `pwd` builtin that reads `PWD` from the provided environment.
+    pub fn with_environment(environment: EnvironmentHandle) -> Self {
+        Pwd { environment }
     }
-}
 
-/// Read the `PWD` environment variable, defaulting to an empty string.
-fn get_pwd() -> String {
-    match env::var("PWD") {
-        Ok(pwd) => pwd,
-        Err(_e) => String::from(""),
+    /// Return the `PWD` environment variable, defaulting to an empty string.
+    pub fn get_pwd(&self) -> String {
+        self.environment.borrow().var("PWD").unwrap_or_default()
     }
 }
@@ -0,0 +1,79 @@
+use super::builtin::Builtin;
+use super::options::OptionsHandle;
+use std::io::{self, Write};
+
+/// The 'set' built-in command, limited to the option flags the shell supports.
+///
+/// # Synopsis
+/// set [-C|+C] [-o option|+o option] ...
+///
+/// # Description
+/// `-C` (or `-o noclobber`) prevents `>` from overwriting existing files; the `+` forms clear it.
+/// Without arguments the current option state is printed.
+pub struct Set {
+    options: OptionsHandle,
+}
+
+impl Builtin for Set {
+    /// Apply each flag in order, rejecting unknown options without changing any state.
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Like `call`, writing the option listing to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        if args.is_empty() {
+            let noclobber = if self.options.borrow().noclobber {
+                "on"
+            } else {
+                "off"
+            };
+            let _ = writeln!(out, "noclobber\t{noclobber}");
+            return Some(0);
+        }
+
+        let mut updated = *self.options.borrow();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let enable = match arg.chars().next() {
+                Some('-') => true,
+                Some('+') => false,
+                _ => {
+                    eprintln!("set: unsupported argument: {arg}");
+                    return Some(2);
+                }
+            };
+            let name = match &arg[1..] {
+                "C" => "noclobber",
+                "o" => match iter.next() {
+                    Some(name) => name.as_str(),
+                    None => {
+                        eprintln!("set: {arg} requires an option name");
+                        return Some(2);
+                    }
+                },
+                _ => {
+                    eprintln!("set: bad option: {arg}");
+                    return Some(2);
+                }
+            };
+            match name {
+                "noclobber" => updated.noclobber = enable,
+                other => {
+                    eprintln!("set: unknown option: {other}");
+                    return Some(2);
+                }
+            }
+        }
+
+        *self.options.borrow_mut() = updated;
+        Some(0)
+    }
+}
+
+impl Set {
+    /// Construct a `set` builtin that updates the shared shell options.
+    pub fn with_options(options: OptionsHandle) -> Self {
+        Self { options }
+    }
+}
@@ -1,4 +1,12 @@
+use crate::process::alias::Alias;
 use crate::process::builtin::Builtin;
+use crate::process::environment::EnvironmentHandle;
+use crate::process::which::search_path;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
 
 // type [-aftpP] name [name ...]
 // -a print all the places that contain an executable named name
@@ -14,20 +22,177 @@ use crate::process::builtin::Builtin;
 // -P Forces a PATH search for each name, even if 'type -t name' would not return file.
 //      If a command is hashed, -p and -P print the hashed value, not necessarily the file that appears first in PATH.
 // With no options, indicate how each name would be interpreted if used as a command name.
-/// Stub implementation of the `type` builtin.
-pub struct Type {}
+/// Words reserved for shell syntax, reported by `type` as `keyword`.
+///
+/// These are the POSIX reserved words; names the shell keeps for its own control structures rather
+/// than looking them up as commands.
+pub const RESERVED_WORDS: &[&str] = &[
+    "!", "{", "}", "case", "do", "done", "elif", "else", "esac", "fi", "for", "if", "in", "then",
+    "until", "while",
+];
+
+/// Implementation of the `type` builtin.
+///
+/// Shell functions are not supported, so `-f` is accepted and has no effect.
+pub struct Type {
+    aliases: Option<Rc<RefCell<Alias>>>,
+    builtin_names: HashSet<String>,
+    environment: EnvironmentHandle,
+}
+
+/// One way a name could be interpreted as a command.
+enum Resolution {
+    Keyword,
+    Alias(String),
+    Builtin,
+    File(PathBuf),
+}
+
+impl Resolution {
+    /// The word `type -t` prints for this resolution.
+    fn kind(&self) -> &'static str {
+        match self {
+            Resolution::Keyword => "keyword",
+            Resolution::Alias(_) => "alias",
+            Resolution::Builtin => "builtin",
+            Resolution::File(_) => "file",
+        }
+    }
+}
+
+/// Flags accepted by `type`.
+#[derive(Default)]
+struct TypeOptions {
+    all: bool,
+    kind_only: bool,
+    path_only: bool,
+    force_path: bool,
+}
 
 impl Builtin for Type {
-    /// Currently prints a placeholder message and exits successfully.
-    fn call(&mut self, _args: &[String]) -> Option<i32> {
-        println!("TYPE!");
-        Some(0)
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Describe how each name would be interpreted, writing the descriptions to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let mut options = TypeOptions::default();
+        let mut names = args.iter().peekable();
+        while let Some(flags) = names.peek().and_then(|arg| arg.strip_prefix('-')) {
+            if flags.is_empty() {
+                break;
+            }
+            names.next();
+            if flags == "-" {
+                break;
+            }
+            for flag in flags.chars() {
+                match flag {
+                    'a' => options.all = true,
+                    't' => options.kind_only = true,
+                    'f' => {}
+                    'p' => options.path_only = true,
+                    'P' => options.force_path = true,
+                    other => {
+                        eprintln!("type: -{other}: invalid option");
+                        eprintln!("type: usage: type [-aftpP] name [name ...]");
+                        return Some(2);
+                    }
+                }
+            }
+        }
+
+        let mut status = 0;
+        for name in names {
+            let resolutions = self.resolve(name, &options);
+            if resolutions.is_empty() {
+                if !(options.kind_only || options.path_only || options.force_path) {
+                    eprintln!("type: {name}: not found");
+                }
+                status = 1;
+                continue;
+            }
+            for resolution in &resolutions {
+                Self::describe(name, resolution, &options, out);
+            }
+        }
+        Some(status)
     }
 }
 
 impl Type {
-    /// Construct a new type builtin instance.
-    pub fn new() -> Self {
-        Type {}
+    /// Construct a `type` builtin that searches the `PATH` of `environment` and can later be
+    /// wired with dependencies.
+    pub fn with_environment(environment: EnvironmentHandle) -> Self {
+        Type {
+            aliases: None,
+            builtin_names: HashSet::new(),
+            environment,
+        }
+    }
+
+    /// Inject the alias table so `type` can report aliases.
+    pub fn set_aliases(&mut self, aliases: Rc<RefCell<Alias>>) {
+        self.aliases = Some(aliases);
+    }
+
+    /// Provide the set of builtin names so they can be reported to the user.
+    pub fn set_builtin_names(&mut self, names: impl IntoIterator<Item = String>) {
+        self.builtin_names = names.into_iter().collect();
+    }
+
+    // Interpretations of `name` in lookup order; all of them with `-a`, else only the first.
+    fn resolve(&self, name: &str, options: &TypeOptions) -> Vec<Resolution> {
+        let mut resolutions = Vec::new();
+        if !options.force_path {
+            if RESERVED_WORDS.contains(&name) {
+                resolutions.push(Resolution::Keyword);
+            }
+            let expansion = self
+                .aliases
+                .as_ref()
+                .and_then(|aliases| aliases.borrow().get_alias_expansion(name).cloned());
+            if let Some(expansion) = expansion {
+                resolutions.push(Resolution::Alias(expansion));
+            }
+            if self.builtin_names.contains(name) {
+                resolutions.push(Resolution::Builtin);
+            }
+        }
+        resolutions.extend(
+            search_path(name, &self.environment.borrow())
+                .into_iter()
+                .map(Resolution::File),
+        );
+        if !options.all {
+            resolutions.truncate(1);
+        }
+        resolutions
+    }
+
+    fn describe(name: &str, resolution: &Resolution, options: &TypeOptions, out: &mut dyn Write) {
+        if options.kind_only {
+            let _ = writeln!(out, "{}", resolution.kind());
+            return;
+        }
+        match resolution {
+            Resolution::File(path) => {
+                if options.path_only || options.force_path {
+                    let _ = writeln!(out, "{}", path.display());
+                } else {
+                    let _ = writeln!(out, "{name} is {}", path.display());
+                }
+            }
+            _ if options.path_only => {}
+            Resolution::Alias(expansion) => {
+                let _ = writeln!(out, "{name} is aliased to `{expansion}'");
+            }
+            Resolution::Builtin => {
+                let _ = writeln!(out, "{name} is a shell builtin");
+            }
+            Resolution::Keyword => {
+                let _ = writeln!(out, "{name} is a shell keyword");
+            }
+        }
     }
 }
@@ -0,0 +1,122 @@
+use super::builtin::Builtin;
+use super::options::OptionsHandle;
+use std::io::{self, Write};
+
+/// The `umask` builtin.
+///
+/// # Synopsis
+/// umask [mode]
+///
+/// # Description
+/// Print the file mode creation mask as four octal digits, or set it to the octal `mode`. The
+/// mask is applied by the operating system whenever a file is created, so it governs buffers
+/// saved to new files as well as files written by commands run afterwards. Only supported on
+/// Unix; elsewhere the builtin reports that and changes nothing.
+pub struct Umask {
+    options: OptionsHandle,
+}
+
+impl Umask {
+    pub fn with_options(options: OptionsHandle) -> Self {
+        Self { options }
+    }
+}
+
+impl Builtin for Umask {
+    fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Write the current mask to `out` when called without a mode.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        let mode = match args {
+            [] => None,
+            [mode] => match parse_mode(mode) {
+                Some(mode) => Some(mode),
+                None => {
+                    eprintln!("umask: invalid mode: {mode}");
+                    return Some(1);
+                }
+            },
+            _ => {
+                eprintln!("umask: too many arguments");
+                eprintln!("usage: umask [mode]");
+                return Some(1);
+            }
+        };
+
+        if !cfg!(unix) {
+            eprintln!("umask: not supported on this platform");
+            return Some(1);
+        }
+
+        match mode {
+            Some(mode) => {
+                set_process_umask(mode);
+                self.options.borrow_mut().umask = Some(mode);
+            }
+            None => {
+                let mask = self
+                    .options
+                    .borrow()
+                    .umask
+                    .unwrap_or_else(current_process_umask);
+                let _ = writeln!(out, "{mask:04o}");
+            }
+        }
+        Some(0)
+    }
+}
+
+/// Parse an octal mode of at most four digits such as `022` or `0077`.
+pub fn parse_mode(mode: &str) -> Option<u32> {
+    if mode.is_empty() || mode.len() > 4 {
+        return None;
+    }
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o777)
+}
+
+#[cfg(unix)]
+fn set_process_umask(mode: u32) {
+    // SAFETY: umask only swaps the calling process's creation mask and cannot fail.
+    unsafe {
+        libc::umask(mode as libc::mode_t);
+    }
+}
+
+#[cfg(not(unix))]
+fn set_process_umask(_mode: u32) {}
+
+#[cfg(unix)]
+fn current_process_umask() -> u32 {
+    // umask can only be read by setting it, so the previous mask is put straight back.
+    // SAFETY: as in `set_process_umask`.
+    unsafe {
+        let mask = libc::umask(0o022);
+        libc::umask(mask);
+        mask as u32
+    }
+}
+
+#[cfg(not(unix))]
+fn current_process_umask() -> u32 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_octal_modes() {
+        assert_eq!(parse_mode("022"), Some(0o022));
+        assert_eq!(parse_mode("0077"), Some(0o077));
+        assert_eq!(parse_mode("7"), Some(0o7));
+        assert_eq!(parse_mode("8"), None);
+        assert_eq!(parse_mode("u=rwx"), None);
+        assert_eq!(parse_mode("01000"), None);
+        assert_eq!(parse_mode(""), None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::process::builtin::Builtin;
+use std::io::{self, Write};
 use terminal_size::{Width, terminal_size};
 
 /// Builtin responsible for rendering the startup banner.
@@ -7,7 +8,12 @@ pub struct Welcome {}
 impl Builtin for Welcome {
     /// Delegate to the shared `welcome` function.
     fn call(&mut self, args: &[String]) -> Option<i32> {
-        welcome(args)
+        welcome(args, &mut io::stdout())
+    }
+
+    /// Write the banner to `out`.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
+        welcome(args, out)
     }
 }
 
@@ -18,8 +24,8 @@ impl Welcome {
     }
 }
 
-/// Print the Iridium banner, centring it using the terminal width when available.
-pub fn welcome(_args: &[String]) -> Option<i32> {
+/// Write the Iridium banner to `out`, centring it using the terminal width when available.
+pub fn welcome(_args: &[String], out: &mut dyn Write) -> Option<i32> {
     const DEFAULT_WIDTH: usize = 80;
     let width = terminal_size()
         .and_then(|(Width(w), _)| usize::try_from(w).ok())
@@ -51,12 +57,12 @@ pub fn welcome(_args: &[String]) -> Option<i32> {
     let end_color_text = "\u{1b}[39m";
 
     for line in heading {
-        println!("{}", center_line(line, width));
+        let _ = writeln!(out, "{}", center_line(line, width));
     }
-    println!();
+    let _ = writeln!(out);
     for line in iridium_msg {
         let padded_line = center_line(line, width);
-        println!("{}{}{}", purple_text, padded_line, end_color_text);
+        let _ = writeln!(out, "{}{}{}", purple_text, padded_line, end_color_text);
     }
 
     Some(0)
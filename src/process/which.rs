@@ -1,8 +1,9 @@
 use crate::process::alias::Alias;
 use crate::process::builtin::Builtin;
+use crate::process::environment::{EnvironmentHandle, ShellEnvironment};
 use std::cell::RefCell;
 use std::collections::HashSet;
-use std::env;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
@@ -10,11 +11,16 @@ use std::rc::Rc;
 pub struct Which {
     aliases: Option<Rc<RefCell<Alias>>>,
     builtin_names: HashSet<String>,
+    environment: EnvironmentHandle,
 }
 
 impl Builtin for Which {
-    /// Resolve a command name to an alias, builtin, or filesystem path.
     fn call(&mut self, args: &[String]) -> Option<i32> {
+        self.call_with_output(args, &mut io::stdout())
+    }
+
+    /// Resolve a command name to an alias, builtin, or filesystem path.
+    fn call_with_output(&mut self, args: &[String], out: &mut dyn Write) -> Option<i32> {
         let aliases = match self.aliases.as_ref() {
             Some(aliases) => aliases.borrow(),
             None => panic!("Aliases is none!"),
@@ -23,54 +29,57 @@ impl Builtin for Which {
         // Check if command is an alias
         if aliases.contains_alias(&args[0]) {
             let expansion = aliases.get_alias_expansion(&args[0]).unwrap();
-            println!("{}: aliased to {}", args[0], expansion);
+            let _ = writeln!(out, "{}: aliased to {}", args[0], expansion);
             return Some(0);
         }
 
         // Check if command is a built in command
         if self.builtin_names.contains(&args[0]) {
-            println!("{}: shell built-in command", args[0]);
+            let _ = writeln!(out, "{}: shell built-in command", args[0]);
             return Some(0);
         }
 
-        // Create path value for prog string that was provided to 'which' and get PATH as string
-        let prog = Path::new(&args[0]);
-        let path_env = match env::var("PATH") {
-            Ok(path_env) => path_env,
-            Err(_e) => {
-                eprintln!("{} not found", &args[0]);
-                return None;
+        // Search each PATH directory for the program, reporting the first match
+        match search_path(&args[0], &self.environment.borrow()).first() {
+            Some(path) => {
+                let _ = writeln!(out, "{}", path.display());
+                Some(0)
             }
-        };
-
-        // Split PATH string on colon to generate iterator
-        let paths_str = path_env.split(":");
-
-        // Iterate through each path defined in the PATH variable and add the program into the path
-        for path_str in paths_str {
-            let path = Path::new(path_str);
-            let mut path_buf: PathBuf = path.into();
-            path_buf.push(prog);
-
-            // If program file has been found, report path and return
-            if path_buf.is_file() {
-                println!("{}", path_buf.to_str().unwrap());
-                return Some(0);
+            None => {
+                // Program was not found, report and return failure
+                eprintln!("{} not found", &args[0]);
+                None
             }
         }
-
-        // Program was not found, report and return failure
-        eprintln!("{} not found", &args[0]);
-        return None;
     }
 }
 
+/// Every file named `prog` in the `PATH` of `env`, in search order.
+///
+/// Empty `PATH` entries (leading, trailing or doubled colons) name the current directory, as
+/// POSIX specifies; matches found there are reported relative to it, e.g. `./prog`.
+pub(crate) fn search_path(prog: &str, env: &ShellEnvironment) -> Vec<PathBuf> {
+    let Some(path_env) = env.var("PATH") else {
+        return Vec::new();
+    };
+    let cwd = env.current_dir().unwrap_or_default();
+
+    path_env
+        .split(':')
+        .map(|dir| if dir.is_empty() { "." } else { dir })
+        .map(|dir| Path::new(dir).join(prog))
+        .filter(|candidate| cwd.join(candidate).is_file())
+        .collect()
+}
+
 impl Which {
-    /// Construct a `which` builtin that can later be wired with dependencies.
-    pub fn new() -> Self {
+    /// Construct a `which` builtin that searches the `PATH` of `environment` and can later be
+    /// wired with dependencies.
+    pub fn with_environment(environment: EnvironmentHandle) -> Self {
         Self {
             aliases: None,
             builtin_names: HashSet::new(),
+            environment,
         }
     }
 
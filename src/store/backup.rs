@@ -0,0 +1,65 @@
+//! Copies of a file's previous contents kept when a save replaces it (`editor.backup`).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Suffix marking a backup file, both beside the original and in a backup directory.
+pub const BACKUP_SUFFIX: char = '~';
+
+/// Whether and where saves back up the file they overwrite.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupOptions {
+    /// Keep a backup on every save that replaces an existing file.
+    pub enabled: bool,
+    /// Central directory for backups (`editor.backupdir`); `None` writes `file~` beside the
+    /// original.
+    pub dir: Option<PathBuf>,
+}
+
+impl BackupOptions {
+    /// Where the backup of `path` goes.
+    ///
+    /// In a backup directory the file is named after the absolute path of the original with
+    /// every separator replaced by `%`, so `/home/me/notes.txt` becomes `%home%me%notes.txt~`
+    /// and files with the same name in different directories do not collide.
+    pub fn backup_path(&self, path: &Path) -> PathBuf {
+        match &self.dir {
+            Some(dir) => dir.join(encoded_file_name(path)),
+            None => {
+                let mut name = path.as_os_str().to_os_string();
+                name.push(BACKUP_SUFFIX.to_string());
+                PathBuf::from(name)
+            }
+        }
+    }
+
+    /// Copy the file at `path` to its backup location before a save overwrites it.
+    ///
+    /// Returns the backup written, or `None` when backups are off or there is no file yet.
+    /// A missing backup directory is created.
+    pub fn write_backup(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        if !self.enabled || !path.is_file() {
+            return Ok(None);
+        }
+        if let Some(dir) = &self.dir {
+            fs::create_dir_all(dir)?;
+        }
+        let backup = self.backup_path(path);
+        fs::copy(path, &backup)?;
+        Ok(Some(backup))
+    }
+}
+
+fn encoded_file_name(path: &Path) -> String {
+    let absolute = fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf());
+    let mut name: String = absolute
+        .to_string_lossy()
+        .chars()
+        .map(|ch| if std::path::is_separator(ch) { '%' } else { ch })
+        .collect();
+    name.push(BACKUP_SUFFIX);
+    name
+}
@@ -1,7 +1,48 @@
 use crate::store::buffer_snapshot::BufferSnapshot;
+use crate::store::compress::{self, CompressionAlgorithm};
+use crate::store::undo::{Change, PendingChange, UndoHistory};
+use std::ffi::OsString;
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// Why [`Buffer::save_to_disk`] refuses scratch buffers.
+pub const SCRATCH_SAVE_MESSAGE: &str = "scratch buffer, use :w <path>";
+
+/// How [`Buffer::align_line`] positions a line's text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Indent the text by exactly the given number of spaces.
+    Left(usize),
+    /// Center the text within the given width.
+    Center(usize),
+    /// Right-align the text so it ends at the given column.
+    Right(usize),
+}
+
+/// How [`Buffer::save_to_disk`] treats the filesystem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// Create missing parent directories instead of failing (`editor.mkdir_on_save`).
+    pub create_parents: bool,
+    /// Flush the new contents to stable storage before replacing the target (`editor.fsync_on_save`).
+    pub fsync: bool,
+}
+
+impl Default for SaveOptions {
+    fn default() -> Self {
+        Self {
+            create_parents: true,
+            fsync: true,
+        }
+    }
+}
+
+/// Source of [`Buffer::revision`] instance ids, so a recreated buffer never matches an old one.
+static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(1);
 
 /// Represents the editable contents of a named buffer in memory.
 ///
@@ -10,10 +51,28 @@ use std::path::Path;
 #[derive(Debug, Clone, Default)]
 pub struct Buffer {
     name: String,
+    /// Where the buffer is saved; `None` means the name doubles as the path.
+    path: Option<String>,
+    /// Modification time of the backing file when it was last loaded or saved.
+    disk_mtime: Option<SystemTime>,
+    /// When the contents were last changed in memory; `None` until the first edit.
+    last_modified: Option<SystemTime>,
     lines: Vec<String>,
     dirty: bool,
     requires_name: bool,
     is_open: bool,
+    /// Throwaway buffer (`:b -t`) that is never saved or persisted.
+    scratch: bool,
+    /// Refuses insert mode and `:w` in the editor (`:set readonly`); not persisted.
+    read_only: bool,
+    /// Earlier and undone changes, capped by `editor.undolevels`.
+    history: UndoHistory,
+    /// Contents when an open undo group began; edits inside it are recorded together on close.
+    undo_group: Option<Vec<String>>,
+    /// Identifies this buffer among every one created in the process.
+    instance: u64,
+    /// Bumped whenever a persisted field changes.
+    generation: u64,
 }
 
 impl Buffer {
@@ -22,17 +81,47 @@ impl Buffer {
         Self::with_name_state(name, false)
     }
 
+    /// Create a buffer for `name`, reading its lines when `name` is an existing regular file.
+    ///
+    /// The loaded buffer starts clean. Any other path gives the same empty buffer as
+    /// [`new`](Self::new), as does a file that cannot be read, after a warning.
+    pub(crate) fn open_file(name: String) -> Self {
+        let mut buffer = Self::new(name);
+        if Path::new(buffer.storage_path()).is_file()
+            && let Err(err) = buffer.load_from_disk()
+        {
+            eprintln!("Warning: unable to read '{}': {err}", buffer.name);
+        }
+        buffer
+    }
+
     pub(crate) fn new_untitled(name: String) -> Self {
         Self::with_name_state(name, true)
     }
 
+    pub(crate) fn new_scratch(name: String) -> Self {
+        Self {
+            scratch: true,
+            ..Self::new(name)
+        }
+    }
+
     fn with_name_state(name: String, requires_name: bool) -> Self {
         Self {
             name,
+            path: None,
+            disk_mtime: None,
+            last_modified: None,
             lines: Vec::new(),
             dirty: false,
             requires_name,
             is_open: true,
+            scratch: false,
+            read_only: false,
+            history: UndoHistory::default(),
+            undo_group: None,
+            instance: NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed),
+            generation: 0,
         }
     }
 
@@ -41,26 +130,41 @@ impl Buffer {
     }
 
     pub fn set_open(&mut self, open: bool) {
-        self.is_open = open;
+        if self.is_open != open {
+            self.is_open = open;
+            self.touch();
+        }
+    }
+
+    /// Instance id and generation; any change to the persisted state yields a new pair.
+    pub(crate) fn revision(&self) -> (u64, u64) {
+        (self.instance, self.generation)
+    }
+
+    fn touch(&mut self) {
+        self.generation += 1;
     }
 
     /// Append a new line of text and mark the buffer dirty.
     pub fn append(&mut self, line: String) {
+        let pending = self.begin_change(self.lines.len()..self.lines.len());
         self.lines.push(line);
-        self.dirty = true;
+        self.mark_modified(pending);
     }
 
     /// Remove all lines from the buffer.
     pub fn clear(&mut self) {
+        let pending = self.begin_change(0..self.lines.len());
         self.lines.clear();
-        self.dirty = true;
+        self.mark_modified(pending);
     }
 
     /// Remove the last line, returning it when present, and mark dirty.
     pub fn remove_last(&mut self) -> Option<String> {
+        let pending = self.begin_change(self.lines.len().saturating_sub(1)..self.lines.len());
         let popped = self.lines.pop();
         if popped.is_some() {
-            self.dirty = true;
+            self.mark_modified(pending);
         }
         popped
     }
@@ -78,6 +182,7 @@ impl Buffer {
 
     /// Insert a character at a given row/column, padding as required.
     pub fn insert_char(&mut self, row: usize, col: usize, ch: char) {
+        let pending = self.begin_change(row..row + 1);
         while self.lines.len() <= row {
             self.lines.push(String::new());
         }
@@ -94,7 +199,7 @@ impl Buffer {
                 let start = Self::byte_index(line, col);
                 line.insert(start, ch);
             }
-            self.dirty = true;
+            self.mark_modified(pending);
         }
     }
 
@@ -103,45 +208,140 @@ impl Buffer {
         &self.lines
     }
 
+    /// Character at `col` of `row`, or `None` past the end of either.
+    pub fn char_at(&self, row: usize, col: usize) -> Option<char> {
+        self.lines.get(row).and_then(|line| line.chars().nth(col))
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Explicit save location, when one was set separately from the name.
+    #[allow(dead_code)]
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    /// Point saves at `path` without renaming the buffer; `None` reverts to the name.
+    #[allow(dead_code)]
+    pub(crate) fn set_path(&mut self, path: Option<String>) {
+        self.path = path.filter(|path| !path.is_empty());
+        self.touch();
+    }
+
+    /// The file `save_to_disk` writes: the explicit path, falling back to the name.
+    pub fn storage_path(&self) -> &str {
+        self.path.as_deref().unwrap_or(&self.name)
+    }
+
     /// Persist the buffer contents to disk, clearing the dirty flag.
-    pub(crate) fn save_to_disk(&mut self) -> io::Result<()> {
-        let path = Path::new(&self.name);
-        if let Some(parent) = path.parent() {
-            if !parent.as_os_str().is_empty() {
-                fs::create_dir_all(parent)?;
-            }
+    ///
+    /// Missing parent directories are created when `options.create_parents` is set; otherwise the
+    /// save fails with [`io::ErrorKind::NotFound`]. Scratch buffers refuse to save.
+    ///
+    /// The contents are written to a temporary file beside the target and renamed over it, so a
+    /// crash mid-save leaves either the old or the new file, never a truncated one.
+    pub(crate) fn save_to_disk(&mut self, options: SaveOptions) -> io::Result<()> {
+        if self.scratch {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                SCRATCH_SAVE_MESSAGE,
+            ));
         }
 
-        let mut file = File::create(path)?;
-        for line in &self.lines {
-            writeln!(file, "{}", line)?;
-        }
+        let path = Path::new(self.storage_path());
+        write_lines_atomically(path, &self.lines, options)?;
+        self.disk_mtime = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        self.dirty = false;
+        self.touch();
+        Ok(())
+    }
+
+    /// Write a copy of the contents to `path`, leaving the buffer's own save state untouched.
+    pub(crate) fn write_copy(&self, path: &Path, options: SaveOptions) -> io::Result<()> {
+        write_lines_atomically(path, &self.lines, options)
+    }
 
+    /// Write the lines in `range` to `path` like [`write_copy`](Self::write_copy).
+    pub(crate) fn write_range_copy(
+        &self,
+        range: Range<usize>,
+        path: &Path,
+        options: SaveOptions,
+    ) -> io::Result<()> {
+        let lines = self.lines.get(range).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "range extends past the end of the buffer",
+            )
+        })?;
+        write_lines_atomically(path, lines, options)
+    }
+
+    /// Replace the contents with the backing file, recording its mtime and marking clean.
+    ///
+    /// Files ending in a compression extension (`.gz`, `.lz4`) are decompressed on the way in,
+    /// and [`save_to_disk`](Self::save_to_disk) compresses them again.
+    pub(crate) fn load_from_disk(&mut self) -> io::Result<()> {
+        let path = Path::new(self.storage_path());
+        let contents = read_text(path)?;
+        self.disk_mtime = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        self.lines = split_lines(&contents);
         self.dirty = false;
+        self.touch();
+        self.reset_history();
         Ok(())
     }
 
+    /// Append the lines of the file at `path`, returning how many were added.
+    ///
+    /// Unlike [`load_from_disk`](Self::load_from_disk) the file is only a source of text: the
+    /// buffer keeps its own name and save target.
+    pub(crate) fn append_file(&mut self, path: &Path) -> io::Result<usize> {
+        let contents = read_text(path)?;
+        let before = self.lines.len();
+        let pending = self.begin_change(before..before);
+        self.lines.extend(contents.lines().map(str::to_string));
+        let added = self.lines.len() - before;
+        if added > 0 {
+            self.mark_modified(pending);
+        }
+        Ok(added)
+    }
+
+    /// Whether the backing file was modified (or removed) since it was last loaded or saved.
+    ///
+    /// Buffers never read from or written to disk have no baseline and report `false`.
+    pub fn disk_changed(&self) -> bool {
+        let Some(recorded) = self.disk_mtime else {
+            return false;
+        };
+        match fs::metadata(self.storage_path()).and_then(|meta| meta.modified()) {
+            Ok(current) => current != recorded,
+            Err(_) => true,
+        }
+    }
+
     /// Delete the character before the provided column, returning new cursor coordinates.
     pub(crate) fn delete_char(&mut self, row: usize, col: usize) -> Option<(usize, usize)> {
-        let line = self.lines.get_mut(row)?;
-        let char_count = line.chars().count();
+        let char_count = self.lines.get(row)?.chars().count();
         if col == 0 || col > char_count {
             return None;
         }
 
+        let pending = self.begin_change(row..row + 1);
+        let line = &mut self.lines[row];
         let start = Self::byte_index(line, col - 1);
         let end = Self::byte_index(line, col);
         line.replace_range(start..end, "");
-        self.dirty = true;
+        self.mark_modified(pending);
         Some((row, col - 1))
     }
 
     /// Insert a newline at the provided location and return the cursor position after insertion.
     pub(crate) fn insert_newline(&mut self, row: usize, col: usize) -> (usize, usize) {
+        let pending = self.begin_change(row..row + 1);
         while self.lines.len() <= row {
             self.lines.push(String::new());
         }
@@ -158,23 +358,150 @@ impl Buffer {
         };
 
         self.lines.insert(row + 1, trailing);
-        self.dirty = true;
+        self.mark_modified(pending);
         (row + 1, 0)
     }
 
-    /// Ensure `row` exists and pad the line with spaces until it reaches `width`.
-    pub(crate) fn pad_line(&mut self, row: usize, width: usize) {
-        while self.lines.len() <= row {
-            self.lines.push(String::new());
+    /// Remove the characters in `start_col..end_col` on `row`, returning the removed text.
+    ///
+    /// Columns are clamped to the line; the buffer is marked dirty only when text was removed.
+    pub(crate) fn delete_range(
+        &mut self,
+        row: usize,
+        start_col: usize,
+        end_col: usize,
+    ) -> Option<String> {
+        let char_count = self.lines.get(row)?.chars().count();
+        let end_col = end_col.min(char_count);
+        let start_col = start_col.min(end_col);
+        if start_col == end_col {
+            return Some(String::new());
         }
 
-        if let Some(line) = self.lines.get_mut(row) {
-            let char_count = line.chars().count();
-            if char_count < width {
-                line.push_str(&" ".repeat(width - char_count));
-                self.dirty = true;
+        let pending = self.begin_change(row..row + 1);
+        let line = &mut self.lines[row];
+        let start = Self::byte_index(line, start_col);
+        let end = Self::byte_index(line, end_col);
+        let removed = line[start..end].to_string();
+        line.replace_range(start..end, "");
+        self.mark_modified(pending);
+        Some(removed)
+    }
+
+    /// Insert `lines` so the first lands at `row` (clamped to the end), marking dirty.
+    pub(crate) fn insert_lines(&mut self, row: usize, lines: &[String]) {
+        if lines.is_empty() {
+            return;
+        }
+        let row = row.min(self.lines.len());
+        let pending = self.begin_change(row..row);
+        self.lines.splice(row..row, lines.iter().cloned());
+        self.mark_modified(pending);
+    }
+
+    /// Replace every line at once, marking the buffer dirty.
+    pub(crate) fn replace_lines(&mut self, lines: Vec<String>) {
+        let before = std::mem::replace(&mut self.lines, lines);
+        self.record_edit(before);
+    }
+
+    /// Remove the line at `row`, returning it when present, and mark dirty.
+    pub(crate) fn delete_line(&mut self, row: usize) -> Option<String> {
+        if row >= self.lines.len() {
+            return None;
+        }
+        let pending = self.begin_change(row..row + 1);
+        let removed = self.lines.remove(row);
+        self.mark_modified(pending);
+        Some(removed)
+    }
+
+    /// Append line `row + 1` onto `row` with a single separating space, returning the join column.
+    ///
+    /// Leading whitespace on the joined line is dropped; joining the last line returns `None`.
+    pub(crate) fn join_lines(&mut self, row: usize) -> Option<usize> {
+        if row + 1 >= self.lines.len() {
+            return None;
+        }
+
+        let pending = self.begin_change(row..row + 2);
+        let next = self.lines.remove(row + 1);
+        let next = next.trim_start();
+        let line = &mut self.lines[row];
+        let join_col = line.chars().count();
+        if !next.is_empty() {
+            line.push(' ');
+            line.push_str(next);
+        }
+        self.mark_modified(pending);
+        Some(join_col)
+    }
+
+    /// Run `edit` over the lines as a single change, returning whether they changed.
+    ///
+    /// However many lines `edit` touches, at most one undo state is recorded and the buffer is
+    /// marked dirty once; an edit that leaves the lines as they were records nothing.
+    pub(crate) fn apply_edit(&mut self, edit: impl FnOnce(&mut Vec<String>)) -> bool {
+        let before = self.lines.clone();
+        edit(&mut self.lines);
+        self.record_edit(before)
+    }
+
+    /// Stable-sort all lines, lexicographically or by leading number (`sort -n` semantics).
+    ///
+    /// Returns whether the order changed; the buffer is only marked dirty in that case.
+    pub(crate) fn sort_lines(&mut self, numeric: bool) -> bool {
+        self.apply_edit(|lines| {
+            if numeric {
+                lines.sort_by(|a, b| numeric_prefix(a).total_cmp(&numeric_prefix(b)));
+            } else {
+                lines.sort();
+            }
+        })
+    }
+
+    /// Replace occurrences of `pattern` on `row`, returning whether the line changed.
+    pub(crate) fn substitute(
+        &mut self,
+        row: usize,
+        pattern: &str,
+        replacement: &str,
+        all: bool,
+    ) -> bool {
+        self.apply_edit(|lines| {
+            if let Some(line) = lines.get_mut(row) {
+                substitute_in(line, pattern, replacement, all);
             }
+        })
+    }
+
+    /// Re-pad `row` so its trimmed text sits at `alignment`, returning whether the line changed.
+    ///
+    /// Text wider than the target width is left-aligned with no padding.
+    pub(crate) fn align_line(&mut self, row: usize, alignment: Alignment) -> bool {
+        let pending = self.begin_change(row..row + 1);
+        let Some(line) = self.lines.get_mut(row) else {
+            return false;
+        };
+        let text = line.trim();
+        let length = text.chars().count();
+        let padding = match alignment {
+            Alignment::Left(indent) => indent,
+            Alignment::Center(width) => width.saturating_sub(length) / 2,
+            Alignment::Right(width) => width.saturating_sub(length),
+        };
+        let aligned = if text.is_empty() {
+            String::new()
+        } else {
+            format!("{}{text}", " ".repeat(padding))
+        };
+
+        if *line == aligned {
+            return false;
         }
+        *line = aligned;
+        self.mark_modified(pending);
+        true
     }
 
     /// Whether the buffer contains unsaved changes.
@@ -182,13 +509,116 @@ impl Buffer {
         self.dirty
     }
 
+    /// When the buffer contents last changed, if they have changed since creation.
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Capture the lines in `rows` before an edit confined to them, for its undo record.
+    fn begin_change(&self, rows: Range<usize>) -> PendingChange {
+        self.history.begin(&self.lines, rows)
+    }
+
+    /// Record the edit that `pending` was captured for and mark the buffer dirty.
+    fn mark_modified(&mut self, pending: PendingChange) {
+        if self.history.is_enabled() && self.undo_group.is_none() {
+            self.history.record(pending.finish(&self.lines));
+        }
+        self.touch_contents();
+    }
+
+    /// Record the difference from `before` as one edit, returning whether there was any.
+    fn record_edit(&mut self, before: Vec<String>) -> bool {
+        let Some(change) = Change::between(before, &self.lines) else {
+            return false;
+        };
+        if self.undo_group.is_none() {
+            self.history.record(change);
+        }
+        self.touch_contents();
+        true
+    }
+
+    /// Start collecting edits into a single undo step, closed by [`Self::end_undo_group`].
+    ///
+    /// Groups do not nest; opening one while another is open keeps the outer one.
+    pub(crate) fn begin_undo_group(&mut self) {
+        if self.history.is_enabled() && self.undo_group.is_none() {
+            self.undo_group = Some(self.lines.clone());
+        }
+    }
+
+    /// Record every edit since [`Self::begin_undo_group`] as one undo step.
+    pub(crate) fn end_undo_group(&mut self) {
+        if let Some(before) = self.undo_group.take()
+            && let Some(change) = Change::between(before, &self.lines)
+        {
+            self.history.record(change);
+        }
+    }
+
+    fn touch_contents(&mut self) {
+        self.dirty = true;
+        self.last_modified = Some(SystemTime::now());
+        self.touch();
+    }
+
+    /// Restore the contents from before the most recent edit, returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let undone = self.history.undo(&mut self.lines);
+        if undone {
+            self.touch_contents();
+        }
+        undone
+    }
+
+    /// Re-apply the most recently undone edit, returning whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let redone = self.history.redo(&mut self.lines);
+        if redone {
+            self.touch_contents();
+        }
+        redone
+    }
+
+    /// Cap the undo and redo stacks at `levels` changes each; `0` disables undo.
+    pub(crate) fn set_undo_levels(&mut self, levels: usize) {
+        self.history.set_levels(levels);
+    }
+
+    /// Undo and redo stack depths, in that order.
+    #[allow(dead_code)]
+    pub fn undo_depth(&self) -> (usize, usize) {
+        (self.history.undo_len(), self.history.redo_len())
+    }
+
+    // Forget all recorded changes, e.g. after the contents were replaced from disk.
+    fn reset_history(&mut self) {
+        self.history.clear();
+        self.undo_group = None;
+    }
+
     pub(crate) fn mark_clean(&mut self) {
         self.dirty = false;
+        self.touch();
     }
 
     pub(crate) fn set_name(&mut self, name: String) {
         self.name = name;
         self.requires_name = false;
+        self.touch();
+    }
+
+    pub fn is_scratch(&self) -> bool {
+        self.scratch
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub(crate) fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
     }
 
     pub(crate) fn requires_name(&self) -> bool {
@@ -197,6 +627,7 @@ impl Buffer {
 
     pub(crate) fn mark_requires_name(&mut self, requires_name: bool) {
         self.requires_name = requires_name;
+        self.touch();
     }
 
     pub(crate) fn to_snapshot(&self) -> BufferSnapshot {
@@ -207,16 +638,29 @@ impl Buffer {
             self.is_open,
             self.dirty,
         )
+        .with_path(self.path.clone())
+        .with_modified(self.last_modified)
     }
 
     pub(crate) fn from_snapshot(snapshot: BufferSnapshot) -> Self {
-        Self {
+        let mut buffer = Self {
             name: snapshot.name,
+            path: snapshot.path,
+            disk_mtime: None,
+            last_modified: snapshot.modified,
             lines: snapshot.lines,
             dirty: snapshot.dirty,
             requires_name: snapshot.requires_name,
             is_open: snapshot.is_open,
-        }
+            scratch: false,
+            read_only: false,
+            history: UndoHistory::default(),
+            undo_group: None,
+            instance: NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed),
+            generation: 0,
+        };
+        buffer.reset_history();
+        buffer
     }
 
     /// Translate a character index into a byte offset for utf-8 strings.
@@ -236,11 +680,152 @@ impl Buffer {
     }
 }
 
+/// Read the file at `path` as text, decompressing it when its extension names an algorithm.
+fn read_text(path: &Path) -> io::Result<String> {
+    match CompressionAlgorithm::from_path(path) {
+        Some(algorithm) => {
+            let decoded = compress::decompress(&fs::read(path)?, algorithm)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            String::from_utf8(decoded)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        }
+        None => fs::read_to_string(path),
+    }
+}
+
+/// Split file contents into lines on `\n`, dropping the final newline that saving writes after
+/// every line, so loading and saving round-trip.
+fn split_lines(contents: &str) -> Vec<String> {
+    if contents.is_empty() {
+        return Vec::new();
+    }
+    contents
+        .strip_suffix('\n')
+        .unwrap_or(contents)
+        .split('\n')
+        .map(str::to_string)
+        .collect()
+}
+
+/// Write `lines` to `path` through a temporary sibling file that is renamed into place.
+///
+/// Paths with a compression extension are written compressed.
+fn write_lines_atomically(path: &Path, lines: &[String], options: SaveOptions) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.is_dir() {
+            if !options.create_parents {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("directory does not exist: {}", parent.display()),
+                ));
+            }
+            fs::create_dir_all(parent)?;
+        }
+    }
+
+    // Replace the file a symlink points at rather than the link itself.
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = target
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut prefix = OsString::from(".");
+    prefix.push(target.file_name().unwrap_or_default());
+    prefix.push(".");
+
+    // A unique name keeps concurrent saves, and any user file named `<file>.tmp`, apart. The
+    // temporary file is removed if it is dropped before being renamed into place.
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix).suffix(".tmp");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // Subject to the umask, as a plain create would be.
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let temp = builder.tempfile_in(dir)?;
+
+    write_contents(temp.as_file(), path, lines, options.fsync)?;
+    if let Ok(metadata) = fs::metadata(&target) {
+        fs::set_permissions(temp.path(), metadata.permissions())?;
+    }
+    temp.persist(&target).map_err(|err| err.error)?;
+    Ok(())
+}
+
+fn write_contents(file: &File, path: &Path, lines: &[String], fsync: bool) -> io::Result<()> {
+    let mut writer = BufWriter::new(file);
+    match CompressionAlgorithm::from_path(path) {
+        Some(algorithm) => {
+            let mut contents = String::new();
+            for line in lines {
+                contents.push_str(line);
+                contents.push('\n');
+            }
+            let encoded =
+                compress::compress(contents.as_bytes(), algorithm).map_err(io::Error::other)?;
+            writer.write_all(&encoded)?;
+        }
+        None => {
+            for line in lines {
+                writeln!(writer, "{}", line)?;
+            }
+        }
+    }
+    writer.flush()?;
+    if fsync {
+        writer.get_ref().sync_all()?;
+    }
+    Ok(())
+}
+
+/// Replace `pattern` in `line`, every occurrence when `all` and otherwise the first, returning
+/// how many occurrences were replaced.
+pub fn substitute_in(line: &mut String, pattern: &str, replacement: &str, all: bool) -> usize {
+    if pattern.is_empty() {
+        return 0;
+    }
+    let occurrences = match all {
+        true => line.matches(pattern).count(),
+        false => usize::from(line.contains(pattern)),
+    };
+    if occurrences > 0 {
+        *line = match all {
+            true => line.replace(pattern, replacement),
+            false => line.replacen(pattern, replacement, 1),
+        };
+    }
+    occurrences
+}
+
+/// Parse the leading integer/decimal of `line`, treating lines without one as `0`.
+fn numeric_prefix(line: &str) -> f64 {
+    let trimmed = line.trim_start();
+    let mut end = 0;
+    let mut seen_digit = false;
+    let mut seen_dot = false;
+    for (idx, ch) in trimmed.char_indices() {
+        match ch {
+            '+' | '-' if idx == 0 => {}
+            '0'..='9' => seen_digit = true,
+            '.' if !seen_dot => seen_dot = true,
+            _ => break,
+        }
+        end = idx + ch.len_utf8();
+    }
+
+    if !seen_digit {
+        return 0.0;
+    }
+    trimmed[..end].trim_end_matches('.').parse().unwrap_or(0.0)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Buffer;
+    use super::{Alignment, Buffer, SaveOptions};
+    use crate::store::compress::{self, CompressionAlgorithm};
     use std::fs;
-    use std::io::Read;
+    use std::io::{self, Read};
 
     /// Appending lines marks the buffer dirty while `clear` resets state.
     #[test]
@@ -268,9 +853,9 @@ mod tests {
         assert_eq!(buffer.lines[0], "abc");
     }
 
-    /// Splitting a line and padding the continuation behaves as expected.
+    /// Splitting a line moves the text after the cursor onto a new line.
     #[test]
-    fn insert_newline_and_pad_line_work() {
+    fn insert_newline_splits_the_line() {
         let mut buffer = Buffer::new("test".into());
         buffer.append("hello".into());
 
@@ -278,9 +863,74 @@ mod tests {
         assert_eq!((row, col), (1, 0));
         assert_eq!(buffer.lines[0], "he");
         assert_eq!(buffer.lines[1], "llo");
+    }
 
-        buffer.pad_line(1, 6);
-        assert_eq!(buffer.lines[1], "llo   ");
+    /// The undo stack never exceeds the cap, and the most recent states stay reachable.
+    #[test]
+    fn undo_history_is_bounded_by_undo_levels() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.set_undo_levels(3);
+        buffer.append(String::new());
+        for ch in "abcdef".chars() {
+            buffer.insert_char(0, buffer.lines[0].len(), ch);
+        }
+        assert_eq!(buffer.undo_depth(), (3, 0));
+
+        for expected in ["abcde", "abcd", "abc"] {
+            assert!(buffer.undo());
+            assert_eq!(buffer.lines[0], expected);
+        }
+        assert!(!buffer.undo(), "older states were dropped");
+        assert_eq!(buffer.undo_depth(), (0, 3));
+
+        assert!(buffer.redo());
+        assert_eq!(buffer.lines[0], "abcd");
+        buffer.insert_char(0, 0, 'X');
+        assert_eq!(
+            buffer.undo_depth(),
+            (2, 0),
+            "a new edit clears the redo stack"
+        );
+        assert_eq!(buffer.lines[0], "Xabcd");
+        assert!(buffer.undo());
+        assert_eq!(buffer.lines[0], "abcd");
+    }
+
+    /// Each keystroke records only the line it touched, not a copy of the whole buffer.
+    #[test]
+    fn undo_history_stores_deltas_on_large_buffers() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.replace_lines((0..200_000).map(|n| format!("line {n}")).collect());
+        buffer.reset_history();
+        for ch in "abcdefghij".repeat(20).chars() {
+            buffer.insert_char(100_000, 0, ch);
+        }
+
+        assert_eq!(buffer.undo_depth(), (200, 0));
+        assert!(
+            buffer.history.bytes() < 200 * 1024,
+            "history holds {} bytes",
+            buffer.history.bytes()
+        );
+        while buffer.undo() {}
+        assert_eq!(buffer.lines[100_000], "line 100000");
+        assert_eq!(buffer.lines.len(), 200_000);
+    }
+
+    /// `undolevels = 0` records nothing so undo is disabled.
+    #[test]
+    fn zero_undo_levels_disables_undo() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("one".into());
+        buffer.append("two".into());
+        assert!(buffer.undo());
+        assert_eq!(buffer.lines, vec!["one".to_string()]);
+
+        buffer.set_undo_levels(0);
+        assert_eq!(buffer.undo_depth(), (0, 0));
+        buffer.append("three".into());
+        assert!(!buffer.undo());
+        assert_eq!(buffer.lines.len(), 2);
     }
 
     /// Removing from an empty buffer is a no-op and leaves the dirty flag untouched.
@@ -302,18 +952,6 @@ mod tests {
         assert!(!buffer.is_dirty());
     }
 
-    /// Padding with a width shorter than the current line keeps the buffer clean.
-    #[test]
-    fn pad_line_with_shorter_width_keeps_clean_state() {
-        let mut buffer = Buffer::new("test".into());
-        buffer.append("abcd".into());
-        buffer.dirty = false;
-
-        buffer.pad_line(0, 2);
-        assert_eq!(buffer.lines[0], "abcd");
-        assert!(!buffer.is_dirty());
-    }
-
     /// Removing existing lines returns them and marks the buffer dirty.
     #[test]
     fn remove_last_marks_dirty_and_returns_line() {
@@ -327,6 +965,109 @@ mod tests {
         assert_eq!(buffer.lines(), &[String::from("alpha")]);
     }
 
+    /// Range deletion clamps columns and returns the removed text.
+    #[test]
+    fn delete_range_removes_span_and_returns_text() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("hello world".into());
+        buffer.dirty = false;
+
+        assert_eq!(buffer.delete_range(0, 3, 3).as_deref(), Some(""));
+        assert!(!buffer.is_dirty());
+        assert_eq!(buffer.delete_range(0, 5, 99).as_deref(), Some(" world"));
+        assert_eq!(buffer.lines()[0], "hello");
+        assert!(buffer.is_dirty());
+        assert!(buffer.delete_range(4, 0, 1).is_none());
+    }
+
+    /// Deleting a line removes it and leaves out-of-range rows untouched.
+    #[test]
+    fn delete_line_removes_row() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("one".into());
+        buffer.append("two".into());
+        buffer.dirty = false;
+
+        assert!(buffer.delete_line(5).is_none());
+        assert!(!buffer.is_dirty());
+        assert_eq!(buffer.delete_line(0).as_deref(), Some("one"));
+        assert_eq!(buffer.lines(), &[String::from("two")]);
+        assert!(buffer.is_dirty());
+    }
+
+    /// Joining collapses leading whitespace and is a no-op on the last line.
+    #[test]
+    fn join_lines_merges_next_row_with_single_space() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("let x =".into());
+        buffer.append("    42;".into());
+        buffer.dirty = false;
+
+        assert!(buffer.join_lines(1).is_none());
+        assert!(!buffer.is_dirty());
+        assert_eq!(buffer.join_lines(0), Some(7));
+        assert_eq!(buffer.lines(), &[String::from("let x = 42;")]);
+        assert!(buffer.is_dirty());
+    }
+
+    /// Numeric sorting orders by leading value and keeps non-numeric lines at zero.
+    #[test]
+    fn sort_lines_numeric_and_lexicographic() {
+        let mut buffer = Buffer::new("test".into());
+        for line in ["10 apples", "2", "-1.5 debt", "none", "1"] {
+            buffer.append(line.into());
+        }
+
+        assert!(buffer.sort_lines(true));
+        assert_eq!(
+            buffer.lines(),
+            &["-1.5 debt", "none", "1", "2", "10 apples"]
+        );
+        assert!(!buffer.sort_lines(true));
+
+        assert!(buffer.sort_lines(false));
+        assert_eq!(
+            buffer.lines(),
+            &["-1.5 debt", "1", "10 apples", "2", "none"]
+        );
+    }
+
+    /// Substitution replaces the first match unless asked to replace all.
+    #[test]
+    fn substitute_replaces_first_or_all_matches() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("foo foo".into());
+        buffer.dirty = false;
+
+        assert!(!buffer.substitute(0, "bar", "baz", false));
+        assert!(!buffer.is_dirty());
+        assert!(buffer.substitute(0, "foo", "bar", false));
+        assert_eq!(buffer.lines()[0], "bar foo");
+        assert!(buffer.substitute(0, "o", "0", true));
+        assert_eq!(buffer.lines()[0], "bar f00");
+    }
+
+    /// Alignment trims surrounding whitespace and pads on the left only.
+    #[test]
+    fn align_line_centers_and_right_aligns_within_width() {
+        let mut buffer = Buffer::new("test".into());
+        buffer.append("  title  ".into());
+        buffer.dirty = false;
+
+        assert!(buffer.align_line(0, Alignment::Center(11)));
+        assert_eq!(buffer.lines()[0], "   title");
+        assert!(buffer.is_dirty());
+        assert!(buffer.align_line(0, Alignment::Right(11)));
+        assert_eq!(buffer.lines()[0], "      title");
+        assert!(buffer.align_line(0, Alignment::Left(0)));
+        assert_eq!(buffer.lines()[0], "title");
+
+        buffer.dirty = false;
+        assert!(!buffer.align_line(0, Alignment::Right(3)));
+        assert!(!buffer.align_line(1, Alignment::Left(0)));
+        assert!(!buffer.is_dirty());
+    }
+
     /// Saving the buffer writes to disk and clears the dirty flag.
     #[test]
     fn save_to_disk_persists_contents_and_clears_dirty_flag() {
@@ -346,7 +1087,9 @@ mod tests {
         buffer.append("line 2".into());
         assert!(buffer.is_dirty());
 
-        buffer.save_to_disk().expect("save_to_disk should succeed");
+        buffer
+            .save_to_disk(SaveOptions::default())
+            .expect("save_to_disk should succeed");
         assert!(!buffer.is_dirty());
 
         let mut file = fs::File::open(&path).expect("file should exist");
@@ -357,4 +1100,137 @@ mod tests {
 
         let _ = fs::remove_file(&path);
     }
+
+    /// Gzip-compressed files are inflated on load and compressed again on save.
+    #[test]
+    fn gzip_files_round_trip_through_load_and_save() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.txt.gz");
+        let encoded = compress::compress(b"first\nsecond\n", CompressionAlgorithm::Gzip).unwrap();
+        fs::write(&path, encoded).unwrap();
+
+        let mut buffer = Buffer::new(path.to_string_lossy().to_string());
+        buffer.load_from_disk().expect("gzip file should load");
+        assert_eq!(buffer.lines(), &["first", "second"]);
+
+        buffer.append("third".into());
+        buffer
+            .save_to_disk(SaveOptions::default())
+            .expect("gzip file should save");
+        let on_disk = fs::read(&path).unwrap();
+        assert_eq!(&on_disk[..2], &[0x1f, 0x8b]);
+        let decoded = compress::decompress(&on_disk, CompressionAlgorithm::Gzip).unwrap();
+        assert_eq!(decoded, b"first\nsecond\nthird\n");
+    }
+
+    /// A file with a compression extension but plain contents fails to load without clobbering.
+    #[test]
+    fn invalid_compressed_file_reports_invalid_data() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("plain.lz4");
+        fs::write(&path, "not compressed").unwrap();
+
+        let mut buffer = Buffer::new(path.to_string_lossy().to_string());
+        buffer.append("kept".into());
+        let err = buffer.load_from_disk().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(buffer.lines(), &["kept"]);
+    }
+
+    /// Saves replace the target in one step and leave no temporary file behind.
+    #[test]
+    fn save_to_disk_replaces_target_atomically() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.txt");
+        fs::write(&path, "old contents that are longer than the new ones\n").unwrap();
+
+        let mut buffer = Buffer::new(path.to_string_lossy().to_string());
+        for line in ["alpha", "beta"] {
+            buffer.append(line.into());
+        }
+        let options = SaveOptions {
+            create_parents: true,
+            fsync: false,
+        };
+        buffer.save_to_disk(options).expect("save should succeed");
+        buffer.append("gamma".into());
+        buffer
+            .save_to_disk(SaveOptions::default())
+            .expect("save should succeed");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "alpha\nbeta\ngamma\n");
+        let leftovers: Vec<_> = fs::read_dir(temp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != "notes.txt")
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+
+    /// A user file that happens to be named `<file>.tmp` survives a save of `<file>`.
+    #[test]
+    fn save_to_disk_leaves_a_tmp_sibling_alone() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("notes.txt");
+        let sibling = temp.path().join("notes.txt.tmp");
+        fs::write(&sibling, "keep me\n").unwrap();
+
+        let mut buffer = Buffer::new(path.to_string_lossy().to_string());
+        buffer.append("alpha".into());
+        buffer
+            .save_to_disk(SaveOptions::default())
+            .expect("save should succeed");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "alpha\n");
+        assert_eq!(fs::read_to_string(&sibling).unwrap(), "keep me\n");
+    }
+
+    /// Saving through a symlink rewrites the file it points at and keeps the link.
+    #[cfg(unix)]
+    #[test]
+    fn save_to_disk_preserves_symlinks_and_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let real = temp.path().join("real.txt");
+        let link = temp.path().join("link.txt");
+        fs::write(&real, "old\n").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o640)).unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut buffer = Buffer::new(link.to_string_lossy().to_string());
+        buffer.append("new".into());
+        buffer
+            .save_to_disk(SaveOptions::default())
+            .expect("save should succeed");
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&real).unwrap(), "new\n");
+        let mode = fs::metadata(&real).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    /// Without permission to create directories, saving into a missing parent fails cleanly.
+    #[test]
+    fn save_to_disk_refuses_missing_parent_when_disabled() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("missing").join("file.txt");
+        let mut buffer = Buffer::new(path.to_string_lossy().to_string());
+        buffer.append("data".into());
+
+        let options = SaveOptions {
+            create_parents: false,
+            fsync: true,
+        };
+        let err = buffer.save_to_disk(options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().starts_with("directory does not exist"));
+        assert!(!temp.path().join("missing").exists());
+        assert!(buffer.is_dirty());
+    }
 }
@@ -1,12 +1,18 @@
 //! Serializable representation of a Buffer for persistence.
 
+use std::time::SystemTime;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BufferSnapshot {
     pub name: String,
+    /// Storage location when it differs from `name`.
+    pub path: Option<String>,
     pub lines: Vec<String>,
     pub requires_name: bool,
     pub is_open: bool,
     pub dirty: bool,
+    /// When the buffer contents were last edited, if ever.
+    pub modified: Option<SystemTime>,
 }
 
 impl BufferSnapshot {
@@ -19,10 +25,46 @@ impl BufferSnapshot {
     ) -> Self {
         Self {
             name,
+            path: None,
             lines,
             requires_name,
             is_open,
             dirty,
+            modified: None,
         }
     }
+
+    /// Attach the on-disk path the buffer saves to.
+    pub fn with_path(mut self, path: Option<String>) -> Self {
+        self.path = path;
+        self
+    }
+
+    /// Attach the time the buffer was last edited.
+    pub fn with_modified(mut self, modified: Option<SystemTime>) -> Self {
+        self.modified = modified;
+        self
+    }
+}
+
+/// Buffers that changed since the store was last persisted, from
+/// [`BufferStore::snapshot_diff`](super::buffer_store::BufferStore::snapshot_diff).
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotDiff {
+    /// Buffers whose persisted state differs from their database record.
+    pub changed: Vec<BufferSnapshot>,
+    /// Buffers whose database record is still current, including suspended ones.
+    pub unchanged: Vec<String>,
+    /// Revision of every resident buffer the diff covers, recorded by `mark_persisted`.
+    pub(crate) revisions: Vec<(String, (u64, u64))>,
+}
+
+impl SnapshotDiff {
+    /// Names of every buffer that should keep a record in the database.
+    pub fn retained_names(&self) -> impl Iterator<Item = &str> {
+        self.unchanged
+            .iter()
+            .map(String::as_str)
+            .chain(self.changed.iter().map(|snapshot| snapshot.name.as_str()))
+    }
 }
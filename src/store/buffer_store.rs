@@ -1,17 +1,70 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
 use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use uuid::Uuid;
 
-use super::buffer::Buffer;
-use super::buffer_snapshot::BufferSnapshot;
+use super::backup::BackupOptions;
+use super::buffer::{Alignment, Buffer, SaveOptions};
+use super::buffer_snapshot::{BufferSnapshot, SnapshotDiff};
+use super::observer::{BufferObserver, ObserverList};
+use super::persistence::PersistenceResult;
+use super::suspend::{IdleSuspend, SuspendBackend};
+use super::undo::DEFAULT_UNDO_LEVELS;
+
+/// Why [`BufferStore::try_open`] refused to open a buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenError {
+    /// Opening another buffer would pass the configured `editor.max_open_buffers`.
+    Limit(usize),
+    /// The buffer name resolves to an existing directory, which could never be saved.
+    Directory(String),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Limit(limit) => write!(
+                f,
+                "Too many open buffers: editor.max_open_buffers is {limit}; close a buffer first"
+            ),
+            Self::Directory(name) => write!(f, "{name} is a directory"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
 
 /// In-memory manager that tracks named buffers and orchestrates their lifecycle.
 ///
 /// `BufferStore` owns the canonical `Buffer` instances, provides lookup helpers,
 /// coordinates persistence, and mediates text mutations for the editor and
 /// control layers.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct BufferStore {
     buffers: HashMap<String, Buffer>,
+    save_options: SaveOptions,
+    backup: BackupOptions,
+    idle: Option<IdleSuspend>,
+    observers: ObserverList,
+    /// Treat every buffer as read-only (`--readonly`, `:set readonly all`).
+    read_only: bool,
+    /// Most buffers [`try_open`](Self::try_open) allows open at once (`editor.max_open_buffers`).
+    max_open: Option<usize>,
+    /// Undo depth given to every buffer (`editor.undolevels`).
+    undo_levels: usize,
+    /// Revision of each buffer as of the last [`mark_persisted`](Self::mark_persisted).
+    persisted: HashMap<String, (u64, u64)>,
+}
+
+impl Default for BufferStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BufferStore {
@@ -19,29 +72,252 @@ impl BufferStore {
     pub fn new() -> Self {
         Self {
             buffers: HashMap::new(),
+            save_options: SaveOptions::default(),
+            backup: BackupOptions::default(),
+            idle: None,
+            observers: ObserverList::default(),
+            read_only: false,
+            max_open: None,
+            undo_levels: DEFAULT_UNDO_LEVELS,
+            persisted: HashMap::new(),
+        }
+    }
+
+    /// Register `observer` for open, close, rename, save and mutation events.
+    ///
+    /// Edits made directly through a `&mut Buffer` (e.g. from [`open`](Self::open) or
+    /// [`get_mut`](Self::get_mut)) bypass the store and are not reported.
+    #[allow(dead_code)]
+    pub fn add_observer(&mut self, observer: Arc<dyn BufferObserver>) {
+        self.observers.push(observer);
+    }
+
+    fn notify_mutated(&self, name: &str, changed: bool) {
+        if changed {
+            self.observers.notify(|observer| observer.mutated(name));
+        }
+    }
+
+    /// Evict buffers untouched for `timeout` into `backend` (`editor.idle_suspend_ms`).
+    pub fn enable_idle_suspend(&mut self, timeout: Duration, backend: Arc<dyn SuspendBackend>) {
+        self.idle = Some(IdleSuspend::new(timeout, backend));
+    }
+
+    /// Whether the named buffer currently lives only in the suspend backend.
+    pub fn is_suspended(&self, name: &str) -> bool {
+        self.idle
+            .as_ref()
+            .is_some_and(|idle| idle.is_suspended(name))
+    }
+
+    /// Names of buffers that currently live only in the suspend backend.
+    pub fn suspended_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .idle
+            .as_ref()
+            .map(|idle| idle.suspended_names().cloned().collect())
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Fetch a buffer for use, transparently reloading it when it was suspended.
+    pub fn access(&mut self, name: &str) -> PersistenceResult<Option<&mut Buffer>> {
+        self.resume_suspended(name)?;
+        if let Some(idle) = self.idle.as_mut()
+            && self.buffers.contains_key(name)
+        {
+            idle.touch(name, Instant::now());
+        }
+        Ok(self.buffers.get_mut(name))
+    }
+
+    /// Suspend every buffer that has been idle for longer than the configured timeout.
+    pub fn suspend_idle(&mut self) -> PersistenceResult<Vec<String>> {
+        self.suspend_idle_at(Instant::now())
+    }
+
+    /// Suspend buffers idle as of `now`, returning the evicted names.
+    pub fn suspend_idle_at(&mut self, now: Instant) -> PersistenceResult<Vec<String>> {
+        let Some(idle) = self.idle.as_mut() else {
+            return Ok(Vec::new());
+        };
+
+        let resident = self
+            .buffers
+            .iter()
+            .filter(|(_, buffer)| !buffer.is_scratch())
+            .map(|(name, _)| name);
+        let names = idle.idle_names(resident, now);
+        if names.is_empty() {
+            return Ok(names);
+        }
+
+        let snapshots: Vec<BufferSnapshot> = names
+            .iter()
+            .filter_map(|name| self.buffers.get(name))
+            .map(|buffer| buffer.to_snapshot())
+            .collect();
+        idle.backend().suspend(&snapshots)?;
+
+        for name in &names {
+            self.buffers.remove(name);
+            idle.mark_suspended(name);
+        }
+        Ok(names)
+    }
+
+    /// Load every suspended buffer back into memory.
+    pub fn resume_all(&mut self) -> PersistenceResult<()> {
+        let names: Vec<String> = match self.idle.as_ref() {
+            Some(idle) => idle.suspended_names().cloned().collect(),
+            None => return Ok(()),
+        };
+        for name in names {
+            self.resume_suspended(&name)?;
+        }
+        Ok(())
+    }
+
+    fn resume_suspended(&mut self, name: &str) -> PersistenceResult<()> {
+        let Some(idle) = self.idle.as_mut() else {
+            return Ok(());
+        };
+        if !idle.is_suspended(name) {
+            return Ok(());
+        }
+
+        if let Some(snapshot) = idle.backend().resume(name)? {
+            let buffer = Self::configured(Buffer::from_snapshot(snapshot), self.undo_levels);
+            self.buffers.insert(name.to_string(), buffer);
+        }
+        idle.take_suspended(name);
+        idle.touch(name, Instant::now());
+        Ok(())
+    }
+
+    /// Keep a backup of the previous file contents on every save (`editor.backup`).
+    pub fn set_backup(&mut self, enabled: bool) {
+        self.backup.enabled = enabled;
+    }
+
+    /// Write backups into `dir` rather than beside each file (`editor.backupdir`).
+    pub fn set_backup_dir(&mut self, dir: Option<PathBuf>) {
+        self.backup.dir = dir;
+    }
+
+    #[allow(dead_code)]
+    pub fn backup_options(&self) -> &BackupOptions {
+        &self.backup
+    }
+
+    /// Control whether saves may create missing parent directories (`editor.mkdir_on_save`).
+    pub fn set_mkdir_on_save(&mut self, enabled: bool) {
+        self.save_options.create_parents = enabled;
+    }
+
+    /// Control whether saves are flushed to stable storage before replacing the file
+    /// (`editor.fsync_on_save`).
+    pub fn set_fsync_on_save(&mut self, enabled: bool) {
+        self.save_options.fsync = enabled;
+    }
+
+    /// Cap how many buffers may be open at once; `None` removes the cap.
+    pub fn set_max_open_buffers(&mut self, limit: Option<usize>) {
+        self.max_open = limit;
+    }
+
+    /// Like [`open`](Self::open), but refuse to open one more buffer past the configured cap,
+    /// or a new buffer whose name is an existing directory.
+    ///
+    /// Buffers that are already open never count against the cap. `open` itself stays
+    /// unchecked for internal callers that only look up or restore buffers.
+    pub fn try_open(&mut self, name: impl Into<String>) -> Result<&mut Buffer, OpenError> {
+        self.try_open_with(name, Buffer::open_file)
+    }
+
+    /// Like [`open_untitled`](Self::open_untitled), but subject to the open-buffer cap.
+    pub fn try_open_untitled(&mut self, name: impl Into<String>) -> Result<&mut Buffer, OpenError> {
+        self.try_open_with(name, Buffer::new_untitled)
+    }
+
+    /// Like [`open_scratch`](Self::open_scratch), but subject to the open-buffer cap.
+    pub fn try_open_scratch(&mut self, name: impl Into<String>) -> Result<&mut Buffer, OpenError> {
+        self.try_open_with(name, Buffer::new_scratch)
+    }
+
+    fn try_open_with(
+        &mut self,
+        name: impl Into<String>,
+        create: fn(String) -> Buffer,
+    ) -> Result<&mut Buffer, OpenError> {
+        let key = name.into();
+        if !self.buffers.contains_key(&key) && Path::new(&key).is_dir() {
+            return Err(OpenError::Directory(key));
+        }
+        if let Some(limit) = self.max_open {
+            let already_open = self.buffers.get(&key).is_some_and(Buffer::is_open);
+            if !already_open && self.open_buffers().len() >= limit {
+                return Err(OpenError::Limit(limit));
+            }
         }
+        Ok(self.open_with(key, create))
+    }
+
+    /// Cap every buffer's undo and redo stacks at `levels` states; `0` disables undo
+    /// (`editor.undolevels`, `:set undolevels`).
+    pub fn set_undo_levels(&mut self, levels: usize) {
+        self.undo_levels = levels;
+        for buffer in self.buffers.values_mut() {
+            buffer.set_undo_levels(levels);
+        }
+    }
+
+    // Apply the store-wide settings that live on each buffer to a newly created one.
+    fn configured(mut buffer: Buffer, undo_levels: usize) -> Buffer {
+        buffer.set_undo_levels(undo_levels);
+        buffer
     }
 
     /// Fetch a mutable reference to the named buffer, creating it if necessary.
+    ///
+    /// A new buffer whose name is an existing file starts with that file's lines, unmodified.
     pub fn open(&mut self, name: impl Into<String>) -> &mut Buffer {
-        self.open_with_state(name, false)
+        self.open_with(name, Buffer::open_file)
     }
 
     /// Create an untitled buffer that still requires a user-supplied name.
+    #[allow(dead_code)]
     pub fn open_untitled(&mut self, name: impl Into<String>) -> &mut Buffer {
-        self.open_with_state(name, true)
+        self.open_with(name, Buffer::new_untitled)
+    }
+
+    /// Open a scratch buffer that is excluded from snapshots and refuses to save to disk.
+    ///
+    /// An existing buffer with the same name is opened unchanged.
+    #[allow(dead_code)]
+    pub fn open_scratch(&mut self, name: impl Into<String>) -> &mut Buffer {
+        self.open_with(name, Buffer::new_scratch)
     }
 
-    fn open_with_state(&mut self, name: impl Into<String>, requires_name: bool) -> &mut Buffer {
+    fn open_with(&mut self, name: impl Into<String>, create: fn(String) -> Buffer) -> &mut Buffer {
         let key = name.into();
+        if let Err(err) = self.resume_suspended(&key) {
+            eprintln!("Warning: unable to reload suspended buffer '{key}': {err}");
+        }
+        if let Some(idle) = self.idle.as_mut() {
+            idle.touch(&key, Instant::now());
+        }
 
-        let buffer = self.buffers.entry(key.clone()).or_insert_with(|| {
-            if requires_name {
-                Buffer::new_untitled(key.clone())
-            } else {
-                Buffer::new(key.clone())
-            }
-        });
+        let was_open = self.buffers.get(&key).is_some_and(Buffer::is_open);
+        if !was_open {
+            self.observers.notify(|observer| observer.opened(&key));
+        }
+        let undo_levels = self.undo_levels;
+        let buffer = self
+            .buffers
+            .entry(key.clone())
+            .or_insert_with(|| Self::configured(create(key.clone()), undo_levels));
         buffer.set_open(true);
         buffer
     }
@@ -56,13 +332,47 @@ impl BufferStore {
         self.buffers.get_mut(name)
     }
 
+    /// A fresh name for an untitled buffer, unique within the store.
+    pub fn generate_untitled_name(&self) -> String {
+        loop {
+            let candidate = Uuid::new_v4().to_string();
+            if !self.buffers.contains_key(&candidate) {
+                return candidate;
+            }
+        }
+    }
+
     /// Return a vector of the buffer names currently tracked in the active set.
     pub fn list(&self) -> Vec<String> {
         let mut names: Vec<String> = self.buffers.keys().cloned().collect();
+        if let Some(idle) = self.idle.as_ref() {
+            names.extend(idle.suspended_names().cloned());
+        }
         names.sort();
         names
     }
 
+    /// Buffer names ordered from most to least recently modified.
+    ///
+    /// Buffers never edited follow in name order, then any suspended buffers.
+    pub fn list_by_modified(&self) -> Vec<String> {
+        let mut resident: Vec<(&String, Option<SystemTime>)> = self
+            .buffers
+            .iter()
+            .map(|(name, buffer)| (name, buffer.last_modified()))
+            .collect();
+        resident.sort_by(|(a_name, a_time), (b_name, b_time)| {
+            b_time.cmp(a_time).then_with(|| a_name.cmp(b_name))
+        });
+        let mut names: Vec<String> = resident.into_iter().map(|(name, _)| name.clone()).collect();
+        if let Some(idle) = self.idle.as_ref() {
+            let mut suspended: Vec<String> = idle.suspended_names().cloned().collect();
+            suspended.sort();
+            names.extend(suspended);
+        }
+        names
+    }
+
     pub fn open_buffers(&self) -> Vec<String> {
         let mut names: Vec<String> = self
             .buffers
@@ -73,6 +383,28 @@ impl BufferStore {
         names
     }
 
+    /// Number of lines in the named buffer, or zero when it does not exist.
+    pub fn line_count(&self, name: &str) -> usize {
+        self.buffers
+            .get(name)
+            .map(|buffer| buffer.lines().len())
+            .unwrap_or(0)
+    }
+
+    /// Character at `row`, `col` of the named buffer, read in place rather than from a snapshot.
+    pub fn char_at(&self, name: &str, row: usize, col: usize) -> Option<char> {
+        self.buffers.get(name)?.char_at(row, col)
+    }
+
+    /// Number of characters on `row` of the named buffer, or zero when absent.
+    pub fn char_count(&self, name: &str, row: usize) -> usize {
+        self.buffers
+            .get(name)
+            .and_then(|buffer| buffer.lines().get(row))
+            .map(|line| line.chars().count())
+            .unwrap_or(0)
+    }
+
     /// Report whether the store contains any buffers.
     pub fn is_empty(&self) -> bool {
         self.buffers.is_empty()
@@ -80,38 +412,144 @@ impl BufferStore {
 
     /// Insert a character at the requested coordinates, growing the buffer as needed.
     pub fn insert_char(&mut self, name: &str, row: usize, col: usize, ch: char) {
+        let undo_levels = self.undo_levels;
         let buffer = self
             .buffers
             .entry(name.to_string())
-            .or_insert_with(|| Buffer::new(name.to_string()));
+            .or_insert_with(|| Self::configured(Buffer::new(name.to_string()), undo_levels));
         buffer.insert_char(row, col, ch);
+        self.notify_mutated(name, true);
     }
 
-    /// Save every dirty buffer to disk.
-    pub fn save_all(&mut self) -> io::Result<()> {
-        for buffer in self.buffers.values_mut() {
-            if buffer.is_dirty() {
-                buffer.save_to_disk()?;
+    /// Save every dirty buffer to disk, returning how many were written.
+    ///
+    /// Buffers still waiting for a name, read-only buffers, and buffers whose file changed on
+    /// disk since it was read are left dirty in memory, as `:w` would refuse them.
+    pub fn save_all(&mut self) -> io::Result<usize> {
+        let mut written = 0;
+        for (name, buffer) in self.buffers.iter_mut() {
+            if buffer.is_dirty()
+                && !buffer.requires_name()
+                && !buffer.is_scratch()
+                && !self.read_only
+                && !buffer.is_read_only()
+                && !buffer.disk_changed()
+            {
+                save_buffer(buffer, self.save_options, &self.backup)?;
+                self.observers.notify(|observer| observer.saved(name));
+                written += 1;
             }
         }
 
-        Ok(())
+        Ok(written)
+    }
+
+    /// Append the lines of the file at `path` to the named buffer, returning how many were added.
+    pub fn append_file(&mut self, name: &str, path: &Path) -> io::Result<usize> {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                let added = buffer.append_file(path)?;
+                self.notify_mutated(name, added > 0);
+                Ok(added)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no buffer named '{name}'"),
+            )),
+        }
+    }
+
+    /// Write a copy of the named buffer to `path` without marking it clean.
+    pub fn write_copy(&self, name: &str, path: &Path) -> io::Result<()> {
+        match self.buffers.get(name) {
+            Some(buffer) => buffer.write_copy(path, self.save_options),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no buffer named '{name}'"),
+            )),
+        }
+    }
+
+    /// Write the zero-based lines `range` of the named buffer to `path` without marking it
+    /// clean, as `:A,Bw path` does.
+    pub fn write_range_copy(&self, name: &str, range: Range<usize>, path: &Path) -> io::Result<()> {
+        match self.buffers.get(name) {
+            Some(buffer) => buffer.write_range_copy(range, path, self.save_options),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no buffer named '{name}'"),
+            )),
+        }
+    }
+
+    /// Write every resident buffer, closed ones included, to a file in `dir` named after it,
+    /// returning how many were written.
+    ///
+    /// Characters that are unsafe in file names become `_`, and names that collide once
+    /// sanitized get a `-2`, `-3`, ... suffix. Suspended buffers are skipped; resume them first.
+    pub fn export_all(&self, dir: &Path) -> io::Result<usize> {
+        fs::create_dir_all(dir)?;
+        let mut names: Vec<&String> = self.buffers.keys().collect();
+        names.sort();
+
+        let mut used = HashSet::new();
+        for name in &names {
+            let base = export_file_name(name);
+            let mut file_name = base.clone();
+            let mut suffix = 2;
+            while !used.insert(file_name.clone()) {
+                file_name = format!("{base}-{suffix}");
+                suffix += 1;
+            }
+            self.buffers[*name].write_copy(&dir.join(file_name), self.save_options)?;
+        }
+        Ok(names.len())
+    }
+
+    /// Whether the named buffer is a scratch buffer.
+    pub fn is_scratch(&self, name: &str) -> bool {
+        self.buffers.get(name).is_some_and(Buffer::is_scratch)
     }
 
     /// Save a specific buffer to disk when it exists.
     pub fn save(&mut self, name: &str) -> io::Result<()> {
         if let Some(buffer) = self.buffers.get_mut(name) {
-            buffer.save_to_disk()
-        } else {
-            Ok(())
+            save_buffer(buffer, self.save_options, &self.backup)?;
+            self.observers.notify(|observer| observer.saved(name));
         }
+        Ok(())
+    }
+
+    /// Reload the named buffer from its file, discarding in-memory edits.
+    pub fn load_from_disk(&mut self, name: &str) -> io::Result<()> {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.load_from_disk()?;
+                self.notify_mutated(name, true);
+                Ok(())
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no buffer named '{name}'"),
+            )),
+        }
+    }
+
+    /// Whether the named buffer's file changed on disk since it was loaded or saved.
+    ///
+    /// Unknown buffers and buffers without a backing file report `false`.
+    pub fn disk_changed(&self, name: &str) -> bool {
+        self.buffers
+            .get(name)
+            .is_some_and(|buffer| buffer.disk_changed())
     }
 
     /// Persist a buffer only if it is dirty, returning whether a write occurred.
     pub fn save_if_dirty(&mut self, name: &str) -> io::Result<bool> {
         if let Some(buffer) = self.buffers.get_mut(name) {
             if buffer.is_dirty() {
-                buffer.save_to_disk()?;
+                save_buffer(buffer, self.save_options, &self.backup)?;
+                self.observers.notify(|observer| observer.saved(name));
                 return Ok(true);
             }
         }
@@ -119,6 +557,18 @@ impl BufferStore {
         Ok(false)
     }
 
+    /// Save the named buffer to `path` from now on, keeping its name; returns whether it exists.
+    #[allow(dead_code)]
+    pub fn set_path(&mut self, name: &str, path: Option<String>) -> bool {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.set_path(path);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Mark a buffer clean without writing it to disk.
     pub fn save_in_memory(&mut self, name: &str) -> bool {
         if let Some(buffer) = self.buffers.get_mut(name) {
@@ -136,6 +586,33 @@ impl BufferStore {
             .unwrap_or(false)
     }
 
+    /// Whether the named buffer refuses edits, on its own or because every buffer does.
+    pub fn is_read_only(&self, name: &str) -> bool {
+        self.read_only || self.buffers.get(name).is_some_and(Buffer::is_read_only)
+    }
+
+    /// Mark a single buffer read-only or writable, returning whether it exists.
+    pub fn set_read_only(&mut self, name: &str, read_only: bool) -> bool {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.set_read_only(read_only);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Make every current and future buffer read-only, or lift both that and each buffer's own
+    /// read-only flag.
+    pub fn set_all_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        if !read_only {
+            for buffer in self.buffers.values_mut() {
+                buffer.set_read_only(false);
+            }
+        }
+    }
+
     /// Whether the buffer still needs to be given a user-specified name.
     pub fn requires_name(&self, name: &str) -> bool {
         self.buffers
@@ -146,40 +623,210 @@ impl BufferStore {
 
     /// Delete a character preceding the provided column, returning the new cursor position.
     pub fn delete_char(&mut self, name: &str, row: usize, col: usize) -> Option<(usize, usize)> {
-        let buffer = self.buffers.get_mut(name)?;
-        buffer.delete_char(row, col)
+        let position = self.buffers.get_mut(name)?.delete_char(row, col);
+        self.notify_mutated(name, position.is_some());
+        position
     }
 
     /// Insert a newline at the specified location, splitting or padding as needed.
     pub fn insert_newline(&mut self, name: &str, row: usize, col: usize) -> (usize, usize) {
+        let undo_levels = self.undo_levels;
         let buffer = self
             .buffers
             .entry(name.to_string())
-            .or_insert_with(|| Buffer::new(name.to_string()));
-        buffer.insert_newline(row, col)
+            .or_insert_with(|| Self::configured(Buffer::new(name.to_string()), undo_levels));
+        let position = buffer.insert_newline(row, col);
+        self.notify_mutated(name, true);
+        position
     }
 
-    /// Pad the requested line with spaces so it reaches `width` characters.
-    pub fn pad_line(&mut self, name: &str, row: usize, width: usize) {
-        let buffer = self
+    /// Remove `start_col..end_col` from a line, returning the removed text.
+    pub fn delete_range(
+        &mut self,
+        name: &str,
+        row: usize,
+        start_col: usize,
+        end_col: usize,
+    ) -> Option<String> {
+        let removed = self
             .buffers
-            .entry(name.to_string())
-            .or_insert_with(|| Buffer::new(name.to_string()));
-        buffer.pad_line(row, width);
+            .get_mut(name)?
+            .delete_range(row, start_col, end_col);
+        self.notify_mutated(name, removed.is_some());
+        removed
+    }
+
+    /// Insert whole lines starting at `row` in the named buffer.
+    pub fn insert_lines(&mut self, name: &str, row: usize, lines: &[String]) -> bool {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.insert_lines(row, lines);
+                self.notify_mutated(name, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the named buffer's contents, returning whether it exists.
+    pub fn replace_lines(&mut self, name: &str, lines: Vec<String>) -> bool {
+        match self.buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.replace_lines(lines);
+                self.notify_mutated(name, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a whole line from the named buffer, returning the removed text.
+    pub fn delete_line(&mut self, name: &str, row: usize) -> Option<String> {
+        let removed = self.buffers.get_mut(name)?.delete_line(row);
+        self.notify_mutated(name, removed.is_some());
+        removed
+    }
+
+    /// Join line `row + 1` onto `row`, returning the column where the lines meet.
+    pub fn join_lines(&mut self, name: &str, row: usize) -> Option<usize> {
+        let joined = self.buffers.get_mut(name)?.join_lines(row);
+        self.notify_mutated(name, joined.is_some());
+        joined
+    }
+
+    /// Edit the named buffer's lines in one transaction, returning whether they changed.
+    ///
+    /// `edit` receives the lines to change freely; the whole edit becomes a single undo step
+    /// and marks the buffer dirty once, so one `undo` reverts all of it.
+    pub fn apply_edit(&mut self, name: &str, edit: impl FnOnce(&mut Vec<String>)) -> bool {
+        let changed = self
+            .buffers
+            .get_mut(name)
+            .is_some_and(|buffer| buffer.apply_edit(edit));
+        self.notify_mutated(name, changed);
+        changed
+    }
+
+    /// Sort the named buffer's lines, returning whether the order changed.
+    pub fn sort_lines(&mut self, name: &str, numeric: bool) -> bool {
+        let changed = self
+            .buffers
+            .get_mut(name)
+            .is_some_and(|buffer| buffer.sort_lines(numeric));
+        self.notify_mutated(name, changed);
+        changed
+    }
+
+    /// Replace `pattern` with `replacement` on a single line, returning whether it changed.
+    #[allow(dead_code)]
+    pub fn substitute(
+        &mut self,
+        name: &str,
+        row: usize,
+        pattern: &str,
+        replacement: &str,
+        all: bool,
+    ) -> bool {
+        let changed = self
+            .buffers
+            .get_mut(name)
+            .is_some_and(|buffer| buffer.substitute(row, pattern, replacement, all));
+        self.notify_mutated(name, changed);
+        changed
+    }
+
+    /// Align a single line's text, returning whether it changed.
+    pub fn align_line(&mut self, name: &str, row: usize, alignment: Alignment) -> bool {
+        let changed = self
+            .buffers
+            .get_mut(name)
+            .is_some_and(|buffer| buffer.align_line(row, alignment));
+        self.notify_mutated(name, changed);
+        changed
+    }
+
+    /// Start collecting the named buffer's edits into one undo step until
+    /// [`Self::end_undo_group`].
+    pub fn begin_undo_group(&mut self, name: &str) {
+        if let Some(buffer) = self.buffers.get_mut(name) {
+            buffer.begin_undo_group();
+        }
+    }
+
+    /// Record the named buffer's edits since [`Self::begin_undo_group`] as one undo step.
+    pub fn end_undo_group(&mut self, name: &str) {
+        if let Some(buffer) = self.buffers.get_mut(name) {
+            buffer.end_undo_group();
+        }
+    }
+
+    /// Revert the most recent edit to a buffer, returning whether there was one to revert.
+    pub fn undo(&mut self, name: &str) -> bool {
+        let changed = self.buffers.get_mut(name).is_some_and(Buffer::undo);
+        self.notify_mutated(name, changed);
+        changed
+    }
+
+    /// Re-apply the most recently undone edit to a buffer, returning whether there was one.
+    pub fn redo(&mut self, name: &str) -> bool {
+        let changed = self.buffers.get_mut(name).is_some_and(Buffer::redo);
+        self.notify_mutated(name, changed);
+        changed
     }
 
     /// Mark a buffer as closed while leaving it in memory.
     pub fn mark_closed(&mut self, name: &str) -> bool {
         if let Some(buffer) = self.buffers.get_mut(name) {
-            buffer.set_open(false);
+            if buffer.is_open() {
+                buffer.set_open(false);
+                self.observers.notify(|observer| observer.closed(name));
+            }
             return true;
         }
         false
     }
 
+    /// Close every open buffer, returning the sorted names of dirty buffers left open.
+    ///
+    /// Dirty buffers are only closed when `force` is set.
+    pub fn close_all(&mut self, force: bool) -> Vec<String> {
+        let mut skipped = Vec::new();
+        for (name, buffer) in self.buffers.iter_mut() {
+            if !buffer.is_open() {
+                continue;
+            }
+            if buffer.is_dirty() && !force {
+                skipped.push(name.clone());
+                continue;
+            }
+            buffer.set_open(false);
+            self.observers.notify(|observer| observer.closed(name));
+        }
+        skipped.sort();
+        skipped
+    }
+
+    /// Reopen every tracked buffer, returning how many were previously closed.
+    pub fn open_all(&mut self) -> usize {
+        let mut reopened = 0;
+        for (name, buffer) in self.buffers.iter_mut() {
+            if !buffer.is_open() {
+                buffer.set_open(true);
+                self.observers.notify(|observer| observer.opened(name));
+                reopened += 1;
+            }
+        }
+        reopened
+    }
+
     /// Remove the specified buffer from memory entirely, regardless of whether it was suspended.
     pub fn remove(&mut self, name: &str) -> bool {
-        if self.buffers.remove(name).is_some() {
+        let was_suspended = self.is_suspended(name);
+        if let Some(idle) = self.idle.as_mut() {
+            idle.forget(name);
+        }
+        if self.buffers.remove(name).is_some() || was_suspended {
+            self.observers.notify(|observer| observer.closed(name));
             return true;
         }
         return false;
@@ -192,6 +839,10 @@ impl BufferStore {
             return false;
         }
 
+        if self.resume_suspended(old_name).is_err() || self.is_suspended(new_name) {
+            return false;
+        }
+
         if self.buffers.contains_key(new_name) {
             return false;
         }
@@ -200,34 +851,225 @@ impl BufferStore {
             Some(mut buffer) => {
                 buffer.set_name(new_name.to_string());
                 self.buffers.insert(new_name.to_string(), buffer);
+                self.observers
+                    .notify(|observer| observer.renamed(old_name, new_name));
                 true
             }
             None => false,
         }
     }
 
-    /// Produce snapshots of every buffer for persistence.
+    /// Produce snapshots of every buffer for persistence, skipping scratch buffers.
+    #[allow(dead_code)]
     pub fn snapshots(&self) -> Vec<BufferSnapshot> {
         self.buffers
             .values()
+            .filter(|buffer| !buffer.is_scratch())
             .map(|buffer| buffer.to_snapshot())
             .collect()
     }
 
+    /// Split non-scratch buffers by whether they changed since the last
+    /// [`mark_persisted`](Self::mark_persisted).
+    ///
+    /// Suspended buffers count as unchanged: the suspend backend already wrote their record.
+    pub fn snapshot_diff(&self) -> SnapshotDiff {
+        let mut diff = SnapshotDiff {
+            unchanged: self.suspended_names(),
+            ..SnapshotDiff::default()
+        };
+        let mut names: Vec<&String> = self
+            .buffers
+            .iter()
+            .filter(|(_, buffer)| !buffer.is_scratch())
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        for name in names {
+            let buffer = &self.buffers[name];
+            let revision = buffer.revision();
+            if self.persisted.get(name) == Some(&revision) {
+                diff.unchanged.push(name.clone());
+            } else {
+                diff.changed.push(buffer.to_snapshot());
+            }
+            diff.revisions.push((name.clone(), revision));
+        }
+        diff
+    }
+
+    /// Record that the buffers covered by `diff` are now persisted as of that diff.
+    pub fn mark_persisted(&mut self, diff: &SnapshotDiff) {
+        self.persisted = diff.revisions.iter().cloned().collect();
+    }
+
+    /// Add a buffer restored from `snapshot` unless one with that name is already loaded.
+    ///
+    /// Returns whether the snapshot was inserted.
+    pub fn insert_snapshot(&mut self, snapshot: BufferSnapshot) -> bool {
+        if self.buffers.contains_key(&snapshot.name) {
+            return false;
+        }
+        let key = snapshot.name.clone();
+        let buffer = Self::configured(Buffer::from_snapshot(snapshot), self.undo_levels);
+        self.buffers.insert(key, buffer);
+        true
+    }
+
     /// Replace the current store contents with the provided snapshots.
     pub fn hydrate(&mut self, snapshots: Vec<BufferSnapshot>) {
         self.buffers.clear();
         for snapshot in snapshots {
             let key = snapshot.name.clone();
-            let buffer = Buffer::from_snapshot(snapshot);
+            let buffer = Self::configured(Buffer::from_snapshot(snapshot), self.undo_levels);
             self.buffers.insert(key, buffer);
         }
     }
 }
 
+/// Save `buffer`, first backing up the file it replaces when `backup` asks for it.
+fn save_buffer(
+    buffer: &mut Buffer,
+    options: SaveOptions,
+    backup: &BackupOptions,
+) -> io::Result<()> {
+    if !buffer.is_scratch() {
+        backup.write_backup(Path::new(buffer.storage_path()))?;
+    }
+    buffer.save_to_disk(options)
+}
+
+/// File name `export_all` uses for the buffer `name`.
+fn export_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            ch if ch.is_control() => '_',
+            ch => ch,
+        })
+        .collect();
+    match sanitized.as_str() {
+        "" | "." | ".." => "_".repeat(sanitized.len().max(1)),
+        _ => sanitized,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::BufferStore;
+    use crate::store::buffer_snapshot::BufferSnapshot;
+    use crate::store::persistence::PersistenceResult;
+    use crate::store::suspend::SuspendBackend;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[derive(Default)]
+    struct MemoryBackend {
+        stored: Mutex<HashMap<String, BufferSnapshot>>,
+    }
+
+    impl SuspendBackend for MemoryBackend {
+        fn suspend(&self, snapshots: &[BufferSnapshot]) -> PersistenceResult<()> {
+            let mut stored = self.stored.lock().unwrap();
+            for snapshot in snapshots {
+                stored.insert(snapshot.name.clone(), snapshot.clone());
+            }
+            Ok(())
+        }
+
+        fn resume(&self, name: &str) -> PersistenceResult<Option<BufferSnapshot>> {
+            Ok(self.stored.lock().unwrap().get(name).cloned())
+        }
+    }
+
+    #[test]
+    fn idle_buffers_are_suspended_and_reloaded_on_access() {
+        let backend = Arc::new(MemoryBackend::default());
+        let timeout = Duration::from_millis(500);
+        let mut store = BufferStore::new();
+        store.enable_idle_suspend(timeout, backend.clone());
+        store.open("idle").append("keep me".into());
+        let start = Instant::now();
+
+        assert!(store.suspend_idle_at(start).unwrap().is_empty());
+        let suspended = store.suspend_idle_at(start + timeout * 2).unwrap();
+
+        assert_eq!(suspended, vec!["idle".to_string()]);
+        assert!(store.get("idle").is_none());
+        assert!(store.is_suspended("idle"));
+        assert_eq!(store.list(), vec!["idle".to_string()]);
+        assert!(backend.stored.lock().unwrap().contains_key("idle"));
+
+        let buffer = store.access("idle").unwrap().expect("buffer should reload");
+        assert_eq!(buffer.lines(), &["keep me".to_string()]);
+        assert!(buffer.is_dirty());
+        assert!(!store.is_suspended("idle"));
+    }
+
+    #[test]
+    fn recently_accessed_buffers_stay_resident() {
+        let timeout = Duration::from_secs(60);
+        let mut store = BufferStore::new();
+        store.enable_idle_suspend(timeout, Arc::new(MemoryBackend::default()));
+        store.open("busy");
+        store.open("quiet");
+
+        let later = Instant::now() + timeout;
+        assert!(store.access("busy").unwrap().is_some());
+        let suspended = store.suspend_idle_at(later).unwrap();
+
+        assert_eq!(suspended, vec!["quiet".to_string()]);
+        assert!(store.get("busy").is_some());
+
+        store.resume_all().unwrap();
+        assert!(store.get("quiet").is_some());
+        assert_eq!(store.snapshots().len(), 2);
+    }
+
+    #[test]
+    fn snapshot_diff_reports_buffers_changed_since_mark_persisted() {
+        let mut store = BufferStore::new();
+        store.open("alpha");
+        store.open("beta");
+        store.open_scratch("scratch");
+
+        let diff = store.snapshot_diff();
+        let changed: Vec<&str> = diff.changed.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(changed, vec!["alpha", "beta"]);
+        store.mark_persisted(&diff);
+        assert!(store.snapshot_diff().changed.is_empty());
+
+        store.insert_char("beta", 0, 0, 'x');
+        let diff = store.snapshot_diff();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].name, "beta");
+        assert_eq!(diff.unchanged, vec!["alpha".to_string()]);
+        store.mark_persisted(&diff);
+
+        store.remove("alpha");
+        store.open("alpha");
+        let diff = store.snapshot_diff();
+        assert_eq!(diff.changed.len(), 1, "a recreated buffer is a new record");
+        assert_eq!(diff.changed[0].name, "alpha");
+    }
+
+    #[test]
+    fn snapshot_diff_retains_suspended_buffers() {
+        let timeout = Duration::from_secs(60);
+        let mut store = BufferStore::new();
+        store.enable_idle_suspend(timeout, Arc::new(MemoryBackend::default()));
+        store.open("quiet");
+        let diff = store.snapshot_diff();
+        store.mark_persisted(&diff);
+
+        store.suspend_idle_at(Instant::now() + timeout).unwrap();
+        let diff = store.snapshot_diff();
+
+        assert!(diff.changed.is_empty());
+        assert_eq!(diff.retained_names().collect::<Vec<_>>(), vec!["quiet"]);
+    }
 
     #[test]
     fn open_creates_and_returns_buffer() {
@@ -302,6 +1144,21 @@ mod tests {
         assert_eq!(store.open_buffers(), vec!["beta".to_string()]);
     }
 
+    #[test]
+    fn line_and_char_counts_read_without_snapshots() {
+        let mut store = BufferStore::new();
+        let buffer = store.open("alpha");
+        buffer.append("abc".into());
+        buffer.append("h\u{e9}llo".into());
+
+        assert_eq!(store.line_count("alpha"), 2);
+        assert_eq!(store.char_count("alpha", 0), 3);
+        assert_eq!(store.char_count("alpha", 1), 5);
+        assert_eq!(store.char_count("alpha", 2), 0);
+        assert_eq!(store.line_count("missing"), 0);
+        assert_eq!(store.char_count("missing", 0), 0);
+    }
+
     #[test]
     fn save_in_memory_marks_buffer_clean() {
         let mut store = BufferStore::new();
@@ -1,10 +1,15 @@
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use lz4_flex::frame::{Error as Lz4FrameError, FrameDecoder, FrameEncoder};
 use std::io::{Read, Write};
+use std::path::Path;
 use thiserror::Error;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionAlgorithm {
     Lz4,
+    Gzip,
 }
 
 impl CompressionAlgorithm {
@@ -15,12 +20,42 @@ impl CompressionAlgorithm {
     pub fn flag_bit(self) -> u32 {
         match self {
             CompressionAlgorithm::Lz4 => 0x0010,
+            CompressionAlgorithm::Gzip => 0x0020,
         }
     }
 
+    pub fn name(self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Lz4 => "lz4",
+            CompressionAlgorithm::Gzip => "gzip",
+        }
+    }
+
+    /// File extension (without the dot) used for files compressed with this algorithm.
+    pub fn extension(self) -> &'static str {
+        match self {
+            CompressionAlgorithm::Lz4 => "lz4",
+            CompressionAlgorithm::Gzip => "gz",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "lz4" => Some(CompressionAlgorithm::Lz4),
+            "gz" => Some(CompressionAlgorithm::Gzip),
+            _ => None,
+        }
+    }
+
+    /// Algorithm implied by the extension of `path`, if it names a compressed file.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
     pub fn from_name(name: &str) -> Option<Self> {
         match name.trim().to_ascii_lowercase().as_str() {
             "lz4" => Some(CompressionAlgorithm::Lz4),
+            "gzip" | "gz" => Some(CompressionAlgorithm::Gzip),
             _ => None,
         }
     }
@@ -42,6 +77,11 @@ pub fn compress(data: &[u8], algorithm: CompressionAlgorithm) -> Result<Vec<u8>,
             let output = encoder.finish()?;
             Ok(output)
         }
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
     }
 }
 
@@ -56,5 +96,11 @@ pub fn decompress(
             decoder.read_to_end(&mut output)?;
             Ok(output)
         }
+        CompressionAlgorithm::Gzip => {
+            let mut decoder = GzDecoder::new(data);
+            let mut output = Vec::new();
+            decoder.read_to_end(&mut output)?;
+            Ok(output)
+        }
     }
 }
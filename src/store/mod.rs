@@ -1,5 +1,9 @@
+pub mod backup;
 pub mod buffer;
 pub mod buffer_snapshot;
 pub mod buffer_store;
 pub mod compress;
+pub mod observer;
 pub mod persistence;
+pub mod suspend;
+pub mod undo;
@@ -0,0 +1,48 @@
+//! Lifecycle notifications for code embedding the buffer store.
+
+use std::fmt;
+use std::sync::Arc;
+
+/// Receives [`BufferStore`](super::buffer_store::BufferStore) lifecycle events.
+///
+/// Every method defaults to doing nothing, so observers only implement what they need. Events
+/// fire from inside the store's methods while it is mutably borrowed, so observers must not
+/// call back into it.
+pub trait BufferObserver: Send + Sync {
+    /// A buffer was created or a closed buffer was reopened.
+    fn opened(&self, _name: &str) {}
+    /// A buffer was closed or removed from the store.
+    fn closed(&self, _name: &str) {}
+    /// A buffer was renamed from `old_name` to `new_name`.
+    fn renamed(&self, _old_name: &str, _new_name: &str) {}
+    /// A buffer was written to disk.
+    fn saved(&self, _name: &str) {}
+    /// A buffer's text was changed through one of the store's editing helpers.
+    fn mutated(&self, _name: &str) {}
+}
+
+/// Observers registered with a store, notified in registration order.
+#[derive(Clone, Default)]
+pub(crate) struct ObserverList {
+    observers: Vec<Arc<dyn BufferObserver>>,
+}
+
+impl ObserverList {
+    pub(crate) fn push(&mut self, observer: Arc<dyn BufferObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub(crate) fn notify(&self, event: impl Fn(&dyn BufferObserver)) {
+        for observer in &self.observers {
+            event(observer.as_ref());
+        }
+    }
+}
+
+impl fmt::Debug for ObserverList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObserverList")
+            .field("len", &self.observers.len())
+            .finish()
+    }
+}
@@ -1,25 +1,74 @@
 use super::error::{PersistenceError, PersistenceResult};
-use super::pipeline::PersistencePipeline;
+use super::pipeline::{CompressionStats, PersistencePipeline};
 use crate::store::buffer_snapshot::BufferSnapshot;
+use sha2::{Digest, Sha256};
 use std::convert::TryInto;
 use std::fs::{self, File};
 use std::io::{self, BufReader, BufWriter, Cursor, Read, Write};
 use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 const MAGIC: &[u8; 8] = b"IRDBUF\0\0";
-const FORMAT_VERSION: u32 = 1;
+/// Every record is preceded by its buffer's [`NameTag`] in the clear.
+const FORMAT_VERSION: u32 = 3;
+/// Every buffer is a length-prefixed record run through the pipeline on its own.
+const UNTAGGED_RECORDS_VERSION: u32 = 2;
+/// Every buffer was run through the pipeline together as one payload.
+const WHOLE_PAYLOAD_VERSION: u32 = 1;
 #[cfg_attr(not(test), allow(dead_code))]
 const HEADER_SIZE: usize = 32;
+/// Set in a buffer's fourth flag byte when a modification timestamp follows its path.
+const FLAG_HAS_MODIFIED: u8 = 0b1;
 
 pub struct BinaryBufferDb;
 
+/// Digest of a buffer name, stored beside its record so the record can be matched to its
+/// buffer without decrypting it.
+pub type NameTag = [u8; 32];
+
+/// The [`NameTag`] written for the buffer called `name`.
+pub fn name_tag(name: &str) -> NameTag {
+    Sha256::new()
+        .chain_update(b"iridium buffer name\0")
+        .chain_update(name.as_bytes())
+        .finalize()
+        .into()
+}
+
+/// One buffer's encoded record, exactly as written to disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredRecord {
+    /// Tag of the buffer the record holds; `None` in databases written before tags existed.
+    pub tag: Option<NameTag>,
+    pub bytes: Vec<u8>,
+}
+
+/// Buffers read from a database, in the form its format version stored them.
+pub enum StoredBuffers {
+    /// One encoded record per buffer.
+    Records(Vec<StoredRecord>),
+    /// Buffers from a version 1 database, which has no separate records.
+    Snapshots(Vec<BufferSnapshot>),
+}
+
 impl BinaryBufferDb {
     pub fn load(
         path: &Path,
         pipeline: &PersistencePipeline,
     ) -> PersistenceResult<Vec<BufferSnapshot>> {
+        match Self::read(path, pipeline)? {
+            StoredBuffers::Records(records) => records
+                .iter()
+                .map(|record| Self::decode_record(pipeline, &record.bytes))
+                .collect(),
+            StoredBuffers::Snapshots(snapshots) => Ok(snapshots),
+        }
+    }
+
+    /// Read the database without decoding individual records.
+    pub fn read(path: &Path, pipeline: &PersistencePipeline) -> PersistenceResult<StoredBuffers> {
         if !path.exists() {
-            return Ok(Vec::new());
+            return Ok(StoredBuffers::Records(Vec::new()));
         }
         let file = File::open(path)?;
         let mut reader = BufReader::new(file);
@@ -27,35 +76,60 @@ impl BinaryBufferDb {
         if header.magic != *MAGIC {
             return Err(PersistenceError::InvalidMagic);
         }
-        if header.version != FORMAT_VERSION {
+        if ![
+            FORMAT_VERSION,
+            UNTAGGED_RECORDS_VERSION,
+            WHOLE_PAYLOAD_VERSION,
+        ]
+        .contains(&header.version)
+        {
             return Err(PersistenceError::UnsupportedVersion(header.version));
         }
-
-        let mut payload = Vec::new();
-        reader.read_to_end(&mut payload)?;
         if header.flags != pipeline.flags() {
             return Err(PersistenceError::UnsupportedFlags(header.flags));
         }
-        let decoded = pipeline.decode(payload)?;
-        let mut cursor = Cursor::new(decoded);
 
         let buffer_count: usize = header
             .buffer_count
             .try_into()
             .map_err(|_| PersistenceError::ValueOverflow("buffer_count"))?;
-        let mut snapshots = Vec::with_capacity(buffer_count);
 
-        for _ in 0..buffer_count {
-            snapshots.push(Self::read_buffer(&mut cursor)?);
+        if header.version == WHOLE_PAYLOAD_VERSION {
+            let mut payload = Vec::new();
+            reader.read_to_end(&mut payload)?;
+            let mut cursor = Cursor::new(pipeline.decode(payload)?);
+            let mut snapshots = Vec::with_capacity(buffer_count);
+            for _ in 0..buffer_count {
+                snapshots.push(Self::read_buffer(&mut cursor)?);
+            }
+            return Ok(StoredBuffers::Snapshots(snapshots));
         }
 
-        Ok(snapshots)
+        let mut records = Vec::with_capacity(buffer_count);
+        for _ in 0..buffer_count {
+            let tag = if header.version == FORMAT_VERSION {
+                let mut tag = NameTag::default();
+                reader.read_exact(&mut tag)?;
+                Some(tag)
+            } else {
+                None
+            };
+            let record_len: usize = read_u64(&mut reader)?
+                .try_into()
+                .map_err(|_| PersistenceError::ValueOverflow("record length"))?;
+            let mut bytes = vec![0u8; record_len];
+            reader.read_exact(&mut bytes)?;
+            records.push(StoredRecord { tag, bytes });
+        }
+        Ok(StoredBuffers::Records(records))
     }
 
-    pub fn store(
+    /// Atomically replace the database with already encoded records, each under its buffer's
+    /// tag.
+    pub fn write_records(
         path: &Path,
         pipeline: &PersistencePipeline,
-        snapshots: &[BufferSnapshot],
+        records: &[(NameTag, Vec<u8>)],
     ) -> PersistenceResult<()> {
         if let Some(parent) = path.parent() {
             if !parent.as_os_str().is_empty() {
@@ -67,11 +141,13 @@ impl BinaryBufferDb {
 
         let file = File::create(&temp_path)?;
         let mut writer = BufWriter::new(file);
-        let payload = Self::encode_snapshots(snapshots)?;
-        let transformed = pipeline.encode(payload)?;
-        let header = FileHeader::new(pipeline.flags(), snapshots.len() as u64);
+        let header = FileHeader::new(pipeline.flags(), records.len() as u64);
         header.write(&mut writer)?;
-        writer.write_all(&transformed)?;
+        for (tag, record) in records {
+            writer.write_all(tag)?;
+            write_u64(&mut writer, record.len() as u64)?;
+            writer.write_all(record)?;
+        }
         writer.flush()?;
         writer.get_ref().sync_all()?;
         drop(writer);
@@ -81,12 +157,23 @@ impl BinaryBufferDb {
         Ok(())
     }
 
-    fn encode_snapshots(snapshots: &[BufferSnapshot]) -> PersistenceResult<Vec<u8>> {
+    /// Serialize one buffer and run it through the pipeline, also returning its compression
+    /// sizes.
+    pub fn encode_record(
+        pipeline: &PersistencePipeline,
+        snapshot: &BufferSnapshot,
+    ) -> PersistenceResult<(Vec<u8>, CompressionStats)> {
         let mut payload = Vec::new();
-        for snapshot in snapshots {
-            Self::write_buffer(&mut payload, snapshot)?;
-        }
-        Ok(payload)
+        Self::write_buffer(&mut payload, snapshot)?;
+        pipeline.encode_with_stats(payload)
+    }
+
+    pub fn decode_record(
+        pipeline: &PersistencePipeline,
+        record: &[u8],
+    ) -> PersistenceResult<BufferSnapshot> {
+        let decoded = pipeline.decode(record.to_vec())?;
+        Self::read_buffer(&mut Cursor::new(decoded))
     }
 
     fn read_buffer(reader: &mut dyn Read) -> PersistenceResult<BufferSnapshot> {
@@ -94,24 +181,37 @@ impl BinaryBufferDb {
         let line_count = read_u32(reader)?;
         let mut flags = [0u8; 4];
         reader.read_exact(&mut flags)?;
-        let _padding1 = read_u32(reader)?;
+        // Formerly padding, so older databases read back as having no separate path.
+        let path_len = read_u32(reader)? as usize;
 
         let mut name_bytes = vec![0u8; name_len];
         reader.read_exact(&mut name_bytes)?;
         let name = String::from_utf8(name_bytes)?;
 
+        let path = if path_len > 0 {
+            let mut path_bytes = vec![0u8; path_len];
+            reader.read_exact(&mut path_bytes)?;
+            Some(String::from_utf8(path_bytes)?)
+        } else {
+            None
+        };
+
+        let modified = if flags[3] & FLAG_HAS_MODIFIED != 0 {
+            Some(UNIX_EPOCH + Duration::from_nanos(read_u64(reader)?))
+        } else {
+            None
+        };
+
         let mut lines = Vec::with_capacity(line_count as usize);
         for _ in 0..line_count {
             lines.push(Self::read_line(reader)?);
         }
 
-        Ok(BufferSnapshot::new(
-            name,
-            lines,
-            flags[0] != 0,
-            flags[1] != 0,
-            flags[2] != 0,
-        ))
+        Ok(
+            BufferSnapshot::new(name, lines, flags[0] != 0, flags[1] != 0, flags[2] != 0)
+                .with_path(path)
+                .with_modified(modified),
+        )
     }
 
     fn write_buffer(writer: &mut dyn Write, snapshot: &BufferSnapshot) -> PersistenceResult<()> {
@@ -120,6 +220,11 @@ impl BinaryBufferDb {
             .len()
             .try_into()
             .map_err(|_| PersistenceError::ValueOverflow("buffer name length"))?;
+        let path_bytes = snapshot.path.as_deref().unwrap_or_default().as_bytes();
+        let path_len: u32 = path_bytes
+            .len()
+            .try_into()
+            .map_err(|_| PersistenceError::ValueOverflow("buffer path length"))?;
         let line_count: u32 = snapshot
             .lines
             .len()
@@ -129,16 +234,26 @@ impl BinaryBufferDb {
         write_u32(writer, name_len)?;
         write_u32(writer, line_count)?;
 
+        let modified = snapshot.modified.map(nanos_since_epoch).transpose()?;
+
         let flags = [
             bool_to_u8(snapshot.requires_name),
             bool_to_u8(snapshot.is_open),
             bool_to_u8(snapshot.dirty),
-            0u8,
+            if modified.is_some() {
+                FLAG_HAS_MODIFIED
+            } else {
+                0
+            },
         ];
         writer.write_all(&flags)?;
-        write_u32(writer, 0)?;
+        write_u32(writer, path_len)?;
 
         writer.write_all(name_bytes)?;
+        writer.write_all(path_bytes)?;
+        if let Some(nanos) = modified {
+            write_u64(writer, nanos)?;
+        }
 
         for line in &snapshot.lines {
             Self::write_line(writer, line)?;
@@ -244,6 +359,14 @@ fn read_u64(reader: &mut dyn Read) -> io::Result<u64> {
     Ok(u64::from_le_bytes(buf))
 }
 
+fn nanos_since_epoch(time: SystemTime) -> PersistenceResult<u64> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    since_epoch
+        .as_nanos()
+        .try_into()
+        .map_err(|_| PersistenceError::ValueOverflow("modification time"))
+}
+
 fn bool_to_u8(value: bool) -> u8 {
     if value { 1 } else { 0 }
 }
@@ -271,4 +394,51 @@ mod tests {
         assert_eq!(parsed.flags, 0xAB);
         assert_eq!(parsed.buffer_count, 42);
     }
+
+    #[test]
+    fn reads_whole_payload_databases() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("legacy.db");
+        let pipeline = PersistencePipeline::new();
+        let snapshots = vec![
+            BufferSnapshot::new("alpha".into(), vec!["a".into()], false, true, false),
+            BufferSnapshot::new("beta".into(), vec![], true, false, true),
+        ];
+        let mut bytes = Vec::new();
+        let mut header = FileHeader::new(pipeline.flags(), snapshots.len() as u64);
+        header.version = WHOLE_PAYLOAD_VERSION;
+        header.write(&mut bytes).unwrap();
+        let mut payload = Vec::new();
+        for snapshot in &snapshots {
+            BinaryBufferDb::write_buffer(&mut payload, snapshot).unwrap();
+        }
+        bytes.extend(pipeline.encode(payload).unwrap());
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(BinaryBufferDb::load(&path, &pipeline).unwrap(), snapshots);
+    }
+
+    #[test]
+    fn records_are_tagged_with_their_buffer_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagged.db");
+        let pipeline = PersistencePipeline::new();
+        let snapshot = BufferSnapshot::new("alpha".into(), vec!["a".into()], false, true, false);
+        let (record, _) = BinaryBufferDb::encode_record(&pipeline, &snapshot).unwrap();
+        BinaryBufferDb::write_records(&path, &pipeline, &[(name_tag("alpha"), record.clone())])
+            .unwrap();
+
+        let StoredBuffers::Records(records) = BinaryBufferDb::read(&path, &pipeline).unwrap()
+        else {
+            panic!("expected a record database");
+        };
+        assert_eq!(
+            records,
+            vec![StoredRecord {
+                tag: Some(name_tag("alpha")),
+                bytes: record,
+            }]
+        );
+        assert_ne!(name_tag("alpha"), name_tag("beta"));
+    }
 }
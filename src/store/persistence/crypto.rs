@@ -4,11 +4,13 @@ use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce as AesNonce};
 use chacha20poly1305::{ChaCha20Poly1305, Nonce as ChaChaNonce};
 use hex::FromHex;
+use hkdf::Hkdf;
 use pbkdf2::pbkdf2_hmac;
 use rand_core::{OsRng, RngCore};
 use sha2::Sha256;
 use std::env;
 use std::fs;
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 
 pub(crate) const ENCRYPT_ENV: &str = "IRIDIUM_PERSIST_ENCRYPT";
 const ENCRYPT_ALGO_ENV: &str = "IRIDIUM_PERSIST_ALGO";
@@ -16,9 +18,14 @@ const ENCRYPT_KEY_ENV: &str = "IRIDIUM_PERSIST_KEY";
 const ENCRYPT_KEY_FILE_ENV: &str = "IRIDIUM_PERSIST_KEY_FILE";
 const ENCRYPT_PASSPHRASE_ENV: &str = "IRIDIUM_PERSIST_PASSPHRASE";
 const ENCRYPT_PBKDF_ITERS_ENV: &str = "IRIDIUM_PERSIST_PBKDF_ITERS";
+const ENCRYPT_RECIPIENT_ENV: &str = "IRIDIUM_PERSIST_RECIPIENT";
+const ENCRYPT_IDENTITY_FILE_ENV: &str = "IRIDIUM_PERSIST_IDENTITY_FILE";
 const DEFAULT_PBKDF2_ITERS: u32 = 600_000;
 const KEY_LEN: usize = 32;
 const SALT_LEN: usize = 16;
+/// Header flag marking a database whose key was wrapped to an X25519 public key.
+const PUBLIC_KEY_FLAG: u32 = 0x0100;
+const X25519_HKDF_INFO: &[u8] = b"iridium persistence x25519";
 
 pub fn resolve_encryption(config: Option<&ConfigurationModel>) -> EncryptionMode {
     if let Ok(val) = env::var(ENCRYPT_ENV) {
@@ -89,6 +96,18 @@ impl EncryptionMode {
             key_source,
         }))
     }
+
+    /// Whether a database written in this mode can be read back with the same configuration,
+    /// which is not the case when encrypting to a recipient without its identity.
+    pub fn can_decrypt(&self) -> bool {
+        !matches!(
+            self,
+            EncryptionMode::Enabled(EncryptionSettings {
+                key_source: EncryptionKeySource::Recipient { .. },
+                ..
+            })
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -97,6 +116,17 @@ pub struct EncryptionSettings {
     pub key_source: EncryptionKeySource,
 }
 
+impl EncryptionSettings {
+    /// Header flags for this configuration: the cipher, plus a bit for public-key wrapping.
+    pub fn flag_bit(&self) -> u32 {
+        if self.key_source.is_public_key() {
+            self.algorithm.flag_bit() | PUBLIC_KEY_FLAG
+        } else {
+            self.algorithm.flag_bit()
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum EncryptionAlgorithm {
     ChaCha20Poly1305,
@@ -111,6 +141,13 @@ impl EncryptionAlgorithm {
         }
     }
 
+    pub fn name(&self) -> &'static str {
+        match self {
+            EncryptionAlgorithm::ChaCha20Poly1305 => "chacha20poly1305",
+            EncryptionAlgorithm::Aes256Gcm => "aes256gcm",
+        }
+    }
+
     pub fn nonce_len(&self) -> usize {
         12
     }
@@ -181,12 +218,38 @@ impl Default for EncryptionAlgorithm {
 #[derive(Debug, Clone)]
 pub enum EncryptionKeySource {
     RawKey([u8; KEY_LEN]),
-    Passphrase { passphrase: String, iterations: u32 },
+    Passphrase {
+        passphrase: String,
+        iterations: u32,
+    },
+    /// X25519 public key to encrypt to; only the matching [`Identity`](Self::Identity) decrypts.
+    Recipient {
+        pubkey: [u8; KEY_LEN],
+    },
+    /// X25519 private key, which decrypts and encrypts to its own public key.
+    Identity {
+        privkey: [u8; KEY_LEN],
+    },
 }
 
 impl EncryptionKeySource {
+    /// Whether the data key is wrapped to an X25519 key pair rather than shared directly.
+    pub fn is_public_key(&self) -> bool {
+        matches!(
+            self,
+            EncryptionKeySource::Recipient { .. } | EncryptionKeySource::Identity { .. }
+        )
+    }
+
     pub fn derive_for_encrypt(&self) -> PersistenceResult<KeyMaterial> {
         match self {
+            EncryptionKeySource::Recipient { pubkey } => {
+                Ok(wrap_to_recipient(&PublicKey::from(*pubkey)))
+            }
+            EncryptionKeySource::Identity { privkey } => {
+                let secret = StaticSecret::from(*privkey);
+                Ok(wrap_to_recipient(&PublicKey::from(&secret)))
+            }
             EncryptionKeySource::RawKey(key) => Ok(KeyMaterial {
                 key: *key,
                 salt: None,
@@ -230,10 +293,59 @@ impl EncryptionKeySource {
                 }
                 derive_key_from_passphrase(passphrase, salt, *iterations)
             }
+            EncryptionKeySource::Recipient { .. } => {
+                Err(PersistenceError::InvalidEncryptionConfig(
+                    "a recipient public key cannot decrypt; configure the identity instead".into(),
+                ))
+            }
+            EncryptionKeySource::Identity { privkey } => {
+                let salt = salt.ok_or(PersistenceError::MissingSalt)?;
+                let ephemeral = <[u8; KEY_LEN]>::try_from(salt).map_err(|_| {
+                    PersistenceError::InvalidEncryptionConfig(
+                        "encrypted file ephemeral key length mismatch".into(),
+                    )
+                })?;
+                let ephemeral = PublicKey::from(ephemeral);
+                let secret = StaticSecret::from(*privkey);
+                let shared = secret.diffie_hellman(&ephemeral);
+                Ok(derive_key_from_shared_secret(
+                    shared.as_bytes(),
+                    &ephemeral,
+                    &PublicKey::from(&secret),
+                ))
+            }
         }
     }
 }
 
+/// Derive a fresh data key for `recipient` from an ephemeral key pair.
+///
+/// The ephemeral public key travels in the salt slot so the identity can redo the exchange.
+fn wrap_to_recipient(recipient: &PublicKey) -> KeyMaterial {
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(recipient);
+    KeyMaterial {
+        key: derive_key_from_shared_secret(shared.as_bytes(), &ephemeral_public, recipient),
+        salt: Some(ephemeral_public.as_bytes().to_vec()),
+    }
+}
+
+fn derive_key_from_shared_secret(
+    shared: &[u8; KEY_LEN],
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> [u8; KEY_LEN] {
+    let mut salt = [0u8; 2 * KEY_LEN];
+    salt[..KEY_LEN].copy_from_slice(ephemeral.as_bytes());
+    salt[KEY_LEN..].copy_from_slice(recipient.as_bytes());
+    let mut key = [0u8; KEY_LEN];
+    Hkdf::<Sha256>::new(Some(&salt), shared)
+        .expand(X25519_HKDF_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
 #[derive(Debug, Clone)]
 pub struct KeyMaterial {
     pub key: [u8; KEY_LEN],
@@ -279,6 +391,17 @@ fn parse_key_source_from_env() -> PersistenceResult<EncryptionKeySource> {
         });
     }
 
+    if let Ok(path) = env::var(ENCRYPT_IDENTITY_FILE_ENV) {
+        let contents = fs::read_to_string(path)?;
+        let privkey = decode_hex_key(contents.trim())?;
+        return Ok(EncryptionKeySource::Identity { privkey });
+    }
+
+    if let Ok(value) = env::var(ENCRYPT_RECIPIENT_ENV) {
+        let pubkey = decode_hex_key(&value)?;
+        return Ok(EncryptionKeySource::Recipient { pubkey });
+    }
+
     Err(PersistenceError::MissingEncryptionKey)
 }
 
@@ -305,6 +428,17 @@ fn parse_key_source_from_config(
         });
     }
 
+    if let Some(path) = section.resolved_identity_path(config) {
+        let contents = fs::read_to_string(&path)?;
+        let privkey = decode_hex_key(contents.trim())?;
+        return Ok(EncryptionKeySource::Identity { privkey });
+    }
+
+    if let Some(recipient) = section.recipient.as_ref() {
+        let pubkey = decode_hex_key(recipient)?;
+        return Ok(EncryptionKeySource::Recipient { pubkey });
+    }
+
     Err(PersistenceError::MissingEncryptionKey)
 }
 
@@ -1,13 +1,21 @@
-use super::binary::BinaryBufferDb;
+use super::binary::{BinaryBufferDb, NameTag, StoredBuffers, name_tag};
 use super::config::PersistenceConfig;
 use super::crypto::EncryptionMode;
-use super::error::PersistenceResult;
-use super::pipeline::{CompressionLayer, EncryptionLayer, PersistencePipeline};
-use crate::store::buffer_snapshot::BufferSnapshot;
+use super::error::{PersistenceError, PersistenceResult};
+use super::pipeline::{CompressionLayer, CompressionStats, EncryptionLayer, PersistencePipeline};
+use crate::store::buffer_snapshot::{BufferSnapshot, SnapshotDiff};
+use crate::store::suspend::SuspendBackend;
+use std::collections::HashSet;
+use std::sync::Mutex;
 
 pub struct PersistenceManager {
     config: PersistenceConfig,
     pipeline: PersistencePipeline,
+    /// Tags of the buffers this manager has encoded. A manager that cannot decrypt never sees
+    /// the buffers of earlier sessions, so it keeps every record outside this set.
+    encoded_tags: Mutex<HashSet<NameTag>>,
+    /// Compression sizes of the records encoded by the most recent write that encoded any.
+    last_compression: Mutex<Option<CompressionStats>>,
 }
 
 impl PersistenceManager {
@@ -17,7 +25,12 @@ impl PersistenceManager {
         if let EncryptionMode::Enabled(settings) = config.encryption().clone() {
             pipeline.push_layer(Box::new(EncryptionLayer::new(settings)));
         }
-        Self { config, pipeline }
+        Self {
+            config,
+            pipeline,
+            encoded_tags: Mutex::new(HashSet::new()),
+            last_compression: Mutex::new(None),
+        }
     }
 
     pub fn load(&self) -> PersistenceResult<Vec<BufferSnapshot>> {
@@ -27,14 +40,168 @@ impl PersistenceManager {
         }
     }
 
-    pub fn store(&self, snapshots: &[BufferSnapshot]) -> PersistenceResult<()> {
-        match self.config.path() {
-            Some(path) => BinaryBufferDb::store(path, &self.pipeline, snapshots),
-            None => Ok(()),
-        }
+    /// Replace the database with `snapshots`, returning the compression sizes of the write.
+    #[allow(dead_code)]
+    pub fn store(&self, snapshots: &[BufferSnapshot]) -> PersistenceResult<CompressionStats> {
+        let Some(path) = self.config.path() else {
+            return Ok(CompressionStats::default());
+        };
+        let mut stats = CompressionStats::default();
+        let records = snapshots
+            .iter()
+            .map(|snapshot| {
+                let record = self.encode_record(snapshot, &mut stats)?;
+                Ok((name_tag(&snapshot.name), record))
+            })
+            .collect::<PersistenceResult<Vec<_>>>()?;
+        BinaryBufferDb::write_records(path, &self.pipeline, &records)?;
+        *self
+            .encoded_tags
+            .lock()
+            .expect("encoded tags lock poisoned") = records.iter().map(|(tag, _)| *tag).collect();
+        self.record_compression(stats);
+        Ok(stats)
+    }
+
+    /// Compression sizes of the last write that encoded any buffer, or `None` before one.
+    pub fn last_compression(&self) -> Option<CompressionStats> {
+        *self
+            .last_compression
+            .lock()
+            .expect("compression stats lock poisoned")
+    }
+
+    /// Apply `diff` to the database, encoding only the changed buffers.
+    ///
+    /// Records of unchanged buffers are carried over byte for byte rather than decoded and
+    /// re-encoded, and records of buffers the diff no longer retains are dropped. The file is
+    /// still rewritten whole, through a temporary file, whenever anything changed; it is left
+    /// untouched otherwise. Returns how many records were encoded.
+    pub fn store_changes(&self, diff: &SnapshotDiff) -> PersistenceResult<usize> {
+        let retained = diff.retained_names().map(name_tag).collect();
+        self.merge_records(&diff.changed, Some(retained))
     }
 
     pub fn is_enabled(&self) -> bool {
         self.config.is_enabled()
     }
+
+    /// Whether this manager can read back what it writes; a recipient-only key cannot.
+    pub fn can_decrypt(&self) -> bool {
+        self.config.encryption().can_decrypt()
+    }
+
+    #[cfg(test)]
+    pub(super) fn pipeline_for_tests(&self) -> &PersistencePipeline {
+        &self.pipeline
+    }
+
+    // Write `changed` over the existing records, dropping those not `retained` (`None` keeps
+    // every record).
+    fn merge_records(
+        &self,
+        changed: &[BufferSnapshot],
+        retained: Option<HashSet<NameTag>>,
+    ) -> PersistenceResult<usize> {
+        let Some(path) = self.config.path() else {
+            return Ok(0);
+        };
+        let mut stats = CompressionStats::default();
+        let existing = match BinaryBufferDb::read(path, &self.pipeline)? {
+            StoredBuffers::Records(records) => records
+                .into_iter()
+                .map(|record| {
+                    let tag = match record.tag {
+                        Some(tag) => tag,
+                        // Untagged records predate tags and must be decoded to be matched.
+                        None => name_tag(
+                            &BinaryBufferDb::decode_record(&self.pipeline, &record.bytes)?.name,
+                        ),
+                    };
+                    Ok((tag, record.bytes))
+                })
+                .collect::<PersistenceResult<Vec<_>>>()?,
+            // Older databases hold no separate records, so every buffer is encoded afresh.
+            StoredBuffers::Snapshots(snapshots) => snapshots
+                .iter()
+                .map(|snapshot| {
+                    let record = self.encode_record(snapshot, &mut stats)?;
+                    Ok((name_tag(&snapshot.name), record))
+                })
+                .collect::<PersistenceResult<Vec<_>>>()?,
+        };
+
+        let existing_len = existing.len();
+        let keeps_unseen = !self.can_decrypt();
+        let encoded = self
+            .encoded_tags
+            .lock()
+            .expect("encoded tags lock poisoned")
+            .clone();
+        let mut records: Vec<(NameTag, Vec<u8>)> = existing
+            .into_iter()
+            .filter(|(tag, _)| match &retained {
+                Some(retained) => {
+                    retained.contains(tag) || (keeps_unseen && !encoded.contains(tag))
+                }
+                None => true,
+            })
+            .collect();
+        if changed.is_empty() && records.len() == existing_len {
+            return Ok(0);
+        }
+        for snapshot in changed {
+            let tag = name_tag(&snapshot.name);
+            let record = self.encode_record(snapshot, &mut stats)?;
+            match records.iter_mut().find(|(existing, _)| *existing == tag) {
+                Some(slot) => slot.1 = record,
+                None => records.push((tag, record)),
+            }
+        }
+        BinaryBufferDb::write_records(path, &self.pipeline, &records)?;
+        self.encoded_tags
+            .lock()
+            .expect("encoded tags lock poisoned")
+            .extend(changed.iter().map(|snapshot| name_tag(&snapshot.name)));
+        if stats.uncompressed > 0 || !changed.is_empty() {
+            self.record_compression(stats);
+        }
+        Ok(changed.len())
+    }
+
+    fn encode_record(
+        &self,
+        snapshot: &BufferSnapshot,
+        stats: &mut CompressionStats,
+    ) -> PersistenceResult<Vec<u8>> {
+        let (record, record_stats) = BinaryBufferDb::encode_record(&self.pipeline, snapshot)?;
+        stats.add(record_stats);
+        Ok(record)
+    }
+
+    fn record_compression(&self, stats: CompressionStats) {
+        *self
+            .last_compression
+            .lock()
+            .expect("compression stats lock poisoned") = Some(stats);
+    }
+}
+
+impl SuspendBackend for PersistenceManager {
+    fn suspend(&self, snapshots: &[BufferSnapshot]) -> PersistenceResult<()> {
+        // Evicting a buffer that cannot be read back would lose it for the rest of the session.
+        if !self.can_decrypt() {
+            return Err(PersistenceError::InvalidEncryptionConfig(
+                "suspended buffers cannot be resumed without the identity".into(),
+            ));
+        }
+        self.merge_records(snapshots, None).map(|_| ())
+    }
+
+    fn resume(&self, name: &str) -> PersistenceResult<Option<BufferSnapshot>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .find(|snapshot| snapshot.name == name))
+    }
 }
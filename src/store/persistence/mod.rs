@@ -13,3 +13,5 @@ pub use crypto::{EncryptionAlgorithm, EncryptionKeySource, EncryptionMode, Encry
 #[allow(unused_imports)]
 pub use error::{PersistenceError, PersistenceResult};
 pub use manager::PersistenceManager;
+#[allow(unused_imports)]
+pub use pipeline::CompressionStats;
@@ -23,12 +23,29 @@ impl PersistencePipeline {
             .fold(0u32, |acc, layer| acc | layer.flag_bit())
     }
 
+    #[cfg_attr(not(test), allow(dead_code))]
     pub fn encode(&self, data: Vec<u8>) -> PersistenceResult<Vec<u8>> {
+        self.encode_with_stats(data).map(|(encoded, _)| encoded)
+    }
+
+    /// Encode `data`, also returning the payload sizes before and after the compression layer.
+    pub fn encode_with_stats(
+        &self,
+        data: Vec<u8>,
+    ) -> PersistenceResult<(Vec<u8>, CompressionStats)> {
+        let mut stats = CompressionStats::default();
         let mut current = data;
         for layer in &self.layers {
+            let before = current.len();
             current = layer.encode(current)?;
+            if layer.compresses() {
+                stats.add(CompressionStats {
+                    uncompressed: before,
+                    compressed: current.len(),
+                });
+            }
         }
-        Ok(current)
+        Ok((current, stats))
     }
 
     pub fn decode(&self, data: Vec<u8>) -> PersistenceResult<Vec<u8>> {
@@ -44,6 +61,33 @@ pub trait PersistenceLayer {
     fn encode(&self, data: Vec<u8>) -> PersistenceResult<Vec<u8>>;
     fn decode(&self, data: Vec<u8>) -> PersistenceResult<Vec<u8>>;
     fn flag_bit(&self) -> u32;
+
+    /// Whether this layer compresses, so its sizes count towards [`CompressionStats`].
+    fn compresses(&self) -> bool {
+        false
+    }
+}
+
+/// Payload sizes on either side of the compression layer, summed over the records of a write.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    pub uncompressed: usize,
+    pub compressed: usize,
+}
+
+impl CompressionStats {
+    /// Uncompressed size divided by compressed size; `1.0` when nothing was compressed.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed == 0 {
+            return 1.0;
+        }
+        self.uncompressed as f64 / self.compressed as f64
+    }
+
+    pub fn add(&mut self, other: CompressionStats) {
+        self.uncompressed += other.uncompressed;
+        self.compressed += other.compressed;
+    }
 }
 
 pub struct CompressionLayer {
@@ -68,6 +112,10 @@ impl PersistenceLayer for CompressionLayer {
     fn flag_bit(&self) -> u32 {
         self.algorithm.flag_bit()
     }
+
+    fn compresses(&self) -> bool {
+        true
+    }
 }
 
 pub struct EncryptionLayer {
@@ -127,7 +175,7 @@ impl PersistenceLayer for EncryptionLayer {
     }
 
     fn flag_bit(&self) -> u32 {
-        self.settings.algorithm.flag_bit()
+        self.settings.flag_bit()
     }
 }
 
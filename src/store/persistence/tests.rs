@@ -1,10 +1,12 @@
+use super::binary::{BinaryBufferDb, StoredBuffers};
 use super::config::PersistenceConfig;
 use super::crypto::{EncryptionAlgorithm, EncryptionKeySource, EncryptionMode, EncryptionSettings};
 use super::manager::PersistenceManager;
 use super::pipeline::{CompressionLayer, EncryptionLayer, PersistenceLayer};
 use crate::conf::ConfigurationModel;
-use crate::store::buffer_snapshot::BufferSnapshot;
+use crate::store::buffer_snapshot::{BufferSnapshot, SnapshotDiff};
 use crate::store::compress::CompressionAlgorithm;
+use crate::store::suspend::SuspendBackend;
 use std::fs;
 use std::path::PathBuf;
 use tempfile::tempdir;
@@ -33,6 +35,55 @@ fn writes_and_loads_snapshots_plaintext() {
     assert_eq!(restored, snapshots);
 }
 
+#[test]
+fn store_reports_compression_of_the_written_records() {
+    let dir = tempdir().unwrap();
+    let manager = PersistenceManager::new(PersistenceConfig::with_path(dir.path().join("db")));
+    assert_eq!(manager.last_compression(), None);
+
+    let snapshots: Vec<BufferSnapshot> = ["alpha", "beta"]
+        .into_iter()
+        .map(|name| {
+            let lines = vec!["the same line again".to_string(); 100];
+            BufferSnapshot::new(name.into(), lines, false, true, false)
+        })
+        .collect();
+    let stats = manager.store(&snapshots).unwrap();
+
+    assert!(stats.compressed < stats.uncompressed);
+    assert!(stats.ratio() > 1.0);
+    assert_eq!(manager.last_compression(), Some(stats));
+
+    let diff = SnapshotDiff {
+        changed: snapshots[..1].to_vec(),
+        ..SnapshotDiff::default()
+    };
+    assert_eq!(manager.store_changes(&diff).unwrap(), 1);
+    let single = manager.last_compression().unwrap();
+    assert!(
+        single.uncompressed < stats.uncompressed,
+        "only records encoded by the write count"
+    );
+}
+
+#[test]
+fn modification_times_survive_a_roundtrip() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("buffers.db");
+    let manager = PersistenceManager::new(PersistenceConfig::with_path(path));
+    let modified = std::time::UNIX_EPOCH + std::time::Duration::new(1_700_000_000, 123_456_789);
+
+    let snapshots = vec![
+        BufferSnapshot::new("edited".into(), vec!["text".into()], false, true, false)
+            .with_path(Some("/tmp/edited.txt".into()))
+            .with_modified(Some(modified)),
+        BufferSnapshot::new("pristine".into(), vec![], false, true, false),
+    ];
+
+    manager.store(&snapshots).unwrap();
+    assert_eq!(manager.load().unwrap(), snapshots);
+}
+
 #[test]
 fn encryption_layer_roundtrip_with_raw_key() {
     let settings = EncryptionSettings {
@@ -62,6 +113,40 @@ fn encryption_layer_roundtrip_with_passphrase() {
     assert_eq!(decoded, plaintext);
 }
 
+#[test]
+fn encryption_layer_wraps_to_a_recipient_and_needs_the_identity() {
+    let privkey = [0x42u8; 32];
+    let pubkey =
+        *x25519_dalek::PublicKey::from(&x25519_dalek::StaticSecret::from(privkey)).as_bytes();
+    let recipient = EncryptionLayer::new(EncryptionSettings {
+        algorithm: EncryptionAlgorithm::Aes256Gcm,
+        key_source: EncryptionKeySource::Recipient { pubkey },
+    });
+    let identity = EncryptionLayer::new(EncryptionSettings {
+        algorithm: EncryptionAlgorithm::Aes256Gcm,
+        key_source: EncryptionKeySource::Identity { privkey },
+    });
+    assert_eq!(recipient.flag_bit(), identity.flag_bit());
+    assert_ne!(
+        recipient.flag_bit(),
+        EncryptionAlgorithm::Aes256Gcm.flag_bit()
+    );
+
+    let plaintext = b"secret payload".to_vec();
+    let ciphertext = recipient.encode(plaintext.clone()).unwrap();
+    assert!(recipient.decode(ciphertext.clone()).is_err());
+    assert_eq!(identity.decode(ciphertext).unwrap(), plaintext);
+
+    let other = EncryptionLayer::new(EncryptionSettings {
+        algorithm: EncryptionAlgorithm::Aes256Gcm,
+        key_source: EncryptionKeySource::Identity {
+            privkey: [0x17u8; 32],
+        },
+    });
+    let ciphertext = identity.encode(plaintext.clone()).unwrap();
+    assert!(other.decode(ciphertext).is_err());
+}
+
 #[test]
 fn encrypted_store_and_load_with_raw_key() {
     let dir = tempdir().unwrap();
@@ -135,3 +220,98 @@ fn compression_respects_config_option() {
     let cfg = PersistenceConfig::from_sources(Some(&config));
     assert_eq!(cfg.compression(), CompressionAlgorithm::Lz4);
 }
+
+#[test]
+fn suspend_backend_merges_into_existing_database() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("buffers.db");
+    let manager = PersistenceManager::new(PersistenceConfig::with_path(path));
+    let alpha = BufferSnapshot::new("alpha".into(), vec!["a".into()], false, true, false);
+    let beta = BufferSnapshot::new("beta".into(), vec!["b".into()], false, true, true);
+    manager.store(std::slice::from_ref(&alpha)).unwrap();
+
+    manager.suspend(std::slice::from_ref(&beta)).unwrap();
+
+    assert_eq!(manager.resume("beta").unwrap(), Some(beta));
+    assert_eq!(manager.resume("alpha").unwrap(), Some(alpha));
+    assert_eq!(manager.resume("gamma").unwrap(), None);
+}
+
+fn raw_records(manager: &PersistenceManager, path: &std::path::Path) -> Vec<Vec<u8>> {
+    match BinaryBufferDb::read(path, manager.pipeline_for_tests()).unwrap() {
+        StoredBuffers::Records(records) => records.into_iter().map(|record| record.bytes).collect(),
+        StoredBuffers::Snapshots(_) => panic!("expected a record database"),
+    }
+}
+
+#[test]
+fn store_changes_reencodes_only_changed_records() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("buffers.db");
+    // Encryption draws a fresh nonce per record, so re-encoding any record changes its bytes.
+    let config = PersistenceConfig::with_path_and_encryption(
+        path.clone(),
+        EncryptionMode::Enabled(EncryptionSettings {
+            algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+            key_source: EncryptionKeySource::RawKey([3u8; 32]),
+        }),
+    );
+    let manager = PersistenceManager::new(config.clone());
+    let mut snapshots: Vec<BufferSnapshot> = (0..8)
+        .map(|idx| {
+            BufferSnapshot::new(
+                format!("buf{idx}"),
+                vec![format!("line {idx}")],
+                false,
+                true,
+                false,
+            )
+        })
+        .collect();
+    manager.store(&snapshots).unwrap();
+    let before = raw_records(&manager, &path);
+
+    snapshots[5].lines.push("edited".into());
+    let diff = SnapshotDiff {
+        changed: vec![snapshots[5].clone()],
+        unchanged: snapshots
+            .iter()
+            .filter(|snapshot| snapshot.name != "buf5")
+            .map(|snapshot| snapshot.name.clone())
+            .collect(),
+        ..SnapshotDiff::default()
+    };
+    assert_eq!(manager.store_changes(&diff).unwrap(), 1);
+
+    let after = raw_records(&manager, &path);
+    assert_eq!(after.len(), before.len());
+    for (idx, (old, new)) in before.iter().zip(&after).enumerate() {
+        assert_eq!(old == new, idx != 5, "record {idx}");
+    }
+    assert_eq!(PersistenceManager::new(config).load().unwrap(), snapshots);
+}
+
+#[test]
+fn store_changes_drops_records_the_diff_no_longer_retains() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("buffers.db");
+    let manager = PersistenceManager::new(PersistenceConfig::with_path(path.clone()));
+    let alpha = BufferSnapshot::new("alpha".into(), vec!["a".into()], false, true, false);
+    let beta = BufferSnapshot::new("beta".into(), vec!["b".into()], false, true, false);
+    manager.store(&[alpha.clone(), beta]).unwrap();
+
+    let unchanged = SnapshotDiff {
+        unchanged: vec!["alpha".into(), "beta".into()],
+        ..SnapshotDiff::default()
+    };
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    assert_eq!(manager.store_changes(&unchanged).unwrap(), 0);
+    assert_eq!(fs::metadata(&path).unwrap().modified().unwrap(), modified);
+
+    let removed = SnapshotDiff {
+        unchanged: vec!["alpha".into()],
+        ..SnapshotDiff::default()
+    };
+    assert_eq!(manager.store_changes(&removed).unwrap(), 0);
+    assert_eq!(manager.load().unwrap(), vec![alpha]);
+}
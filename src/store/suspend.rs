@@ -0,0 +1,98 @@
+//! Idle-suspend policy that evicts untouched buffers into the persistence database.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::buffer_snapshot::BufferSnapshot;
+use super::persistence::PersistenceResult;
+
+/// Storage that holds buffers evicted from memory by [`IdleSuspend`].
+pub trait SuspendBackend: Send + Sync {
+    /// Write (or replace) the given buffers in the backing store.
+    fn suspend(&self, snapshots: &[BufferSnapshot]) -> PersistenceResult<()>;
+    /// Read a previously suspended buffer back.
+    fn resume(&self, name: &str) -> PersistenceResult<Option<BufferSnapshot>>;
+}
+
+/// Per-store bookkeeping for the `editor.idle_suspend_ms` policy.
+#[derive(Clone)]
+pub struct IdleSuspend {
+    timeout: Duration,
+    backend: Arc<dyn SuspendBackend>,
+    last_access: HashMap<String, Instant>,
+    suspended: HashSet<String>,
+}
+
+impl IdleSuspend {
+    pub fn new(timeout: Duration, backend: Arc<dyn SuspendBackend>) -> Self {
+        Self {
+            timeout,
+            backend,
+            last_access: HashMap::new(),
+            suspended: HashSet::new(),
+        }
+    }
+
+    pub fn backend(&self) -> &dyn SuspendBackend {
+        self.backend.as_ref()
+    }
+
+    /// Record an access to `name` at `now`.
+    pub fn touch(&mut self, name: &str, now: Instant) {
+        self.last_access.insert(name.to_string(), now);
+    }
+
+    /// Names resident in memory whose last access is at least `timeout` before `now`.
+    ///
+    /// Buffers never seen before start their idle clock at `now` rather than being evicted
+    /// immediately.
+    pub fn idle_names<'a>(
+        &mut self,
+        resident: impl Iterator<Item = &'a String>,
+        now: Instant,
+    ) -> Vec<String> {
+        let mut idle = Vec::new();
+        for name in resident {
+            let last = *self.last_access.entry(name.clone()).or_insert(now);
+            if now.saturating_duration_since(last) >= self.timeout {
+                idle.push(name.clone());
+            }
+        }
+        idle.sort();
+        idle
+    }
+
+    pub fn mark_suspended(&mut self, name: &str) {
+        self.last_access.remove(name);
+        self.suspended.insert(name.to_string());
+    }
+
+    /// Forget `name` as suspended, returning whether it was.
+    pub fn take_suspended(&mut self, name: &str) -> bool {
+        self.suspended.remove(name)
+    }
+
+    pub fn is_suspended(&self, name: &str) -> bool {
+        self.suspended.contains(name)
+    }
+
+    pub fn suspended_names(&self) -> impl Iterator<Item = &String> {
+        self.suspended.iter()
+    }
+
+    pub fn forget(&mut self, name: &str) {
+        self.last_access.remove(name);
+        self.suspended.remove(name);
+    }
+}
+
+impl fmt::Debug for IdleSuspend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleSuspend")
+            .field("timeout", &self.timeout)
+            .field("suspended", &self.suspended)
+            .finish_non_exhaustive()
+    }
+}
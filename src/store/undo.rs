@@ -0,0 +1,325 @@
+//! Bounded undo and redo stacks of line-range changes.
+
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::Range;
+
+/// Undo depth used when `editor.undolevels` is not configured.
+pub const DEFAULT_UNDO_LEVELS: usize = 1000;
+
+/// Memory the undo and redo stacks may hold together before the oldest changes are dropped.
+pub const DEFAULT_UNDO_BYTES: usize = 64 * 1024 * 1024;
+
+/// Bookkeeping charged per stored line on top of its text.
+const LINE_OVERHEAD: usize = mem::size_of::<String>();
+
+/// One edit: the lines `start..start + removed.len()` were replaced by `inserted`.
+///
+/// Only the lines an edit touched are kept, so typing a character into a huge buffer stores a
+/// single line rather than a copy of the whole buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub start: usize,
+    pub removed: Vec<String>,
+    pub inserted: Vec<String>,
+}
+
+impl Change {
+    /// The change between `before` and `after`, trimmed to the lines that differ.
+    ///
+    /// Returns `None` when the two are equal.
+    pub fn between(mut before: Vec<String>, after: &[String]) -> Option<Self> {
+        let prefix = before
+            .iter()
+            .zip(after)
+            .take_while(|(old, new)| old == new)
+            .count();
+        if prefix == before.len() && prefix == after.len() {
+            return None;
+        }
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        before.truncate(before.len() - suffix);
+        Some(Self {
+            start: prefix,
+            removed: before.split_off(prefix),
+            inserted: after[prefix..after.len() - suffix].to_vec(),
+        })
+    }
+
+    /// Undo this change in `lines`, returning the change that redoes it.
+    pub fn revert(self, lines: &mut Vec<String>) -> Self {
+        let range = self.start..self.start + self.inserted.len();
+        lines.splice(range, self.removed.iter().cloned());
+        Self {
+            start: self.start,
+            removed: self.inserted,
+            inserted: self.removed,
+        }
+    }
+
+    /// Approximate heap and bookkeeping bytes held by this change.
+    pub fn size(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .removed
+                .iter()
+                .chain(&self.inserted)
+                .map(|line| line.capacity() + LINE_OVERHEAD)
+                .sum::<usize>()
+    }
+}
+
+/// Lines an edit is about to touch, captured by [`UndoHistory::begin`] before it runs.
+#[derive(Debug)]
+pub struct PendingChange {
+    start: usize,
+    removed: Vec<String>,
+    total: usize,
+}
+
+impl PendingChange {
+    /// Complete the change now that `lines` hold the edited text.
+    ///
+    /// Lines outside the captured range are assumed untouched; the range grew or shrank by
+    /// however many lines the edit added or removed.
+    pub fn finish(self, lines: &[String]) -> Change {
+        let end = (self.start + self.removed.len() + lines.len()).saturating_sub(self.total);
+        Change {
+            start: self.start,
+            inserted: lines[self.start..end.max(self.start)].to_vec(),
+            removed: self.removed,
+        }
+    }
+}
+
+/// Earlier and undone changes, each stack holding at most `levels` entries.
+///
+/// Both stacks together are also held to `max_bytes`, dropping the oldest changes first, so a
+/// long session on a large buffer cannot grow without bound. With `levels == 0` nothing is
+/// recorded and undo is disabled.
+#[derive(Debug, Clone)]
+pub struct UndoHistory {
+    levels: usize,
+    max_bytes: usize,
+    bytes: usize,
+    undo: VecDeque<Change>,
+    redo: VecDeque<Change>,
+}
+
+impl UndoHistory {
+    pub fn new(levels: usize) -> Self {
+        Self::with_limits(levels, DEFAULT_UNDO_BYTES)
+    }
+
+    pub fn with_limits(levels: usize, max_bytes: usize) -> Self {
+        Self {
+            levels,
+            max_bytes,
+            bytes: 0,
+            undo: VecDeque::new(),
+            redo: VecDeque::new(),
+        }
+    }
+
+    /// Change the cap, dropping the oldest changes of both stacks that no longer fit.
+    pub fn set_levels(&mut self, levels: usize) {
+        self.levels = levels;
+        self.trim();
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.levels > 0
+    }
+
+    /// Capture `rows` of `lines` before an edit that touches only those rows.
+    ///
+    /// Nothing is copied while undo is disabled.
+    pub fn begin(&self, lines: &[String], rows: Range<usize>) -> PendingChange {
+        let start = rows.start.min(lines.len());
+        let end = rows.end.clamp(start, lines.len());
+        let removed = match self.is_enabled() {
+            true => lines[start..end].to_vec(),
+            false => Vec::new(),
+        };
+        PendingChange {
+            start,
+            removed,
+            total: lines.len(),
+        }
+    }
+
+    /// Remember `change` as the most recent edit; any undone changes are discarded.
+    pub fn record(&mut self, change: Change) {
+        if !self.is_enabled() {
+            return;
+        }
+        for undone in self.redo.drain(..) {
+            self.bytes -= undone.size();
+        }
+        self.bytes += change.size();
+        self.undo.push_back(change);
+        self.trim();
+    }
+
+    /// Revert the most recent change in `lines`, returning whether there was one.
+    pub fn undo(&mut self, lines: &mut Vec<String>) -> bool {
+        let Some(change) = self.undo.pop_back() else {
+            return false;
+        };
+        self.bytes -= change.size();
+        let redo = change.revert(lines);
+        self.bytes += redo.size();
+        self.redo.push_back(redo);
+        self.trim();
+        true
+    }
+
+    /// Re-apply the most recently undone change in `lines`, returning whether there was one.
+    pub fn redo(&mut self, lines: &mut Vec<String>) -> bool {
+        let Some(change) = self.redo.pop_back() else {
+            return false;
+        };
+        self.bytes -= change.size();
+        let undo = change.revert(lines);
+        self.bytes += undo.size();
+        self.undo.push_back(undo);
+        self.trim();
+        true
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Approximate bytes held by both stacks.
+    #[allow(dead_code)]
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.bytes = 0;
+    }
+
+    // Enforce the entry cap on each stack, then the byte cap, oldest undo changes first.
+    fn trim(&mut self) {
+        while self.undo.len() > self.levels {
+            let dropped = self.undo.pop_front().expect("stack is over its cap");
+            self.bytes -= dropped.size();
+        }
+        while self.redo.len() > self.levels {
+            let dropped = self.redo.pop_front().expect("stack is over its cap");
+            self.bytes -= dropped.size();
+        }
+        while self.bytes > self.max_bytes {
+            let Some(dropped) = self.undo.pop_front().or_else(|| self.redo.pop_front()) else {
+                break;
+            };
+            self.bytes -= dropped.size();
+        }
+    }
+}
+
+impl Default for UndoHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_UNDO_LEVELS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    /// Record the edit from `before` to `after`, leaving `after` as the current text.
+    fn edit(history: &mut UndoHistory, before: &[&str], after: &[&str]) -> Vec<String> {
+        let after = lines(after);
+        history.record(Change::between(lines(before), &after).unwrap());
+        after
+    }
+
+    #[test]
+    fn changes_keep_only_the_lines_that_differ() {
+        let change = Change::between(lines(&["a", "b", "c", "d"]), &lines(&["a", "x", "y", "d"]));
+        assert_eq!(
+            change,
+            Some(Change {
+                start: 1,
+                removed: lines(&["b", "c"]),
+                inserted: lines(&["x", "y"]),
+            })
+        );
+        assert_eq!(Change::between(lines(&["a"]), &lines(&["a"])), None);
+    }
+
+    #[test]
+    fn undo_and_redo_move_between_stacks() {
+        let mut history = UndoHistory::new(10);
+        edit(&mut history, &["a"], &["ab"]);
+        let mut current = edit(&mut history, &["ab"], &["abc"]);
+
+        assert!(history.undo(&mut current));
+        assert_eq!(current, lines(&["ab"]));
+        assert!(history.undo(&mut current));
+        assert_eq!(current, lines(&["a"]));
+        assert!(!history.undo(&mut current));
+        assert_eq!(history.redo_len(), 2);
+
+        assert!(history.redo(&mut current));
+        assert_eq!(current, lines(&["ab"]));
+        edit(&mut history, &["ab"], &["abd"]);
+        assert_eq!(history.redo_len(), 0, "a new edit discards redo states");
+    }
+
+    #[test]
+    fn lowering_levels_drops_the_oldest_changes() {
+        let mut history = UndoHistory::new(5);
+        let mut current = lines(&["0"]);
+        for text in ["1", "2", "3", "4", "5"] {
+            let previous = current[0].clone();
+            current = edit(&mut history, &[&previous], &[text]);
+        }
+        history.set_levels(2);
+
+        assert_eq!(history.undo_len(), 2);
+        assert!(history.undo(&mut current));
+        assert!(history.undo(&mut current));
+        assert_eq!(current, lines(&["3"]));
+        assert!(!history.undo(&mut current));
+    }
+
+    #[test]
+    fn byte_cap_drops_the_oldest_changes() {
+        let long = "x".repeat(1000);
+        let mut history = UndoHistory::with_limits(100, 3000);
+        for _ in 0..10 {
+            edit(&mut history, &[""], &[&long]);
+        }
+
+        assert!(history.bytes() <= 3000);
+        assert!(history.undo_len() < 3);
+        assert!(history.undo_len() > 0, "recent changes that fit are kept");
+    }
+
+    #[test]
+    fn zero_levels_records_nothing() {
+        let mut history = UndoHistory::new(0);
+        let mut current = edit(&mut history, &["a"], &["b"]);
+        assert_eq!(history.undo_len(), 0);
+        assert!(!history.undo(&mut current));
+    }
+}
@@ -9,12 +9,12 @@ fn rejects_non_buffer_commands() {
 
 #[test]
 fn parses_grouped_short_options_in_order() {
-    let command = bufcmd::parse(":b -ab file1 file2").expect("expected to parse :b command");
+    let command = bufcmd::parse(":b -lb file1 file2").expect("expected to parse :b command");
 
     let expected_args = vec![String::from("file1"), String::from("file2")];
 
     assert!(command.pre_session_options().is_empty());
-    assert_eq!(command.post_session_options(), &['a', 'b']);
+    assert_eq!(command.post_session_options(), &['l', 'b']);
     assert_eq!(command.args(), expected_args.as_slice());
 }
 
@@ -1,2 +1,142 @@
+use iridium::complete::completer::{
+    complete_directory, complete_prompt_command, complete_variable, variable_candidates,
+};
+
+fn replacements(candidates: &[rustyline::completion::Pair]) -> Vec<&str> {
+    candidates
+        .iter()
+        .map(|pair| pair.replacement.as_str())
+        .collect()
+}
+
 #[test]
-fn complete_completer_placeholder() {}
+fn dollar_prefix_completes_environment_variable_names() {
+    unsafe {
+        std::env::set_var("PRIRIDIUM_ALPHA", "1");
+        std::env::set_var("PRIRIDIUM_BETA", "2");
+    }
+
+    let line = "echo $PRIRIDIUM_";
+    let (start, candidates) = complete_variable(line, line.len()).expect("variable token");
+    assert_eq!(start, "echo $".len());
+    assert_eq!(
+        replacements(&candidates),
+        vec!["PRIRIDIUM_ALPHA", "PRIRIDIUM_BETA"]
+    );
+
+    let (_, candidates) = complete_variable("echo $PR", 8).expect("variable token");
+    assert!(replacements(&candidates).contains(&"PRIRIDIUM_ALPHA"));
+    assert!(candidates.iter().all(|pair| pair.display.starts_with("PR")));
+}
+
+#[test]
+fn braced_variables_are_closed() {
+    let names = ["PROMPT", "PRINTER", "PATH"].map(String::from);
+    let candidates = variable_candidates("PR", true, names);
+    assert_eq!(replacements(&candidates), vec!["PRINTER}", "PROMPT}"]);
+    assert_eq!(candidates[0].display, "PRINTER");
+
+    let line = "echo ${PRIRIDIUM";
+    let (start, _) = complete_variable(line, line.len()).expect("variable token");
+    assert_eq!(start, "echo ${".len());
+}
+
+#[test]
+fn other_tokens_fall_through_to_path_completion() {
+    assert!(complete_variable("ls src/ma", 9).is_none());
+    assert!(complete_variable("echo PR", 7).is_none());
+}
+
+#[test]
+fn cd_completion_lists_only_directories() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp.path().join("src")).unwrap();
+    std::fs::create_dir(temp.path().join("scripts")).unwrap();
+    std::fs::create_dir(temp.path().join(".hidden")).unwrap();
+    std::fs::write(temp.path().join("setup.sh"), "").unwrap();
+    std::fs::write(temp.path().join("README"), "").unwrap();
+    let dir = format!("{}/", temp.path().display());
+
+    let line = format!("cd {dir}");
+    let (start, candidates) = complete_directory(&line, line.len()).expect("cd operand");
+    assert_eq!(start, 3);
+    assert_eq!(
+        replacements(&candidates),
+        vec![format!("{dir}scripts/"), format!("{dir}src/")]
+    );
+
+    let line = format!("cd {dir}s");
+    let (_, candidates) = complete_directory(&line, line.len()).expect("cd operand");
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[1].display, "src/");
+
+    let line = format!("cd {dir}.");
+    let (_, candidates) = complete_directory(&line, line.len()).expect("cd operand");
+    assert_eq!(replacements(&candidates), vec![format!("{dir}.hidden/")]);
+}
+
+#[test]
+fn cd_completion_escapes_spaces() {
+    let temp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(temp.path().join("My Docs")).unwrap();
+    std::fs::create_dir(temp.path().join("My Docs").join("old notes")).unwrap();
+    let dir = format!("{}/", temp.path().display());
+
+    let line = format!("cd {dir}My");
+    let (start, candidates) = complete_directory(&line, line.len()).expect("cd operand");
+    assert_eq!(start, 3);
+    assert_eq!(replacements(&candidates), vec![format!("{dir}My\\ Docs/")]);
+    assert_eq!(candidates[0].display, "My Docs/");
+
+    let line = format!("cd {dir}My\\ Docs/o");
+    let (start, candidates) = complete_directory(&line, line.len()).expect("cd operand");
+    assert_eq!(start, 3);
+    assert_eq!(
+        replacements(&candidates),
+        vec![format!("{dir}My\\ Docs/old\\ notes/")]
+    );
+}
+
+#[test]
+fn directory_completion_is_only_for_cd() {
+    assert!(complete_directory("ls /tm", 6).is_none());
+    assert!(complete_directory("cd", 2).is_none());
+    assert!(complete_directory("echo cd /tm", 11).is_none());
+}
+
+#[test]
+fn colon_prefix_completes_prompt_commands() {
+    let (start, candidates) = complete_prompt_command(":", 1).expect("colon command");
+    assert_eq!(start, 0);
+    let commands = replacements(&candidates);
+    for command in [":b", ":m", ":p"] {
+        assert!(
+            commands.contains(&command),
+            "{command} missing from {commands:?}"
+        );
+    }
+
+    let (_, candidates) = complete_prompt_command(":f", 2).expect("colon command");
+    assert_eq!(replacements(&candidates), vec![":fc"]);
+}
+
+#[test]
+fn buffer_command_options_are_completed_after_a_dash() {
+    let line = ":b -";
+    let (start, candidates) = complete_prompt_command(line, line.len()).expect(":b option");
+    assert_eq!(start, 3);
+    assert!(replacements(&candidates).contains(&"-l"));
+    assert!(candidates.iter().any(|pair| pair.display == "-t"));
+
+    let line = ":b -ls";
+    let (_, candidates) = complete_prompt_command(line, line.len()).expect(":b option");
+    assert!(replacements(&candidates).contains(&"-lst"));
+    assert!(!replacements(&candidates).contains(&"-lsl"));
+}
+
+#[test]
+fn prompt_command_completion_leaves_other_words_alone() {
+    assert!(complete_prompt_command("ls :b", 5).is_none());
+    assert!(complete_prompt_command(":b notes", 8).is_none());
+    assert!(complete_prompt_command(":m -", 4).is_none());
+}
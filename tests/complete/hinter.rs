@@ -1,2 +1,65 @@
+use iridium::complete::hinter::{IridiumHinter, path_hint};
+use rustyline::Context;
+use rustyline::hint::Hinter;
+use rustyline::history::{DefaultHistory, History};
+use std::fs;
+
 #[test]
-fn complete_hinter_placeholder() {}
+fn cd_hints_the_rest_of_a_unique_directory() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::create_dir(temp.path().join("projects")).unwrap();
+    fs::write(temp.path().join("profile"), "").unwrap();
+
+    let line = format!("cd {}/pro", temp.path().display());
+    assert_eq!(path_hint(&line, line.len()).as_deref(), Some("jects/"));
+    assert_eq!(
+        path_hint(&line, line.len() - 1),
+        None,
+        "cursor inside the word"
+    );
+
+    fs::create_dir(temp.path().join("prototypes")).unwrap();
+    assert_eq!(path_hint(&line, line.len()), None, "ambiguous prefix");
+}
+
+#[test]
+fn path_hints_need_a_partial_cd_operand() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::create_dir(temp.path().join("only")).unwrap();
+
+    let line = format!("ls {}/on", temp.path().display());
+    assert_eq!(path_hint(&line, line.len()), None);
+    let line = format!("cd {}/only/", temp.path().display());
+    assert_eq!(path_hint(&line, line.len()), None);
+}
+
+#[test]
+fn history_hints_win_unless_paths_come_first() {
+    let temp = tempfile::tempdir().unwrap();
+    fs::create_dir(temp.path().join("projects")).unwrap();
+    let line = format!("cd {}/pro", temp.path().display());
+
+    let mut history = DefaultHistory::new();
+    history.add(&format!("{line}totype")).unwrap();
+    let ctx = Context::new(&history);
+
+    let hinter = IridiumHinter::new();
+    assert_eq!(
+        hinter.hint(&line, line.len(), &ctx).as_deref(),
+        Some("totype")
+    );
+    let hinter = IridiumHinter::new().with_paths_first(true);
+    assert_eq!(
+        hinter.hint(&line, line.len(), &ctx).as_deref(),
+        Some("jects/")
+    );
+
+    let empty = DefaultHistory::new();
+    let ctx = Context::new(&empty);
+    assert_eq!(
+        IridiumHinter::new()
+            .hint(&line, line.len(), &ctx)
+            .as_deref(),
+        Some("jects/")
+    );
+}
@@ -1,2 +1,163 @@
+use iridium::conf::{self, ConfigError, ConfigSource};
+use std::env;
+use std::fs;
+use std::sync::Mutex;
+use tempfile::TempDir;
+
+// Discovery reads process-wide variables, so tests touching them run one at a time.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+// Variables that steer discovery or override persistence settings; cleared for each test.
+const MANAGED_VARS: [&str; 6] = [
+    "IRIDIUM_CONFIG",
+    "XDG_CONFIG_HOME",
+    "HOME",
+    "IRIDIUM_BUFFER_DB_PATH",
+    "IRIDIUM_DISABLE_PERSISTENCE",
+    "IRIDIUM_PERSIST_ENCRYPT",
+];
+
+struct EnvGuard {
+    saved: Vec<(&'static str, Option<String>)>,
+}
+
+impl EnvGuard {
+    fn new() -> Self {
+        let saved = MANAGED_VARS
+            .iter()
+            .map(|key| (*key, env::var(key).ok()))
+            .collect();
+        for key in MANAGED_VARS {
+            unsafe { env::remove_var(key) };
+        }
+        Self { saved }
+    }
+
+    fn set(&self, key: &str, value: &std::path::Path) {
+        unsafe { env::set_var(key, value) };
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (key, value) in &self.saved {
+            match value {
+                Some(value) => unsafe { env::set_var(key, value) },
+                None => unsafe { env::remove_var(key) },
+            }
+        }
+    }
+}
+
 #[test]
-fn config_placeholder() {}
+fn env_override_is_loaded_and_reported() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("custom.yaml");
+    fs::write(&path, "editor:\n  mkdir_on_save: false\n").unwrap();
+    let guard = EnvGuard::new();
+    guard.set("IRIDIUM_CONFIG", &path);
+
+    let (config, source) = conf::load().expect("override should load");
+
+    assert_eq!(source, ConfigSource::Env(path.clone()));
+    assert_eq!(config.source_path(), Some(path.as_path()));
+    assert!(!config.editor.mkdir_on_save());
+}
+
+#[test]
+fn env_override_wins_over_xdg_and_home() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let xdg = temp.path().join("xdg");
+    fs::create_dir_all(xdg.join("iridium")).unwrap();
+    fs::write(xdg.join("iridium/config.yaml"), "{}\n").unwrap();
+    let override_path = temp.path().join("override.yaml");
+    fs::write(&override_path, "{}\n").unwrap();
+    let guard = EnvGuard::new();
+    guard.set("XDG_CONFIG_HOME", &xdg);
+    guard.set("HOME", temp.path());
+    guard.set("IRIDIUM_CONFIG", &override_path);
+
+    let (_, source) = conf::load().unwrap();
+
+    assert_eq!(source, ConfigSource::Env(override_path));
+}
+
+#[test]
+fn xdg_config_precedes_home_config() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let xdg = temp.path().join("xdg");
+    let home = temp.path().join("home");
+    fs::create_dir_all(xdg.join("iridium")).unwrap();
+    fs::create_dir_all(home.join(".config/iridium")).unwrap();
+    fs::write(xdg.join("iridium/config.yaml"), "{}\n").unwrap();
+    fs::write(home.join(".config/iridium/config.yaml"), "{}\n").unwrap();
+    let guard = EnvGuard::new();
+    guard.set("XDG_CONFIG_HOME", &xdg);
+    guard.set("HOME", &home);
+
+    let (_, source) = conf::load().unwrap();
+    assert_eq!(
+        source,
+        ConfigSource::XdgConfigHome(xdg.join("iridium/config.yaml"))
+    );
+
+    fs::remove_file(xdg.join("iridium/config.yaml")).unwrap();
+    let (_, source) = conf::load().unwrap();
+    assert_eq!(
+        source,
+        ConfigSource::HomeConfig(home.join(".config/iridium/config.yaml"))
+    );
+}
+
+#[test]
+fn missing_files_fall_back_to_defaults() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let guard = EnvGuard::new();
+    guard.set("HOME", temp.path());
+
+    let (config, source) = conf::load().unwrap();
+
+    assert_eq!(source, ConfigSource::Default);
+    assert!(config.source_path().is_none());
+}
+
+#[test]
+fn malformed_config_reports_parse_error_with_path() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("broken.yaml");
+    fs::write(&path, "editor: [unterminated\n").unwrap();
+    let guard = EnvGuard::new();
+    guard.set("IRIDIUM_CONFIG", &path);
+
+    let err = conf::load().unwrap_err();
+
+    assert!(matches!(err, ConfigError::Parse { .. }));
+    assert!(err.to_string().contains(&path.display().to_string()));
+}
+
+#[test]
+fn print_config_reports_path_and_compression_but_not_passphrase() {
+    let _lock = ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("print.yaml");
+    fs::write(
+        &path,
+        "persistence:\n  database_path: buffers.db\n  compression: lz4\n  encrypt: true\n  passphrase: hunter2-secret\n  pbkdf2_iterations: 1000\n",
+    )
+    .unwrap();
+    let guard = EnvGuard::new();
+    guard.set("IRIDIUM_CONFIG", &path);
+
+    let report = conf::effective_config().expect("config should render");
+
+    assert!(report.contains(&temp.path().join("buffers.db").display().to_string()));
+    assert!(report.contains("compression = \"lz4\""));
+    assert!(report.contains("encryption = \"chacha20poly1305\""));
+    assert!(report.contains("passphrase = \"<redacted>\""));
+    assert!(!report.contains("hunter2-secret"));
+}
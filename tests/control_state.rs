@@ -71,3 +71,32 @@ fn rename_option_renames_multiple_pairs() {
     let names = sorted_names(&state);
     assert_eq!(names, vec!["delta".to_string(), "gamma".to_string()]);
 }
+
+#[test]
+fn isolated_state_keeps_cd_out_of_process_environment() {
+    unsafe {
+        std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
+        std::env::set_var("IRIDIUM_DISABLE_PERSISTENCE", "1");
+    }
+    let target = tempfile::tempdir().unwrap();
+    let process_pwd = std::env::var("PWD").ok();
+    let process_cwd = std::env::current_dir().unwrap();
+
+    let mut state = ControlState::with_isolated_environment(true);
+    let line = format!("cd {}", target.path().display());
+    assert_eq!(state.handle_line(&line), ControlFlow::CONTINUE);
+
+    let environment = state.environment();
+    let environment = environment.borrow();
+    assert!(environment.is_isolated());
+    assert_eq!(
+        environment.current_dir().unwrap(),
+        target.path().canonicalize().unwrap()
+    );
+    assert_eq!(
+        environment.var("PWD").as_deref(),
+        Some(target.path().to_str().unwrap())
+    );
+    assert_eq!(std::env::var("PWD").ok(), process_pwd);
+    assert_eq!(std::env::current_dir().unwrap(), process_cwd);
+}
@@ -1,2 +1,25 @@
+use iridium::editor::terminal::{Terminal, TerminalMode};
+use std::io::ErrorKind;
+
 #[test]
 fn editor_terminal_placeholder() {}
+
+#[test]
+fn raw_mode_requires_both_streams_to_be_ttys() {
+    assert_eq!(TerminalMode::from_streams(true, true), TerminalMode::Raw);
+    assert_eq!(TerminalMode::from_streams(false, true), TerminalMode::Line);
+    assert_eq!(TerminalMode::from_streams(true, false), TerminalMode::Line);
+    assert_eq!(TerminalMode::from_streams(false, false), TerminalMode::Line);
+}
+
+#[test]
+fn line_mode_terminal_refuses_editor_session() {
+    let terminal = Terminal::with_mode(TerminalMode::Line);
+    assert!(!terminal.is_interactive());
+
+    let err = terminal
+        .enter()
+        .expect_err("entering without a TTY must fail");
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert!(terminal.terminate().is_ok());
+}
@@ -0,0 +1,58 @@
+use iridium::control::{LaunchOptions, LineEditor, run_loop_with_editor};
+use iridium::control_state::ControlState;
+use iridium::editor::buffer_editor::BufferEditor;
+use iridium::editor::config::EditorConfig;
+use iridium::editor::terminal::Terminal;
+use rustyline::error::ReadlineError;
+use std::collections::VecDeque;
+use std::io::Cursor;
+
+struct ScriptedEditor {
+    responses: VecDeque<Result<String, ReadlineError>>,
+}
+
+impl LineEditor for ScriptedEditor {
+    fn readline(&mut self, _: &str) -> Result<String, ReadlineError> {
+        self.responses
+            .pop_front()
+            .unwrap_or(Err(ReadlineError::Eof))
+    }
+
+    fn add_history_entry(&mut self, _: &str) -> rustyline::Result<bool> {
+        Ok(true)
+    }
+}
+
+#[test]
+fn readonly_launch_refuses_insert_mode_in_every_buffer() {
+    unsafe {
+        std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
+        std::env::set_var("IRIDIUM_DISABLE_PERSISTENCE", "1");
+    }
+    let options = LaunchOptions::from_args(["--readonly"]);
+    let mut control_state = ControlState::with_isolated_environment(true);
+    options.apply(&mut control_state);
+    let mut editor = ScriptedEditor {
+        responses: vec![Ok(":b alpha beta".into()), Err(ReadlineError::Eof)].into(),
+    };
+    let mut sink = Cursor::new(Vec::new());
+
+    run_loop_with_editor(&mut control_state, &mut editor, &mut sink).unwrap();
+
+    let store = Terminal::instance().store_handle();
+    for name in ["alpha", "beta"] {
+        assert!(store.lock().unwrap().is_read_only(name));
+
+        let mut buffer_editor = BufferEditor::new(name);
+        buffer_editor.configure(&EditorConfig {
+            new_buffer_insert: true,
+            ..Default::default()
+        });
+        buffer_editor.open(name);
+        buffer_editor.execute_colon_command("i").expect(":i");
+        assert!(
+            buffer_editor.prompt_string().contains("-- READ --"),
+            "{name} entered insert mode"
+        );
+    }
+}
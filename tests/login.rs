@@ -0,0 +1,79 @@
+use iridium::control::LaunchOptions;
+use iridium::control_state::{ControlFlow, ControlState};
+use std::fs;
+use std::path::Path;
+
+/// A control state whose `HOME` is `home`, whose profile holds `profile`, and whose history
+/// goes to a file under `home` rather than the real one.
+fn state_with_profile(home: &Path, profile: &str) -> ControlState {
+    unsafe {
+        std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
+        std::env::set_var("IRIDIUM_DISABLE_PERSISTENCE", "1");
+    }
+    fs::write(home.join(".iridium_profile"), profile).unwrap();
+    let mut control_state = ControlState::with_isolated_environment(true);
+    control_state.set_history_file(home.join(".iridium_history"));
+    control_state
+        .environment()
+        .borrow_mut()
+        .set_var("HOME", home.to_str().unwrap());
+    control_state
+}
+
+fn cd_profile(target: &Path) -> String {
+    format!("# login setup\n\ncd {}\n", target.display())
+}
+
+#[test]
+fn login_launch_sources_the_user_profile() {
+    let home = tempfile::tempdir().unwrap();
+    let target = tempfile::tempdir().unwrap();
+    let mut control_state = state_with_profile(home.path(), &cd_profile(target.path()));
+
+    let flow = LaunchOptions::from_args(["--login"]).apply(&mut control_state);
+
+    assert_eq!(flow, ControlFlow::CONTINUE);
+    let environment = control_state.environment();
+    assert_eq!(
+        environment.borrow().var("PWD").as_deref(),
+        Some(target.path().to_str().unwrap())
+    );
+    assert!(
+        !home.path().join(".iridium_history").exists(),
+        "sourced lines are not recorded in history"
+    );
+}
+
+#[test]
+fn non_login_launch_skips_profiles() {
+    let home = tempfile::tempdir().unwrap();
+    let target = tempfile::tempdir().unwrap();
+    let mut control_state = state_with_profile(home.path(), &cd_profile(target.path()));
+
+    LaunchOptions::from_args(["--readonly"]).apply(&mut control_state);
+
+    let environment = control_state.environment();
+    assert_ne!(
+        environment.borrow().var("PWD").as_deref(),
+        Some(target.path().to_str().unwrap())
+    );
+}
+
+#[test]
+fn exit_in_a_profile_ends_the_login_session() {
+    let home = tempfile::tempdir().unwrap();
+    let target = tempfile::tempdir().unwrap();
+    let profile = format!("exit\ncd {}\n", target.path().display());
+    let mut control_state = state_with_profile(home.path(), &profile);
+
+    let flow = LaunchOptions::from_args(["--login"]).apply(&mut control_state);
+
+    assert_eq!(flow, ControlFlow::EXIT);
+    let environment = control_state.environment();
+    assert_ne!(
+        environment.borrow().var("PWD").as_deref(),
+        Some(target.path().to_str().unwrap()),
+        "lines after exit are not run"
+    );
+    assert!(!home.path().join(".iridium_history").exists());
+}
@@ -5,6 +5,8 @@ use iridium::store::persistence::{
     PersistenceConfig, PersistenceManager,
 };
 use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
 
 #[test]
@@ -37,6 +39,31 @@ fn buffer_snapshots_roundtrip_plaintext() {
     assert!(!rehydrated.get("beta").unwrap().is_open());
 }
 
+#[test]
+fn buffer_path_survives_roundtrip_separately_from_name() {
+    let dir = tempdir().expect("tempdir");
+    let db_path = dir.path().join("buffers.db");
+    let manager = PersistenceManager::new(PersistenceConfig::with_path(db_path));
+    let target = dir.path().join("saved.txt");
+
+    let mut store = BufferStore::new();
+    store.open("untitled-1").append("draft".into());
+    store.set_path("untitled-1", Some(target.to_string_lossy().to_string()));
+    store.open("plain").append("no path".into());
+
+    manager.store(&store.snapshots()).expect("store snapshots");
+    let mut rehydrated = BufferStore::new();
+    rehydrated.hydrate(manager.load().expect("load snapshots"));
+
+    let titled = rehydrated.get("untitled-1").unwrap();
+    assert_eq!(titled.path(), Some(target.to_str().unwrap()));
+    assert_eq!(titled.lines(), &["draft".to_string()]);
+    assert_eq!(rehydrated.get("plain").unwrap().path(), None);
+
+    rehydrated.save("untitled-1").expect("save to path");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "draft\n");
+}
+
 #[test]
 fn buffer_snapshots_roundtrip_encrypted_raw_key() {
     let dir = tempdir().expect("tempdir");
@@ -139,3 +166,142 @@ fn buffer_snapshots_roundtrip_encrypted_via_config() {
         &["first".to_string()]
     );
 }
+
+#[test]
+fn buffer_snapshots_roundtrip_encrypted_to_recipient() {
+    let dir = tempdir().expect("tempdir");
+    let db_path = dir.path().join("buffers.db");
+    let identity = x25519_dalek::StaticSecret::random_from_rng(rand_core::OsRng);
+    let recipient = x25519_dalek::PublicKey::from(&identity);
+
+    let encryption = EncryptionMode::Enabled(EncryptionSettings {
+        algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+        key_source: EncryptionKeySource::Recipient {
+            pubkey: recipient.to_bytes(),
+        },
+    });
+    let manager = PersistenceManager::new(PersistenceConfig::with_path_and_encryption(
+        db_path.clone(),
+        encryption,
+    ));
+    let mut store = BufferStore::new();
+    store.open("gamma").append("shared secret".into());
+    manager.store(&store.snapshots()).expect("store snapshots");
+    assert!(manager.load().is_err());
+
+    let encryption = EncryptionMode::Enabled(EncryptionSettings {
+        algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+        key_source: EncryptionKeySource::Identity {
+            privkey: identity.to_bytes(),
+        },
+    });
+    let manager = PersistenceManager::new(PersistenceConfig::with_path_and_encryption(
+        db_path, encryption,
+    ));
+    let mut rehydrated = BufferStore::new();
+    rehydrated.hydrate(manager.load().expect("load snapshots"));
+    assert_eq!(
+        rehydrated.get("gamma").unwrap().lines(),
+        &["shared secret".to_string()]
+    );
+}
+
+/// Configs that encrypt `db_path` to a fresh recipient key and decrypt it with its identity.
+fn recipient_and_identity_configs(
+    db_path: &std::path::Path,
+) -> (PersistenceConfig, PersistenceConfig) {
+    let identity = x25519_dalek::StaticSecret::random_from_rng(rand_core::OsRng);
+    let config = |key_source| {
+        PersistenceConfig::with_path_and_encryption(
+            db_path.to_path_buf(),
+            EncryptionMode::Enabled(EncryptionSettings {
+                algorithm: EncryptionAlgorithm::ChaCha20Poly1305,
+                key_source,
+            }),
+        )
+    };
+    (
+        config(EncryptionKeySource::Recipient {
+            pubkey: x25519_dalek::PublicKey::from(&identity).to_bytes(),
+        }),
+        config(EncryptionKeySource::Identity {
+            privkey: identity.to_bytes(),
+        }),
+    )
+}
+
+#[test]
+fn recipient_only_sessions_keep_the_buffers_of_earlier_ones() {
+    let dir = tempdir().expect("tempdir");
+    let db_path = dir.path().join("buffers.db");
+    let (recipient, identity) = recipient_and_identity_configs(&db_path);
+    let first_session = PersistenceManager::new(recipient.clone());
+    let mut store = BufferStore::new();
+    store.open("alpha").append("first session".into());
+    store.open("beta").append("replaced later".into());
+    first_session
+        .store_changes(&store.snapshot_diff())
+        .expect("store first session");
+
+    let second_session = PersistenceManager::new(recipient);
+    assert!(second_session.load().is_err());
+    let mut store = BufferStore::new();
+    store.open("beta").append("second session".into());
+    store.open("gamma").append("dropped".into());
+    second_session
+        .store_changes(&store.snapshot_diff())
+        .expect("store second session");
+    store.remove("gamma");
+    second_session
+        .store_changes(&store.snapshot_diff())
+        .expect("store after removing a buffer");
+
+    let mut rehydrated = BufferStore::new();
+    let snapshots = PersistenceManager::new(identity).load();
+    rehydrated.hydrate(snapshots.expect("load with the identity"));
+    let mut names = rehydrated.list();
+    names.sort();
+    assert_eq!(names, vec!["alpha".to_string(), "beta".to_string()]);
+    assert_eq!(
+        rehydrated.get("alpha").unwrap().lines(),
+        &["first session".to_string()]
+    );
+    assert_eq!(
+        rehydrated.get("beta").unwrap().lines(),
+        &["second session".to_string()]
+    );
+}
+
+#[test]
+fn recipient_only_key_keeps_idle_buffers_resident() {
+    let dir = tempdir().expect("tempdir");
+    let db_path = dir.path().join("buffers.db");
+    let (recipient, identity) = recipient_and_identity_configs(&db_path);
+    let timeout = Duration::from_millis(10);
+    let later = Instant::now() + timeout * 2;
+
+    let mut store = BufferStore::new();
+    store.enable_idle_suspend(timeout, Arc::new(PersistenceManager::new(recipient)));
+    store.open("alpha").append("still here".into());
+    assert!(store.suspend_idle_at(later).is_err());
+    assert!(!store.is_suspended("alpha"));
+    assert_eq!(
+        store.get("alpha").unwrap().lines(),
+        &["still here".to_string()]
+    );
+
+    let mut store = BufferStore::new();
+    store.enable_idle_suspend(timeout, Arc::new(PersistenceManager::new(identity)));
+    store.open("alpha").append("round trip".into());
+    assert_eq!(
+        store
+            .suspend_idle_at(later)
+            .expect("suspend with the identity"),
+        vec!["alpha".to_string()]
+    );
+    let resumed = store
+        .access("alpha")
+        .expect("resume with the identity")
+        .expect("suspended buffer");
+    assert_eq!(resumed.lines(), &["round trip".to_string()]);
+}
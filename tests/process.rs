@@ -4,6 +4,12 @@ mod alias;
 #[path = "process/cd.rs"]
 mod cd;
 
+#[path = "process/date.rs"]
+mod date;
+
+#[path = "process/dirs.rs"]
+mod dirs;
+
 #[path = "process/exit.rs"]
 mod exit_mod;
 
@@ -27,3 +33,21 @@ mod welcome;
 
 #[path = "process/which.rs"]
 mod which;
+
+#[path = "process/redirect.rs"]
+mod redirect;
+
+#[path = "process/compress.rs"]
+mod compress;
+
+#[path = "process/umask.rs"]
+mod umask;
+
+#[path = "process/pipeline.rs"]
+mod pipeline;
+
+#[path = "process/jobs.rs"]
+mod jobs;
+
+#[path = "process/list.rs"]
+mod list;
@@ -101,6 +101,32 @@ fn alias_writes_results_to_stdout_and_stderr() {
     assert_eq!(buffer_to_string(&stderr), "alias: missing: not found\n");
 }
 
+#[test]
+fn dash_s_prints_sorted_sourceable_definitions() {
+    let map = BuiltinMap::new();
+    let (stdout, stderr) = configure_alias_io(&map);
+
+    assert_eq!(
+        invoke_alias(&map, &["ll=ls -al", "gs=git status", "say=echo 'hi'"]),
+        Some(0)
+    );
+    stdout.borrow_mut().clear();
+
+    assert_eq!(invoke_alias(&map, &["-s"]), Some(0));
+    assert_eq!(
+        buffer_to_string(&stdout),
+        "alias gs='git status'\nalias ll='ls -al'\nalias say='echo '\\''hi'\\'''\n"
+    );
+    stdout.borrow_mut().clear();
+
+    assert_eq!(invoke_alias(&map, &["-s", "ll", "missing", "gs"]), Some(1));
+    assert_eq!(
+        buffer_to_string(&stdout),
+        "alias gs='git status'\nalias ll='ls -al'\n"
+    );
+    assert_eq!(buffer_to_string(&stderr), "alias: missing: not found\n");
+}
+
 #[test]
 fn invalid_option_returns_error_status() {
     let map = BuiltinMap::new();
@@ -121,6 +147,25 @@ fn dash_p_is_rejected() {
     assert_eq!(buffer_to_string(&stderr), "alias: -p: invalid option\n");
 }
 
+#[test]
+fn invalid_alias_names_are_rejected() {
+    let map = BuiltinMap::new();
+    let (stdout, stderr) = configure_alias_io(&map);
+
+    assert_eq!(invoke_alias(&map, &["gs=git status"]), Some(0));
+    assert_eq!(invoke_alias(&map, &["my alias=ls", "ok=ls"]), Some(1));
+    assert_eq!(buffer_to_string(&stdout), "");
+    assert_eq!(
+        buffer_to_string(&stderr),
+        "alias: my alias: invalid alias name\n"
+    );
+
+    let alias = map.get_alias();
+    assert!(alias.borrow().contains_alias("gs"));
+    assert!(alias.borrow().contains_alias("ok"));
+    assert!(!alias.borrow().contains_alias("my alias"));
+}
+
 #[test]
 fn default_sinks_write_to_standard_streams_without_override() {
     let map = BuiltinMap::new();
@@ -1,5 +1,6 @@
 use iridium::process::builtin::Builtin;
 use iridium::process::cd::Cd;
+use iridium::process::environment::ShellEnvironment;
 use once_cell::sync::Lazy;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -143,6 +144,29 @@ fn cd_honors_cdpath_and_prints_target() {
     assert_path_str_equal(output.trim_end(), &target);
 }
 
+#[test]
+fn cd_expands_tilde_in_cdpath_entries() {
+    let _guard = lock_env();
+    let mut env_state = TestEnv::new();
+    let root = env_state.root();
+    let home = root.join("home dir");
+    let target = home.join("paths").join("project");
+    let elsewhere = root.join("elsewhere");
+    fs::create_dir_all(&target).unwrap();
+    fs::create_dir_all(&elsewhere).unwrap();
+    env_state.set_var("HOME", home.to_str().unwrap());
+    env_state.set_var("CDPATH", "/nonexistent-cdpath:~/paths");
+    env_state.set_var("PWD", elsewhere.to_str().unwrap());
+    env_state.set_current_dir(&elsewhere);
+
+    let mut cd = Cd::new();
+    let buffer = capture_output(&mut cd);
+    assert_eq!(run_cd(&mut cd, &["project"]), Some(0));
+    assert_paths_equal(&env::current_dir().unwrap(), &target);
+    let output = buffer_to_string(&buffer);
+    assert_path_str_equal(output.trim_end(), &target);
+}
+
 #[cfg(unix)]
 #[test]
 fn cd_physical_option_resolves_symlinks() {
@@ -187,3 +211,89 @@ fn assert_paths_equal(lhs: &Path, rhs: &Path) {
 fn assert_path_str_equal(lhs: &str, rhs: &Path) {
     assert_eq!(canonical_path(Path::new(lhs)), canonical_path(rhs));
 }
+
+#[test]
+fn cd_with_isolated_environment_leaves_process_state_alone() {
+    let _guard = lock_env();
+    let mut env_state = TestEnv::new();
+    let root = env_state.root();
+    let nested = root.join("nested");
+    fs::create_dir_all(&nested).unwrap();
+    env_state.set_var("PWD", root.to_str().unwrap());
+    env_state.set_current_dir(&root);
+
+    let environment = ShellEnvironment::isolated().into_handle();
+    let mut cd = Cd::with_environment(Rc::clone(&environment));
+    assert_eq!(run_cd(&mut cd, &["nested"]), Some(0));
+
+    assert_path_str_equal(&environment.borrow().var("PWD").unwrap(), &nested);
+    assert_path_str_equal(&environment.borrow().var("OLDPWD").unwrap(), &root);
+    assert_paths_equal(&environment.borrow().current_dir().unwrap(), &nested);
+    assert_path_str_equal(&env::var("PWD").unwrap(), &root);
+    assert_paths_equal(&env::current_dir().unwrap(), &root);
+}
+
+fn capture_errors(cd: &mut Cd) -> Rc<RefCell<Vec<u8>>> {
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    cd.capture_error_buffer(buffer.clone());
+    buffer
+}
+
+#[cfg(unix)]
+#[test]
+fn cd_into_regular_file_reports_not_a_directory() {
+    let _guard = lock_env();
+    let mut env_state = TestEnv::new();
+    let root = env_state.root();
+    fs::write(root.join("file.txt"), "contents").unwrap();
+    env_state.set_var("PWD", root.to_str().unwrap());
+    env_state.set_current_dir(&root);
+
+    let mut cd = Cd::new();
+    let errors = capture_errors(&mut cd);
+    assert_eq!(run_cd(&mut cd, &["file.txt"]), Some(1));
+    assert_eq!(buffer_to_string(&errors), "cd: file.txt: not a directory\n");
+    assert_paths_equal(&env::current_dir().unwrap(), &root);
+}
+
+#[cfg(unix)]
+#[test]
+fn cd_into_unsearchable_directory_reports_permission_denied() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let _guard = lock_env();
+    let mut env_state = TestEnv::new();
+    let root = env_state.root();
+    let locked = root.join("locked");
+    fs::create_dir(&locked).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    env_state.set_var("PWD", root.to_str().unwrap());
+    env_state.set_current_dir(&root);
+
+    // Privileged users bypass permission checks, so there is nothing to observe.
+    if fs::read_dir(&locked).is_ok() {
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        return;
+    }
+
+    let mut cd = Cd::new();
+    let errors = capture_errors(&mut cd);
+    let status = run_cd(&mut cd, &["locked"]);
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+    assert_eq!(status, Some(1));
+    assert_eq!(buffer_to_string(&errors), "cd: locked: permission denied\n");
+    assert_paths_equal(&env::current_dir().unwrap(), &root);
+}
+
+#[test]
+fn cd_into_missing_directory_reports_no_such_file() {
+    let _guard = lock_env();
+    let mut cd = Cd::with_environment(ShellEnvironment::isolated().into_handle());
+    let errors = capture_errors(&mut cd);
+    assert_eq!(run_cd(&mut cd, &["/definitely/not/here"]), Some(1));
+    assert_eq!(
+        buffer_to_string(&errors),
+        "cd: /definitely/not/here: no such file or directory\n"
+    );
+}
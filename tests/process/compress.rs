@@ -0,0 +1,81 @@
+use iridium::process::builtin::Builtin;
+use iridium::process::compress::{Compress, Direction};
+use std::fs;
+use tempfile::TempDir;
+
+fn run(direction: Direction, args: &[&str]) -> Option<i32> {
+    let owned: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    Compress::new(direction).call(&owned)
+}
+
+#[test]
+fn compress_and_decompress_round_trip_a_file() {
+    let dir = TempDir::new().unwrap();
+    let original = dir.path().join("notes.txt");
+    let contents = "alpha\nbeta\n".repeat(64);
+    fs::write(&original, &contents).unwrap();
+    let compressed = dir.path().join("notes.txt.lz4");
+
+    assert_eq!(
+        run(Direction::Compress, &[original.to_str().unwrap()]),
+        Some(0)
+    );
+    assert!(compressed.exists());
+    assert_ne!(fs::read(&compressed).unwrap(), contents.as_bytes());
+
+    fs::remove_file(&original).unwrap();
+    assert_eq!(
+        run(
+            Direction::Decompress,
+            &["-a", "lz4", compressed.to_str().unwrap()]
+        ),
+        Some(0)
+    );
+    assert_eq!(fs::read_to_string(&original).unwrap(), contents);
+}
+
+#[test]
+fn missing_input_is_an_error() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.txt");
+
+    assert_eq!(
+        run(Direction::Compress, &[missing.to_str().unwrap()]),
+        Some(1)
+    );
+    assert!(!dir.path().join("missing.txt.lz4").exists());
+}
+
+#[test]
+fn existing_output_is_not_overwritten() {
+    let dir = TempDir::new().unwrap();
+    let original = dir.path().join("data.txt");
+    let compressed = dir.path().join("data.txt.lz4");
+    fs::write(&original, "fresh").unwrap();
+    fs::write(&compressed, "keep me").unwrap();
+
+    assert_eq!(
+        run(Direction::Compress, &[original.to_str().unwrap()]),
+        Some(1)
+    );
+    assert_eq!(fs::read_to_string(&compressed).unwrap(), "keep me");
+
+    assert_eq!(
+        run(Direction::Decompress, &[compressed.to_str().unwrap()]),
+        Some(1)
+    );
+    assert_eq!(fs::read_to_string(&original).unwrap(), "fresh");
+}
+
+#[test]
+fn decompress_requires_a_known_extension() {
+    let dir = TempDir::new().unwrap();
+    let plain = dir.path().join("plain.txt");
+    fs::write(&plain, "text").unwrap();
+
+    assert_eq!(
+        run(Direction::Decompress, &[plain.to_str().unwrap()]),
+        Some(1)
+    );
+    assert_eq!(run(Direction::Compress, &["-a", "zip", "x"]), Some(2));
+}
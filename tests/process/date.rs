@@ -0,0 +1,59 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn run_date(operands: &[&str]) -> (Option<i32>, String) {
+    let map = BuiltinMap::new();
+    let mut argv = args(&["date"]);
+    argv.extend(args(operands));
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(&map, &argv, &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+fn has_shape(text: &str, shape: &str) -> bool {
+    text.len() == shape.len()
+        && text
+            .chars()
+            .zip(shape.chars())
+            .all(|(ch, expected)| match expected {
+                '9' => ch.is_ascii_digit(),
+                other => ch == other,
+            })
+}
+
+#[test]
+fn date_formats_the_current_time_with_a_plus_format() {
+    let (status, output) = run_date(&["+%Y-%m-%d"]);
+
+    assert_eq!(status, Some(0));
+    let line = output.strip_suffix('\n').expect("trailing newline");
+    assert!(has_shape(line, "9999-99-99"), "unexpected date: {line:?}");
+}
+
+#[test]
+fn date_without_operands_prints_an_iso_like_timestamp() {
+    let (status, output) = run_date(&[]);
+
+    assert_eq!(status, Some(0));
+    let line = output.strip_suffix('\n').expect("trailing newline");
+    let (stamp, offset) = line.rsplit_once(' ').expect("utc offset");
+    assert!(
+        has_shape(stamp, "9999-99-99 99:99:99"),
+        "unexpected time: {line:?}"
+    );
+    assert!(
+        has_shape(offset, "+99:99") || has_shape(offset, "-99:99"),
+        "unexpected offset: {offset:?}"
+    );
+}
+
+#[test]
+fn date_rejects_bad_operands_and_formats() {
+    assert_eq!(run_date(&["%Y"]), (Some(1), String::new()));
+    assert_eq!(run_date(&["+%Y", "+%m"]), (Some(1), String::new()));
+    assert_eq!(run_date(&["+%Q"]), (Some(1), String::new()));
+}
@@ -0,0 +1,79 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::environment::ShellEnvironment;
+use tempfile::TempDir;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn run(map: &BuiltinMap, command: &[&str]) -> (Option<i32>, String) {
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(map, &args(command), &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+/// A map over an isolated environment rooted at `root`, with `HOME` pointing at `root/home`.
+fn isolated_map(root: &TempDir) -> BuiltinMap {
+    let root = root.path().canonicalize().unwrap();
+    for dir in ["home/src", "a", "b"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    let environment = ShellEnvironment::isolated().into_handle();
+    {
+        let mut env = environment.borrow_mut();
+        env.set_current_dir(&root).unwrap();
+        env.set_var("PWD", root.to_str().unwrap());
+        env.set_var("HOME", root.join("home").to_str().unwrap());
+    }
+    BuiltinMap::with_environment(environment)
+}
+
+#[test]
+fn dirs_lists_stack_with_indices() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+    let root = temp.path().canonicalize().unwrap();
+    let root = root.display();
+
+    assert_eq!(run(&map, &["pushd", "a"]).0, Some(0));
+    assert_eq!(run(&map, &["pushd", "../home/src"]).0, Some(0));
+
+    assert_eq!(
+        run(&map, &["dirs", "-v"]),
+        (Some(0), format!(" 0  ~/src\n 1  {root}/a\n 2  {root}\n"))
+    );
+    assert_eq!(run(&map, &["dirs"]).1, format!("~/src {root}/a {root}\n"));
+    assert_eq!(
+        run(&map, &["dirs", "-lp"]).1,
+        format!("{root}/home/src\n{root}/a\n{root}\n")
+    );
+}
+
+#[test]
+fn dirs_clear_leaves_only_the_current_directory() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+    let root = temp.path().canonicalize().unwrap();
+
+    run(&map, &["pushd", "a"]);
+    run(&map, &["pushd", "../b"]);
+    assert_eq!(map.dir_stack().borrow().saved().len(), 2);
+
+    assert_eq!(run(&map, &["dirs", "-c"]), (Some(0), String::new()));
+    assert!(map.dir_stack().borrow().saved().is_empty());
+    assert_eq!(
+        run(&map, &["dirs", "-v"]).1,
+        format!(" 0  {}/b\n", root.display())
+    );
+    assert_eq!(run(&map, &["popd"]).0, Some(1));
+}
+
+#[test]
+fn dirs_rejects_unknown_options_and_operands() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+
+    assert_eq!(run(&map, &["dirs", "-x"]), (Some(1), String::new()));
+    assert_eq!(run(&map, &["dirs", "extra"]), (Some(1), String::new()));
+}
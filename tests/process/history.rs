@@ -1,2 +1,48 @@
+use iridium::process::builtin::Builtin;
+use iridium::process::history::History;
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use tempfile::TempDir;
+
+const HISTORY: &str = "100:0:ls -la\n101:0:cargo build\n102:1:git status\n103:0:cargo test\n";
+
+fn run_history(args: &[&str]) -> String {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(".iridium_history");
+    fs::write(&path, HISTORY).unwrap();
+
+    let buffer = Rc::new(RefCell::new(Vec::new()));
+    let mut history = History::with_history_file(&path);
+    history.capture_output_buffer(buffer.clone());
+    let owned: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+    assert_eq!(history.call(&owned), Some(0));
+
+    String::from_utf8(buffer.borrow().clone()).unwrap()
+}
+
 #[test]
 fn process_history_placeholder() {}
+
+#[test]
+fn history_lists_all_commands_in_order() {
+    assert_eq!(
+        run_history(&[]),
+        "0 ls -la\n1 cargo build\n2 git status\n3 cargo test\n"
+    );
+}
+
+#[test]
+fn history_filters_by_command_substring() {
+    assert_eq!(run_history(&["cargo"]), "1 cargo build\n3 cargo test\n");
+    // Timestamps and status codes are not part of the match.
+    assert_eq!(run_history(&["10"]), "");
+}
+
+#[test]
+fn history_reverse_flag_prints_newest_first() {
+    assert_eq!(
+        run_history(&["-r", "cargo"]),
+        "3 cargo test\n1 cargo build\n"
+    );
+}
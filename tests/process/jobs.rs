@@ -0,0 +1,173 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::jobs::Jobs;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn trailing_ampersand_returns_without_waiting_and_records_the_job() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    let started = Instant::now();
+    let status = process::execute_with_output(&map, &args(&["sleep", "2", "&"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert!(started.elapsed() < Duration::from_secs(1));
+    let jobs = map.jobs();
+    let jobs = jobs.borrow();
+    assert_eq!(jobs.len(), 1);
+    let job = jobs.iter().next().unwrap();
+    assert_eq!(job.id, 1);
+    assert_eq!(job.command, "sleep 2");
+    assert_eq!(
+        String::from_utf8(sink).unwrap(),
+        format!("[1] {}\n", job.pid)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn background_jobs_read_an_empty_stdin() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    // With the terminal's stdin `cat` would wait for input; with /dev/null it ends at once.
+    process::execute_with_output(&map, &args(&["cat", "&"]), &mut sink);
+
+    let jobs = map.jobs();
+    let finished = reap_until(&mut jobs.borrow_mut(), 1);
+    assert_eq!(finished, vec![(1, Some(0))]);
+}
+
+#[cfg(unix)]
+#[test]
+fn background_jobs_run_in_their_own_process_group() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    process::execute_with_output(&map, &args(&["sleep", "30", "&"]), &mut sink);
+
+    let jobs = map.jobs();
+    let pid = jobs.borrow().iter().next().unwrap().pid as libc::pid_t;
+    // SAFETY: both calls only take a process id and touch no memory of ours.
+    let group = unsafe { libc::getpgid(pid) };
+    unsafe { libc::kill(pid, libc::SIGKILL) };
+    assert_eq!(group, pid);
+    assert_eq!(reap_until(&mut jobs.borrow_mut(), 1), vec![(1, None)]);
+}
+
+/// Reap `jobs` until `count` have finished, returning their numbers and statuses; gives up after
+/// a few seconds so a hung child fails the test instead of blocking it.
+fn reap_until(jobs: &mut Jobs, count: usize) -> Vec<(usize, Option<i32>)> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut finished = Vec::new();
+    while finished.len() < count && Instant::now() < deadline {
+        finished.extend(
+            jobs.reap()
+                .into_iter()
+                .map(|(job, status)| (job.id, status)),
+        );
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    finished
+}
+
+#[cfg(unix)]
+#[test]
+fn finished_jobs_are_reaped_with_their_status() {
+    let mut jobs = Jobs::default();
+    // `cat` keeps running until the stdin held here is closed.
+    let mut blocked = Command::new("cat")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let stdin = blocked.stdin.take().unwrap();
+    assert_eq!(jobs.add(blocked, "cat"), 1);
+    assert_eq!(jobs.add(Command::new("false").spawn().unwrap(), "false"), 2);
+
+    assert_eq!(reap_until(&mut jobs, 1), vec![(2, Some(1))]);
+    assert_eq!(jobs.len(), 1, "the blocked job is still running");
+    assert_eq!(
+        jobs.add(Command::new("true").spawn().unwrap(), "true"),
+        2,
+        "numbers continue from the highest running job"
+    );
+
+    drop(stdin);
+    let mut finished = reap_until(&mut jobs, 2);
+    finished.sort();
+    assert_eq!(finished, vec![(1, Some(0)), (2, Some(0))]);
+    assert!(jobs.is_empty());
+}
+
+fn jobs_listing(map: &BuiltinMap, options: &[&str]) -> String {
+    let mut sink = Vec::new();
+    assert_eq!(
+        map.invoke_with_output("jobs", &args(options), &mut sink),
+        Some(Some(0))
+    );
+    String::from_utf8(sink).unwrap()
+}
+
+/// Run the `jobs` builtin until its listing contains `expected`, returning that listing.
+fn jobs_listing_with(map: &BuiltinMap, expected: &str) -> String {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let listing = jobs_listing(map, &[]);
+        if listing.contains(expected) || Instant::now() >= deadline {
+            return listing;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn jobs_builtin_reports_finished_jobs_once_and_lists_running_ones() {
+    let map = BuiltinMap::new();
+    let jobs = map.jobs();
+    let mut blocked = Command::new("cat")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    let stdin = blocked.stdin.take().unwrap();
+    let pid = blocked.id();
+    jobs.borrow_mut().add(blocked, "cat");
+    jobs.borrow_mut()
+        .add(Command::new("false").spawn().unwrap(), "false");
+
+    assert_eq!(
+        jobs_listing_with(&map, "Exit"),
+        "[2]  Exit 1\tfalse\n[1]  Running\tcat\n"
+    );
+    assert_eq!(jobs_listing(&map, &[]), "[1]  Running\tcat\n");
+    assert_eq!(
+        jobs_listing(&map, &["-l"]),
+        format!("[1]  {pid} Running\tcat\n")
+    );
+    assert_eq!(jobs_listing(&map, &["-p"]), format!("{pid}\n"));
+
+    drop(stdin);
+    assert_eq!(jobs_listing_with(&map, "Done"), "[1]  Done\tcat\n");
+    assert_eq!(jobs_listing(&map, &[]), "");
+}
+
+#[test]
+fn lone_ampersand_and_background_pipelines_are_rejected() {
+    let map = BuiltinMap::new();
+
+    assert_eq!(process::execute(&map, &args(&["&"])), Some(2));
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "hi", "|", "cat", "&"])),
+        Some(2)
+    );
+    assert!(map.jobs().borrow().is_empty());
+}
@@ -0,0 +1,103 @@
+use iridium::process::{self, Connector};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+/// Run `line` through `execute_list`, treating `true` and `false` as their usual commands and
+/// recording the name of every command that ran.
+fn run(line: &str) -> (Option<i32>, Vec<String>) {
+    let mut ran = Vec::new();
+    let status = process::execute_list(&args(line), |command| {
+        ran.push(command.join(" "));
+        match command[0].as_str() {
+            "true" => Some(0),
+            "false" => Some(1),
+            "missing" => None,
+            "exit" => Some(process::exit::EXIT_CODE),
+            other => panic!("unexpected command {other}"),
+        }
+    });
+    (status, ran)
+}
+
+#[test]
+fn and_runs_the_next_command_only_after_success() {
+    assert_eq!(run("true && false"), (Some(1), args("true false")));
+    assert_eq!(run("false && true"), (Some(1), args("false")));
+}
+
+#[test]
+fn or_runs_the_next_command_only_after_failure() {
+    assert_eq!(run("false || true"), (Some(0), args("false true")));
+    assert_eq!(run("true || false"), (Some(0), args("true")));
+    assert_eq!(run("missing || true"), (Some(0), args("missing true")));
+}
+
+#[test]
+fn operators_associate_left_and_skipped_commands_keep_the_last_status() {
+    assert_eq!(
+        run("true || false && true"),
+        (Some(0), args("true true")),
+        "the skipped false leaves true's status for &&"
+    );
+    assert_eq!(run("false && true || true"), (Some(0), args("false true")));
+    assert_eq!(run("false && true && true"), (Some(1), args("false")));
+}
+
+#[test]
+fn exit_stops_the_list() {
+    assert_eq!(
+        run("exit && true"),
+        (Some(process::exit::EXIT_CODE), args("exit"))
+    );
+}
+
+#[test]
+fn operators_without_a_command_on_both_sides_are_syntax_errors() {
+    for line in ["&& true", "true ||", "true && || false"] {
+        assert!(process::split_list(&args(line)).is_err(), "{line}");
+        assert_eq!(run(line), (Some(2), Vec::new()), "{line}");
+    }
+}
+
+#[test]
+fn split_list_pairs_commands_with_their_operators() {
+    let tokens = args("make all && ./run -v || echo failed");
+    let commands = process::split_list(&tokens).unwrap();
+    assert_eq!(
+        commands,
+        vec![
+            (None, &tokens[0..2]),
+            (Some(Connector::And), &tokens[3..5]),
+            (Some(Connector::Or), &tokens[6..8]),
+        ]
+    );
+}
+
+#[test]
+fn quoted_operators_do_not_split_the_list() {
+    let tokens = process::split_words(r#"echo '&&' "||" \&\& && true"#).unwrap();
+    let commands = process::split_list(&tokens).unwrap();
+    assert_eq!(commands.len(), 2);
+    assert_eq!(
+        commands[0].1.len(),
+        4,
+        "the quoted operators stay arguments of echo"
+    );
+    assert_eq!(commands[1], (Some(Connector::And), &tokens[5..]));
+}
+
+#[test]
+fn split_words_follows_shell_quoting() {
+    assert_eq!(
+        process::split_words(r#"echo 'a b' "c\"d" e\ f # comment"#),
+        Some(vec![
+            "echo".to_string(),
+            "a b".to_string(),
+            "c\"d".to_string(),
+            "e f".to_string(),
+        ])
+    );
+    assert_eq!(process::split_words("echo 'open"), None);
+}
@@ -0,0 +1,302 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::store::buffer_store::BufferStore;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn pipeline_connects_stages_and_captures_the_last_output() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    let status = process::execute_with_output(
+        &map,
+        &args(&[
+            "printf",
+            "alpha\\nbeta\\ngamma\\n",
+            "|",
+            "grep",
+            "a$",
+            "|",
+            "sort",
+            "-r",
+        ]),
+        &mut sink,
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "gamma\nbeta\nalpha\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn pipeline_status_is_that_of_the_last_stage() {
+    let map = BuiltinMap::new();
+
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "hi", "|", "cat"])),
+        Some(0)
+    );
+    assert_eq!(
+        process::execute(&map, &args(&["false", "|", "true"])),
+        Some(0)
+    );
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "hi", "|", "grep", "nomatch"])),
+        Some(1)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn pipeline_output_can_be_redirected_from_the_last_stage() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("out.txt");
+    let map = BuiltinMap::new();
+
+    let status = process::execute(
+        &map,
+        &args(&[
+            "echo",
+            "piped",
+            "|",
+            "tr",
+            "a-z",
+            "A-Z",
+            ">",
+            target.to_str().unwrap(),
+        ]),
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "PIPED\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn leading_builtin_output_feeds_the_next_stage() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    let status =
+        process::execute_with_output(&map, &args(&["date", "+%Y", "|", "wc", "-l"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap().trim(), "1");
+}
+
+#[cfg(unix)]
+#[test]
+fn every_leading_builtin_writes_into_the_pipeline() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    let status = process::execute_with_output(&map, &args(&["set", "|", "cat"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "noclobber\toff\n");
+
+    let mut sink = Vec::new();
+    let status =
+        process::execute_with_output(&map, &args(&["welcome", "|", "wc", "-l"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert_ne!(String::from_utf8(sink).unwrap().trim(), "0");
+}
+
+#[cfg(unix)]
+#[test]
+fn a_stage_that_cannot_redirect_starts_no_stage() {
+    let dir = TempDir::new().unwrap();
+    let marker = dir.path().join("marker");
+    let target = dir.path().join("out.txt");
+    fs::write(&target, "keep\n").unwrap();
+    let map = BuiltinMap::new();
+    map.invoke("set", &args(&["-o", "noclobber"]));
+
+    let status = process::execute(
+        &map,
+        &args(&[
+            "touch",
+            marker.to_str().unwrap(),
+            "|",
+            "cat",
+            ">",
+            target.to_str().unwrap(),
+        ]),
+    );
+
+    assert_eq!(status, Some(1));
+    assert!(!marker.exists());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "keep\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn tee_stage_fills_a_buffer_and_passes_its_input_on() {
+    let buffers = Arc::new(Mutex::new(BufferStore::new()));
+    let mut map = BuiltinMap::new();
+    map.set_buffer_store(Arc::clone(&buffers));
+    let mut sink = Vec::new();
+
+    let status = process::execute_with_output(
+        &map,
+        &args(&[
+            "printf",
+            "beta\\nalpha\\n",
+            "|",
+            "tee",
+            "@pipeline_tee_listing",
+            "|",
+            "sort",
+        ]),
+        &mut sink,
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "alpha\nbeta\n");
+    let store = buffers.lock().unwrap();
+    assert_eq!(
+        store.get("pipeline_tee_listing").unwrap().lines(),
+        ["beta", "alpha"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn tee_stage_may_end_a_pipeline_started_by_a_builtin() {
+    let buffers = Arc::new(Mutex::new(BufferStore::new()));
+    let mut map = BuiltinMap::new();
+    map.set_buffer_store(Arc::clone(&buffers));
+    let mut sink = Vec::new();
+
+    let status = process::execute_with_output(
+        &map,
+        &args(&["set", "|", "tee", "@pipeline_tee_options"]),
+        &mut sink,
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "noclobber\toff\n");
+    let store = buffers.lock().unwrap();
+    assert_eq!(
+        store.get("pipeline_tee_options").unwrap().lines(),
+        ["noclobber\toff"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn tee_stage_streams_until_downstream_stops_reading() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let buffers = Arc::new(Mutex::new(BufferStore::new()));
+        let mut map = BuiltinMap::new();
+        map.set_buffer_store(Arc::clone(&buffers));
+        let mut sink = Vec::new();
+        let status = process::execute_with_output(
+            &map,
+            &args(&[
+                "yes",
+                "|",
+                "tee",
+                "@pipeline_tee_stream",
+                "|",
+                "head",
+                "-n",
+                "3",
+            ]),
+            &mut sink,
+        );
+        let lines = buffers
+            .lock()
+            .unwrap()
+            .get("pipeline_tee_stream")
+            .unwrap()
+            .lines()
+            .to_vec();
+        sender.send((status, sink, lines)).unwrap();
+    });
+
+    let (status, sink, lines) = receiver
+        .recv_timeout(Duration::from_secs(10))
+        .expect("an endless producer should stop once head exits");
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "y\ny\ny\n");
+    assert!(lines.len() >= 3);
+    assert!(lines.iter().all(|line| line == "y"));
+}
+
+#[cfg(unix)]
+#[test]
+fn tee_stage_refuses_read_only_buffers() {
+    let buffers = Arc::new(Mutex::new(BufferStore::new()));
+    {
+        let mut store = buffers.lock().unwrap();
+        store.open("pipeline_tee_locked").append("keep".into());
+        store.set_read_only("pipeline_tee_locked", true);
+    }
+    let mut map = BuiltinMap::new();
+    map.set_buffer_store(Arc::clone(&buffers));
+    let mut sink = Vec::new();
+
+    let status = process::execute_with_output(
+        &map,
+        &args(&["printf", "new\\n", "|", "tee", "@pipeline_tee_locked"]),
+        &mut sink,
+    );
+
+    assert_eq!(status, Some(1));
+    assert!(sink.is_empty(), "nothing is spawned");
+    let store = buffers.lock().unwrap();
+    assert_eq!(store.get("pipeline_tee_locked").unwrap().lines(), ["keep"]);
+}
+
+#[test]
+fn tee_stage_without_a_buffer_store_fails() {
+    let map = BuiltinMap::new();
+
+    assert_eq!(
+        process::execute(&map, &args(&["set", "|", "tee", "@nowhere"])),
+        Some(1)
+    );
+}
+
+#[test]
+fn builtins_after_the_first_stage_and_empty_stages_are_rejected() {
+    let map = BuiltinMap::new();
+
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "hi", "|", "cd"])),
+        Some(2)
+    );
+    assert_eq!(process::execute(&map, &args(&["echo", "hi", "|"])), Some(2));
+    assert_eq!(process::execute(&map, &args(&["|", "cat"])), Some(2));
+}
+
+#[cfg(unix)]
+#[test]
+fn quoted_operators_are_passed_as_arguments() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+
+    let line = r"printf 'a|b\nc\n' | grep '|'";
+    let status =
+        process::execute_with_output(&map, &process::split_words(line).unwrap(), &mut sink);
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "a|b\n");
+
+    let mut sink = Vec::new();
+    let line = r#"printf '%s\n' '>' "<" \>\> '&'"#;
+    let status =
+        process::execute_with_output(&map, &process::split_words(line).unwrap(), &mut sink);
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), ">\n<\n>>\n&\n");
+}
@@ -1,2 +1,82 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::environment::ShellEnvironment;
+use tempfile::TempDir;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn run(map: &BuiltinMap, command: &[&str]) -> (Option<i32>, String) {
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(map, &args(command), &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+fn isolated_map(root: &TempDir) -> BuiltinMap {
+    let root = root.path().canonicalize().unwrap();
+    for dir in ["a", "b"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    let environment = ShellEnvironment::isolated().into_handle();
+    {
+        let mut env = environment.borrow_mut();
+        env.set_current_dir(&root).unwrap();
+        env.set_var("PWD", root.to_str().unwrap());
+        env.set_var("HOME", "/nonexistent-home");
+    }
+    BuiltinMap::with_environment(environment)
+}
+
+fn pwd(map: &BuiltinMap) -> String {
+    map.environment().borrow().var("PWD").unwrap()
+}
+
 #[test]
-fn process_pushd_placeholder() {}
+fn pushd_changes_directory_and_prints_the_stack() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+    let root = temp.path().canonicalize().unwrap();
+    let root = root.display();
+
+    assert_eq!(
+        run(&map, &["pushd", "a"]),
+        (Some(0), format!("{root}/a {root}\n"))
+    );
+    assert_eq!(pwd(&map), format!("{root}/a"));
+
+    assert_eq!(
+        run(&map, &["pushd"]),
+        (Some(0), format!("{root} {root}/a\n"))
+    );
+    assert_eq!(pwd(&map), root.to_string());
+}
+
+#[test]
+fn popd_returns_to_the_saved_directory() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+    let root = temp.path().canonicalize().unwrap();
+    let root = root.display();
+
+    run(&map, &["pushd", "a"]);
+    run(&map, &["pushd", "../b"]);
+    assert_eq!(
+        run(&map, &["popd"]),
+        (Some(0), format!("{root}/a {root}\n"))
+    );
+    assert_eq!(pwd(&map), format!("{root}/a"));
+    assert_eq!(run(&map, &["popd"]).0, Some(0));
+    assert_eq!(pwd(&map), root.to_string());
+    assert_eq!(run(&map, &["popd"]), (Some(1), String::new()));
+}
+
+#[test]
+fn failed_pushd_leaves_the_stack_untouched() {
+    let temp = TempDir::new().unwrap();
+    let map = isolated_map(&temp);
+
+    assert_eq!(run(&map, &["pushd", "missing"]), (Some(1), String::new()));
+    assert_eq!(run(&map, &["pushd"]), (Some(1), String::new()));
+    assert!(map.dir_stack().borrow().saved().is_empty());
+}
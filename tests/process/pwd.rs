@@ -1,2 +1,56 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::environment::ShellEnvironment;
+use tempfile::TempDir;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
 #[test]
-fn process_pwd_placeholder() {}
+fn pwd_output_is_written_to_the_capture_sink() {
+    let temp = TempDir::new().unwrap();
+    let environment = ShellEnvironment::isolated().into_handle();
+    environment
+        .borrow_mut()
+        .set_var("PWD", temp.path().to_str().unwrap());
+    let map = BuiltinMap::with_environment(environment);
+
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(&map, &args(&["pwd"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert_eq!(
+        String::from_utf8(sink).unwrap(),
+        format!("{}\n", temp.path().display())
+    );
+}
+
+#[test]
+fn physical_pwd_and_alias_listings_are_captured() {
+    let temp = TempDir::new().unwrap();
+    let environment = ShellEnvironment::isolated().into_handle();
+    environment
+        .borrow_mut()
+        .set_var("PWD", temp.path().to_str().unwrap());
+    let map = BuiltinMap::with_environment(environment);
+
+    let mut sink = Vec::new();
+    assert_eq!(
+        process::execute_with_output(&map, &args(&["pwd", "-P"]), &mut sink),
+        Some(0)
+    );
+    let canonical = temp.path().canonicalize().unwrap();
+    assert_eq!(
+        String::from_utf8(sink).unwrap(),
+        format!("{}\n", canonical.display())
+    );
+
+    process::execute(&map, &args(&["alias", "ll=ls -l"]));
+    let mut sink = Vec::new();
+    assert_eq!(
+        process::execute_with_output(&map, &args(&["alias"]), &mut sink),
+        Some(0)
+    );
+    assert!(String::from_utf8(sink).unwrap().contains("ll="));
+}
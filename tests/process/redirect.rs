@@ -0,0 +1,218 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use std::fs;
+use tempfile::TempDir;
+
+fn args(parts: &[&str]) -> Vec<String> {
+    parts.iter().map(|part| part.to_string()).collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn redirect_writes_command_output_to_file() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("out.txt");
+    let map = BuiltinMap::new();
+
+    let status = process::execute(&map, &args(&["echo", "hi", ">", target.to_str().unwrap()]));
+
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "hi\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn noclobber_refuses_to_overwrite_with_plain_redirect() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("keep.txt");
+    fs::write(&target, "original\n").unwrap();
+    let map = BuiltinMap::new();
+    assert_eq!(map.invoke("set", &args(&["-C"])), Some(Some(0)));
+
+    let status = process::execute(&map, &args(&["echo", "new", ">", target.to_str().unwrap()]));
+
+    assert_eq!(status, Some(1));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "original\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn noclobber_allows_forced_redirect_and_new_files() {
+    let dir = TempDir::new().unwrap();
+    let existing = dir.path().join("existing.txt");
+    let fresh = dir.path().join("fresh.txt");
+    fs::write(&existing, "original\n").unwrap();
+    let map = BuiltinMap::new();
+    map.invoke("set", &args(&["-o", "noclobber"]));
+
+    let forced = process::execute(
+        &map,
+        &args(&["echo", "forced", ">|", existing.to_str().unwrap()]),
+    );
+    let created = process::execute(&map, &args(&["echo", "new", ">", fresh.to_str().unwrap()]));
+
+    assert_eq!(forced, Some(0));
+    assert_eq!(fs::read_to_string(&existing).unwrap(), "forced\n");
+    assert_eq!(created, Some(0));
+    assert_eq!(fs::read_to_string(&fresh).unwrap(), "new\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn clearing_noclobber_restores_overwrite() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("file.txt");
+    fs::write(&target, "original\n").unwrap();
+    let map = BuiltinMap::new();
+    map.invoke("set", &args(&["-C"]));
+    map.invoke("set", &args(&["+C"]));
+
+    let status = process::execute(
+        &map,
+        &args(&["echo", "again", ">", target.to_str().unwrap()]),
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "again\n");
+}
+
+#[test]
+fn builtin_output_is_redirected_to_file() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("cwd.txt");
+    let map = BuiltinMap::new();
+    let cwd = map.environment().borrow().current_dir().unwrap();
+
+    let status = process::execute(&map, &args(&["pwd", ">", target.to_str().unwrap()]));
+
+    assert_eq!(status, Some(0));
+    assert_eq!(
+        fs::read_to_string(&target).unwrap(),
+        format!("{}\n", cwd.display())
+    );
+}
+
+#[test]
+fn builtin_redirect_honours_noclobber() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("keep.txt");
+    fs::write(&target, "original\n").unwrap();
+    let map = BuiltinMap::new();
+    map.invoke("set", &args(&["-C"]));
+
+    let refused = process::execute(&map, &args(&["set", ">", target.to_str().unwrap()]));
+    assert_eq!(refused, Some(1));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "original\n");
+
+    let forced = process::execute(&map, &args(&["set", ">|", target.to_str().unwrap()]));
+    assert_eq!(forced, Some(0));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "noclobber\ton\n");
+}
+
+#[test]
+fn set_rejects_unknown_options() {
+    let map = BuiltinMap::new();
+    assert_eq!(map.invoke("set", &args(&["-o", "bogus"])), Some(Some(2)));
+    assert!(!map.options().borrow().noclobber);
+}
+
+#[cfg(unix)]
+#[test]
+fn captured_execution_collects_external_command_output() {
+    let map = BuiltinMap::new();
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(&map, &args(&["echo", "captured"]), &mut sink);
+
+    assert_eq!(status, Some(0));
+    assert_eq!(String::from_utf8(sink).unwrap(), "captured\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn append_redirect_adds_to_the_file_while_plain_redirect_truncates() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("log.txt");
+    let path = target.to_str().unwrap();
+    let map = BuiltinMap::new();
+
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "first", ">>", path])),
+        Some(0)
+    );
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "second", ">>", path])),
+        Some(0)
+    );
+    assert_eq!(fs::read_to_string(&target).unwrap(), "first\nsecond\n");
+
+    assert_eq!(
+        process::execute(&map, &args(&["echo", "third", ">", path])),
+        Some(0)
+    );
+    assert_eq!(fs::read_to_string(&target).unwrap(), "third\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn append_redirect_is_allowed_under_noclobber() {
+    let dir = TempDir::new().unwrap();
+    let target = dir.path().join("log.txt");
+    fs::write(&target, "kept\n").unwrap();
+    let map = BuiltinMap::new();
+    map.invoke("set", &args(&["-C"]));
+
+    let status = process::execute(
+        &map,
+        &args(&["echo", "more", ">>", target.to_str().unwrap()]),
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read_to_string(&target).unwrap(), "kept\nmore\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn input_redirect_feeds_the_file_to_the_command() {
+    let dir = TempDir::new().unwrap();
+    let source = dir.path().join("in.txt");
+    let sorted = dir.path().join("sorted.txt");
+    fs::write(&source, "pear\napple\nfig\n").unwrap();
+    let map = BuiltinMap::new();
+
+    let status = process::execute(
+        &map,
+        &args(&[
+            "sort",
+            "<",
+            source.to_str().unwrap(),
+            ">",
+            sorted.to_str().unwrap(),
+        ]),
+    );
+
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read_to_string(&sorted).unwrap(), "apple\nfig\npear\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn missing_input_file_fails_without_running_the_command() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.txt");
+    let target = dir.path().join("out.txt");
+    let map = BuiltinMap::new();
+
+    let status = process::execute(
+        &map,
+        &args(&[
+            "cat",
+            "<",
+            missing.to_str().unwrap(),
+            ">",
+            target.to_str().unwrap(),
+        ]),
+    );
+
+    assert_eq!(status, Some(1));
+    assert!(!target.exists(), "the output target is not created");
+}
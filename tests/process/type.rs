@@ -1,2 +1,97 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::environment::ShellEnvironment;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn run(map: &BuiltinMap, command: &[&str]) -> (Option<i32>, String) {
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(map, &args(command), &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+/// A map whose isolated environment sits in `cwd` with `PATH` set to `path`.
+fn map_with_path(cwd: &TempDir, path: &str) -> BuiltinMap {
+    let environment = ShellEnvironment::isolated().into_handle();
+    {
+        let mut env = environment.borrow_mut();
+        env.set_current_dir(cwd.path()).unwrap();
+        env.set_var("PATH", path);
+    }
+    BuiltinMap::with_environment(environment)
+}
+
+fn write_program(dir: &TempDir, name: &str) {
+    let program = dir.path().join(name);
+    std::fs::write(&program, "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
 #[test]
-fn process_type_placeholder() {}
+fn type_p_finds_programs_through_an_empty_path_entry() {
+    let cwd = TempDir::new().unwrap();
+    write_program(&cwd, "localtool");
+    let map = map_with_path(&cwd, ":/tmp");
+
+    assert_eq!(
+        run(&map, &["type", "-p", "localtool"]),
+        (Some(0), "./localtool\n".to_string())
+    );
+    assert_eq!(
+        run(&map, &["type", "localtool"]).1,
+        "localtool is ./localtool\n"
+    );
+}
+
+#[test]
+fn type_describes_builtins_and_aliases() {
+    let cwd = TempDir::new().unwrap();
+    let map = map_with_path(&cwd, "");
+    assert_eq!(run(&map, &["alias", "ll=ls -l"]).0, Some(0));
+
+    assert_eq!(run(&map, &["type", "cd"]).1, "cd is a shell builtin\n");
+    assert_eq!(run(&map, &["type", "ll"]).1, "ll is aliased to `ls -l'\n");
+    assert_eq!(run(&map, &["type", "-t", "ll", "cd"]).1, "alias\nbuiltin\n");
+    assert_eq!(run(&map, &["type", "-p", "cd"]), (Some(0), String::new()));
+}
+
+#[test]
+fn type_reports_missing_names_and_bad_options() {
+    let cwd = TempDir::new().unwrap();
+    let map = map_with_path(&cwd, "");
+
+    assert_eq!(
+        run(&map, &["type", "no-such-command"]),
+        (Some(1), String::new())
+    );
+    assert_eq!(run(&map, &["type", "-x", "cd"]).0, Some(2));
+}
+
+#[test]
+fn type_reports_reserved_words_as_keywords() {
+    let cwd = TempDir::new().unwrap();
+    write_program(&cwd, "if");
+    let map = map_with_path(&cwd, cwd.path().to_str().unwrap());
+    assert_eq!(run(&map, &["alias", "then=echo"]).0, Some(0));
+
+    assert_eq!(
+        run(&map, &["type", "-t", "if"]),
+        (Some(0), "keyword\n".to_string())
+    );
+    assert_eq!(
+        run(&map, &["type", "while"]).1,
+        "while is a shell keyword\n"
+    );
+    assert_eq!(
+        run(&map, &["type", "-t", "then", "cd"]).1,
+        "keyword\nbuiltin\n"
+    );
+
+    let (status, all) = run(&map, &["type", "-at", "if"]);
+    assert_eq!((status, all.as_str()), (Some(0), "keyword\nfile\n"));
+    assert_eq!(run(&map, &["type", "-p", "if"]), (Some(0), String::new()));
+}
@@ -0,0 +1,43 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+
+fn run_umask(map: &BuiltinMap, operands: &[&str]) -> (Option<i32>, String) {
+    let mut argv = vec!["umask".to_string()];
+    argv.extend(operands.iter().map(|operand| operand.to_string()));
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(map, &argv, &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+#[test]
+fn umask_rejects_invalid_modes() {
+    let map = BuiltinMap::new();
+    assert_eq!(run_umask(&map, &["999"]).0, Some(1));
+    assert_eq!(run_umask(&map, &["022", "077"]).0, Some(1));
+}
+
+#[cfg(unix)]
+#[test]
+fn umask_applies_to_buffers_saved_to_new_files() {
+    use iridium::store::buffer_store::BufferStore;
+    use std::os::unix::fs::PermissionsExt;
+
+    let map = BuiltinMap::new();
+    let (_, previous) = run_umask(&map, &[]);
+    let previous = previous.trim().to_string();
+
+    assert_eq!(run_umask(&map, &["027"]).0, Some(0));
+    assert_eq!(run_umask(&map, &[]).1, "0027\n");
+
+    let temp = tempfile::tempdir().unwrap();
+    let path = temp.path().join("created.txt");
+    let name = path.to_string_lossy().to_string();
+    let mut store = BufferStore::new();
+    store.open(name.as_str()).append("text".into());
+    let saved = store.save(&name);
+    run_umask(&map, &[&previous]);
+
+    saved.unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
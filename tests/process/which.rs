@@ -1,2 +1,79 @@
+use iridium::process;
+use iridium::process::builtin::map::BuiltinMap;
+use iridium::process::environment::ShellEnvironment;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+fn args(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn run(map: &BuiltinMap, command: &[&str]) -> (Option<i32>, String) {
+    let mut sink = Vec::new();
+    let status = process::execute_with_output(map, &args(command), &mut sink);
+    (status, String::from_utf8(sink).unwrap())
+}
+
+/// A map whose isolated environment sits in `cwd` with `PATH` set to `path`.
+fn map_with_path(cwd: &TempDir, path: &str) -> BuiltinMap {
+    let environment = ShellEnvironment::isolated().into_handle();
+    {
+        let mut env = environment.borrow_mut();
+        env.set_current_dir(cwd.path()).unwrap();
+        env.set_var("PATH", path);
+    }
+    BuiltinMap::with_environment(environment)
+}
+
+fn write_program(dir: &TempDir, name: &str) {
+    let program = dir.path().join(name);
+    std::fs::write(&program, "#!/bin/sh\n").unwrap();
+    std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+}
+
 #[test]
-fn process_which_placeholder() {}
+fn leading_empty_path_entry_searches_the_current_directory() {
+    let cwd = TempDir::new().unwrap();
+    write_program(&cwd, "localtool");
+    let map = map_with_path(&cwd, ":/tmp");
+
+    assert_eq!(
+        run(&map, &["which", "localtool"]),
+        (Some(0), "./localtool\n".to_string())
+    );
+}
+
+#[test]
+fn trailing_and_doubled_empty_entries_search_the_current_directory() {
+    let cwd = TempDir::new().unwrap();
+    let bin = TempDir::new().unwrap();
+    write_program(&cwd, "localtool");
+    let bin_path = bin.path().to_str().unwrap();
+
+    let trailing = map_with_path(&cwd, &format!("{bin_path}:"));
+    assert_eq!(run(&trailing, &["which", "localtool"]).0, Some(0));
+
+    let doubled = map_with_path(&cwd, &format!("{bin_path}::/nonexistent"));
+    assert_eq!(
+        run(&doubled, &["which", "localtool"]).1,
+        "./localtool\n".to_string()
+    );
+}
+
+#[test]
+fn path_without_empty_entries_skips_the_current_directory() {
+    let cwd = TempDir::new().unwrap();
+    let bin = TempDir::new().unwrap();
+    write_program(&cwd, "localtool");
+    write_program(&bin, "bintool");
+    let map = map_with_path(&cwd, bin.path().to_str().unwrap());
+
+    assert_eq!(run(&map, &["which", "localtool"]).0, None);
+    assert_eq!(
+        run(&map, &["which", "bintool"]),
+        (
+            Some(0),
+            format!("{}\n", bin.path().join("bintool").display())
+        )
+    );
+}
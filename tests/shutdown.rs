@@ -0,0 +1,51 @@
+use iridium::control::{LineEditor, run_loop_with_editor};
+use iridium::control_state::ControlState;
+use iridium::store::persistence::{PersistenceConfig, PersistenceManager};
+use rustyline::error::ReadlineError;
+use std::collections::VecDeque;
+use std::io::Cursor;
+use tempfile::tempdir;
+
+struct ScriptedEditor {
+    responses: VecDeque<Result<String, ReadlineError>>,
+}
+
+impl LineEditor for ScriptedEditor {
+    fn readline(&mut self, _: &str) -> Result<String, ReadlineError> {
+        self.responses
+            .pop_front()
+            .unwrap_or(Err(ReadlineError::Eof))
+    }
+
+    fn add_history_entry(&mut self, _: &str) -> rustyline::Result<bool> {
+        Ok(true)
+    }
+}
+
+#[test]
+fn eof_shutdown_writes_buffers_to_the_database() {
+    let dir = tempdir().expect("tempdir");
+    let db_path = dir.path().join("buffers.db");
+    unsafe {
+        std::env::set_var("IRIDIUM_SKIP_EDITOR", "1");
+        std::env::remove_var("IRIDIUM_DISABLE_PERSISTENCE");
+        std::env::set_var("IRIDIUM_BUFFER_DB_PATH", &db_path);
+    }
+    let mut control_state = ControlState::with_isolated_environment(true);
+    let mut editor = ScriptedEditor {
+        responses: vec![Ok(":b scratchpad".into()), Err(ReadlineError::Eof)].into(),
+    };
+    let mut sink = Cursor::new(Vec::new());
+
+    run_loop_with_editor(&mut control_state, &mut editor, &mut sink).unwrap();
+
+    assert!(db_path.exists());
+    let snapshots = PersistenceManager::new(PersistenceConfig::with_path(db_path))
+        .load()
+        .expect("load snapshots");
+    assert!(
+        snapshots
+            .iter()
+            .any(|snapshot| snapshot.name == "scratchpad")
+    );
+}
@@ -57,7 +57,7 @@ fn buffer_insert_and_delete_characters_via_store() {
 }
 
 #[test]
-fn buffer_insert_newline_and_pad_line_via_store() {
+fn buffer_insert_newline_via_store() {
     let mut store = BufferStore::new();
     store.insert_char("mem://buffer", 0, 0, 'h');
     store.insert_char("mem://buffer", 0, 1, 'e');
@@ -68,8 +68,6 @@ fn buffer_insert_newline_and_pad_line_via_store() {
     let (row, col) = store.insert_newline("mem://buffer", 0, 2);
     assert_eq!((row, col), (1, 0));
 
-    store.pad_line("mem://buffer", 1, 5);
-
     let buffer = store.get("mem://buffer").expect("buffer");
-    assert_eq!(buffer.lines(), &["he".to_string(), "llo  ".to_string()]);
+    assert_eq!(buffer.lines(), &["he".to_string(), "llo".to_string()]);
 }
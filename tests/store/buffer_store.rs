@@ -1,6 +1,8 @@
-use iridium::store::buffer_store::BufferStore;
+use iridium::store::buffer_store::{BufferStore, OpenError};
+use iridium::store::observer::BufferObserver;
 use std::fs;
 use std::io::Read;
+use std::sync::Arc;
 
 fn unique_temp_file() -> std::path::PathBuf {
     let mut path = std::env::temp_dir();
@@ -40,6 +42,90 @@ fn save_persists_buffer_contents() {
     let _ = fs::remove_file(&path);
 }
 
+#[test]
+fn save_writes_to_path_distinct_from_name() {
+    let path = unique_temp_file();
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut store = BufferStore::new();
+    store.open("notes").append("kept under a title".into());
+    assert!(store.set_path("notes", Some(path_str.clone())));
+    assert!(!store.set_path("missing", Some(path_str.clone())));
+
+    store.save("notes").expect("save should succeed");
+
+    assert!(!store.is_dirty("notes"));
+    assert!(!std::path::Path::new("notes").exists());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "kept under a title\n");
+    let buffer = store.get("notes").unwrap();
+    assert_eq!(buffer.name(), "notes");
+    assert_eq!(buffer.storage_path(), path_str);
+
+    let _ = fs::remove_file(&path);
+}
+
+fn push_mtime_forward(path: &std::path::Path) {
+    let file = fs::OpenOptions::new().write(true).open(path).unwrap();
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    file.set_modified(later).unwrap();
+}
+
+#[test]
+fn disk_changed_detects_external_modification_after_load() {
+    let path = unique_temp_file();
+    let path_str = path.to_string_lossy().to_string();
+    fs::write(&path, "alpha\nbeta\n").unwrap();
+
+    let mut store = BufferStore::new();
+    store.open(path_str.clone());
+    store
+        .load_from_disk(&path_str)
+        .expect("load should succeed");
+    assert_eq!(
+        store.get(&path_str).unwrap().lines(),
+        &["alpha".to_string(), "beta".to_string()]
+    );
+    assert!(!store.is_dirty(&path_str));
+    assert!(!store.disk_changed(&path_str));
+
+    fs::write(&path, "changed elsewhere\n").unwrap();
+    push_mtime_forward(&path);
+    assert!(store.disk_changed(&path_str));
+
+    store
+        .load_from_disk(&path_str)
+        .expect("reload should succeed");
+    assert!(!store.disk_changed(&path_str));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn saving_resets_the_disk_baseline() {
+    let path = unique_temp_file();
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut store = BufferStore::new();
+    store.open(path_str.clone()).append("draft".into());
+    assert!(!store.disk_changed(&path_str));
+
+    store.save(&path_str).expect("save should succeed");
+    assert!(!store.disk_changed(&path_str));
+
+    push_mtime_forward(&path);
+    assert!(store.disk_changed(&path_str));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn disk_changed_is_false_without_a_file() {
+    let mut store = BufferStore::new();
+    store.open("never-saved").append("text".into());
+    assert!(!store.disk_changed("never-saved"));
+    assert!(!store.disk_changed("missing"));
+}
+
 #[test]
 fn store_lists_buffers_and_reports_emptiness() {
     let mut store = BufferStore::new();
@@ -54,6 +140,30 @@ fn store_lists_buffers_and_reports_emptiness() {
     assert!(!store.is_empty());
 }
 
+#[test]
+fn list_by_modified_orders_most_recent_edit_first() {
+    let mut store = BufferStore::new();
+    store.open("alpha");
+    store.open("beta");
+    store.open("gamma");
+    store.open("untouched");
+
+    for name in ["beta", "alpha", "gamma"] {
+        store.insert_char(name, 0, 0, 'x');
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    assert_eq!(
+        store.list_by_modified(),
+        vec!["gamma", "alpha", "beta", "untouched"]
+    );
+
+    store.insert_char("beta", 0, 1, 'y');
+    assert_eq!(
+        store.list_by_modified(),
+        vec!["beta", "gamma", "alpha", "untouched"]
+    );
+}
+
 #[test]
 fn store_insert_and_delete_characters() {
     let mut store = BufferStore::new();
@@ -71,7 +181,7 @@ fn store_insert_and_delete_characters() {
 }
 
 #[test]
-fn store_insert_newline_and_pad_line() {
+fn store_insert_newline_splits_the_line() {
     let mut store = BufferStore::new();
     store.insert_char("buf", 0, 0, 'h');
     store.insert_char("buf", 0, 1, 'i');
@@ -79,10 +189,8 @@ fn store_insert_newline_and_pad_line() {
     let (row, col) = store.insert_newline("buf", 0, 1);
     assert_eq!((row, col), (1, 0));
 
-    store.pad_line("buf", 1, 3);
-
     let buffer = store.get("buf").expect("buffer");
-    assert_eq!(buffer.lines(), &["h".to_string(), "i  ".to_string()]);
+    assert_eq!(buffer.lines(), &["h".to_string(), "i".to_string()]);
 }
 
 #[test]
@@ -163,3 +271,379 @@ fn untitled_buffers_require_names_until_renamed() {
     assert!(store.rename("Untitled-1", "named"));
     assert!(!store.requires_name("named"));
 }
+
+#[test]
+fn save_creates_missing_parent_directories_by_default() {
+    let root = unique_temp_file();
+    let path = root.join("nested").join("deeper").join("file.txt");
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut store = BufferStore::new();
+    store.open(path_str.clone()).append("content".into());
+    store.save(&path_str).expect("save should create parents");
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "content\n");
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn save_refuses_missing_parent_when_mkdir_disabled() {
+    let root = unique_temp_file();
+    let path = root.join("nested").join("file.txt");
+    let path_str = path.to_string_lossy().to_string();
+
+    let mut store = BufferStore::new();
+    store.set_mkdir_on_save(false);
+    store.open(path_str.clone()).append("content".into());
+
+    let err = store.save(&path_str).expect_err("save should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert!(err.to_string().contains("directory does not exist"));
+    assert!(!root.exists());
+    assert!(store.is_dirty(&path_str));
+}
+
+#[test]
+fn close_all_skips_dirty_buffers_unless_forced_and_open_all_restores() {
+    let mut store = BufferStore::new();
+    for name in ["alpha", "beta", "gamma"] {
+        store.open(name);
+    }
+    store.open("beta").append("pending".into());
+
+    assert_eq!(store.close_all(false), vec!["beta".to_string()]);
+    assert_eq!(store.open_buffers(), vec!["beta".to_string()]);
+
+    assert!(store.close_all(true).is_empty());
+    assert!(store.open_buffers().is_empty());
+    assert_eq!(store.list().len(), 3);
+
+    assert_eq!(store.open_all(), 3);
+    let mut open = store.open_buffers();
+    open.sort();
+    assert_eq!(open, vec!["alpha", "beta", "gamma"]);
+}
+
+#[test]
+fn try_open_refuses_buffers_past_the_open_limit() {
+    let mut store = BufferStore::new();
+    store.set_max_open_buffers(Some(2));
+
+    assert!(store.try_open("alpha").is_ok());
+    assert!(store.try_open_untitled("Untitled-1").is_ok());
+    let err = store
+        .try_open("gamma")
+        .expect_err("third buffer should be refused");
+    assert_eq!(err, OpenError::Limit(2));
+    assert_eq!(
+        err.to_string(),
+        "Too many open buffers: editor.max_open_buffers is 2; close a buffer first"
+    );
+    assert!(store.get("gamma").is_none());
+
+    assert!(
+        store.try_open("alpha").is_ok(),
+        "already open buffers are allowed"
+    );
+    store.mark_closed("alpha");
+    assert!(store.try_open("gamma").is_ok());
+    assert!(store.try_open("alpha").is_err());
+
+    store.set_max_open_buffers(None);
+    assert!(store.try_open("alpha").is_ok());
+}
+
+#[test]
+fn apply_edit_is_a_single_undoable_change() {
+    let mut store = BufferStore::new();
+    {
+        let buffer = store.open("notes");
+        for line in ["pear", "apple", "fig"] {
+            buffer.append(line.into());
+        }
+    }
+    store.save_in_memory("notes");
+    assert!(!store.is_dirty("notes"));
+
+    assert!(store.apply_edit("notes", |lines| lines.sort()));
+    assert_eq!(
+        store.get("notes").unwrap().lines(),
+        ["apple", "fig", "pear"]
+    );
+    assert!(store.is_dirty("notes"));
+
+    assert!(store.undo("notes"));
+    assert_eq!(
+        store.get("notes").unwrap().lines(),
+        ["pear", "apple", "fig"]
+    );
+
+    assert!(
+        !store.apply_edit("notes", |lines| lines.rotate_left(0)),
+        "an edit that changes nothing is not recorded"
+    );
+    assert!(!store.apply_edit("missing", |lines| lines.clear()));
+}
+
+#[test]
+fn try_open_refuses_directories() {
+    let dir = unique_temp_file();
+    fs::create_dir(&dir).unwrap();
+    let name = dir.to_string_lossy().to_string();
+    let mut store = BufferStore::new();
+
+    let err = store
+        .try_open(name.clone())
+        .expect_err("a directory cannot back a buffer");
+    assert_eq!(err, OpenError::Directory(name.clone()));
+    assert_eq!(err.to_string(), format!("{name} is a directory"));
+    assert!(store.try_open_untitled(name.clone()).is_err());
+    assert!(store.get(&name).is_none(), "no buffer is created");
+
+    fs::remove_dir(&dir).unwrap();
+}
+
+#[test]
+fn read_only_applies_per_buffer_or_to_every_buffer() {
+    let mut store = BufferStore::new();
+    store.open("alpha");
+    store.open("beta");
+
+    assert!(store.set_read_only("alpha", true));
+    assert!(!store.set_read_only("missing", true));
+    assert!(store.is_read_only("alpha"));
+    assert!(!store.is_read_only("beta"));
+
+    store.set_all_read_only(true);
+    store.open("gamma");
+    assert!(store.is_read_only("beta"));
+    assert!(store.is_read_only("gamma"));
+
+    store.set_all_read_only(false);
+    for name in ["alpha", "beta", "gamma"] {
+        assert!(!store.is_read_only(name), "{name} should be writable");
+    }
+}
+
+fn save_over_existing_file(store: &mut BufferStore, path: &std::path::Path) {
+    fs::write(path, "old\n").unwrap();
+    let name = path.to_string_lossy().to_string();
+    store.open(name.as_str());
+    store.apply_edit(&name, |lines| *lines = vec!["new".to_string()]);
+    store.save(&name).unwrap();
+}
+
+#[test]
+fn backupdir_collects_backups_named_after_the_original_path() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let work = dir.path().join("project");
+    fs::create_dir(&work).unwrap();
+    let backups = dir.path().join("backups");
+    let mut store = BufferStore::new();
+    store.set_backup(true);
+    store.set_backup_dir(Some(backups.clone()));
+
+    let path = work.join("notes.txt");
+    save_over_existing_file(&mut store, &path);
+
+    let original = fs::canonicalize(&path).unwrap();
+    let expected = format!("{}~", original.to_string_lossy().replace('/', "%"));
+    assert!(expected.starts_with('%') && expected.ends_with("%project%notes.txt~"));
+    assert_eq!(
+        fs::read_to_string(backups.join(&expected)).unwrap(),
+        "old\n"
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+    assert!(!work.join("notes.txt~").exists());
+    assert_eq!(
+        store.backup_options().backup_path(&path),
+        backups.join(expected)
+    );
+}
+
+#[test]
+fn backups_fall_back_beside_the_file_and_are_off_by_default() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut store = BufferStore::new();
+    let untouched = dir.path().join("plain.txt");
+    save_over_existing_file(&mut store, &untouched);
+    assert!(!dir.path().join("plain.txt~").exists());
+
+    store.set_backup(true);
+    let path = dir.path().join("kept.txt");
+    save_over_existing_file(&mut store, &path);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("kept.txt~")).unwrap(),
+        "old\n"
+    );
+}
+
+#[test]
+fn export_all_writes_every_buffer_to_a_sanitized_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut store = BufferStore::new();
+    store.open("notes").append("first".into());
+    let nested = store.open("src/exported.rs");
+    nested.append("pub fn a() {}".into());
+    nested.append("pub fn b() {}".into());
+    store.mark_closed("src/exported.rs");
+
+    let export_dir = dir.path().join("export");
+    assert_eq!(store.export_all(&export_dir).unwrap(), 2);
+
+    assert_eq!(
+        fs::read_to_string(export_dir.join("notes")).unwrap(),
+        "first\n"
+    );
+    assert_eq!(
+        fs::read_to_string(export_dir.join("src_exported.rs")).unwrap(),
+        "pub fn a() {}\npub fn b() {}\n"
+    );
+    assert!(store.is_dirty("notes"), "exporting does not save buffers");
+}
+
+#[test]
+fn export_all_suffixes_names_that_collide_once_sanitized() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let mut store = BufferStore::new();
+    store.open("a/b").append("slash".into());
+    store.open("a:b").append("colon".into());
+
+    assert_eq!(store.export_all(dir.path()).unwrap(), 2);
+    assert_eq!(
+        fs::read_to_string(dir.path().join("a_b")).unwrap(),
+        "slash\n"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.path().join("a_b-2")).unwrap(),
+        "colon\n"
+    );
+}
+
+#[derive(Default)]
+struct CountingObserver {
+    events: std::sync::Mutex<Vec<String>>,
+}
+
+impl BufferObserver for CountingObserver {
+    fn opened(&self, name: &str) {
+        self.events.lock().unwrap().push(format!("opened {name}"));
+    }
+
+    fn closed(&self, name: &str) {
+        self.events.lock().unwrap().push(format!("closed {name}"));
+    }
+
+    fn renamed(&self, old_name: &str, new_name: &str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("renamed {old_name} {new_name}"));
+    }
+
+    fn saved(&self, name: &str) {
+        self.events.lock().unwrap().push(format!("saved {name}"));
+    }
+
+    fn mutated(&self, name: &str) {
+        self.events.lock().unwrap().push(format!("mutated {name}"));
+    }
+}
+
+#[test]
+fn observers_see_lifecycle_events_in_order() {
+    let path = unique_temp_file();
+    let path_str = path.to_string_lossy().to_string();
+    let observer = Arc::new(CountingObserver::default());
+    let mut store = BufferStore::new();
+    store.add_observer(observer.clone());
+
+    store.open("alpha");
+    store.open("alpha");
+    store.insert_char("alpha", 0, 0, 'x');
+    assert!(store.delete_char("alpha", 5, 0).is_none());
+    store.rename("alpha", &path_str);
+    store.save(&path_str).expect("save should succeed");
+    store.mark_closed(&path_str);
+    store.mark_closed(&path_str);
+    store.open_all();
+
+    let events = observer.events.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        vec![
+            "opened alpha".to_string(),
+            "mutated alpha".to_string(),
+            format!("renamed alpha {path_str}"),
+            format!("saved {path_str}"),
+            format!("closed {path_str}"),
+            format!("opened {path_str}"),
+        ]
+    );
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn open_loads_an_existing_file_as_a_clean_buffer() {
+    let path = unique_temp_file();
+    fs::write(&path, "alpha\n\nbeta\n").unwrap();
+    let name = path.to_string_lossy().to_string();
+    let mut store = BufferStore::new();
+
+    assert_eq!(store.open(name.as_str()).lines(), &["alpha", "", "beta"]);
+    assert!(!store.is_dirty(&name));
+
+    store.open(name.as_str()).append("gamma".into());
+    store.save(&name).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "alpha\n\nbeta\ngamma\n");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_keeps_a_last_line_without_a_trailing_newline() {
+    let path = unique_temp_file();
+    fs::write(&path, "one\ntwo").unwrap();
+    let name = path.to_string_lossy().to_string();
+    let mut store = BufferStore::new();
+
+    assert_eq!(
+        store.try_open(name.as_str()).unwrap().lines(),
+        &["one", "two"]
+    );
+    assert!(!store.is_dirty(&name));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_of_a_missing_path_starts_empty() {
+    let path = unique_temp_file();
+    let name = path.to_string_lossy().to_string();
+    let mut store = BufferStore::new();
+
+    assert!(store.open(name.as_str()).lines().is_empty());
+    assert!(!store.is_dirty(&name));
+    assert!(!path.exists(), "opening does not create the file");
+}
+
+#[test]
+fn write_range_copy_writes_only_the_requested_lines() {
+    let path = unique_temp_file();
+    let mut store = BufferStore::new();
+    let buffer = store.open_untitled("notes");
+    for line in ["one", "two", "three", "four"] {
+        buffer.append(line.into());
+    }
+
+    store.write_range_copy("notes", 1..3, &path).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "two\nthree\n");
+    assert!(
+        store.is_dirty("notes"),
+        "copying a range does not save the buffer"
+    );
+
+    let err = store.write_range_copy("notes", 2..5, &path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "two\nthree\n");
+    fs::remove_file(&path).unwrap();
+}